@@ -44,14 +44,10 @@ pub enum TaskStatus {
     Failed,
 }
 
-/// Agent type for task execution.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum AgentType {
-    Claude,
-    Gemini,
-    Codex,
-}
+/// Agent type for task execution, as registered for the project (e.g. `claude`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AgentType(pub String);
 
 /// A session in the Manifest system.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,6 +134,14 @@ pub struct ProjectWithDirectories {
     pub directories: Vec<ProjectDirectory>,
 }
 
+/// One page of a list endpoint's results.
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    items: Vec<T>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
 /// HTTP client for the Manifest API.
 #[derive(Clone)]
 pub struct ManifestClient {
@@ -160,8 +164,7 @@ impl ManifestClient {
     /// Get the list of projects (blocking).
     pub fn get_projects(&self) -> Result<Vec<Project>, ClientError> {
         let url = format!("{}/projects", self.base_url);
-        let response: Vec<Project> = ureq::get(&url).call()?.into_json()?;
-        Ok(response)
+        self.get_all_pages(&url)
     }
 
     /// Get the feature tree for a project (blocking).
@@ -244,8 +247,7 @@ impl ManifestClient {
     /// Get sessions for a feature (blocking).
     pub fn get_feature_sessions(&self, feature_id: &Uuid) -> Result<Vec<Session>, ClientError> {
         let url = format!("{}/features/{}/sessions", self.base_url, feature_id);
-        let response: Vec<Session> = ureq::get(&url).call()?.into_json()?;
-        Ok(response)
+        self.get_all_pages(&url)
     }
 
     /// Get tasks for a session (blocking).
@@ -254,4 +256,84 @@ impl ManifestClient {
         let response: Vec<Task> = ureq::get(&url).call()?.into_json()?;
         Ok(response)
     }
+
+    /// Fetch every page of a paginated list endpoint, following `next_cursor`.
+    fn get_all_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<Vec<T>, ClientError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut request = ureq::get(url);
+            if let Some(c) = &cursor {
+                request = request.query("cursor", c);
+            }
+            let page: Page<T> = request.call()?.into_json()?;
+            items.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(items),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve one canned JSON body per connection, recording each request line.
+    fn serve(bodies: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/api/v1", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                requests.push(line.trim_end().to_string());
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (base_url, handle)
+    }
+
+    #[test]
+    fn get_projects_follows_next_cursor() {
+        let first = Uuid::from_u128(1);
+        let second = Uuid::from_u128(2);
+        let (base_url, server) = serve(vec![
+            format!(
+                r#"{{"items":[{{"id":"{first}","name":"one"}}],"next_cursor":"abc","total":2}}"#
+            ),
+            format!(r#"{{"items":[{{"id":"{second}","name":"two"}}],"next_cursor":null}}"#),
+        ]);
+
+        let projects = ManifestClient::new(base_url).get_projects().unwrap();
+
+        let ids: Vec<Uuid> = projects.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![first, second]);
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0], "GET /api/v1/projects HTTP/1.1");
+        assert_eq!(requests[1], "GET /api/v1/projects?cursor=abc HTTP/1.1");
+    }
 }
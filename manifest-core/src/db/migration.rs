@@ -0,0 +1,210 @@
+//! Bookkeeping shared by the backends' migration runners: which embedded
+//! migrations a database has applied, whether they were edited since, and
+//! what rolling back would revert.
+
+use anyhow::{bail, Result};
+
+pub(super) struct Migration {
+    pub version: &'static str,
+    pub name: &'static str,
+    pub sql: &'static str,
+    /// Reverts `sql`, for rolling the database back past this migration.
+    pub down: Option<&'static str>,
+}
+
+/// Where an embedded migration stands in a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationState {
+    Pending,
+    Applied,
+    /// Applied, but its SQL has been edited since (checksum mismatch).
+    Modified,
+    /// Applied by a newer build; this one doesn't know the migration.
+    Unknown,
+}
+
+/// One row of `mfst db status`.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: String,
+    pub name: String,
+    pub state: MigrationState,
+    /// RFC 3339 time the migration was applied, unless pending.
+    pub applied_at: Option<String>,
+    /// Whether the migration has a down script.
+    pub reversible: bool,
+}
+
+/// A migration `mfst db migrate` would apply.
+#[derive(Debug, Clone)]
+pub struct PendingMigration {
+    pub version: &'static str,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// A `schema_migrations` row.
+pub(super) struct AppliedMigration {
+    pub version: String,
+    pub name: String,
+    pub applied_at: String,
+    pub checksum: Option<String>,
+}
+
+/// FNV-1a hash of a migration's SQL, with line endings normalized so a
+/// Windows checkout hashes the same. Detects edits, not tampering.
+pub(super) fn checksum(sql: &str) -> String {
+    let hash = sql
+        .replace("\r\n", "\n")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+pub(super) fn find(migrations: &'static [Migration], version: &str) -> Option<&'static Migration> {
+    migrations.iter().find(|m| m.version == version)
+}
+
+/// Version of the newest migration in `migrations`.
+pub(super) fn latest_version(migrations: &[Migration]) -> &'static str {
+    migrations.last().map(|m| m.version).unwrap_or("000")
+}
+
+/// Refuse a database migrated by a newer build, or whose applied migrations
+/// no longer match the embedded SQL. Returns the applied migrations that
+/// have no checksum recorded yet.
+pub(super) fn verify(
+    migrations: &'static [Migration],
+    applied: &[AppliedMigration],
+) -> Result<Vec<&'static Migration>> {
+    let unknown: Vec<&str> = applied
+        .iter()
+        .filter(|a| find(migrations, &a.version).is_none())
+        .map(|a| a.version.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        bail!(
+            "The database has migrations this build doesn't know ({}); its schema is newer \
+             than {}. Upgrade mfst, or restore a backup made with this version.",
+            unknown.join(", "),
+            latest_version(migrations)
+        );
+    }
+
+    let mut unrecorded = Vec::new();
+    for row in applied {
+        let Some(migration) = find(migrations, &row.version) else {
+            continue;
+        };
+        match &row.checksum {
+            Some(recorded) if *recorded != checksum(migration.sql) => bail!(
+                "Migration {} ({}) was edited after it was applied to this database. \
+                 Put its SQL back and add a new migration for the change instead.",
+                migration.version,
+                migration.name
+            ),
+            Some(_) => {}
+            None => unrecorded.push(migration),
+        }
+    }
+    Ok(unrecorded)
+}
+
+/// Every embedded migration and every applied one, by version.
+pub(super) fn status(
+    migrations: &'static [Migration],
+    applied: Vec<AppliedMigration>,
+) -> Vec<MigrationStatus> {
+    let mut statuses: Vec<MigrationStatus> = migrations
+        .iter()
+        .map(|migration| {
+            let row = applied.iter().find(|a| a.version == migration.version);
+            let state = match row {
+                None => MigrationState::Pending,
+                Some(row) => match &row.checksum {
+                    Some(recorded) if *recorded != checksum(migration.sql) => {
+                        MigrationState::Modified
+                    }
+                    _ => MigrationState::Applied,
+                },
+            };
+            MigrationStatus {
+                version: migration.version.to_string(),
+                name: migration.name.to_string(),
+                state,
+                applied_at: row.map(|r| r.applied_at.clone()),
+                reversible: migration.down.is_some(),
+            }
+        })
+        .collect();
+    statuses.extend(
+        applied
+            .into_iter()
+            .filter(|a| find(migrations, &a.version).is_none())
+            .map(|a| MigrationStatus {
+                version: a.version,
+                name: a.name,
+                state: MigrationState::Unknown,
+                applied_at: Some(a.applied_at),
+                reversible: false,
+            }),
+    );
+    statuses.sort_by(|a, b| a.version.cmp(&b.version));
+    statuses
+}
+
+/// The migrations a runner would apply, in order.
+pub(super) fn pending(
+    migrations: &'static [Migration],
+    applied: Vec<AppliedMigration>,
+) -> Vec<PendingMigration> {
+    status(migrations, applied)
+        .into_iter()
+        .filter(|s| s.state == MigrationState::Pending)
+        .filter_map(|s| find(migrations, &s.version))
+        .map(|m| PendingMigration {
+            version: m.version,
+            name: m.name,
+            sql: m.sql,
+        })
+        .collect()
+}
+
+/// The applied migrations newer than `target`, newest first: what rolling
+/// back to `target` would revert. Fails if any of them can't be reverted.
+pub(super) fn plan_rollback(
+    migrations: &'static [Migration],
+    applied: &[AppliedMigration],
+    target: &str,
+) -> Result<Vec<PendingMigration>> {
+    if find(migrations, target).is_none() {
+        bail!("Unknown migration version '{}'", target);
+    }
+    applied
+        .iter()
+        .rev()
+        .filter(|a| a.version.as_str() > target)
+        .map(|a| {
+            let Some(migration) = find(migrations, &a.version) else {
+                bail!(
+                    "Migration {} was applied by a newer build; roll back with that build",
+                    a.version
+                );
+            };
+            let Some(down) = migration.down else {
+                bail!(
+                    "Migration {} ({}) has no down script, so the database can't be rolled back past it",
+                    migration.version,
+                    migration.name
+                );
+            };
+            Ok(PendingMigration {
+                version: migration.version,
+                name: migration.name,
+                sql: down,
+            })
+        })
+        .collect()
+}
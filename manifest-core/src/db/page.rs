@@ -0,0 +1,113 @@
+//! Keyset pagination shared by the list queries.
+//!
+//! A cursor holds the sort key of the last row of a page. The next page
+//! starts strictly after that key, so rows inserted or deleted meanwhile
+//! don't make it skip or repeat rows the way an offset would. Each backend
+//! runs the query; the SQL and the cursors are the same for both.
+
+use anyhow::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use super::ManifestError;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Direction {
+    Asc,
+    Desc,
+}
+
+/// Sort order of a paginated query.
+///
+/// Keys are SQL expressions over the query's columns. They must not be NULL,
+/// and together they must be unique per row (end with the id).
+pub(super) struct Order {
+    /// Written into cursors, so a cursor isn't reused with another order.
+    pub name: &'static str,
+    pub keys: &'static [(&'static str, Direction)],
+}
+
+/// Which page of a query to return.
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct PageRequest<'a> {
+    pub cursor: Option<&'a str>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Count the rows of `sql`.
+pub(super) fn count_sql(sql: &str) -> String {
+    format!("SELECT COUNT(*) FROM ({}) q", sql)
+}
+
+/// Wrap `sql` (a SELECT without ORDER BY or LIMIT) into the query for one
+/// page in `order`. The order's keys are appended to each row. If `after` is
+/// the first of the parameters holding a cursor's keys, only rows after that
+/// key are selected. The limit and offset are parameters `limit` and
+/// `limit + 1`.
+pub(super) fn page_sql(sql: &str, order: &Order, after: Option<usize>, limit: usize) -> String {
+    let key_exprs: Vec<&str> = order.keys.iter().map(|(expr, _)| *expr).collect();
+    let mut paged = format!("SELECT q.*, {} FROM ({}) q", key_exprs.join(", "), sql);
+    if let Some(first) = after {
+        paged.push_str(&format!(" WHERE {}", after_condition(order, first)));
+    }
+    let order_by: Vec<String> = order
+        .keys
+        .iter()
+        .map(|(expr, direction)| match direction {
+            Direction::Asc => expr.to_string(),
+            Direction::Desc => format!("{} DESC", expr),
+        })
+        .collect();
+    paged.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+    paged.push_str(&format!(" LIMIT ?{} OFFSET ?{}", limit, limit + 1));
+    paged
+}
+
+/// WHERE condition selecting the rows after the key bound to parameters
+/// `?first` onwards: `(k1 > ?a) OR (k1 = ?a AND k2 > ?b) OR ...`.
+fn after_condition(order: &Order, first: usize) -> String {
+    let alternatives: Vec<String> = (0..order.keys.len())
+        .map(|i| {
+            let mut terms: Vec<String> = order.keys[..i]
+                .iter()
+                .enumerate()
+                .map(|(j, (expr, _))| format!("{} = ?{}", expr, first + j))
+                .collect();
+            let (expr, direction) = order.keys[i];
+            let op = match direction {
+                Direction::Asc => ">",
+                Direction::Desc => "<",
+            };
+            terms.push(format!("{} {} ?{}", expr, op, first + i));
+            format!("({})", terms.join(" AND "))
+        })
+        .collect();
+    format!("({})", alternatives.join(" OR "))
+}
+
+/// A cursor for the row whose sort key is `keys`. Keys are strings and
+/// numbers. The JSON is base64url-encoded, so cursors are opaque and can go
+/// into URLs as they are.
+pub(super) fn encode_cursor(order: &Order, keys: Vec<serde_json::Value>) -> String {
+    let json = serde_json::json!({ "o": order.name, "k": keys }).to_string();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+/// The sort key held by a cursor issued for `order`.
+pub(super) fn decode_cursor(cursor: &str, order: &Order) -> Result<Vec<serde_json::Value>> {
+    let invalid = || ManifestError::validation("Invalid cursor");
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let json: serde_json::Value = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+    if json["o"] != order.name {
+        return Err(
+            ManifestError::validation("Cursor was issued for a different sort order").into(),
+        );
+    }
+    let keys = json["k"].as_array().ok_or_else(invalid)?;
+    if keys.len() != order.keys.len() || !keys.iter().all(|k| k.is_string() || k.is_number()) {
+        return Err(invalid().into());
+    }
+    Ok(keys.clone())
+}
@@ -0,0 +1,245 @@
+//! Blocking access to pooled PostgreSQL connections.
+//!
+//! The store's operations are synchronous, like SQLite's, and are called
+//! both from plain threads and from async handlers. The connections' I/O is
+//! driven by a runtime of its own; queries wait on it with [`wait`].
+
+use std::error::Error;
+use std::future::Future;
+
+use anyhow::Result;
+use bytes::BytesMut;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Object, Transaction};
+use futures_executor::block_on;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio_postgres::types::{to_sql_checked, FromSql, IsNull, ToSql, Type};
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Block on `future`. On a worker of a multi-threaded tokio runtime (an
+/// async handler), the worker first hands its other tasks to another
+/// thread, as `spawn_blocking` would, so a slow query doesn't hold them up.
+/// `futures_executor::block_on`, unlike a tokio runtime, may block inside
+/// another runtime's task.
+pub(super) fn wait<F: Future>(future: F) -> F::Output {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| block_on(future))
+        }
+        _ => block_on(future),
+    }
+}
+
+/// A query parameter.
+pub(super) type Param = Box<dyn ToSql + Sync + Send>;
+
+pub(super) fn param_refs(params: &[Param]) -> Vec<&(dyn ToSql + Sync)> {
+    params
+        .iter()
+        .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+        .collect()
+}
+
+/// Something queries run on: the client, or a transaction on it.
+///
+/// SQL is written with SQLite's placeholders (`?` and `?N`) so fragments
+/// can be shared with the SQLite store; they're rewritten to `$N`.
+pub(super) trait Conn {
+    fn query(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>>;
+
+    fn execute(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64>;
+
+    /// Run statements without parameters.
+    fn batch_execute(&self, sql: &str) -> Result<()>;
+
+    fn query_opt(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Option<Row>> {
+        Ok(self.query(sql, params)?.into_iter().next())
+    }
+
+    fn query_one(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row> {
+        self.query_opt(sql, params)?
+            .ok_or_else(|| anyhow::anyhow!("Query returned no rows"))
+    }
+}
+
+/// A pooled client whose queries block, returned to the pool when dropped.
+/// Wrapping it hides the client's own async methods, which would otherwise
+/// be picked over [`Conn`]'s.
+pub(super) struct Connection(pub(super) Object);
+
+/// A transaction on a [`Connection`], rolled back if dropped uncommitted.
+pub(super) struct Tx<'a>(Transaction<'a>);
+
+impl Conn for Connection {
+    fn query(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>> {
+        Ok(wait(self.0.query(&numbered(sql), params))?)
+    }
+
+    fn execute(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64> {
+        Ok(wait(self.0.execute(&numbered(sql), params))?)
+    }
+
+    fn batch_execute(&self, sql: &str) -> Result<()> {
+        Ok(wait(self.0.batch_execute(sql))?)
+    }
+}
+
+impl Conn for Tx<'_> {
+    fn query(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>> {
+        Ok(wait(self.0.query(&numbered(sql), params))?)
+    }
+
+    fn execute(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64> {
+        Ok(wait(self.0.execute(&numbered(sql), params))?)
+    }
+
+    fn batch_execute(&self, sql: &str) -> Result<()> {
+        Ok(wait(self.0.batch_execute(sql))?)
+    }
+}
+
+pub(super) fn begin(conn: &mut Connection) -> Result<Tx<'_>> {
+    Ok(Tx(wait(conn.0.transaction())?))
+}
+
+pub(super) fn commit(tx: Tx<'_>) -> Result<()> {
+    Ok(wait(tx.0.commit())?)
+}
+
+/// Rewrite SQLite placeholders to PostgreSQL's: `?N` becomes `$N`, and a
+/// bare `?` the parameter after the highest one so far, as SQLite numbers
+/// them. Quoted text is left alone.
+fn numbered(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len() + 8);
+    let mut chars = sql.chars().peekable();
+    let mut highest = 0;
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '?') => {
+                let mut digits = String::new();
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(d);
+                }
+                let n = match digits.parse::<usize>() {
+                    Ok(n) => n,
+                    Err(_) => highest + 1,
+                };
+                highest = highest.max(n);
+                out.push('$');
+                out.push_str(&n.to_string());
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// A column value of any of the types the schema uses, for copying rows
+/// whose columns aren't known in advance (snapshots, cursor keys). UUIDs
+/// and timestamps read as text, the way SQLite stores them.
+///
+/// As a parameter, it converts to the type the server expects, the way
+/// SQLite's dynamic typing would.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl<'a> FromSql<'a> for Value {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(match *ty {
+            Type::BOOL => Value::Integer(bool::from_sql(ty, raw)? as i64),
+            Type::INT2 => Value::Integer(i16::from_sql(ty, raw)?.into()),
+            Type::INT4 => Value::Integer(i32::from_sql(ty, raw)?.into()),
+            Type::INT8 => Value::Integer(i64::from_sql(ty, raw)?),
+            Type::FLOAT4 => Value::Real(f32::from_sql(ty, raw)?.into()),
+            Type::FLOAT8 => Value::Real(f64::from_sql(ty, raw)?),
+            Type::BYTEA => Value::Blob(Vec::<u8>::from_sql(ty, raw)?),
+            Type::UUID => Value::Text(Uuid::from_sql(ty, raw)?.to_string()),
+            Type::TIMESTAMPTZ => Value::Text(DateTime::<Utc>::from_sql(ty, raw)?.to_rfc3339()),
+            _ => Value::Text(String::from_sql(ty, raw)?),
+        })
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Value::Null)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(
+            *ty,
+            Type::BOOL
+                | Type::INT2
+                | Type::INT4
+                | Type::INT8
+                | Type::FLOAT4
+                | Type::FLOAT8
+                | Type::BYTEA
+                | Type::UUID
+                | Type::TIMESTAMPTZ
+        ) || <String as FromSql>::accepts(ty)
+    }
+}
+
+impl ToSql for Value {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match (self, ty) {
+            (Value::Null, _) => Ok(IsNull::Yes),
+            (Value::Integer(i), &Type::BOOL) => (*i != 0).to_sql(ty, out),
+            (Value::Integer(i), &Type::INT2) => i16::try_from(*i)?.to_sql(ty, out),
+            (Value::Integer(i), &Type::INT4) => i32::try_from(*i)?.to_sql(ty, out),
+            (Value::Integer(i), &Type::INT8) => i.to_sql(ty, out),
+            (Value::Integer(i), &Type::FLOAT4 | &Type::FLOAT8) => {
+                Value::Real(*i as f64).to_sql(ty, out)
+            }
+            (Value::Integer(i), _) => i.to_string().to_sql(ty, out),
+            (Value::Real(f), &Type::FLOAT4) => (*f as f32).to_sql(ty, out),
+            (Value::Real(f), &Type::FLOAT8) => f.to_sql(ty, out),
+            (Value::Real(f), _) => f.to_string().to_sql(ty, out),
+            (Value::Text(s), &Type::INT2 | &Type::INT4 | &Type::INT8) => {
+                Value::Integer(s.trim().parse()?).to_sql(ty, out)
+            }
+            (Value::Text(s), &Type::UUID) => s.parse::<Uuid>()?.to_sql(ty, out),
+            (Value::Text(s), &Type::TIMESTAMPTZ) => {
+                DateTime::parse_from_rfc3339(s)?.to_sql(ty, out)
+            }
+            (Value::Text(s), _) => s.to_sql(ty, out),
+            (Value::Blob(bytes), &Type::BYTEA) => bytes.to_sql(ty, out),
+            (Value::Blob(bytes), _) => String::from_utf8_lossy(bytes).into_owned().to_sql(ty, out),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <Value as FromSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::numbered;
+
+    #[test]
+    fn test_placeholders_are_numbered_like_sqlite() {
+        assert_eq!(
+            numbered("SELECT ? WHERE a = ?3 AND b = ? AND c = '?'"),
+            "SELECT $1 WHERE a = $3 AND b = $4 AND c = '?'"
+        );
+    }
+}
@@ -0,0 +1,184 @@
+-- The PostgreSQL schema, equivalent to the SQLite schema at its migration
+-- 021. Later changes are added to both backends, each numbering its own
+-- migrations.
+--
+-- Ids are UUIDs and timestamps TIMESTAMPTZ; other columns keep SQLite's
+-- types (integer booleans, JSON as text). Both sort like SQLite's text ids
+-- and RFC 3339 timestamps, so snapshots and cursors move between backends
+-- unchanged. Text compares byte-wise like SQLite's, and tag names ignore
+-- case like COLLATE NOCASE. Foreign keys are checked at commit, so a
+-- snapshot's rows can be inserted in any order.
+
+CREATE COLLATION IF NOT EXISTS nocase (provider = icu, locale = 'und-u-ks-level2', deterministic = false);
+
+CREATE TABLE projects (
+    id UUID PRIMARY KEY,
+    name TEXT COLLATE "C" NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    instructions TEXT,
+    max_concurrent_sessions BIGINT NOT NULL DEFAULT 1,
+    provision_worktrees BIGINT NOT NULL DEFAULT 0,
+    key TEXT COLLATE "C",
+    next_feature_number BIGINT NOT NULL DEFAULT 1
+);
+CREATE UNIQUE INDEX idx_projects_key ON projects(key);
+
+CREATE TABLE project_directories (
+    id UUID PRIMARY KEY,
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    path TEXT COLLATE "C" NOT NULL,
+    git_remote TEXT,
+    is_primary BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    instructions TEXT
+);
+CREATE INDEX idx_project_directories_project ON project_directories(project_id);
+
+CREATE TABLE features (
+    id UUID PRIMARY KEY,
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    parent_id UUID REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    title TEXT COLLATE "C" NOT NULL,
+    details TEXT,
+    state TEXT NOT NULL DEFAULT 'proposed' CHECK (state IN ('proposed', 'specified', 'implemented', 'deprecated')),
+    priority BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    desired_details TEXT,
+    slug TEXT COLLATE "C",
+    path TEXT COLLATE "C",
+    key TEXT COLLATE "C"
+);
+CREATE INDEX idx_features_project ON features(project_id);
+CREATE INDEX idx_features_parent ON features(parent_id);
+CREATE UNIQUE INDEX idx_features_path ON features(project_id, path);
+CREATE UNIQUE INDEX idx_features_key ON features(key);
+
+CREATE TABLE feature_redirects (
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    path TEXT COLLATE "C" NOT NULL,
+    feature_id UUID NOT NULL REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (project_id, path)
+);
+
+CREATE TABLE feature_history (
+    id UUID PRIMARY KEY,
+    feature_id UUID REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    session_id UUID,
+    summary TEXT NOT NULL,
+    files_changed TEXT,
+    author TEXT,
+    details TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE sessions (
+    id UUID PRIMARY KEY,
+    feature_id UUID REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    goal TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('active', 'completed', 'failed')),
+    feature_version_before BIGINT,
+    feature_version_after BIGINT,
+    created_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    kind TEXT COLLATE "C",
+    commits TEXT
+);
+CREATE INDEX idx_sessions_feature ON sessions(feature_id);
+CREATE UNIQUE INDEX idx_one_active_session_per_kind
+    ON sessions(feature_id, COALESCE(kind, '')) WHERE status = 'active';
+
+CREATE TABLE agent_types (
+    id UUID PRIMARY KEY,
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    name TEXT COLLATE "C" NOT NULL,
+    label TEXT NOT NULL,
+    instructions TEXT,
+    max_concurrency BIGINT,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (project_id, name)
+);
+CREATE INDEX idx_agent_types_project ON agent_types(project_id);
+
+CREATE TABLE tasks (
+    id UUID PRIMARY KEY,
+    session_id UUID REFERENCES sessions(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    parent_id UUID REFERENCES tasks(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    title TEXT COLLATE "C" NOT NULL,
+    scope TEXT,
+    status TEXT DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    agent_type TEXT COLLATE "C" NOT NULL,
+    worktree_path TEXT,
+    branch TEXT COLLATE "C",
+    created_at TIMESTAMPTZ NOT NULL,
+    result TEXT,
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+CREATE INDEX idx_tasks_session ON tasks(session_id);
+CREATE INDEX idx_tasks_parent ON tasks(parent_id);
+
+CREATE TABLE feature_code_links (
+    id UUID PRIMARY KEY,
+    feature_id UUID NOT NULL REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    directory_id UUID NOT NULL REFERENCES project_directories(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    path TEXT COLLATE "C" NOT NULL,
+    line_start BIGINT,
+    line_end BIGINT,
+    symbol TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX idx_feature_code_links_feature ON feature_code_links(feature_id);
+CREATE INDEX idx_feature_code_links_directory ON feature_code_links(directory_id);
+
+CREATE TABLE tags (
+    id UUID PRIMARY KEY,
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    name TEXT COLLATE nocase NOT NULL,
+    color TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (project_id, name)
+);
+
+CREATE TABLE feature_tags (
+    feature_id UUID NOT NULL REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    PRIMARY KEY (feature_id, tag_id)
+);
+CREATE INDEX idx_feature_tags_tag ON feature_tags(tag_id);
+
+CREATE TABLE feature_relations (
+    id UUID PRIMARY KEY,
+    from_feature_id UUID NOT NULL REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    to_feature_id UUID NOT NULL REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    kind TEXT NOT NULL CHECK (kind IN ('depends_on', 'blocks', 'relates_to', 'supersedes')),
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (from_feature_id, to_feature_id, kind)
+);
+CREATE INDEX idx_feature_relations_to ON feature_relations(to_feature_id);
+
+CREATE TABLE acceptance_criteria (
+    id UUID PRIMARY KEY,
+    feature_id UUID NOT NULL REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    position BIGINT NOT NULL,
+    text TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'unverified' CHECK (status IN ('unverified', 'passing', 'failing')),
+    test_name TEXT COLLATE "C",
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX idx_acceptance_criteria_feature ON acceptance_criteria(feature_id, position);
+
+CREATE TABLE test_results (
+    id UUID PRIMARY KEY,
+    feature_id UUID NOT NULL REFERENCES features(id) ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    criterion_id UUID REFERENCES acceptance_criteria(id) ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED,
+    test_name TEXT COLLATE "C" NOT NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('passed', 'failed', 'skipped')),
+    message TEXT,
+    reported_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (feature_id, test_name)
+);
@@ -0,0 +1,13 @@
+-- Responses to POST requests sent with an Idempotency-Key header, so a
+-- retried request gets the original response instead of running again.
+-- status is NULL while the first request is still running.
+
+CREATE TABLE idempotency_keys (
+    key TEXT COLLATE "C" PRIMARY KEY,
+    request TEXT NOT NULL,
+    status BIGINT,
+    content_type TEXT,
+    body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX idx_idempotency_keys_created ON idempotency_keys(created_at);
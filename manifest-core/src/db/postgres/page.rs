@@ -0,0 +1,90 @@
+//! Runs the paginated list queries on PostgreSQL; see [`crate::db::page`].
+
+use anyhow::Result;
+use tokio_postgres::Row;
+
+use super::conn::{param_refs, Conn, Param, Value};
+use crate::db::page::{count_sql, decode_cursor, encode_cursor, page_sql, Order, PageRequest};
+use crate::models::Page;
+
+/// Run `sql` (a SELECT without ORDER BY or LIMIT) and return one page of it,
+/// in `order`.
+///
+/// The total is counted only when no cursor is given. An unreadable cursor,
+/// or one issued for another order, is a validation error.
+pub(super) fn query_page<T>(
+    conn: &impl Conn,
+    sql: &str,
+    mut params: Vec<Param>,
+    order: &Order,
+    request: PageRequest,
+    map: impl Fn(&Row) -> Result<T>,
+) -> Result<Page<T>> {
+    let total = match request.cursor {
+        Some(_) => None,
+        None => {
+            let count: i64 = conn
+                .query_one(&count_sql(sql), &param_refs(&params))?
+                .try_get(0)?;
+            Some(count as u64)
+        }
+    };
+
+    let mut after = None;
+    if let Some(cursor) = request.cursor {
+        after = Some(params.len() + 1);
+        for key in decode_cursor(cursor, order)? {
+            let value = match key {
+                serde_json::Value::String(s) => Value::Text(s),
+                serde_json::Value::Number(n) => n
+                    .as_i64()
+                    .map(Value::Integer)
+                    .unwrap_or_else(|| Value::Real(n.as_f64().unwrap_or_default())),
+                _ => Value::Null,
+            };
+            params.push(Box::new(value));
+        }
+    }
+    // Fetch one extra row to learn whether there is a next page. A NULL
+    // LIMIT means no limit.
+    params.push(Box::new(request.limit.map(|l| i64::from(l) + 1)));
+    params.push(Box::new(i64::from(request.offset.unwrap_or(0))));
+    let paged = page_sql(sql, order, after, params.len() - 1);
+
+    let mut rows = conn
+        .query(&paged, &param_refs(&params))?
+        .iter()
+        .map(|row| {
+            let first_key = row.len() - order.keys.len();
+            let keys = (first_key..row.len())
+                .map(|i| row.try_get::<_, Value>(i))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((map(row)?, keys))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut next_cursor = None;
+    if let Some(limit) = request.limit {
+        if rows.len() > limit as usize {
+            rows.truncate(limit as usize);
+            next_cursor = rows.last().map(|(_, keys)| {
+                let keys = keys
+                    .iter()
+                    .map(|value| match value {
+                        Value::Integer(i) => (*i).into(),
+                        Value::Real(f) => (*f).into(),
+                        Value::Text(s) => s.as_str().into(),
+                        Value::Null | Value::Blob(_) => serde_json::Value::Null,
+                    })
+                    .collect();
+                encode_cursor(order, keys)
+            });
+        }
+    }
+
+    Ok(Page {
+        items: rows.into_iter().map(|(item, _)| item).collect(),
+        next_cursor,
+        total,
+    })
+}
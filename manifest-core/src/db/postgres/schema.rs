@@ -0,0 +1,156 @@
+use super::conn::{begin, commit, Conn, Connection};
+use crate::db::migration::{self, checksum, AppliedMigration, Migration};
+use crate::db::{MigrationStatus, PendingMigration};
+use anyhow::{Context, Result};
+
+/// PostgreSQL support starts at the SQLite schema's version 021, so the
+/// first migration creates that schema whole. Later changes go to both
+/// backends, each under its own next version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: "001",
+        name: "initial",
+        sql: include_str!("migrations/001_initial.sql"),
+        down: None,
+    },
+    Migration {
+        version: "002",
+        name: "idempotency_keys",
+        sql: include_str!("migrations/002_idempotency_keys.sql"),
+        down: Some(include_str!("migrations/002_idempotency_keys.down.sql")),
+    },
+];
+
+/// Key of the advisory lock held while migrating, so that several processes
+/// starting against one server don't apply the same migration twice.
+const MIGRATION_LOCK: i64 = 0x6d66_7374;
+
+pub(super) fn run_migrations(client: &mut Connection) -> Result<()> {
+    client.query("SELECT pg_advisory_lock(?)", &[&MIGRATION_LOCK])?;
+    let result = apply_pending(client);
+    client.query("SELECT pg_advisory_unlock(?)", &[&MIGRATION_LOCK])?;
+    result
+}
+
+fn apply_pending(client: &mut Connection) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version TEXT COLLATE \"C\" PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL,
+                checksum TEXT
+            )",
+        )
+        .context("Failed to create schema_migrations table")?;
+
+    let applied = read_applied(client)?;
+    for migration in migration::verify(MIGRATIONS, &applied)? {
+        client.execute(
+            "UPDATE schema_migrations SET checksum = ? WHERE version = ?",
+            &[&checksum(migration.sql), &migration.version],
+        )?;
+    }
+
+    for migration in MIGRATIONS {
+        if !applied.iter().any(|a| a.version == migration.version) {
+            apply_migration(client, migration)?;
+        }
+    }
+    Ok(())
+}
+
+/// Every embedded migration and every applied one, by version.
+pub(super) fn status(client: &Connection) -> Result<Vec<MigrationStatus>> {
+    Ok(migration::status(MIGRATIONS, read_applied(client)?))
+}
+
+/// The migrations `run_migrations` would apply, in order.
+pub(super) fn pending(client: &Connection) -> Result<Vec<PendingMigration>> {
+    Ok(migration::pending(MIGRATIONS, read_applied(client)?))
+}
+
+/// The applied migrations newer than `target`, newest first.
+pub(super) fn plan_rollback(client: &Connection, target: &str) -> Result<Vec<PendingMigration>> {
+    migration::plan_rollback(MIGRATIONS, &read_applied(client)?, target)
+}
+
+/// Revert every applied migration newer than `target` with its down script,
+/// newest first, each in its own transaction. Returns the reverted versions.
+pub(super) fn rollback(client: &mut Connection, target: &str) -> Result<Vec<&'static str>> {
+    let plan = plan_rollback(client, target)?;
+    for step in &plan {
+        tracing::info!("Reverting migration {}: {}", step.version, step.name);
+        let tx = begin(client)?;
+        tx.batch_execute(step.sql)
+            .and_then(|()| {
+                tx.execute(
+                    "DELETE FROM schema_migrations WHERE version = ?",
+                    &[&step.version],
+                )
+            })
+            .with_context(|| {
+                format!("Failed to revert migration {}: {}", step.version, step.name)
+            })?;
+        commit(tx)?;
+    }
+    Ok(plan.into_iter().map(|step| step.version).collect())
+}
+
+/// The `schema_migrations` rows, oldest first. Reads nothing from databases
+/// that were never migrated.
+fn read_applied(client: &Connection) -> Result<Vec<AppliedMigration>> {
+    let tracked: bool = client
+        .query_one("SELECT to_regclass('schema_migrations') IS NOT NULL", &[])?
+        .try_get(0)?;
+    if !tracked {
+        return Ok(vec![]);
+    }
+    client
+        .query(
+            "SELECT version, name, applied_at, checksum FROM schema_migrations ORDER BY version",
+            &[],
+        )?
+        .iter()
+        .map(|row| {
+            Ok(AppliedMigration {
+                version: row.try_get(0)?,
+                name: row.try_get(1)?,
+                applied_at: row
+                    .try_get::<_, chrono::DateTime<chrono::Utc>>(2)?
+                    .to_rfc3339(),
+                checksum: row.try_get(3)?,
+            })
+        })
+        .collect()
+}
+
+fn apply_migration(client: &mut Connection, migration: &Migration) -> Result<()> {
+    tracing::info!(
+        "Applying migration {}: {}",
+        migration.version,
+        migration.name
+    );
+
+    let tx = begin(client)?;
+    tx.batch_execute(migration.sql)
+        .and_then(|()| {
+            tx.execute(
+                "INSERT INTO schema_migrations (version, name, applied_at, checksum)
+                 VALUES (?, ?, ?, ?)",
+                &[
+                    &migration.version,
+                    &migration.name,
+                    &chrono::Utc::now(),
+                    &checksum(migration.sql),
+                ],
+            )
+        })
+        .with_context(|| {
+            format!(
+                "Failed to apply migration {}: {}",
+                migration.version, migration.name
+            )
+        })?;
+    commit(tx)
+}
@@ -0,0 +1,2 @@
+-- Task result reports are lost
+ALTER TABLE tasks DROP COLUMN result;
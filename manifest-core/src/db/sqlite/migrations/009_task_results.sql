@@ -0,0 +1,4 @@
+-- Add structured result reports to tasks
+-- Agents record summary, files changed, commits, test outcome and failure reason
+
+ALTER TABLE tasks ADD COLUMN result JSON;
@@ -0,0 +1,30 @@
+-- The agent type registry is lost; tasks of types other than the former
+-- built-in ones keep a NULL agent_type so the restored CHECK holds
+
+DROP TABLE agent_types;
+
+CREATE TABLE tasks_old (
+    id TEXT PRIMARY KEY,
+    session_id TEXT REFERENCES sessions(id) ON DELETE CASCADE,
+    parent_id TEXT REFERENCES tasks_old(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    scope TEXT,
+    status TEXT DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    agent_type TEXT CHECK (agent_type IN ('claude', 'gemini', 'codex')),
+    worktree_path TEXT,
+    branch TEXT,
+    created_at TEXT NOT NULL,
+    result JSON
+);
+
+INSERT INTO tasks_old (id, session_id, parent_id, title, scope, status, agent_type, worktree_path, branch, created_at, result)
+SELECT id, session_id, parent_id, title, scope, status,
+       CASE WHEN agent_type IN ('claude', 'gemini', 'codex') THEN agent_type END,
+       worktree_path, branch, created_at, result
+FROM tasks;
+
+DROP TABLE tasks;
+ALTER TABLE tasks_old RENAME TO tasks;
+
+CREATE INDEX idx_tasks_session ON tasks(session_id);
+CREATE INDEX idx_tasks_parent ON tasks(parent_id);
@@ -0,0 +1,55 @@
+-- Per-project agent type registry
+-- Replaces the hard-coded CHECK on tasks.agent_type; validation now happens
+-- against the project's registered types when tasks are created.
+
+CREATE TABLE agent_types (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    label TEXT NOT NULL,
+    instructions TEXT,
+    max_concurrency INTEGER,
+    created_at TEXT NOT NULL,
+    UNIQUE (project_id, name)
+);
+
+CREATE INDEX idx_agent_types_project ON agent_types(project_id);
+
+-- Register the previously built-in types for existing projects
+-- (ids are random version 4 UUIDs built from randomblob)
+INSERT INTO agent_types (id, project_id, name, label, created_at)
+SELECT lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' ||
+       substr(lower(hex(randomblob(2))), 2) || '-a' || substr(lower(hex(randomblob(2))), 2) || '-' ||
+       lower(hex(randomblob(6))),
+       p.id, t.name, t.label, datetime('now')
+FROM projects p
+CROSS JOIN (
+    SELECT 'claude' AS name, 'Anthropic Claude' AS label
+    UNION ALL SELECT 'gemini', 'Google Gemini'
+    UNION ALL SELECT 'codex', 'OpenAI Codex'
+) t;
+
+-- Recreate tasks without the agent_type CHECK constraint
+CREATE TABLE tasks_new (
+    id TEXT PRIMARY KEY,
+    session_id TEXT REFERENCES sessions(id) ON DELETE CASCADE,
+    parent_id TEXT REFERENCES tasks_new(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    scope TEXT,
+    status TEXT DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    agent_type TEXT NOT NULL,
+    worktree_path TEXT,
+    branch TEXT,
+    created_at TEXT NOT NULL,
+    result JSON
+);
+
+INSERT INTO tasks_new (id, session_id, parent_id, title, scope, status, agent_type, worktree_path, branch, created_at, result)
+SELECT id, session_id, parent_id, title, scope, status, COALESCE(agent_type, 'claude'), worktree_path, branch, created_at, result
+FROM tasks;
+
+DROP TABLE tasks;
+ALTER TABLE tasks_new RENAME TO tasks;
+
+CREATE INDEX idx_tasks_session ON tasks(session_id);
+CREATE INDEX idx_tasks_parent ON tasks(parent_id);
@@ -0,0 +1,3 @@
+-- Task start and finish times are lost
+ALTER TABLE tasks DROP COLUMN started_at;
+ALTER TABLE tasks DROP COLUMN finished_at;
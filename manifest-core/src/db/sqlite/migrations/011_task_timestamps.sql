@@ -0,0 +1,5 @@
+-- Track when tasks start and finish
+-- Set automatically by status transitions; used for duration metrics
+
+ALTER TABLE tasks ADD COLUMN started_at TEXT;
+ALTER TABLE tasks ADD COLUMN finished_at TEXT;
@@ -0,0 +1,9 @@
+-- Session kinds and per-project limits are lost. Fails while a feature has
+-- more than one active session, since only one is allowed again.
+
+DROP INDEX IF EXISTS idx_one_active_session_per_kind;
+CREATE UNIQUE INDEX idx_one_active_session
+    ON sessions(feature_id) WHERE status = 'active';
+
+ALTER TABLE sessions DROP COLUMN kind;
+ALTER TABLE projects DROP COLUMN max_concurrent_sessions;
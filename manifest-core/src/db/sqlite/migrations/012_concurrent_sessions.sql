@@ -0,0 +1,10 @@
+-- Allow several active sessions per feature when a project opts in
+-- Concurrent sessions are told apart by kind (e.g. 'docs', 'implementation')
+
+ALTER TABLE projects ADD COLUMN max_concurrent_sessions INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE sessions ADD COLUMN kind TEXT;
+
+-- Replace the one-active-session index with one active session per kind
+DROP INDEX IF EXISTS idx_one_active_session;
+CREATE UNIQUE INDEX idx_one_active_session_per_kind
+    ON sessions(feature_id, COALESCE(kind, '')) WHERE status = 'active';
@@ -0,0 +1,5 @@
+-- Opt-in git worktree provisioning for tasks
+-- When enabled, the server creates a worktree and branch per task in the
+-- project's primary directory (local mode only)
+
+ALTER TABLE projects ADD COLUMN provision_worktrees INTEGER NOT NULL DEFAULT 0;
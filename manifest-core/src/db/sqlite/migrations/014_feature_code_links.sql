@@ -0,0 +1,16 @@
+-- Links from features to where they are implemented in the source tree
+-- path is relative to the directory and may be a glob
+
+CREATE TABLE feature_code_links (
+    id TEXT PRIMARY KEY,
+    feature_id TEXT NOT NULL REFERENCES features(id) ON DELETE CASCADE,
+    directory_id TEXT NOT NULL REFERENCES project_directories(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    line_start INTEGER,
+    line_end INTEGER,
+    symbol TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_feature_code_links_feature ON feature_code_links(feature_id);
+CREATE INDEX idx_feature_code_links_directory ON feature_code_links(directory_id);
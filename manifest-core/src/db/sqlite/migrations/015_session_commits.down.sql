@@ -0,0 +1,2 @@
+-- Commits recorded on active sessions are lost
+ALTER TABLE sessions DROP COLUMN commits;
@@ -0,0 +1,4 @@
+-- Commits recorded against an active session (e.g. by the git post-commit hook)
+-- JSON array of CommitRef, merged into the history entry when the session completes
+
+ALTER TABLE sessions ADD COLUMN commits TEXT;
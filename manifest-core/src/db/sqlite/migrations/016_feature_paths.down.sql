@@ -0,0 +1,6 @@
+DROP TABLE feature_redirects;
+
+DROP INDEX idx_features_path;
+
+ALTER TABLE features DROP COLUMN path;
+ALTER TABLE features DROP COLUMN slug;
@@ -0,0 +1,18 @@
+-- Human-readable feature references
+-- slug is derived from the title and unique among siblings; path joins the
+-- slugs from the root (auth/oauth-integration). Rows that predate this
+-- migration are filled in by Database::migrate.
+
+ALTER TABLE features ADD COLUMN slug TEXT;
+ALTER TABLE features ADD COLUMN path TEXT;
+
+CREATE UNIQUE INDEX idx_features_path ON features(project_id, path);
+
+-- Paths a feature had before it was renamed or moved
+CREATE TABLE feature_redirects (
+    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    feature_id TEXT NOT NULL REFERENCES features(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (project_id, path)
+);
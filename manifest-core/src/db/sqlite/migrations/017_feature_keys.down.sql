@@ -0,0 +1,10 @@
+-- Keys are allocated again, in a new order, if the migration is reapplied
+
+DROP INDEX idx_features_key;
+
+ALTER TABLE features DROP COLUMN key;
+
+DROP INDEX idx_projects_key;
+
+ALTER TABLE projects DROP COLUMN next_feature_number;
+ALTER TABLE projects DROP COLUMN key;
@@ -0,0 +1,13 @@
+-- Short sequential feature keys (MAN-42)
+-- Each project has a key prefix and a counter; features take the next number
+-- when they are created. Rows that predate this migration are filled in by
+-- Database::migrate.
+
+ALTER TABLE projects ADD COLUMN key TEXT;
+ALTER TABLE projects ADD COLUMN next_feature_number INTEGER NOT NULL DEFAULT 1;
+
+CREATE UNIQUE INDEX idx_projects_key ON projects(key);
+
+ALTER TABLE features ADD COLUMN key TEXT;
+
+CREATE UNIQUE INDEX idx_features_key ON features(key);
@@ -0,0 +1,2 @@
+DROP TABLE feature_tags;
+DROP TABLE tags;
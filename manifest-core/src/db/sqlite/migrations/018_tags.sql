@@ -0,0 +1,19 @@
+-- Project-scoped labels for cross-cutting concerns (security, v2, frontend)
+-- Names are unique per project, ignoring case
+
+CREATE TABLE tags (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    name TEXT NOT NULL COLLATE NOCASE,
+    color TEXT,
+    created_at TEXT NOT NULL,
+    UNIQUE (project_id, name)
+);
+
+CREATE TABLE feature_tags (
+    feature_id TEXT NOT NULL REFERENCES features(id) ON DELETE CASCADE,
+    tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (feature_id, tag_id)
+);
+
+CREATE INDEX idx_feature_tags_tag ON feature_tags(tag_id);
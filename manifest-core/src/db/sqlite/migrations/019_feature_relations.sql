@@ -0,0 +1,13 @@
+-- Typed links between features: depends_on, blocks, relates_to, supersedes
+-- "A blocks B" is the same dependency as "B depends_on A"
+
+CREATE TABLE feature_relations (
+    id TEXT PRIMARY KEY,
+    from_feature_id TEXT NOT NULL REFERENCES features(id) ON DELETE CASCADE,
+    to_feature_id TEXT NOT NULL REFERENCES features(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('depends_on', 'blocks', 'relates_to', 'supersedes')),
+    created_at TEXT NOT NULL,
+    UNIQUE (from_feature_id, to_feature_id, kind)
+);
+
+CREATE INDEX idx_feature_relations_to ON feature_relations(to_feature_id);
@@ -0,0 +1 @@
+DROP TABLE acceptance_criteria;
@@ -0,0 +1,15 @@
+-- Acceptance criteria: ordered, checkable statements a feature must satisfy
+-- position is 0-based and contiguous within a feature
+
+CREATE TABLE acceptance_criteria (
+    id TEXT PRIMARY KEY,
+    feature_id TEXT NOT NULL REFERENCES features(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'unverified' CHECK (status IN ('unverified', 'passing', 'failing')),
+    test_name TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_acceptance_criteria_feature ON acceptance_criteria(feature_id, position);
@@ -0,0 +1,13 @@
+-- Latest test results per feature, from JUnit or libtest reports pushed by CI
+-- Each report replaces the rows of the features it matched
+
+CREATE TABLE test_results (
+    id TEXT PRIMARY KEY,
+    feature_id TEXT NOT NULL REFERENCES features(id) ON DELETE CASCADE,
+    criterion_id TEXT REFERENCES acceptance_criteria(id) ON DELETE SET NULL,
+    test_name TEXT NOT NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('passed', 'failed', 'skipped')),
+    message TEXT,
+    reported_at TEXT NOT NULL,
+    UNIQUE (feature_id, test_name)
+);
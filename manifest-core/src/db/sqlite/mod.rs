@@ -0,0 +1,3146 @@
+//! The SQLite store: a single database file, or memory for tests.
+
+mod page;
+mod schema;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use uuid::Uuid;
+
+use super::page::{Direction, Order, PageRequest};
+use super::*;
+use crate::models::*;
+use page::query_page;
+
+/// Version of the newest SQLite migration. Snapshots from either backend
+/// carry it as their `schema_version`, so they move between backends.
+pub(super) fn latest_schema_version() -> &'static str {
+    schema::latest_version()
+}
+
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+    /// The database file; `None` in memory.
+    path: Option<PathBuf>,
+}
+
+impl SqliteStore {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Database path has no parent directory"))?;
+        std::fs::create_dir_all(parent)?;
+        let conn = Connection::open(&path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        // Other processes (a server, `mfst mcp --local`) may be writing too;
+        // wait for their transactions rather than failing with SQLITE_BUSY
+        conn.busy_timeout(Duration::from_secs(10))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            path: Some(path),
+        })
+    }
+
+    pub fn open_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            path: None,
+        })
+    }
+
+    /// Lock `<database>.lock`, blocking while another process holds it, so
+    /// processes starting together don't migrate the same file at once. The
+    /// lock is released when the returned file is dropped. In-memory
+    /// databases have no other users and take no lock.
+    fn lock_schema(&self) -> Result<Option<File>> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?;
+        file.lock()?;
+        Ok(Some(file))
+    }
+}
+
+impl Store for SqliteStore {
+    fn backend(&self) -> Backend {
+        Backend::Sqlite
+    }
+
+    fn migrate(&self) -> Result<()> {
+        let _schema_lock = self.lock_schema()?;
+        let conn = self.conn.lock().expect("database lock poisoned");
+        schema::run_migrations(&conn)?;
+        backfill_feature_paths(&conn)?;
+        backfill_feature_keys(&conn)
+    }
+
+    fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        schema::status(&conn)
+    }
+
+    fn pending_migrations(&self) -> Result<Vec<PendingMigration>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        schema::pending(&conn)
+    }
+
+    fn plan_rollback(&self, version: &str) -> Result<Vec<PendingMigration>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        schema::plan_rollback(&conn, version)
+    }
+
+    fn rollback_migrations(&self, version: &str) -> Result<Vec<&'static str>> {
+        let _schema_lock = self.lock_schema()?;
+        let conn = self.conn.lock().expect("database lock poisoned");
+        schema::rollback(&conn, version)
+    }
+
+    // ============================================================
+    // Backup operations
+    // ============================================================
+
+    fn backup_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("partial");
+        let mut dest = Connection::open(&partial)?;
+        match &self.path {
+            // Copy from a connection of our own, a few pages at a time, so
+            // requests keep using the store while the backup runs
+            Some(source) => {
+                let source = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+                source.busy_timeout(Duration::from_secs(10))?;
+                copy_database(&source, &mut dest, BACKUP_STEP_PAGES)?;
+            }
+            // Another connection would open a different, empty database
+            None => {
+                let conn = self.conn.lock().expect("database lock poisoned");
+                copy_database(&conn, &mut dest, -1)?;
+            }
+        }
+        drop(dest);
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    fn restore_from(&self, path: &Path) -> Result<String> {
+        if !path.is_file() {
+            return Err(ManifestError::not_found("Backup file").into());
+        }
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let version = schema::applied_version(&source)
+            .ok()
+            .flatten()
+            .ok_or_else(|| {
+                ManifestError::validation(format!("{} is not a Manifest database", path.display()))
+            })?;
+        if version.as_str() > schema::latest_version() {
+            return Err(ManifestError::validation(format!(
+                "Backup comes from a newer database schema ({}) than this one ({})",
+                version,
+                schema::latest_version()
+            ))
+            .into());
+        }
+
+        {
+            let mut conn = self.conn.lock().expect("database lock poisoned");
+            copy_database(&source, &mut conn, -1)?;
+        }
+        self.migrate()?;
+        Ok(version)
+    }
+
+    // ============================================================
+    // Project operations
+    // ============================================================
+
+    fn list_projects(&self, query: &PageQuery) -> Result<Page<Project>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let request = PageRequest {
+            cursor: query.cursor.as_deref(),
+            limit: query.limit,
+            offset: None,
+        };
+        query_page(
+            &conn,
+            "SELECT id, name, description, instructions, created_at, updated_at, max_concurrent_sessions,
+                    provision_worktrees, key
+             FROM projects",
+            vec![],
+            &Order {
+                name: "name",
+                keys: &[("name", Direction::Asc), ("id", Direction::Asc)],
+            },
+            request,
+            |row| {
+                Ok(Project {
+                    id: parse_uuid(row.get::<_, String>(0)?),
+                    name: row.get(1)?,
+                    key: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+                    description: row.get(2)?,
+                    instructions: row.get(3)?,
+                    created_at: parse_datetime(row.get::<_, String>(4)?),
+                    updated_at: parse_datetime(row.get::<_, String>(5)?),
+                    max_concurrent_sessions: row.get(6)?,
+                    provision_worktrees: row.get::<_, i32>(7)? != 0,
+                })
+            },
+        )
+    }
+
+    fn get_project(&self, id: Uuid) -> Result<Option<Project>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, instructions, created_at, updated_at, max_concurrent_sessions,
+                    provision_worktrees, key
+             FROM projects WHERE id = ?",
+        )?;
+
+        let mut rows = stmt.query([id.to_string()])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Project {
+                id: parse_uuid(row.get::<_, String>(0)?),
+                name: row.get(1)?,
+                key: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+                description: row.get(2)?,
+                instructions: row.get(3)?,
+                created_at: parse_datetime(row.get::<_, String>(4)?),
+                updated_at: parse_datetime(row.get::<_, String>(5)?),
+                max_concurrent_sessions: row.get(6)?,
+                provision_worktrees: row.get::<_, i32>(7)? != 0,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn create_project(&self, input: CreateProjectInput) -> Result<Project> {
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        let key = match input.key {
+            Some(key) => {
+                let key = key.trim().to_ascii_uppercase();
+                if !is_project_key(&key) {
+                    return Err(ManifestError::validation(
+                        "Project key must be a letter followed by up to nine letters or digits",
+                    )
+                    .into());
+                }
+                if project_key_taken(&tx, &key)? {
+                    return Err(ManifestError::validation(format!(
+                        "Project key '{}' is already in use",
+                        key
+                    ))
+                    .into());
+                }
+                key
+            }
+            None => derive_project_key(&input.name, |key| project_key_taken(&tx, key))?,
+        };
+
+        tx.execute(
+            "INSERT INTO projects (id, name, description, instructions, created_at, updated_at, key)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                &input.name,
+                &input.description,
+                &input.instructions,
+                now.to_rfc3339(),
+                now.to_rfc3339(),
+                &key,
+            ),
+        )?;
+
+        // Every project starts with the default agent types registered
+        for (name, label) in DEFAULT_AGENT_TYPES {
+            tx.execute(
+                "INSERT INTO agent_types (id, project_id, name, label, created_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                (
+                    Uuid::new_v4().to_string(),
+                    id.to_string(),
+                    name,
+                    label,
+                    now.to_rfc3339(),
+                ),
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(Project {
+            id,
+            name: input.name,
+            key,
+            description: input.description,
+            instructions: input.instructions,
+            max_concurrent_sessions: 1,
+            provision_worktrees: false,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    fn update_project(&self, id: Uuid, input: UpdateProjectInput) -> Result<Option<Project>> {
+        let Some(existing) = self.get_project(id)? else {
+            return Ok(None);
+        };
+
+        if input.max_concurrent_sessions == Some(0) {
+            return Err(
+                ManifestError::validation("max_concurrent_sessions must be at least 1").into(),
+            );
+        }
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let now = Utc::now();
+        let name = input.name.unwrap_or(existing.name);
+        let description = input.description.or(existing.description);
+        let instructions = input.instructions.or(existing.instructions);
+        let max_concurrent_sessions = input
+            .max_concurrent_sessions
+            .unwrap_or(existing.max_concurrent_sessions);
+        let provision_worktrees = input
+            .provision_worktrees
+            .unwrap_or(existing.provision_worktrees);
+
+        conn.execute(
+            "UPDATE projects SET name = ?, description = ?, instructions = ?, max_concurrent_sessions = ?,
+                provision_worktrees = ?, updated_at = ?
+             WHERE id = ?",
+            (
+                &name,
+                &description,
+                &instructions,
+                max_concurrent_sessions,
+                provision_worktrees as i32,
+                now.to_rfc3339(),
+                id.to_string(),
+            ),
+        )?;
+
+        Ok(Some(Project {
+            id,
+            name,
+            key: existing.key,
+            description,
+            instructions,
+            max_concurrent_sessions,
+            provision_worktrees,
+            created_at: existing.created_at,
+            updated_at: now,
+        }))
+    }
+
+    fn delete_project(&self, id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let rows = conn.execute("DELETE FROM projects WHERE id = ?", [id.to_string()])?;
+        Ok(rows > 0)
+    }
+
+    // ============================================================
+    // Project Directory operations
+    // ============================================================
+
+    fn get_project_directories(&self, project_id: Uuid) -> Result<Vec<ProjectDirectory>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, path, git_remote, is_primary, instructions, created_at
+             FROM project_directories WHERE project_id = ? ORDER BY is_primary DESC, path",
+        )?;
+
+        let dirs = stmt
+            .query_map([project_id.to_string()], |row| {
+                Ok(ProjectDirectory {
+                    id: parse_uuid(row.get::<_, String>(0)?),
+                    project_id: parse_uuid(row.get::<_, String>(1)?),
+                    path: row.get(2)?,
+                    git_remote: row.get(3)?,
+                    is_primary: row.get::<_, i32>(4)? != 0,
+                    instructions: row.get(5)?,
+                    created_at: parse_datetime(row.get::<_, String>(6)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(dirs)
+    }
+
+    fn add_project_directory(
+        &self,
+        project_id: Uuid,
+        input: AddDirectoryInput,
+    ) -> Result<ProjectDirectory> {
+        self.get_project(project_id)?
+            .ok_or_else(|| ManifestError::not_found("Project"))?;
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        conn.execute(
+            "INSERT INTO project_directories (id, project_id, path, git_remote, is_primary, instructions, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                project_id.to_string(),
+                &input.path,
+                &input.git_remote,
+                if input.is_primary { 1 } else { 0 },
+                &input.instructions,
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        Ok(ProjectDirectory {
+            id,
+            project_id,
+            path: input.path,
+            git_remote: input.git_remote,
+            is_primary: input.is_primary,
+            instructions: input.instructions,
+            created_at: now,
+        })
+    }
+
+    fn remove_project_directory(&self, id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let rows = conn.execute(
+            "DELETE FROM project_directories WHERE id = ?",
+            [id.to_string()],
+        )?;
+        Ok(rows > 0)
+    }
+
+    fn get_project_by_directory(&self, path: &str) -> Result<Option<ProjectWithDirectories>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+
+        // Get all directories ordered by path length (longest first for best match)
+        let mut stmt = conn.prepare(
+            "SELECT project_id, path FROM project_directories ORDER BY length(path) DESC",
+        )?;
+
+        let mut rows = stmt.query([])?;
+        let mut found_project_id = None;
+
+        while let Some(row) = rows.next()? {
+            let dir_path: String = row.get(1)?;
+            // Check exact match or subdirectory match
+            if path == dir_path || path.starts_with(&format!("{}/", dir_path)) {
+                found_project_id = Some(row.get::<_, String>(0)?);
+                break;
+            }
+        }
+
+        drop(rows);
+        drop(stmt);
+        drop(conn);
+
+        match found_project_id {
+            Some(id) => self.get_project_with_directories(parse_uuid(id)),
+            None => Ok(None),
+        }
+    }
+
+    // ============================================================
+    // Agent Type operations
+    // ============================================================
+
+    fn get_agent_types(&self, project_id: Uuid) -> Result<Vec<AgentTypeConfig>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, name, label, instructions, max_concurrency, created_at
+             FROM agent_types WHERE project_id = ? ORDER BY created_at, name",
+        )?;
+
+        let agent_types = stmt
+            .query_map([project_id.to_string()], agent_type_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(agent_types)
+    }
+
+    fn get_agent_type(&self, id: Uuid) -> Result<Option<AgentTypeConfig>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, name, label, instructions, max_concurrency, created_at
+             FROM agent_types WHERE id = ?",
+        )?;
+
+        let mut rows = stmt.query([id.to_string()])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(agent_type_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn create_agent_type(
+        &self,
+        project_id: Uuid,
+        input: CreateAgentTypeInput,
+    ) -> Result<AgentTypeConfig> {
+        self.get_project(project_id)?
+            .ok_or_else(|| ManifestError::not_found("Project"))?;
+
+        if AgentType::from_str(input.name.as_str()).is_err() {
+            return Err(ManifestError::validation(format!(
+                "Invalid agent type name '{}'. Use lowercase letters, digits, '-' and '_'",
+                input.name
+            ))
+            .into());
+        }
+        if input.max_concurrency == Some(0) {
+            return Err(ManifestError::validation("max_concurrency must be at least 1").into());
+        }
+        if self
+            .get_agent_types(project_id)?
+            .iter()
+            .any(|t| t.name == input.name)
+        {
+            return Err(ManifestError::invalid_state(format!(
+                "Agent type '{}' is already registered for this project",
+                input.name
+            ))
+            .into());
+        }
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        conn.execute(
+            "INSERT INTO agent_types (id, project_id, name, label, instructions, max_concurrency, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                project_id.to_string(),
+                input.name.as_str(),
+                &input.label,
+                &input.instructions,
+                input.max_concurrency,
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        Ok(AgentTypeConfig {
+            id,
+            project_id,
+            name: input.name,
+            label: input.label,
+            instructions: input.instructions,
+            max_concurrency: input.max_concurrency,
+            created_at: now,
+        })
+    }
+
+    fn update_agent_type(
+        &self,
+        id: Uuid,
+        input: UpdateAgentTypeInput,
+    ) -> Result<Option<AgentTypeConfig>> {
+        let Some(existing) = self.get_agent_type(id)? else {
+            return Ok(None);
+        };
+
+        if input.max_concurrency == Some(0) {
+            return Err(ManifestError::validation("max_concurrency must be at least 1").into());
+        }
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let label = input.label.unwrap_or(existing.label);
+        let instructions = input.instructions.or(existing.instructions);
+        let max_concurrency = input.max_concurrency.or(existing.max_concurrency);
+
+        conn.execute(
+            "UPDATE agent_types SET label = ?, instructions = ?, max_concurrency = ? WHERE id = ?",
+            (&label, &instructions, max_concurrency, id.to_string()),
+        )?;
+
+        Ok(Some(AgentTypeConfig {
+            label,
+            instructions,
+            max_concurrency,
+            ..existing
+        }))
+    }
+
+    fn delete_agent_type(&self, id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let rows = conn.execute("DELETE FROM agent_types WHERE id = ?", [id.to_string()])?;
+        Ok(rows > 0)
+    }
+
+    // ============================================================
+    // Tag operations
+    // ============================================================
+
+    fn get_tags(&self, project_id: Uuid) -> Result<Vec<Tag>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, name, color, created_at
+             FROM tags WHERE project_id = ? ORDER BY name",
+        )?;
+
+        let tags = stmt
+            .query_map([project_id.to_string()], tag_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    fn get_tag(&self, id: Uuid) -> Result<Option<Tag>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let tag = conn
+            .query_row(
+                "SELECT id, project_id, name, color, created_at FROM tags WHERE id = ?",
+                [id.to_string()],
+                tag_from_row,
+            )
+            .optional()?;
+        Ok(tag)
+    }
+
+    fn create_tag(&self, project_id: Uuid, input: CreateTagInput) -> Result<Tag> {
+        self.get_project(project_id)?
+            .ok_or_else(|| ManifestError::not_found("Project"))?;
+
+        let name = validate_tag_name(&input.name)?.to_string();
+        if let Some(color) = &input.color {
+            validate_tag_color(color)?;
+        }
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        if tag_id_by_name(&conn, project_id, &name)?.is_some() {
+            return Err(ManifestError::invalid_state(format!(
+                "Tag '{}' already exists in this project",
+                name
+            ))
+            .into());
+        }
+
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        conn.execute(
+            "INSERT INTO tags (id, project_id, name, color, created_at) VALUES (?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                project_id.to_string(),
+                &name,
+                &input.color,
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        Ok(Tag {
+            id,
+            project_id,
+            name,
+            color: input.color,
+            created_at: now,
+        })
+    }
+
+    fn update_tag(&self, id: Uuid, input: UpdateTagInput) -> Result<Option<Tag>> {
+        let Some(existing) = self.get_tag(id)? else {
+            return Ok(None);
+        };
+
+        let name = match &input.name {
+            Some(name) => validate_tag_name(name)?.to_string(),
+            None => existing.name.clone(),
+        };
+        if let Some(color) = &input.color {
+            validate_tag_color(color)?;
+        }
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        if tag_id_by_name(&conn, existing.project_id, &name)?
+            .is_some_and(|other| other != id.to_string())
+        {
+            return Err(ManifestError::invalid_state(format!(
+                "Tag '{}' already exists in this project",
+                name
+            ))
+            .into());
+        }
+
+        let color = input.color.or(existing.color.clone());
+        conn.execute(
+            "UPDATE tags SET name = ?, color = ? WHERE id = ?",
+            (&name, &color, id.to_string()),
+        )?;
+
+        Ok(Some(Tag {
+            name,
+            color,
+            ..existing
+        }))
+    }
+
+    fn delete_tag(&self, id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let rows = conn.execute("DELETE FROM tags WHERE id = ?", [id.to_string()])?;
+        Ok(rows > 0)
+    }
+
+    // ============================================================
+    // Feature operations
+    // ============================================================
+
+    fn list_features(
+        &self,
+        project_id: Option<Uuid>,
+        query: &ListFeaturesQuery,
+    ) -> Result<Page<Feature>> {
+        let order = feature_order(query.sort.as_deref())?;
+        let states = parse_list_param(query.state.as_deref())
+            .into_iter()
+            .map(|s| {
+                FeatureState::from_str(&s).map_err(|_| {
+                    ManifestError::validation(format!(
+                        "Unknown state '{}'. Expected proposed, specified, implemented or deprecated",
+                        s
+                    ))
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+
+        let mut sql = "SELECT id, project_id, parent_id, title, details, desired_details, state, priority, created_at, updated_at,
+                    slug, path, key
+             FROM features WHERE (?1 IS NULL OR project_id = ?1)"
+            .to_string();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> =
+            vec![Box::new(project_id.map(|p| p.to_string()))];
+        for tag in parse_list_param(query.tag.as_deref()) {
+            params.push(Box::new(tag));
+            sql.push_str(&has_tag(params.len()));
+        }
+        if !states.is_empty() {
+            let placeholders: Vec<String> = states
+                .iter()
+                .map(|state| {
+                    params.push(Box::new(state.as_str()));
+                    format!("?{}", params.len())
+                })
+                .collect();
+            sql.push_str(&format!(" AND state IN ({})", placeholders.join(", ")));
+        }
+        if let Some(parent_id) = query.parent_id {
+            params.push(Box::new(parent_id.to_string()));
+            sql.push_str(&format!(" AND parent_id = ?{}", params.len()));
+        }
+        if let Some(depth) = query.depth {
+            // A feature's depth is the number of slashes in its path
+            params.push(Box::new(depth));
+            sql.push_str(&format!(
+                " AND length(path) - length(replace(path, '/', '')) <= ?{}",
+                params.len()
+            ));
+        }
+        if let Some(leaf) = query.leaf {
+            sql.push_str(if leaf {
+                " AND NOT EXISTS"
+            } else {
+                " AND EXISTS"
+            });
+            sql.push_str(" (SELECT 1 FROM features c WHERE c.parent_id = features.id)");
+        }
+        if let Some(pending) = query.has_pending_changes {
+            sql.push_str(if pending {
+                " AND desired_details IS NOT NULL"
+            } else {
+                " AND desired_details IS NULL"
+            });
+        }
+        if let Some(active) = query.has_active_session {
+            sql.push_str(if active {
+                " AND EXISTS"
+            } else {
+                " AND NOT EXISTS"
+            });
+            sql.push_str(
+                " (SELECT 1 FROM sessions s WHERE s.feature_id = features.id AND s.status = 'active')",
+            );
+        }
+        if let Some(since) = query.updated_since {
+            params.push(Box::new(since.to_rfc3339()));
+            sql.push_str(&format!(
+                " AND julianday(updated_at) >= julianday(?{})",
+                params.len()
+            ));
+        }
+
+        let request = PageRequest {
+            cursor: query.cursor.as_deref(),
+            limit: query.limit,
+            offset: query.offset,
+        };
+        let mut page = query_page(&conn, &sql, params, order, request, feature_from_row)?;
+        attach_tags(&conn, &mut page.items)?;
+
+        Ok(page)
+    }
+
+    fn resolve_feature_ref(&self, reference: &FeatureRef) -> Result<Option<Uuid>> {
+        let (project_id, path) = match reference {
+            FeatureRef::Id(id) => return Ok(Some(*id)),
+            FeatureRef::Key(key) => {
+                let conn = self.conn.lock().expect("database lock poisoned");
+                let id: Option<String> = conn
+                    .query_row("SELECT id FROM features WHERE key = ?", [key], |row| {
+                        row.get(0)
+                    })
+                    .optional()?;
+                return Ok(id.map(parse_uuid));
+            }
+            FeatureRef::Path { project_id, path } => (project_id.map(|p| p.to_string()), path),
+        };
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        for sql in [
+            "SELECT id, project_id FROM features WHERE path = ?1 AND (?2 IS NULL OR project_id = ?2)",
+            "SELECT feature_id, project_id FROM feature_redirects WHERE path = ?1 AND (?2 IS NULL OR project_id = ?2)",
+        ] {
+            let mut stmt = conn.prepare(sql)?;
+            let matches = stmt
+                .query_map((path, &project_id), |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            match matches.as_slice() {
+                [] => continue,
+                [(id, _)] => return Ok(Some(parse_uuid(id.clone()))),
+                [(_, first), ..] => {
+                    return Err(ManifestError::validation(format!(
+                        "Feature path '{}' exists in several projects. Prefix it with the project id, e.g. {}/{}",
+                        path, first, path
+                    ))
+                    .into())
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_feature(&self, id: Uuid) -> Result<Option<Feature>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, parent_id, title, details, desired_details, state, priority, created_at, updated_at,
+                    slug, path, key
+             FROM features WHERE id = ?",
+        )?;
+
+        let mut rows = stmt.query([id.to_string()])?;
+        if let Some(row) = rows.next()? {
+            let mut feature = feature_from_row(row)?;
+            feature.tags = feature_tag_names(&conn, id)?;
+            Ok(Some(feature))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn create_feature(&self, project_id: Uuid, input: CreateFeatureInput) -> Result<Feature> {
+        // Verify project exists
+        self.get_project(project_id)?
+            .ok_or_else(|| ManifestError::not_found("Project"))?;
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        let id = input.id.unwrap_or_else(Uuid::new_v4);
+        let now = Utc::now();
+        let state = input.state.unwrap_or(FeatureState::Proposed);
+        let priority = input.priority.unwrap_or(0);
+        let (slug, path) = assign_path(&tx, project_id, input.parent_id, &input.title, id)?;
+        let key = allocate_feature_key(&tx, project_id)?;
+
+        tx.execute(
+            "INSERT INTO features (id, project_id, parent_id, title, details, state, priority, created_at, updated_at, slug, path, key)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                project_id.to_string(),
+                input.parent_id.map(|u| u.to_string()),
+                &input.title,
+                &input.details,
+                state.as_str(),
+                priority,
+                now.to_rfc3339(),
+                now.to_rfc3339(),
+                &slug,
+                &path,
+                &key,
+            ),
+        )?;
+        let tags = set_feature_tags(&tx, project_id, id, &input.tags)?;
+        tx.commit()?;
+
+        Ok(Feature {
+            id,
+            project_id,
+            parent_id: input.parent_id,
+            key,
+            title: input.title,
+            slug,
+            path,
+            details: input.details,
+            desired_details: None,
+            state,
+            priority,
+            tags,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    fn create_features_bulk(
+        &self,
+        project_id: Uuid,
+        inputs: Vec<CreateFeatureInput>,
+    ) -> Result<Vec<Feature>> {
+        // Verify project exists
+        self.get_project(project_id)?
+            .ok_or_else(|| ManifestError::not_found("Project"))?;
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+
+        let mut features = Vec::with_capacity(inputs.len());
+
+        for input in inputs {
+            let id = input.id.unwrap_or_else(Uuid::new_v4);
+            let state = input.state.unwrap_or(FeatureState::Proposed);
+            let priority = input.priority.unwrap_or(0);
+            let (slug, path) = assign_path(&tx, project_id, input.parent_id, &input.title, id)?;
+            let key = allocate_feature_key(&tx, project_id)?;
+
+            tx.execute(
+                "INSERT INTO features (id, project_id, parent_id, title, details, state, priority, created_at, updated_at, slug, path, key)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                (
+                    id.to_string(),
+                    project_id.to_string(),
+                    input.parent_id.map(|u| u.to_string()),
+                    &input.title,
+                    &input.details,
+                    state.as_str(),
+                    priority,
+                    now.to_rfc3339(),
+                    now.to_rfc3339(),
+                    &slug,
+                    &path,
+                    &key,
+                ),
+            )?;
+            let tags = set_feature_tags(&tx, project_id, id, &input.tags)?;
+
+            features.push(Feature {
+                id,
+                project_id,
+                parent_id: input.parent_id,
+                key,
+                title: input.title,
+                slug,
+                path,
+                details: input.details,
+                desired_details: None,
+                state,
+                priority,
+                tags,
+                created_at: now,
+                updated_at: now,
+            });
+        }
+
+        tx.commit()?;
+        Ok(features)
+    }
+
+    fn update_feature(&self, id: Uuid, input: UpdateFeatureInput) -> Result<Option<Feature>> {
+        let Some(existing) = self.get_feature(id)? else {
+            return Ok(None);
+        };
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+        let renamed = input.title.as_ref().is_some_and(|t| *t != existing.title);
+        let title = input.title.unwrap_or(existing.title);
+        let details = input.details.or(existing.details);
+        let desired_details = input.desired_details.or(existing.desired_details);
+        let state = input.state.unwrap_or(existing.state);
+        let parent_id = input.parent_id.or(existing.parent_id);
+        let priority = input.priority.unwrap_or(existing.priority);
+
+        let (slug, path) = if renamed || parent_id != existing.parent_id {
+            assign_path(&tx, existing.project_id, parent_id, &title, id)?
+        } else {
+            (existing.slug, existing.path.clone())
+        };
+        if path != existing.path {
+            move_subtree(&tx, existing.project_id, id, &existing.path, &path)?;
+        }
+
+        tx.execute(
+            "UPDATE features SET parent_id = ?, title = ?, details = ?, desired_details = ?, state = ?, priority = ?, updated_at = ?,
+                    slug = ?, path = ?
+             WHERE id = ?",
+            (
+                parent_id.map(|u| u.to_string()),
+                &title,
+                &details,
+                &desired_details,
+                state.as_str(),
+                priority,
+                now.to_rfc3339(),
+                &slug,
+                &path,
+                id.to_string(),
+            ),
+        )?;
+        let tags = match input.tags {
+            Some(tags) => set_feature_tags(&tx, existing.project_id, id, &tags)?,
+            None => existing.tags,
+        };
+        tx.commit()?;
+
+        Ok(Some(Feature {
+            id,
+            project_id: existing.project_id,
+            parent_id,
+            key: existing.key,
+            title,
+            slug,
+            path,
+            details,
+            desired_details,
+            state,
+            priority,
+            tags,
+            created_at: existing.created_at,
+            updated_at: now,
+        }))
+    }
+
+    fn delete_feature(&self, id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let rows = conn.execute("DELETE FROM features WHERE id = ?", [id.to_string()])?;
+        Ok(rows > 0)
+    }
+
+    fn get_root_features(&self, project_id: Uuid) -> Result<Vec<Feature>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, parent_id, title, details, desired_details, state, priority, created_at, updated_at,
+                    slug, path, key
+             FROM features WHERE project_id = ? AND parent_id IS NULL ORDER BY priority, title",
+        )?;
+
+        let mut features = stmt
+            .query_map([project_id.to_string()], feature_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_tags(&conn, &mut features)?;
+
+        Ok(features)
+    }
+
+    fn get_children(&self, parent_id: Uuid) -> Result<Vec<Feature>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, parent_id, title, details, desired_details, state, priority, created_at, updated_at,
+                    slug, path, key
+             FROM features WHERE parent_id = ? ORDER BY priority, title",
+        )?;
+
+        let mut features = stmt
+            .query_map([parent_id.to_string()], feature_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_tags(&conn, &mut features)?;
+
+        Ok(features)
+    }
+
+    fn is_leaf(&self, feature_id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let count: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM features WHERE parent_id = ?",
+            [feature_id.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(count == 0)
+    }
+
+    fn search_features(
+        &self,
+        query: &str,
+        project_id: Option<Uuid>,
+        tags: &[String],
+        page: &PageQuery,
+    ) -> Result<Page<FeatureSummary>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+
+        // Use LIKE for case-insensitive search
+        // Ranking: title matches get higher priority than details matches
+        let search_pattern = format!("%{}%", query);
+        let key = query.trim().to_ascii_uppercase();
+
+        let mut sql = "SELECT id, project_id, parent_id, title, state, priority, path, key
+             FROM features
+             WHERE (?1 IS NULL OR project_id = ?1) AND (key = ?3 OR title LIKE ?2 OR details LIKE ?2)"
+            .to_string();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![
+            Box::new(project_id.map(|p| p.to_string())),
+            Box::new(search_pattern),
+            Box::new(key),
+        ];
+        for tag in tags {
+            params.push(Box::new(tag.clone()));
+            sql.push_str(&has_tag(params.len()));
+        }
+        let request = PageRequest {
+            cursor: page.cursor.as_deref(),
+            limit: Some(page.limit.unwrap_or(10)),
+            offset: None,
+        };
+
+        let mut page = query_page(
+            &conn,
+            &sql,
+            params,
+            &Order {
+                name: "relevance",
+                keys: &[
+                    (
+                        "CASE WHEN key = ?3 THEN 0 WHEN title LIKE ?2 THEN 1 ELSE 2 END",
+                        Direction::Asc,
+                    ),
+                    ("priority", Direction::Asc),
+                    ("title", Direction::Asc),
+                    ("id", Direction::Asc),
+                ],
+            },
+            request,
+            |row| {
+                Ok(FeatureSummary {
+                    id: parse_uuid(row.get::<_, String>(0)?),
+                    project_id: parse_uuid(row.get::<_, String>(1)?),
+                    parent_id: row.get::<_, Option<String>>(2)?.map(parse_uuid),
+                    key: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+                    title: row.get(3)?,
+                    path: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                    state: FeatureState::from_str(&row.get::<_, String>(4)?)
+                        .unwrap_or(FeatureState::Proposed),
+                    priority: row.get(5)?,
+                    tags: vec![],
+                })
+            },
+        )?;
+        for feature in &mut page.items {
+            feature.tags = feature_tag_names(&conn, feature.id)?;
+        }
+
+        Ok(page)
+    }
+
+    // ============================================================
+    // Code Link operations
+    // ============================================================
+
+    fn get_code_links(&self, feature_id: Uuid) -> Result<Vec<FeatureCodeLink>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, feature_id, directory_id, path, line_start, line_end, symbol, created_at
+             FROM feature_code_links WHERE feature_id = ? ORDER BY path, line_start",
+        )?;
+
+        let links = stmt
+            .query_map([feature_id.to_string()], code_link_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(links)
+    }
+
+    fn get_code_link(&self, id: Uuid) -> Result<Option<FeatureCodeLink>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, feature_id, directory_id, path, line_start, line_end, symbol, created_at
+             FROM feature_code_links WHERE id = ?",
+        )?;
+
+        let mut rows = stmt.query([id.to_string()])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(code_link_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn create_code_link(
+        &self,
+        feature_id: Uuid,
+        input: CreateCodeLinkInput,
+    ) -> Result<FeatureCodeLink> {
+        let feature = self
+            .get_feature(feature_id)?
+            .ok_or_else(|| ManifestError::not_found("Feature"))?;
+        let directories = self.get_project_directories(feature.project_id)?;
+
+        let directory = match input.directory_id {
+            Some(id) => directories.iter().find(|d| d.id == id).ok_or_else(|| {
+                ManifestError::validation("Directory does not belong to the feature's project")
+            })?,
+            None => match directories.iter().find(|d| d.is_primary) {
+                Some(d) => d,
+                None if directories.len() == 1 => &directories[0],
+                None => {
+                    return Err(ManifestError::validation(
+                        "Project has no primary directory; pass directory_id",
+                    )
+                    .into())
+                }
+            },
+        };
+
+        let path = normalize_code_path(&input.path);
+        validate_code_link(&path, input.line_start, input.line_end)?;
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        conn.execute(
+            "INSERT INTO feature_code_links (id, feature_id, directory_id, path, line_start, line_end, symbol, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                feature_id.to_string(),
+                directory.id.to_string(),
+                &path,
+                input.line_start,
+                input.line_end,
+                &input.symbol,
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        Ok(FeatureCodeLink {
+            id,
+            feature_id,
+            directory_id: directory.id,
+            path,
+            line_start: input.line_start,
+            line_end: input.line_end,
+            symbol: input.symbol,
+            created_at: now,
+        })
+    }
+
+    fn update_code_link(
+        &self,
+        id: Uuid,
+        input: UpdateCodeLinkInput,
+    ) -> Result<Option<FeatureCodeLink>> {
+        let Some(existing) = self.get_code_link(id)? else {
+            return Ok(None);
+        };
+
+        let path = input
+            .path
+            .map(|p| normalize_code_path(&p))
+            .unwrap_or(existing.path);
+        let line_start = input.line_start.unwrap_or(existing.line_start);
+        let line_end = input.line_end.unwrap_or(existing.line_end);
+        let symbol = input.symbol.unwrap_or(existing.symbol);
+        validate_code_link(&path, line_start, line_end)?;
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        conn.execute(
+            "UPDATE feature_code_links SET path = ?, line_start = ?, line_end = ?, symbol = ? WHERE id = ?",
+            (&path, line_start, line_end, &symbol, id.to_string()),
+        )?;
+
+        Ok(Some(FeatureCodeLink {
+            path,
+            line_start,
+            line_end,
+            symbol,
+            ..existing
+        }))
+    }
+
+    fn delete_code_link(&self, id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let rows = conn.execute(
+            "DELETE FROM feature_code_links WHERE id = ?",
+            [id.to_string()],
+        )?;
+        Ok(rows > 0)
+    }
+
+    fn find_features_by_path(&self, project_id: Uuid, path: &str) -> Result<Vec<FeatureCodeMatch>> {
+        // (directory id, path relative to it); None means any directory
+        let candidates: Vec<(Option<Uuid>, String)> = if Path::new(path).is_absolute() {
+            self.get_project_directories(project_id)?
+                .into_iter()
+                .filter_map(|d| {
+                    let relative = Path::new(path).strip_prefix(&d.path).ok()?;
+                    Some((Some(d.id), relative.to_string_lossy().into_owned()))
+                })
+                .collect()
+        } else {
+            vec![(None, path.to_string())]
+        };
+
+        let links: Vec<FeatureCodeLink> = {
+            let conn = self.conn.lock().expect("database lock poisoned");
+            let mut stmt = conn.prepare(
+                "SELECT l.id, l.feature_id, l.directory_id, l.path, l.line_start, l.line_end, l.symbol, l.created_at
+                 FROM feature_code_links l JOIN features f ON f.id = l.feature_id
+                 WHERE f.project_id = ? ORDER BY l.path, l.line_start",
+            )?;
+            let links = stmt
+                .query_map([project_id.to_string()], code_link_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            links
+        };
+
+        let mut matches: Vec<FeatureCodeMatch> = Vec::new();
+        for link in links {
+            let covered = candidates.iter().any(|(directory_id, relative)| {
+                directory_id.is_none_or(|id| id == link.directory_id) && link.matches(relative)
+            });
+            if !covered {
+                continue;
+            }
+            if let Some(m) = matches.iter_mut().find(|m| m.feature.id == link.feature_id) {
+                m.links.push(link);
+            } else if let Some(feature) = self.get_feature(link.feature_id)? {
+                matches.push(FeatureCodeMatch {
+                    feature,
+                    links: vec![link],
+                });
+            }
+        }
+
+        Ok(matches)
+    }
+
+    // ============================================================
+    // Feature Relation operations
+    // ============================================================
+
+    fn get_relations(&self, feature_id: Uuid) -> Result<Vec<FeatureRelation>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, from_feature_id, to_feature_id, kind, created_at
+             FROM feature_relations WHERE from_feature_id = ?1 OR to_feature_id = ?1
+             ORDER BY created_at",
+        )?;
+
+        let relations = stmt
+            .query_map([feature_id.to_string()], relation_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(relations)
+    }
+
+    fn create_relation(
+        &self,
+        feature_id: Uuid,
+        input: CreateRelationInput,
+    ) -> Result<FeatureRelation> {
+        let from = self
+            .get_feature(feature_id)?
+            .ok_or_else(|| ManifestError::not_found("Feature"))?;
+        let to = self
+            .get_feature(input.to_feature_id)?
+            .ok_or_else(|| ManifestError::not_found("Feature"))?;
+        if from.id == to.id {
+            return Err(ManifestError::validation("A feature can't be related to itself").into());
+        }
+        if from.project_id != to.project_id {
+            return Err(ManifestError::validation(
+                "Related features must belong to the same project",
+            )
+            .into());
+        }
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM feature_relations
+                           WHERE from_feature_id = ? AND to_feature_id = ? AND kind = ?)",
+            (from.id.to_string(), to.id.to_string(), input.kind.as_str()),
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Err(ManifestError::invalid_state(format!(
+                "{} already {} {}",
+                from.key,
+                input.kind.as_str(),
+                to.key
+            ))
+            .into());
+        }
+        if let Some((dependent, dependency)) = input.kind.dependency(from.id, to.id) {
+            if dependency_reaches(&conn, dependency, dependent)? {
+                return Err(ManifestError::validation(format!(
+                    "{} {} {} would create a dependency cycle",
+                    from.key,
+                    input.kind.as_str(),
+                    to.key
+                ))
+                .into());
+            }
+        }
+
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        conn.execute(
+            "INSERT INTO feature_relations (id, from_feature_id, to_feature_id, kind, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                from.id.to_string(),
+                to.id.to_string(),
+                input.kind.as_str(),
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        Ok(FeatureRelation {
+            id,
+            from_feature_id: from.id,
+            to_feature_id: to.id,
+            kind: input.kind,
+            created_at: now,
+        })
+    }
+
+    fn delete_relation(&self, id: Uuid) -> Result<bool> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let rows = conn.execute(
+            "DELETE FROM feature_relations WHERE id = ?",
+            [id.to_string()],
+        )?;
+        Ok(rows > 0)
+    }
+
+    fn get_dependencies(
+        &self,
+        feature_id: Uuid,
+        direction: DependencyDirection,
+    ) -> Result<Vec<Feature>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "{}
+             SELECT id, project_id, parent_id, title, details, desired_details, state, priority, created_at, updated_at,
+                    slug, path, key
+             FROM features WHERE id IN (SELECT id FROM reach) ORDER BY priority, title",
+            dependency_closure(direction)
+        ))?;
+
+        let mut features = stmt
+            .query_map([feature_id.to_string()], feature_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_tags(&conn, &mut features)?;
+
+        Ok(features)
+    }
+
+    fn get_next_features(&self, project_id: Uuid) -> Result<Vec<Feature>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(&format!(
+            "WITH edges(dependent, dependency) AS ({})
+             SELECT id, project_id, parent_id, title, details, desired_details, state, priority, created_at, updated_at,
+                    slug, path, key
+             FROM features f
+             WHERE project_id = ? AND state = 'specified'
+               AND NOT EXISTS (SELECT 1 FROM features c WHERE c.parent_id = f.id)
+               AND NOT EXISTS (
+                   SELECT 1 FROM edges e JOIN features d ON d.id = e.dependency
+                   WHERE e.dependent = f.id AND d.state NOT IN ('implemented', 'deprecated'))
+             ORDER BY priority, title",
+            DEPENDENCY_EDGES
+        ))?;
+
+        let mut features = stmt
+            .query_map([project_id.to_string()], feature_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_tags(&conn, &mut features)?;
+
+        Ok(features)
+    }
+
+    fn get_feature_graph(&self, project_id: Uuid) -> Result<FeatureGraph> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.from_feature_id, r.to_feature_id, r.kind, r.created_at
+             FROM feature_relations r JOIN features f ON f.id = r.from_feature_id
+             WHERE f.project_id = ? ORDER BY r.created_at",
+        )?;
+        let relations = stmt
+            .query_map([project_id.to_string()], relation_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, parent_id, title, details, desired_details, state, priority, created_at, updated_at,
+                    slug, path, key
+             FROM features
+             WHERE id IN (SELECT from_feature_id FROM feature_relations
+                          UNION SELECT to_feature_id FROM feature_relations)
+               AND project_id = ?
+             ORDER BY path",
+        )?;
+        let mut features = stmt
+            .query_map([project_id.to_string()], feature_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        attach_tags(&conn, &mut features)?;
+
+        Ok(FeatureGraph {
+            features: features.into_iter().map(Into::into).collect(),
+            relations,
+        })
+    }
+
+    // ============================================================
+    // Acceptance Criterion operations
+    // ============================================================
+
+    fn get_criteria(&self, feature_id: Uuid) -> Result<Vec<AcceptanceCriterion>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, feature_id, position, text, status, test_name, created_at, updated_at
+             FROM acceptance_criteria WHERE feature_id = ? ORDER BY position",
+        )?;
+
+        let criteria = stmt
+            .query_map([feature_id.to_string()], criterion_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(criteria)
+    }
+
+    fn get_criterion(&self, id: Uuid) -> Result<Option<AcceptanceCriterion>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let criterion = conn
+            .query_row(
+                "SELECT id, feature_id, position, text, status, test_name, created_at, updated_at
+                 FROM acceptance_criteria WHERE id = ?",
+                [id.to_string()],
+                criterion_from_row,
+            )
+            .optional()?;
+
+        Ok(criterion)
+    }
+
+    fn create_criterion(
+        &self,
+        feature_id: Uuid,
+        input: CreateCriterionInput,
+    ) -> Result<AcceptanceCriterion> {
+        self.get_feature(feature_id)?
+            .ok_or_else(|| ManifestError::not_found("Feature"))?;
+        let text = input.text.trim().to_string();
+        if text.is_empty() {
+            return Err(ManifestError::validation("Criterion text cannot be empty").into());
+        }
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        let count: u32 = tx.query_row(
+            "SELECT COUNT(*) FROM acceptance_criteria WHERE feature_id = ?",
+            [feature_id.to_string()],
+            |row| row.get(0),
+        )?;
+        let position = input.position.map_or(count, |p| p.min(count));
+        tx.execute(
+            "UPDATE acceptance_criteria SET position = position + 1
+             WHERE feature_id = ? AND position >= ?",
+            (feature_id.to_string(), position),
+        )?;
+
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        let status = input.status.unwrap_or_default();
+        tx.execute(
+            "INSERT INTO acceptance_criteria (id, feature_id, position, text, status, test_name, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                feature_id.to_string(),
+                position,
+                &text,
+                status.as_str(),
+                &input.test_name,
+                now.to_rfc3339(),
+                now.to_rfc3339(),
+            ),
+        )?;
+        tx.commit()?;
+
+        Ok(AcceptanceCriterion {
+            id,
+            feature_id,
+            position,
+            text,
+            status,
+            test_name: input.test_name,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    fn update_criterion(
+        &self,
+        id: Uuid,
+        input: UpdateCriterionInput,
+    ) -> Result<Option<AcceptanceCriterion>> {
+        let Some(existing) = self.get_criterion(id)? else {
+            return Ok(None);
+        };
+        let text = match input.text {
+            Some(text) if text.trim().is_empty() => {
+                return Err(ManifestError::validation("Criterion text cannot be empty").into())
+            }
+            Some(text) => text.trim().to_string(),
+            None => existing.text,
+        };
+        let status = input.status.unwrap_or(existing.status);
+        let test_name = input.test_name.or(existing.test_name);
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        let mut position = existing.position;
+        if let Some(target) = input.position {
+            let last: u32 = tx.query_row(
+                "SELECT COUNT(*) - 1 FROM acceptance_criteria WHERE feature_id = ?",
+                [existing.feature_id.to_string()],
+                |row| row.get(0),
+            )?;
+            let target = target.min(last);
+            // Close the gap left behind and open one at the target
+            if target > position {
+                tx.execute(
+                    "UPDATE acceptance_criteria SET position = position - 1
+                     WHERE feature_id = ? AND position > ? AND position <= ?",
+                    (existing.feature_id.to_string(), position, target),
+                )?;
+            } else if target < position {
+                tx.execute(
+                    "UPDATE acceptance_criteria SET position = position + 1
+                     WHERE feature_id = ? AND position >= ? AND position < ?",
+                    (existing.feature_id.to_string(), target, position),
+                )?;
+            }
+            position = target;
+        }
+
+        let now = Utc::now();
+        tx.execute(
+            "UPDATE acceptance_criteria SET position = ?, text = ?, status = ?, test_name = ?, updated_at = ?
+             WHERE id = ?",
+            (
+                position,
+                &text,
+                status.as_str(),
+                &test_name,
+                now.to_rfc3339(),
+                id.to_string(),
+            ),
+        )?;
+        tx.commit()?;
+
+        Ok(Some(AcceptanceCriterion {
+            position,
+            text,
+            status,
+            test_name,
+            updated_at: now,
+            ..existing
+        }))
+    }
+
+    fn delete_criterion(&self, id: Uuid) -> Result<bool> {
+        let Some(existing) = self.get_criterion(id)? else {
+            return Ok(false);
+        };
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM acceptance_criteria WHERE id = ?",
+            [id.to_string()],
+        )?;
+        tx.execute(
+            "UPDATE acceptance_criteria SET position = position - 1
+             WHERE feature_id = ? AND position > ?",
+            (existing.feature_id.to_string(), existing.position),
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    // ============================================================
+    // Test Result operations
+    // ============================================================
+
+    fn record_test_results(
+        &self,
+        project_id: Uuid,
+        cases: Vec<TestCaseResult>,
+    ) -> Result<Option<TestReportResult>> {
+        if self.get_project(project_id)?.is_none() {
+            return Ok(None);
+        }
+        let TestCaseMatches {
+            rows,
+            touched,
+            matched,
+            unmatched,
+        } = match_test_cases(self, project_id, &cases)?;
+
+        {
+            let mut conn = self.conn.lock().expect("database lock poisoned");
+            let tx = conn.transaction()?;
+            let now = Utc::now();
+            for feature_id in &touched {
+                tx.execute(
+                    "DELETE FROM test_results WHERE feature_id = ?",
+                    [feature_id.to_string()],
+                )?;
+            }
+            for (feature_id, criterion_id, case) in &rows {
+                tx.execute(
+                    "INSERT OR REPLACE INTO test_results (id, feature_id, criterion_id, test_name, outcome, message, reported_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?)",
+                    (
+                        Uuid::new_v4().to_string(),
+                        feature_id.to_string(),
+                        criterion_id.map(|id| id.to_string()),
+                        &case.name,
+                        case.outcome.as_str(),
+                        &case.message,
+                        now.to_rfc3339(),
+                    ),
+                )?;
+                let status = match case.outcome {
+                    TestOutcome::Passed => CriterionStatus::Passing,
+                    TestOutcome::Failed => CriterionStatus::Failing,
+                    TestOutcome::Skipped => continue,
+                };
+                if let Some(criterion_id) = criterion_id {
+                    tx.execute(
+                        "UPDATE acceptance_criteria SET status = ?, updated_at = ? WHERE id = ?",
+                        (status.as_str(), now.to_rfc3339(), criterion_id.to_string()),
+                    )?;
+                }
+            }
+            tx.commit()?;
+        }
+
+        let features = self
+            .get_test_summaries(project_id)?
+            .into_iter()
+            .filter(|s| touched.contains(&s.feature_id))
+            .collect();
+        Ok(Some(TestReportResult {
+            received: cases.len() as u32,
+            matched,
+            unmatched,
+            features,
+        }))
+    }
+
+    fn get_test_summaries(&self, project_id: Uuid) -> Result<Vec<FeatureTestSummary>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT f.id, f.key, f.title, f.state,
+                    SUM(t.outcome = 'passed'), SUM(t.outcome = 'failed'), SUM(t.outcome = 'skipped'),
+                    MAX(t.reported_at)
+             FROM test_results t JOIN features f ON f.id = t.feature_id
+             WHERE f.project_id = ?
+             GROUP BY f.id ORDER BY f.path",
+        )?;
+
+        let summaries = stmt
+            .query_map([project_id.to_string()], |row| {
+                Ok(FeatureTestSummary {
+                    feature_id: parse_uuid(row.get::<_, String>(0)?),
+                    key: row.get(1)?,
+                    title: row.get(2)?,
+                    state: FeatureState::from_str(&row.get::<_, String>(3)?)
+                        .unwrap_or(FeatureState::Proposed),
+                    passed: row.get(4)?,
+                    failed: row.get(5)?,
+                    skipped: row.get(6)?,
+                    reported_at: parse_datetime(row.get::<_, String>(7)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(summaries)
+    }
+
+    fn get_test_results(&self, feature_id: Uuid) -> Result<Vec<TestResult>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, feature_id, criterion_id, test_name, outcome, message, reported_at
+             FROM test_results WHERE feature_id = ?
+             ORDER BY outcome != 'failed', test_name",
+        )?;
+
+        let results = stmt
+            .query_map([feature_id.to_string()], |row| {
+                Ok(TestResult {
+                    id: parse_uuid(row.get::<_, String>(0)?),
+                    feature_id: parse_uuid(row.get::<_, String>(1)?),
+                    criterion_id: row.get::<_, Option<String>>(2)?.map(parse_uuid),
+                    test_name: row.get(3)?,
+                    outcome: TestOutcome::from_str(&row.get::<_, String>(4)?)
+                        .unwrap_or(TestOutcome::Skipped),
+                    message: row.get(5)?,
+                    reported_at: parse_datetime(row.get::<_, String>(6)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
+    // ============================================================
+    // Snapshot operations
+    // ============================================================
+
+    fn export_snapshot(&self, project_id: Uuid) -> Result<Option<ProjectSnapshot>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM projects WHERE id = ?",
+                [project_id.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let mut tables = BTreeMap::new();
+        for (table, filter) in SNAPSHOT_TABLES {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT * FROM {} WHERE {} ORDER BY rowid",
+                table, filter
+            ))?;
+            let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+            let rows = stmt
+                .query_map([project_id.to_string()], |row| {
+                    let mut values = serde_json::Map::new();
+                    for (i, column) in columns.iter().enumerate() {
+                        values.insert(column.clone(), sql_to_json(row.get_ref(i)?));
+                    }
+                    Ok(values)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            tables.insert(table.to_string(), rows);
+        }
+
+        Ok(Some(ProjectSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            schema_version: schema::latest_version().to_string(),
+            project_id,
+            exported_at: Utc::now(),
+            tables,
+        }))
+    }
+
+    fn import_snapshot(&self, snapshot: &ProjectSnapshot, replace: bool) -> Result<Project> {
+        if snapshot.format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(ManifestError::validation(format!(
+                "Unsupported snapshot format version {} (expected {})",
+                snapshot.format_version, SNAPSHOT_FORMAT_VERSION
+            ))
+            .into());
+        }
+        if snapshot.schema_version.as_str() > schema::latest_version() {
+            return Err(ManifestError::validation(format!(
+                "Snapshot comes from a newer database schema ({}) than this one ({})",
+                snapshot.schema_version,
+                schema::latest_version()
+            ))
+            .into());
+        }
+        if let Some(table) = snapshot
+            .tables
+            .keys()
+            .find(|t| !SNAPSHOT_TABLES.iter().any(|(name, _)| name == t))
+        {
+            return Err(ManifestError::validation(format!(
+                "Snapshot has rows for unknown table '{}'",
+                table
+            ))
+            .into());
+        }
+        let project_id = snapshot.project_id.to_string();
+        let project_rows = snapshot.tables.get("projects").map_or(&[][..], |r| r);
+        if project_rows.len() != 1
+            || project_rows[0].get("id").and_then(|id| id.as_str()) != Some(&project_id)
+        {
+            return Err(ManifestError::validation(
+                "Snapshot must hold exactly one project row, matching project_id",
+            )
+            .into());
+        }
+
+        {
+            let mut conn = self.conn.lock().expect("database lock poisoned");
+            let tx = conn.transaction()?;
+            let exists = tx
+                .query_row("SELECT 1 FROM projects WHERE id = ?", [&project_id], |_| {
+                    Ok(())
+                })
+                .optional()?
+                .is_some();
+            if exists && !replace {
+                return Err(ManifestError::invalid_state(format!(
+                    "Project {} already exists; import with replace to overwrite it",
+                    project_id
+                ))
+                .into());
+            }
+
+            // Also clears rows a deleted project left behind, which would
+            // otherwise collide with the snapshot's IDs
+            for (table, filter) in SNAPSHOT_TABLES.iter().rev() {
+                tx.execute(
+                    &format!("DELETE FROM {} WHERE {}", table, filter),
+                    [&project_id],
+                )?;
+            }
+
+            for (table, filter) in SNAPSHOT_TABLES {
+                let Some(rows) = snapshot.tables.get(*table) else {
+                    continue;
+                };
+                let known: Vec<String> = tx
+                    .prepare(&format!("SELECT * FROM {} LIMIT 0", table))?
+                    .column_names()
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                for row in rows {
+                    if let Some(column) = row.keys().find(|c| !known.contains(c)) {
+                        return Err(ManifestError::validation(format!(
+                            "Snapshot has unknown column '{}' in table '{}'",
+                            column, table
+                        ))
+                        .into());
+                    }
+                    let columns: Vec<&str> = row.keys().map(String::as_str).collect();
+                    let placeholders = vec!["?"; columns.len()].join(", ");
+                    let values: Vec<rusqlite::types::Value> =
+                        row.values().map(json_to_sql).collect();
+                    tx.execute(
+                        &format!(
+                            "INSERT INTO {} ({}) VALUES ({})",
+                            table,
+                            columns.join(", "),
+                            placeholders
+                        ),
+                        rusqlite::params_from_iter(values),
+                    )
+                    .map_err(|e| match e.sqlite_error_code() {
+                        Some(rusqlite::ErrorCode::ConstraintViolation) => {
+                            ManifestError::invalid_state(format!(
+                                "Snapshot conflicts with existing data in '{}': {}",
+                                table, e
+                            ))
+                            .into()
+                        }
+                        _ => anyhow::Error::from(e),
+                    })?;
+                }
+
+                let imported: usize = tx.query_row(
+                    &format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter),
+                    [&project_id],
+                    |row| row.get(0),
+                )?;
+                if imported != rows.len() {
+                    return Err(ManifestError::validation(format!(
+                        "Snapshot has rows in '{}' that belong to another project",
+                        table
+                    ))
+                    .into());
+                }
+            }
+            tx.commit()?;
+        }
+
+        self.get_project(snapshot.project_id)?
+            .ok_or_else(|| ManifestError::not_found("Project").into())
+    }
+
+    // ============================================================
+    // Session operations
+    // ============================================================
+
+    fn get_session(&self, id: Uuid) -> Result<Option<Session>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, feature_id, goal, status, created_at, completed_at, kind, commits
+             FROM sessions WHERE id = ?",
+        )?;
+
+        let mut rows = stmt.query([id.to_string()])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(session_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn list_sessions_by_feature(
+        &self,
+        feature_id: Uuid,
+        query: &PageQuery,
+    ) -> Result<Page<Session>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let request = PageRequest {
+            cursor: query.cursor.as_deref(),
+            limit: query.limit,
+            offset: None,
+        };
+        query_page(
+            &conn,
+            "SELECT id, feature_id, goal, status, created_at, completed_at, kind, commits
+             FROM sessions WHERE feature_id = ?1",
+            vec![Box::new(feature_id.to_string())],
+            &Order {
+                name: "newest",
+                keys: &[("created_at", Direction::Desc), ("id", Direction::Desc)],
+            },
+            request,
+            session_from_row,
+        )
+    }
+
+    fn create_session(&self, input: CreateSessionInput) -> Result<SessionResponse> {
+        let feature = check_new_session(self, &input)?;
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+
+        let session_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        tx.execute(
+            "INSERT INTO sessions (id, feature_id, goal, kind, status, created_at)
+             VALUES (?, ?, ?, ?, 'active', ?)",
+            (
+                session_id.to_string(),
+                input.feature_id.to_string(),
+                &input.goal,
+                &input.kind,
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        // Auto-transition feature from 'proposed' to 'specified'
+        if feature.state == FeatureState::Proposed {
+            tx.execute(
+                "UPDATE features SET state = 'specified', updated_at = ? WHERE id = ?",
+                (now.to_rfc3339(), input.feature_id.to_string()),
+            )?;
+        }
+
+        let session = Session {
+            id: session_id,
+            feature_id: input.feature_id,
+            goal: input.goal.clone(),
+            kind: input.kind.clone(),
+            status: SessionStatus::Active,
+            commits: vec![],
+            created_at: now,
+            completed_at: None,
+        };
+
+        // Create tasks within the same transaction
+        let mut tasks = Vec::new();
+        for task_input in input.tasks {
+            let task_id = Uuid::new_v4();
+
+            tx.execute(
+                "INSERT INTO tasks (id, session_id, parent_id, title, scope, status, agent_type, created_at)
+                 VALUES (?, ?, ?, ?, ?, 'pending', ?, ?)",
+                (
+                    task_id.to_string(),
+                    session_id.to_string(),
+                    task_input.parent_id.map(|u| u.to_string()),
+                    &task_input.title,
+                    &task_input.scope,
+                    task_input.agent_type.as_str(),
+                    now.to_rfc3339(),
+                ),
+            )?;
+
+            tasks.push(Task {
+                id: task_id,
+                session_id,
+                parent_id: task_input.parent_id,
+                title: task_input.title,
+                scope: task_input.scope,
+                status: TaskStatus::Pending,
+                agent_type: task_input.agent_type,
+                worktree_path: None,
+                branch: None,
+                result: None,
+                created_at: now,
+                started_at: None,
+                finished_at: None,
+            });
+        }
+
+        tx.commit()?;
+        Ok(SessionResponse { session, tasks })
+    }
+
+    fn complete_session(
+        &self,
+        id: Uuid,
+        input: CompleteSessionInput,
+    ) -> Result<Option<SessionCompletionResult>> {
+        let session = match self.get_session(id)? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+
+        if session.status != SessionStatus::Active {
+            return Err(ManifestError::invalid_state("Session is not active").into());
+        }
+
+        let feature_state = input.feature_state;
+        let history_details = completion_details(self, &session, input)?;
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+
+        // Create history entry with structured details (inlined for transaction)
+        let history_id = Uuid::new_v4();
+        let details_json = serde_json::to_string(&history_details)?;
+
+        tx.execute(
+            "INSERT INTO feature_history (id, feature_id, session_id, summary, files_changed, author, details, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            (
+                history_id.to_string(),
+                session.feature_id.to_string(),
+                Some(id.to_string()),
+                &history_details.summary,
+                "[]", // deprecated
+                "",   // deprecated
+                &details_json,
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        let history_entry = FeatureHistory {
+            id: history_id,
+            feature_id: session.feature_id,
+            session_id: Some(id),
+            details: history_details,
+            created_at: now,
+        };
+
+        // Delete tasks
+        tx.execute("DELETE FROM tasks WHERE session_id = ?", [id.to_string()])?;
+
+        // Update session status
+        tx.execute(
+            "UPDATE sessions SET status = 'completed', completed_at = ? WHERE id = ?",
+            (now.to_rfc3339(), id.to_string()),
+        )?;
+
+        // Update feature state if provided
+        // When marking as implemented, also promote desired_details → details
+        if let Some(state) = feature_state {
+            if state == FeatureState::Implemented {
+                // Promote desired_details to details and clear desired_details
+                tx.execute(
+                    "UPDATE features SET
+                        state = ?,
+                        details = COALESCE(desired_details, details),
+                        desired_details = NULL,
+                        updated_at = ?
+                    WHERE id = ?",
+                    (
+                        state.as_str(),
+                        now.to_rfc3339(),
+                        session.feature_id.to_string(),
+                    ),
+                )?;
+            } else {
+                tx.execute(
+                    "UPDATE features SET state = ?, updated_at = ? WHERE id = ?",
+                    (
+                        state.as_str(),
+                        now.to_rfc3339(),
+                        session.feature_id.to_string(),
+                    ),
+                )?;
+            }
+        }
+
+        tx.commit()?;
+
+        let completed_session = Session {
+            id: session.id,
+            feature_id: session.feature_id,
+            goal: session.goal,
+            kind: session.kind,
+            status: SessionStatus::Completed,
+            commits: session.commits,
+            created_at: session.created_at,
+            completed_at: Some(now),
+        };
+
+        Ok(Some(SessionCompletionResult {
+            session: completed_session,
+            history_entry,
+            warnings: vec![],
+        }))
+    }
+
+    // ============================================================
+    // Task operations
+    // ============================================================
+
+    fn get_task(&self, id: Uuid) -> Result<Option<Task>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, parent_id, title, scope, status, agent_type, worktree_path, branch, created_at, result,
+                    started_at, finished_at
+             FROM tasks WHERE id = ?"
+        )?;
+
+        let mut rows = stmt.query([id.to_string()])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(task_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_tasks_by_session(&self, session_id: Uuid) -> Result<Vec<Task>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, parent_id, title, scope, status, agent_type, worktree_path, branch, created_at, result,
+                    started_at, finished_at
+             FROM tasks WHERE session_id = ? ORDER BY created_at"
+        )?;
+
+        let tasks = stmt
+            .query_map([session_id.to_string()], task_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    fn get_task_children(&self, parent_id: Uuid) -> Result<Vec<Task>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, parent_id, title, scope, status, agent_type, worktree_path, branch, created_at, result,
+                    started_at, finished_at
+             FROM tasks WHERE parent_id = ? ORDER BY created_at"
+        )?;
+
+        let tasks = stmt
+            .query_map([parent_id.to_string()], task_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tasks)
+    }
+
+    fn create_task(&self, session_id: Uuid, input: CreateTaskInput) -> Result<Task> {
+        // Verify session exists and is active
+        let session = self
+            .get_session(session_id)?
+            .ok_or_else(|| ManifestError::not_found("Session"))?;
+
+        if session.status != SessionStatus::Active {
+            return Err(
+                ManifestError::invalid_state("Cannot add tasks to a completed session").into(),
+            );
+        }
+
+        registered_agent_type(self, session.feature_id, &input.agent_type)?;
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        conn.execute(
+            "INSERT INTO tasks (id, session_id, parent_id, title, scope, status, agent_type, created_at)
+             VALUES (?, ?, ?, ?, ?, 'pending', ?, ?)",
+            (
+                id.to_string(),
+                session_id.to_string(),
+                input.parent_id.map(|u| u.to_string()),
+                &input.title,
+                &input.scope,
+                input.agent_type.as_str(),
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        Ok(Task {
+            id,
+            session_id,
+            parent_id: input.parent_id,
+            title: input.title,
+            scope: input.scope,
+            status: TaskStatus::Pending,
+            agent_type: input.agent_type,
+            worktree_path: None,
+            branch: None,
+            result: None,
+            created_at: now,
+            started_at: None,
+            finished_at: None,
+        })
+    }
+
+    fn update_task(&self, id: Uuid, input: UpdateTaskInput) -> Result<bool> {
+        let limit = if input.status == Some(TaskStatus::Running) {
+            concurrency_limit(self, id)?
+        } else {
+            None
+        };
+
+        let mut conn = self.conn.lock().expect("database lock poisoned");
+        // Immediate, so other processes can't start tasks between the count and the update
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        if let Some(limit) = limit {
+            let running: u32 = tx.query_row(
+                COUNT_RUNNING_TASKS,
+                (limit.project_id.to_string(), limit.agent_type.as_str()),
+                |row| row.get(0),
+            )?;
+            limit.check(running)?;
+        }
+
+        let mut updates = Vec::new();
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(status) = input.status {
+            updates.push("status = ?");
+            params.push(Box::new(status.as_str().to_string()));
+
+            let now = Utc::now().to_rfc3339();
+            match status {
+                TaskStatus::Pending => updates.push("finished_at = NULL"),
+                TaskStatus::Running => {
+                    updates.push("started_at = COALESCE(started_at, ?)");
+                    params.push(Box::new(now));
+                    updates.push("finished_at = NULL");
+                }
+                TaskStatus::Completed | TaskStatus::Failed => {
+                    updates.push("finished_at = ?");
+                    params.push(Box::new(now));
+                }
+            }
+        }
+        if let Some(worktree_path) = input.worktree_path {
+            updates.push("worktree_path = ?");
+            params.push(Box::new(worktree_path));
+        }
+        if let Some(branch) = input.branch {
+            updates.push("branch = ?");
+            params.push(Box::new(branch));
+        }
+        if let Some(result) = input.result {
+            updates.push("result = ?");
+            params.push(Box::new(serde_json::to_string(&result)?));
+        }
+
+        if updates.is_empty() {
+            return Ok(false);
+        }
+
+        params.push(Box::new(id.to_string()));
+
+        let sql = format!("UPDATE tasks SET {} WHERE id = ?", updates.join(", "));
+        let params_ref: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let rows = tx.execute(&sql, params_ref.as_slice())?;
+        tx.commit()?;
+
+        Ok(rows > 0)
+    }
+
+    // ============================================================
+    // Feature History operations
+    // ============================================================
+
+    fn create_history_entry(&self, input: CreateHistoryInput) -> Result<FeatureHistory> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        let details_json = serde_json::to_string(&input.details)?;
+
+        // Note: files_changed and author columns are deprecated but kept for schema compatibility
+        conn.execute(
+            "INSERT INTO feature_history (id, feature_id, session_id, summary, files_changed, author, details, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.to_string(),
+                input.feature_id.to_string(),
+                input.session_id.map(|u| u.to_string()),
+                &input.details.summary,
+                "[]", // deprecated
+                "",   // deprecated
+                &details_json,
+                now.to_rfc3339(),
+            ),
+        )?;
+
+        Ok(FeatureHistory {
+            id,
+            feature_id: input.feature_id,
+            session_id: input.session_id,
+            details: input.details,
+            created_at: now,
+        })
+    }
+
+    fn record_commit(&self, feature_id: Uuid, input: RecordCommitInput) -> Result<RecordedCommit> {
+        let recorded = |session_id, history_id| RecordedCommit {
+            feature_id,
+            session_id,
+            history_id,
+            already_recorded: false,
+        };
+
+        match place_commit(self, feature_id, input)? {
+            CommitPlacement::Recorded(recorded) => Ok(recorded),
+            CommitPlacement::Session(session_id, commits) => {
+                let conn = self.conn.lock().expect("database lock poisoned");
+                conn.execute(
+                    "UPDATE sessions SET commits = ? WHERE id = ?",
+                    (serde_json::to_string(&commits)?, session_id.to_string()),
+                )?;
+                Ok(recorded(Some(session_id), None))
+            }
+            CommitPlacement::History(input) => {
+                let entry = self.create_history_entry(input)?;
+                Ok(recorded(None, Some(entry.id)))
+            }
+        }
+    }
+
+    fn list_feature_history(
+        &self,
+        feature_id: Uuid,
+        query: &PageQuery,
+    ) -> Result<Page<FeatureHistory>> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        let request = PageRequest {
+            cursor: query.cursor.as_deref(),
+            limit: query.limit,
+            offset: None,
+        };
+        query_page(
+            &conn,
+            "SELECT id, feature_id, session_id, details, created_at
+             FROM feature_history WHERE feature_id = ?1",
+            vec![Box::new(feature_id.to_string())],
+            &Order {
+                name: "newest",
+                keys: &[("created_at", Direction::Desc), ("id", Direction::Desc)],
+            },
+            request,
+            |row| {
+                let details_json: String = row.get(3)?;
+                let details: HistoryDetails =
+                    serde_json::from_str(&details_json).unwrap_or_default();
+
+                Ok(FeatureHistory {
+                    id: parse_uuid(row.get::<_, String>(0)?),
+                    feature_id: parse_uuid(row.get::<_, String>(1)?),
+                    session_id: row.get::<_, Option<String>>(2)?.map(parse_uuid),
+                    details,
+                    created_at: parse_datetime(row.get::<_, String>(4)?),
+                })
+            },
+        )
+    }
+
+    // ============================================================
+    // Metrics
+    // ============================================================
+
+    fn get_project_metrics(&self, project_id: Uuid) -> Result<Option<ProjectMetrics>> {
+        if self.get_project(project_id)?.is_none() {
+            return Ok(None);
+        }
+
+        let conn = self.conn.lock().expect("database lock poisoned");
+
+        let mut stmt = conn.prepare(
+            "SELECT s.feature_id, f.title, s.status, s.created_at, s.completed_at
+             FROM sessions s JOIN features f ON s.feature_id = f.id
+             WHERE f.project_id = ?",
+        )?;
+        let sessions = stmt
+            .query_map([project_id.to_string()], |row| {
+                Ok(SessionSpan {
+                    feature_id: parse_uuid(row.get::<_, String>(0)?),
+                    feature_title: row.get::<_, String>(1)?,
+                    status: SessionStatus::from_str(&row.get::<_, String>(2)?)
+                        .unwrap_or(SessionStatus::Active),
+                    created_at: parse_datetime(row.get::<_, String>(3)?),
+                    completed_at: row.get::<_, Option<String>>(4)?.map(parse_datetime),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut stmt = conn.prepare(
+            "SELECT t.agent_type, t.status, t.started_at, t.finished_at
+             FROM tasks t
+             JOIN sessions s ON t.session_id = s.id
+             JOIN features f ON s.feature_id = f.id
+             WHERE f.project_id = ?",
+        )?;
+        let timings = stmt
+            .query_map([project_id.to_string()], |row| {
+                Ok(TaskTiming {
+                    agent_type: AgentType::new(row.get::<_, String>(0)?),
+                    status: TaskStatus::from_str(&row.get::<_, String>(1)?)
+                        .unwrap_or(TaskStatus::Pending),
+                    started_at: row.get::<_, Option<String>>(2)?.map(parse_datetime),
+                    finished_at: row.get::<_, Option<String>>(3)?.map(parse_datetime),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut stmt = conn.prepare(
+            "SELECT h.details FROM feature_history h
+             JOIN features f ON h.feature_id = f.id
+             WHERE f.project_id = ? AND h.details IS NOT NULL",
+        )?;
+        let archived = stmt
+            .query_map([project_id.to_string()], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(build_project_metrics(
+            project_id, sessions, timings, archived,
+        )))
+    }
+
+    // ============================================================
+    // Idempotency keys
+    // ============================================================
+
+    fn claim_idempotency_key(
+        &self,
+        key: &str,
+        request: &str,
+        ttl: Duration,
+    ) -> Result<IdempotencyClaim> {
+        let now = Utc::now();
+        let expired = now - chrono::Duration::from_std(ttl)?;
+        let conn = self.conn.lock().expect("database lock poisoned");
+        conn.execute(
+            "DELETE FROM idempotency_keys WHERE created_at < ?",
+            [expired.to_rfc3339()],
+        )?;
+
+        let claimed = conn.execute(
+            "INSERT OR IGNORE INTO idempotency_keys (key, request, created_at) VALUES (?, ?, ?)",
+            (key, request, now.to_rfc3339()),
+        )?;
+        if claimed == 1 {
+            return Ok(IdempotencyClaim::Claimed);
+        }
+
+        let (claimed_by, status, content_type, body) = conn.query_row(
+            "SELECT request, status, content_type, body FROM idempotency_keys WHERE key = ?",
+            [key],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<Vec<u8>>>(3)?,
+                ))
+            },
+        )?;
+        Ok(match status {
+            _ if claimed_by != request => IdempotencyClaim::Mismatch,
+            None => IdempotencyClaim::InProgress,
+            Some(status) => IdempotencyClaim::Completed(StoredResponse {
+                status: status as u16,
+                content_type,
+                body: body.unwrap_or_default(),
+            }),
+        })
+    }
+
+    fn complete_idempotency_key(&self, key: &str, response: &StoredResponse) -> Result<()> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        conn.execute(
+            "UPDATE idempotency_keys SET status = ?, content_type = ?, body = ? WHERE key = ?",
+            (
+                i64::from(response.status),
+                &response.content_type,
+                &response.body,
+                key,
+            ),
+        )?;
+        Ok(())
+    }
+
+    fn release_idempotency_key(&self, key: &str) -> Result<()> {
+        let conn = self.conn.lock().expect("database lock poisoned");
+        conn.execute("DELETE FROM idempotency_keys WHERE key = ?", [key])?;
+        Ok(())
+    }
+}
+
+/// Build a `Session` from a row selected as
+/// `id, feature_id, goal, status, created_at, completed_at, kind`.
+/// Pick the slug and path for a feature titled `title` under `parent_id`.
+///
+/// The slug gets a numeric suffix if a sibling already has it. Taking a path
+/// drops any redirect from it, so it resolves to its new owner.
+fn assign_path(
+    conn: &Connection,
+    project_id: Uuid,
+    parent_id: Option<Uuid>,
+    title: &str,
+    id: Uuid,
+) -> Result<(String, String)> {
+    let prefix = match parent_id {
+        Some(parent_id) => {
+            let parent_path: Option<String> = conn
+                .query_row(
+                    "SELECT path FROM features WHERE id = ?",
+                    [parent_id.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| ManifestError::not_found("Parent feature"))?;
+            format!("{}/", parent_path.unwrap_or_default())
+        }
+        None => String::new(),
+    };
+
+    let mut base = slugify(title);
+    if base.is_empty() {
+        base = "feature".to_string();
+    } else if Uuid::parse_str(&base).is_ok() {
+        // Would be read back as an id
+        base = format!("f-{}", base);
+    }
+
+    let mut slug = base.clone();
+    for n in 2.. {
+        let path = format!("{}{}", prefix, slug);
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM features WHERE project_id = ? AND path = ? AND id != ?)",
+            (project_id.to_string(), &path, id.to_string()),
+            |row| row.get(0),
+        )?;
+        if !taken {
+            conn.execute(
+                "DELETE FROM feature_redirects WHERE project_id = ? AND path = ?",
+                (project_id.to_string(), &path),
+            )?;
+            return Ok((slug, path));
+        }
+        slug = format!("{}-{}", base, n);
+    }
+    unreachable!("slug suffixes are unbounded")
+}
+
+/// Re-root the descendants of feature `id` from `old_path` to `new_path`,
+/// leaving redirects at the old paths of the feature and its descendants.
+fn move_subtree(
+    conn: &Connection,
+    project_id: Uuid,
+    id: Uuid,
+    old_path: &str,
+    new_path: &str,
+) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    let redirect = |path: &str, feature_id: &str| {
+        conn.execute(
+            "INSERT OR REPLACE INTO feature_redirects (project_id, path, feature_id, created_at)
+             VALUES (?, ?, ?, ?)",
+            (project_id.to_string(), path, feature_id, &now),
+        )
+    };
+    redirect(old_path, &id.to_string())?;
+
+    let old_prefix = format!("{}/", old_path);
+    let descendants = conn
+        .prepare("SELECT id, path FROM features WHERE project_id = ? AND substr(path, 1, ?) = ?")?
+        .query_map(
+            (project_id.to_string(), old_prefix.len() as i64, &old_prefix),
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (descendant_id, path) in descendants {
+        redirect(&path, &descendant_id)?;
+        conn.execute(
+            "UPDATE features SET path = ? WHERE id = ?",
+            (
+                format!("{}/{}", new_path, &path[old_prefix.len()..]),
+                &descendant_id,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Give features created before paths existed their slug and path,
+/// parents before children.
+fn backfill_feature_paths(conn: &Connection) -> Result<()> {
+    loop {
+        let pending = conn
+            .prepare(
+                "SELECT f.id, f.project_id, f.parent_id, f.title
+                 FROM features f LEFT JOIN features p ON p.id = f.parent_id
+                 WHERE f.path IS NULL AND (f.parent_id IS NULL OR p.path IS NOT NULL)
+                 ORDER BY f.created_at",
+            )?
+            .query_map([], |row| {
+                Ok((
+                    parse_uuid(row.get::<_, String>(0)?),
+                    parse_uuid(row.get::<_, String>(1)?),
+                    row.get::<_, Option<String>>(2)?.map(parse_uuid),
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        for (id, project_id, parent_id, title) in pending {
+            let (slug, path) = assign_path(conn, project_id, parent_id, &title, id)?;
+            conn.execute(
+                "UPDATE features SET slug = ?, path = ? WHERE id = ?",
+                (slug, path, id.to_string()),
+            )?;
+        }
+    }
+}
+
+/// Order for a feature list `sort` parameter. Ties are broken by title and
+/// id so pages are stable.
+fn feature_order(sort: Option<&str>) -> Result<&'static Order> {
+    use Direction::{Asc, Desc};
+    Ok(match sort.map(str::trim).unwrap_or("priority") {
+        "priority" => &Order {
+            name: "priority",
+            keys: &[("priority", Asc), ("title", Asc), ("id", Asc)],
+        },
+        "-priority" => &Order {
+            name: "-priority",
+            keys: &[("priority", Desc), ("title", Asc), ("id", Asc)],
+        },
+        "title" => &Order {
+            name: "title",
+            keys: &[("title", Asc), ("id", Asc)],
+        },
+        "-title" => &Order {
+            name: "-title",
+            keys: &[("title", Desc), ("id", Asc)],
+        },
+        "updated_at" => &Order {
+            name: "updated_at",
+            keys: &[("julianday(updated_at)", Asc), ("title", Asc), ("id", Asc)],
+        },
+        "-updated_at" => &Order {
+            name: "-updated_at",
+            keys: &[("julianday(updated_at)", Desc), ("title", Asc), ("id", Asc)],
+        },
+        "created_at" => &Order {
+            name: "created_at",
+            keys: &[("julianday(created_at)", Asc), ("title", Asc), ("id", Asc)],
+        },
+        "-created_at" => &Order {
+            name: "-created_at",
+            keys: &[("julianday(created_at)", Desc), ("title", Asc), ("id", Asc)],
+        },
+        other => {
+            return Err(ManifestError::validation(format!(
+                "Unknown sort '{}'. Expected priority, title, updated_at or created_at, optionally prefixed with '-'",
+                other
+            ))
+            .into())
+        }
+    })
+}
+
+/// Pages copied per step when backing up from a connection of our own.
+const BACKUP_STEP_PAGES: std::os::raw::c_int = 256;
+
+/// Copy `source` into `dest`, `pages` pages per step (-1 for all at once).
+/// SQLite restarts the copy when another connection writes to `source`
+/// between steps, so the result is always consistent.
+fn copy_database(
+    source: &Connection,
+    dest: &mut Connection,
+    pages: std::os::raw::c_int,
+) -> Result<()> {
+    let backup = Backup::new(source, dest)?;
+    loop {
+        match backup.step(pages)? {
+            StepResult::Done => return Ok(()),
+            StepResult::More => {}
+            // Another process holds a lock on one of the databases
+            _ => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+/// A stored column value as JSON. Blobs become arrays of bytes.
+fn sql_to_json(value: rusqlite::types::ValueRef) -> serde_json::Value {
+    use rusqlite::types::ValueRef;
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => f.into(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into(),
+        ValueRef::Blob(bytes) => bytes.to_vec().into(),
+    }
+}
+
+/// The inverse of [`sql_to_json`]. Objects, which no column stores, are kept as JSON text.
+fn json_to_sql(value: &serde_json::Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value;
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        serde_json::Value::Array(items) => Value::Blob(
+            items
+                .iter()
+                .map(|b| b.as_u64().unwrap_or_default() as u8)
+                .collect(),
+        ),
+        serde_json::Value::Object(_) => Value::Text(value.to_string()),
+    }
+}
+
+/// Whether `dependent` depends on `dependency`, directly or transitively.
+fn dependency_reaches(conn: &Connection, dependent: Uuid, dependency: Uuid) -> Result<bool> {
+    if dependent == dependency {
+        return Ok(true);
+    }
+    let reaches = conn.query_row(
+        &format!(
+            "{} SELECT EXISTS(SELECT 1 FROM reach WHERE id = ?2)",
+            dependency_closure(DependencyDirection::Upstream)
+        ),
+        (dependent.to_string(), dependency.to_string()),
+        |row| row.get(0),
+    )?;
+    Ok(reaches)
+}
+
+/// Names of a feature's tags, sorted.
+fn feature_tag_names(conn: &Connection, feature_id: Uuid) -> Result<Vec<String>> {
+    let names = conn
+        .prepare(
+            "SELECT t.name FROM feature_tags ft JOIN tags t ON t.id = ft.tag_id
+             WHERE ft.feature_id = ? ORDER BY t.name",
+        )?
+        .query_map([feature_id.to_string()], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(names)
+}
+
+/// Fill in the tags of `features`, with one query per project.
+fn attach_tags(conn: &Connection, features: &mut [Feature]) -> Result<()> {
+    let projects: std::collections::BTreeSet<Uuid> =
+        features.iter().map(|f| f.project_id).collect();
+    let mut tags: std::collections::HashMap<Uuid, Vec<String>> = std::collections::HashMap::new();
+    for project_id in projects {
+        let rows = conn
+            .prepare(
+                "SELECT ft.feature_id, t.name FROM feature_tags ft JOIN tags t ON t.id = ft.tag_id
+                 WHERE t.project_id = ? ORDER BY t.name",
+            )?
+            .query_map([project_id.to_string()], |row| {
+                Ok((
+                    parse_uuid(row.get::<_, String>(0)?),
+                    row.get::<_, String>(1)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (feature_id, name) in rows {
+            tags.entry(feature_id).or_default().push(name);
+        }
+    }
+    for feature in features {
+        feature.tags = tags.remove(&feature.id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Replace a feature's tags with `names`, creating the tags the project
+/// doesn't have yet. Returns the stored names, sorted.
+fn set_feature_tags(
+    conn: &Connection,
+    project_id: Uuid,
+    feature_id: Uuid,
+    names: &[String],
+) -> Result<Vec<String>> {
+    conn.execute(
+        "DELETE FROM feature_tags WHERE feature_id = ?",
+        [feature_id.to_string()],
+    )?;
+    for name in names {
+        let name = validate_tag_name(name)?;
+        let tag_id = match tag_id_by_name(conn, project_id, name)? {
+            Some(id) => id,
+            None => {
+                let id = Uuid::new_v4().to_string();
+                conn.execute(
+                    "INSERT INTO tags (id, project_id, name, created_at) VALUES (?, ?, ?, ?)",
+                    (&id, project_id.to_string(), name, Utc::now().to_rfc3339()),
+                )?;
+                id
+            }
+        };
+        conn.execute(
+            "INSERT OR IGNORE INTO feature_tags (feature_id, tag_id) VALUES (?, ?)",
+            (feature_id.to_string(), tag_id),
+        )?;
+    }
+    feature_tag_names(conn, feature_id)
+}
+
+/// Id of the project's tag called `name`, ignoring case.
+fn tag_id_by_name(conn: &Connection, project_id: Uuid, name: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT id FROM tags WHERE project_id = ? AND name = ?",
+            (project_id.to_string(), name),
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Take the next key in a project's sequence (`MAN-42`).
+fn allocate_feature_key(conn: &Connection, project_id: Uuid) -> Result<String> {
+    let (key, number): (String, i64) = conn
+        .query_row(
+            "UPDATE projects SET next_feature_number = next_feature_number + 1 WHERE id = ?
+             RETURNING key, next_feature_number - 1",
+            [project_id.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| ManifestError::not_found("Project"))?;
+    Ok(format!("{}-{}", key, number))
+}
+
+fn project_key_taken(conn: &Connection, key: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM projects WHERE key = ?)",
+        [key],
+        |row| row.get(0),
+    )?)
+}
+
+/// Give projects and features created before keys existed their keys,
+/// numbering features in creation order.
+fn backfill_feature_keys(conn: &Connection) -> Result<()> {
+    let projects = conn
+        .prepare("SELECT id, name FROM projects WHERE key IS NULL ORDER BY created_at")?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (id, name) in projects {
+        let key = derive_project_key(&name, |key| project_key_taken(conn, key))?;
+        conn.execute("UPDATE projects SET key = ? WHERE id = ?", (key, id))?;
+    }
+
+    let features = conn
+        .prepare("SELECT id, project_id FROM features WHERE key IS NULL ORDER BY created_at")?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                parse_uuid(row.get::<_, String>(1)?),
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (id, project_id) in features {
+        let key = allocate_feature_key(conn, project_id)?;
+        conn.execute("UPDATE features SET key = ? WHERE id = ?", (key, id))?;
+    }
+    Ok(())
+}
+
+/// Map a row selected as `id, project_id, parent_id, title, details,
+/// desired_details, state, priority, created_at, updated_at, slug, path, key`.
+/// Tags are loaded separately; see [`attach_tags`].
+fn feature_from_row(row: &rusqlite::Row) -> rusqlite::Result<Feature> {
+    Ok(Feature {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        project_id: parse_uuid(row.get::<_, String>(1)?),
+        parent_id: row.get::<_, Option<String>>(2)?.map(parse_uuid),
+        key: row.get::<_, Option<String>>(12)?.unwrap_or_default(),
+        title: row.get(3)?,
+        slug: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+        path: row.get::<_, Option<String>>(11)?.unwrap_or_default(),
+        details: row.get(4)?,
+        desired_details: row.get(5)?,
+        state: FeatureState::from_str(&row.get::<_, String>(6)?).unwrap_or(FeatureState::Proposed),
+        priority: row.get(7)?,
+        tags: vec![],
+        created_at: parse_datetime(row.get::<_, String>(8)?),
+        updated_at: parse_datetime(row.get::<_, String>(9)?),
+    })
+}
+
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+    Ok(Session {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        feature_id: parse_uuid(row.get::<_, String>(1)?),
+        goal: row.get(2)?,
+        kind: row.get(6)?,
+        status: SessionStatus::from_str(&row.get::<_, String>(3)?).unwrap_or(SessionStatus::Active),
+        created_at: parse_datetime(row.get::<_, String>(4)?),
+        completed_at: row.get::<_, Option<String>>(5)?.map(parse_datetime),
+        commits: row
+            .get::<_, Option<String>>(7)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+    })
+}
+
+/// Build a `Task` from a row selected as
+/// `id, session_id, parent_id, title, scope, status, agent_type, worktree_path, branch, created_at, result,
+/// started_at, finished_at`.
+fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        session_id: parse_uuid(row.get::<_, String>(1)?),
+        parent_id: row.get::<_, Option<String>>(2)?.map(parse_uuid),
+        title: row.get(3)?,
+        scope: row.get(4)?,
+        status: TaskStatus::from_str(&row.get::<_, String>(5)?).unwrap_or(TaskStatus::Pending),
+        agent_type: AgentType::new(row.get::<_, String>(6)?),
+        worktree_path: row.get(7)?,
+        branch: row.get(8)?,
+        created_at: parse_datetime(row.get::<_, String>(9)?),
+        result: row
+            .get::<_, Option<String>>(10)?
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        started_at: row.get::<_, Option<String>>(11)?.map(parse_datetime),
+        finished_at: row.get::<_, Option<String>>(12)?.map(parse_datetime),
+    })
+}
+
+/// Build an `AgentTypeConfig` from a row selected as
+/// `id, project_id, name, label, instructions, max_concurrency, created_at`.
+fn agent_type_from_row(row: &rusqlite::Row) -> rusqlite::Result<AgentTypeConfig> {
+    Ok(AgentTypeConfig {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        project_id: parse_uuid(row.get::<_, String>(1)?),
+        name: AgentType::new(row.get::<_, String>(2)?),
+        label: row.get(3)?,
+        instructions: row.get(4)?,
+        max_concurrency: row.get(5)?,
+        created_at: parse_datetime(row.get::<_, String>(6)?),
+    })
+}
+
+fn tag_from_row(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        project_id: parse_uuid(row.get::<_, String>(1)?),
+        name: row.get(2)?,
+        color: row.get(3)?,
+        created_at: parse_datetime(row.get::<_, String>(4)?),
+    })
+}
+
+fn relation_from_row(row: &rusqlite::Row) -> rusqlite::Result<FeatureRelation> {
+    Ok(FeatureRelation {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        from_feature_id: parse_uuid(row.get::<_, String>(1)?),
+        to_feature_id: parse_uuid(row.get::<_, String>(2)?),
+        kind: RelationKind::from_str(&row.get::<_, String>(3)?).unwrap_or(RelationKind::RelatesTo),
+        created_at: parse_datetime(row.get::<_, String>(4)?),
+    })
+}
+
+fn criterion_from_row(row: &rusqlite::Row) -> rusqlite::Result<AcceptanceCriterion> {
+    Ok(AcceptanceCriterion {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        feature_id: parse_uuid(row.get::<_, String>(1)?),
+        position: row.get(2)?,
+        text: row.get(3)?,
+        status: CriterionStatus::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+        test_name: row.get(5)?,
+        created_at: parse_datetime(row.get::<_, String>(6)?),
+        updated_at: parse_datetime(row.get::<_, String>(7)?),
+    })
+}
+
+/// Build a `FeatureCodeLink` from a row selected as
+/// `id, feature_id, directory_id, path, line_start, line_end, symbol, created_at`.
+fn code_link_from_row(row: &rusqlite::Row) -> rusqlite::Result<FeatureCodeLink> {
+    Ok(FeatureCodeLink {
+        id: parse_uuid(row.get::<_, String>(0)?),
+        feature_id: parse_uuid(row.get::<_, String>(1)?),
+        directory_id: parse_uuid(row.get::<_, String>(2)?),
+        path: row.get(3)?,
+        line_start: row.get(4)?,
+        line_end: row.get(5)?,
+        symbol: row.get(6)?,
+        created_at: parse_datetime(row.get::<_, String>(7)?),
+    })
+}
@@ -0,0 +1,583 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use crate::db::migration::{self, checksum, AppliedMigration, Migration};
+use crate::db::{MigrationStatus, PendingMigration};
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: "001",
+        name: "initial",
+        sql: include_str!("migrations/001_initial.sql"),
+        down: None,
+    },
+    Migration {
+        version: "002",
+        name: "add_instructions",
+        sql: include_str!("migrations/002_add_instructions.sql"),
+        down: None,
+    },
+    Migration {
+        version: "003",
+        name: "remove_notes",
+        sql: include_str!("migrations/003_remove_notes.sql"),
+        down: None,
+    },
+    Migration {
+        version: "004",
+        name: "history_details",
+        sql: include_str!("migrations/004_history_details.sql"),
+        down: None,
+    },
+    Migration {
+        version: "005",
+        name: "feature_priority",
+        sql: include_str!("migrations/005_feature_priority.sql"),
+        down: None,
+    },
+    Migration {
+        version: "006",
+        name: "remove_story",
+        sql: include_str!("migrations/006_remove_story.sql"),
+        down: None,
+    },
+    Migration {
+        version: "007",
+        name: "desired_details",
+        sql: include_str!("migrations/007_desired_details.sql"),
+        down: None,
+    },
+    Migration {
+        version: "008",
+        name: "remove_history_legacy_columns",
+        sql: include_str!("migrations/008_remove_history_legacy_columns.sql"),
+        down: None,
+    },
+    Migration {
+        version: "009",
+        name: "task_results",
+        sql: include_str!("migrations/009_task_results.sql"),
+        down: Some(include_str!("migrations/009_task_results.down.sql")),
+    },
+    Migration {
+        version: "010",
+        name: "agent_types",
+        sql: include_str!("migrations/010_agent_types.sql"),
+        down: Some(include_str!("migrations/010_agent_types.down.sql")),
+    },
+    Migration {
+        version: "011",
+        name: "task_timestamps",
+        sql: include_str!("migrations/011_task_timestamps.sql"),
+        down: Some(include_str!("migrations/011_task_timestamps.down.sql")),
+    },
+    Migration {
+        version: "012",
+        name: "concurrent_sessions",
+        sql: include_str!("migrations/012_concurrent_sessions.sql"),
+        down: Some(include_str!("migrations/012_concurrent_sessions.down.sql")),
+    },
+    Migration {
+        version: "013",
+        name: "provision_worktrees",
+        sql: include_str!("migrations/013_provision_worktrees.sql"),
+        down: Some(include_str!("migrations/013_provision_worktrees.down.sql")),
+    },
+    Migration {
+        version: "014",
+        name: "feature_code_links",
+        sql: include_str!("migrations/014_feature_code_links.sql"),
+        down: Some(include_str!("migrations/014_feature_code_links.down.sql")),
+    },
+    Migration {
+        version: "015",
+        name: "session_commits",
+        sql: include_str!("migrations/015_session_commits.sql"),
+        down: Some(include_str!("migrations/015_session_commits.down.sql")),
+    },
+    Migration {
+        version: "016",
+        name: "feature_paths",
+        sql: include_str!("migrations/016_feature_paths.sql"),
+        down: Some(include_str!("migrations/016_feature_paths.down.sql")),
+    },
+    Migration {
+        version: "017",
+        name: "feature_keys",
+        sql: include_str!("migrations/017_feature_keys.sql"),
+        down: Some(include_str!("migrations/017_feature_keys.down.sql")),
+    },
+    Migration {
+        version: "018",
+        name: "tags",
+        sql: include_str!("migrations/018_tags.sql"),
+        down: Some(include_str!("migrations/018_tags.down.sql")),
+    },
+    Migration {
+        version: "019",
+        name: "feature_relations",
+        sql: include_str!("migrations/019_feature_relations.sql"),
+        down: Some(include_str!("migrations/019_feature_relations.down.sql")),
+    },
+    Migration {
+        version: "020",
+        name: "acceptance_criteria",
+        sql: include_str!("migrations/020_acceptance_criteria.sql"),
+        down: Some(include_str!("migrations/020_acceptance_criteria.down.sql")),
+    },
+    Migration {
+        version: "021",
+        name: "test_results",
+        sql: include_str!("migrations/021_test_results.sql"),
+        down: Some(include_str!("migrations/021_test_results.down.sql")),
+    },
+    Migration {
+        version: "022",
+        name: "idempotency_keys",
+        sql: include_str!("migrations/022_idempotency_keys.sql"),
+        down: Some(include_str!("migrations/022_idempotency_keys.down.sql")),
+    },
+];
+
+/// Version of the newest migration this build knows.
+pub(super) fn latest_version() -> &'static str {
+    migration::latest_version(MIGRATIONS)
+}
+
+pub fn run_migrations(conn: &Connection) -> Result<()> {
+    // Create migrations tracking table
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+    )
+    .context("Failed to create schema_migrations table")?;
+    // Databases tracked before checksums get them filled in below
+    if !has_checksum_column(conn)? {
+        conn.execute_batch("ALTER TABLE schema_migrations ADD COLUMN checksum TEXT")?;
+    }
+
+    // Check for existing database without version tracking (upgrade path)
+    let needs_baseline = check_needs_baseline(conn)?;
+    if needs_baseline {
+        mark_migration_applied(conn, &MIGRATIONS[0])?;
+        tracing::info!("Detected existing database, marked migration 001 as applied");
+    }
+
+    verify_applied(conn)?;
+
+    // Get applied migrations
+    let applied = get_applied_migrations(conn)?;
+
+    // Run pending migrations
+    for migration in MIGRATIONS {
+        if !applied.contains(&migration.version.to_string()) {
+            apply_migration(conn, migration)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse a database migrated by a newer build, or whose applied migrations
+/// no longer match the embedded SQL. Records missing checksums as they are.
+fn verify_applied(conn: &Connection) -> Result<()> {
+    for migration in migration::verify(MIGRATIONS, &read_applied(conn)?)? {
+        conn.execute(
+            "UPDATE schema_migrations SET checksum = ? WHERE version = ?",
+            (checksum(migration.sql), migration.version),
+        )?;
+    }
+    Ok(())
+}
+
+/// Every embedded migration and every applied one, by version.
+pub(super) fn status(conn: &Connection) -> Result<Vec<MigrationStatus>> {
+    Ok(migration::status(MIGRATIONS, read_applied(conn)?))
+}
+
+/// The migrations `run_migrations` would apply, in order.
+pub(super) fn pending(conn: &Connection) -> Result<Vec<PendingMigration>> {
+    Ok(migration::pending(MIGRATIONS, read_applied(conn)?))
+}
+
+/// The applied migrations newer than `target`, newest first: what rolling
+/// back to `target` would revert. Fails if any of them can't be reverted.
+pub(super) fn plan_rollback(conn: &Connection, target: &str) -> Result<Vec<PendingMigration>> {
+    migration::plan_rollback(MIGRATIONS, &read_applied(conn)?, target)
+}
+
+/// Revert every applied migration newer than `target` with its down script,
+/// newest first. Returns the reverted versions.
+pub(super) fn rollback(conn: &Connection, target: &str) -> Result<Vec<&'static str>> {
+    let plan = plan_rollback(conn, target)?;
+    for step in &plan {
+        tracing::info!("Reverting migration {}: {}", step.version, step.name);
+        conn.execute_batch(&format!(
+            "BEGIN TRANSACTION; {} DELETE FROM schema_migrations WHERE version = '{}'; COMMIT;",
+            step.sql, step.version
+        ))
+        .with_context(|| format!("Failed to revert migration {}: {}", step.version, step.name))?;
+    }
+    Ok(plan.into_iter().map(|step| step.version).collect())
+}
+
+fn has_checksum_column(conn: &Connection) -> Result<bool> {
+    let count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('schema_migrations') WHERE name = 'checksum'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// The `schema_migrations` rows, oldest first. Reads nothing from databases
+/// that were never migrated.
+fn read_applied(conn: &Connection) -> Result<Vec<AppliedMigration>> {
+    if applied_version(conn)?.is_none() {
+        return Ok(vec![]);
+    }
+    let checksum = if has_checksum_column(conn)? {
+        "checksum"
+    } else {
+        "NULL"
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT version, name, applied_at, {} FROM schema_migrations ORDER BY version",
+        checksum
+    ))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(AppliedMigration {
+                version: row.get(0)?,
+                name: row.get(1)?,
+                applied_at: row.get(2)?,
+                checksum: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn check_needs_baseline(conn: &Connection) -> Result<bool> {
+    // If schema_migrations is empty but tables exist, this is an existing database
+    let migration_count: i32 =
+        conn.query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+            row.get(0)
+        })?;
+
+    if migration_count > 0 {
+        return Ok(false);
+    }
+
+    // Check if core tables exist (features table is a good indicator)
+    let tables_exist: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='features'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    Ok(tables_exist > 0)
+}
+
+/// Newest migration applied to the database, or `None` if it has never been
+/// migrated (no `schema_migrations` table).
+pub(super) fn applied_version(conn: &Connection) -> Result<Option<String>> {
+    let tracked: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='schema_migrations'",
+        [],
+        |row| row.get(0),
+    )?;
+    if tracked == 0 {
+        return Ok(None);
+    }
+    Ok(
+        conn.query_row("SELECT MAX(version) FROM schema_migrations", [], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+fn get_applied_migrations(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT version FROM schema_migrations ORDER BY version")?;
+    let versions = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(versions)
+}
+
+fn mark_migration_applied(conn: &Connection, migration: &Migration) -> Result<()> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO schema_migrations (version, name, applied_at, checksum) VALUES (?, ?, ?, ?)",
+        (
+            migration.version,
+            migration.name,
+            &now,
+            checksum(migration.sql),
+        ),
+    )?;
+    Ok(())
+}
+
+fn apply_migration(conn: &Connection, migration: &Migration) -> Result<()> {
+    tracing::info!(
+        "Applying migration {}: {}",
+        migration.version,
+        migration.name
+    );
+
+    // Run migration in a transaction
+    conn.execute_batch(&format!("BEGIN TRANSACTION; {} COMMIT;", migration.sql))
+        .with_context(|| {
+            format!(
+                "Failed to apply migration {}: {}",
+                migration.version, migration.name
+            )
+        })?;
+
+    mark_migration_applied(conn, migration)?;
+
+    tracing::info!("Migration {} applied successfully", migration.version);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MigrationState;
+
+    #[test]
+    fn test_migrations_run_on_fresh_db() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+
+        // Verify tables exist
+        let count: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='features'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Verify all migrations were recorded
+        let versions = get_applied_migrations(&conn).unwrap();
+        assert_eq!(
+            versions,
+            vec![
+                "001", "002", "003", "004", "005", "006", "007", "008", "009", "010", "011", "012",
+                "013", "014", "015", "016", "017", "018", "019", "020", "021", "022"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrations_are_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        run_migrations(&conn).unwrap(); // Should not fail
+
+        let versions = get_applied_migrations(&conn).unwrap();
+        assert_eq!(
+            versions,
+            vec![
+                "001", "002", "003", "004", "005", "006", "007", "008", "009", "010", "011", "012",
+                "013", "014", "015", "016", "017", "018", "019", "020", "021", "022"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_existing_db_gets_baseline() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        // Simulate existing database created before migration tracking
+        // This represents the 001_initial schema (without later migrations like priority)
+        // Baseline marks 001 as applied, then the remaining migrations will run
+        conn.execute_batch("
+            CREATE TABLE features (
+                id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL,
+                parent_id TEXT,
+                title TEXT NOT NULL,
+                story TEXT,
+                details TEXT,
+                state TEXT NOT NULL DEFAULT 'proposed',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE projects (id TEXT PRIMARY KEY, name TEXT, description TEXT, created_at TEXT, updated_at TEXT);
+            CREATE TABLE project_directories (id TEXT PRIMARY KEY, project_id TEXT, path TEXT, git_remote TEXT, is_primary INTEGER, created_at TEXT);
+            CREATE TABLE feature_history (id TEXT PRIMARY KEY, feature_id TEXT, session_id TEXT, summary TEXT, files_changed JSON, author TEXT, created_at TEXT);
+            CREATE TABLE sessions (id TEXT PRIMARY KEY, feature_id TEXT, goal TEXT, status TEXT, feature_version_before INTEGER, feature_version_after INTEGER, created_at TEXT, completed_at TEXT);
+            CREATE TABLE tasks (id TEXT PRIMARY KEY, session_id TEXT, parent_id TEXT, title TEXT, scope TEXT, status TEXT, agent_type TEXT, worktree_path TEXT, branch TEXT, created_at TEXT);
+            CREATE INDEX idx_features_project ON features(project_id);
+            CREATE INDEX idx_features_parent ON features(parent_id);
+        ").unwrap();
+
+        // Run migrations - should detect existing DB and baseline, then apply remaining
+        run_migrations(&conn).unwrap();
+
+        let versions = get_applied_migrations(&conn).unwrap();
+        assert_eq!(
+            versions,
+            vec![
+                "001", "002", "003", "004", "005", "006", "007", "008", "009", "010", "011", "012",
+                "013", "014", "015", "016", "017", "018", "019", "020", "021", "022"
+            ]
+        );
+    }
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?",
+            [name],
+            |row| row.get::<_, i32>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn test_rollback_runs_down_scripts_and_migrations_reapply() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+
+        let reverted = rollback(&conn, "012").unwrap();
+
+        assert_eq!(
+            reverted,
+            vec!["022", "021", "020", "019", "018", "017", "016", "015", "014", "013"]
+        );
+        assert_eq!(applied_version(&conn).unwrap().as_deref(), Some("012"));
+        assert!(!table_exists(&conn, "tags"));
+        assert_eq!(pending(&conn).unwrap().len(), 10);
+
+        run_migrations(&conn).unwrap();
+        assert_eq!(applied_version(&conn).unwrap().as_deref(), Some("022"));
+        assert!(table_exists(&conn, "tags"));
+    }
+
+    fn column_exists(conn: &Connection, table: &str, column: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            [table, column],
+            |row| row.get::<_, i32>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn test_rollback_to_008_restores_the_old_tasks_table() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (id, title, agent_type, created_at) VALUES ('t1', 'Built-in', 'codex', 'now');
+             INSERT INTO tasks (id, title, agent_type, created_at) VALUES ('t2', 'Custom', 'aider', 'now');",
+        )
+        .unwrap();
+
+        let reverted = rollback(&conn, "008").unwrap();
+
+        assert_eq!(reverted.len(), 14);
+        assert_eq!(applied_version(&conn).unwrap().as_deref(), Some("008"));
+        assert!(!table_exists(&conn, "agent_types"));
+        assert!(!column_exists(&conn, "tasks", "result"));
+        assert!(!column_exists(&conn, "tasks", "started_at"));
+        assert!(!column_exists(&conn, "sessions", "kind"));
+        assert!(!column_exists(&conn, "projects", "max_concurrent_sessions"));
+        let agent_types: Vec<Option<String>> = conn
+            .prepare("SELECT agent_type FROM tasks ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(agent_types, vec![Some("codex".to_string()), None]);
+
+        run_migrations(&conn).unwrap();
+        assert_eq!(applied_version(&conn).unwrap().as_deref(), Some("022"));
+        assert!(table_exists(&conn, "agent_types"));
+        assert!(column_exists(&conn, "sessions", "kind"));
+    }
+
+    #[test]
+    fn test_rollback_refuses_migrations_without_down_scripts() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+
+        assert!(rollback(&conn, "007").is_err());
+        assert!(rollback(&conn, "999").is_err());
+        assert_eq!(applied_version(&conn).unwrap().as_deref(), Some("022"));
+    }
+
+    #[test]
+    fn test_edited_migrations_are_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn.execute(
+            "UPDATE schema_migrations SET checksum = 'edited' WHERE version = '005'",
+            [],
+        )
+        .unwrap();
+
+        let err = run_migrations(&conn).unwrap_err();
+
+        assert!(err.to_string().contains("005"));
+        let states: Vec<MigrationState> = status(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.state)
+            .collect();
+        assert_eq!(states[4], MigrationState::Modified);
+        assert_eq!(states[3], MigrationState::Applied);
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES ('999', 'future', 'now')",
+            [],
+        )
+        .unwrap();
+
+        let err = run_migrations(&conn).unwrap_err();
+
+        assert!(err.to_string().contains("999"));
+        let last = status(&conn).unwrap().pop().unwrap();
+        assert_eq!(
+            (last.version.as_str(), last.state),
+            ("999", MigrationState::Unknown)
+        );
+    }
+
+    #[test]
+    fn test_checksums_are_recorded_for_databases_tracked_without_them() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE schema_migrations (version TEXT PRIMARY KEY, name TEXT NOT NULL, applied_at TEXT NOT NULL)",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+        conn.execute("UPDATE schema_migrations SET checksum = NULL", [])
+            .unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let missing: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM schema_migrations WHERE checksum IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(missing, 0);
+    }
+}
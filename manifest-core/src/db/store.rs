@@ -0,0 +1,600 @@
+//! The operations a storage backend provides.
+//!
+//! [`Database`](super::Database) hands every call to a [`Store`]: the
+//! SQLite store for a local file, or the PostgreSQL store when
+//! `MANIFEST_DATABASE_URL` names a server. Operations built only from other
+//! operations are provided here, so the backends share them.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use super::{ManifestError, MigrationStatus, PendingMigration};
+use crate::models::*;
+
+/// Which kind of database a [`Store`] keeps its data in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+pub trait Store: Send + Sync {
+    /// The kind of database behind this store.
+    fn backend(&self) -> Backend;
+
+    /// Apply pending migrations. Refuses a database with a newer schema than
+    /// this build, or whose applied migrations were edited since.
+    fn migrate(&self) -> Result<()>;
+
+    /// Every migration this build knows, plus any applied by a newer one.
+    fn migration_status(&self) -> Result<Vec<MigrationStatus>>;
+
+    /// The migrations [`Store::migrate`] would apply, in order.
+    fn pending_migrations(&self) -> Result<Vec<PendingMigration>>;
+
+    /// The down scripts rolling back to `version` would run, newest first.
+    fn plan_rollback(&self, version: &str) -> Result<Vec<PendingMigration>>;
+
+    /// Revert the migrations applied after `version` with their down scripts.
+    /// Nothing is reverted if any of them has no down script.
+    fn rollback_migrations(&self, version: &str) -> Result<Vec<&'static str>>;
+
+    // ============================================================
+    // Backup operations
+    // ============================================================
+
+    /// Copy the whole database to `path`, consistently even while the server
+    /// keeps writing. A failed backup never leaves a partial file at `path`.
+    fn backup_to(&self, path: &Path) -> Result<()>;
+
+    /// Replace the database's contents with the backup at `path`, then bring
+    /// it up to this build's schema. Returns the backup's schema version.
+    ///
+    /// Refuses files that aren't Manifest databases and backups from a newer
+    /// schema, which this build couldn't read.
+    fn restore_from(&self, path: &Path) -> Result<String>;
+
+    // ============================================================
+    // Project operations
+    // ============================================================
+
+    /// List a page of projects, ordered by name.
+    fn list_projects(&self, query: &PageQuery) -> Result<Page<Project>>;
+
+    fn get_all_projects(&self) -> Result<Vec<Project>> {
+        Ok(self.list_projects(&PageQuery::default())?.items)
+    }
+
+    fn get_project(&self, id: Uuid) -> Result<Option<Project>>;
+
+    fn create_project(&self, input: CreateProjectInput) -> Result<Project>;
+
+    fn update_project(&self, id: Uuid, input: UpdateProjectInput) -> Result<Option<Project>>;
+
+    fn delete_project(&self, id: Uuid) -> Result<bool>;
+
+    // ============================================================
+    // Project Directory operations
+    // ============================================================
+
+    fn get_project_directories(&self, project_id: Uuid) -> Result<Vec<ProjectDirectory>>;
+
+    fn add_project_directory(
+        &self,
+        project_id: Uuid,
+        input: AddDirectoryInput,
+    ) -> Result<ProjectDirectory>;
+
+    fn remove_project_directory(&self, id: Uuid) -> Result<bool>;
+
+    fn get_project_with_directories(&self, id: Uuid) -> Result<Option<ProjectWithDirectories>> {
+        let project = match self.get_project(id)? {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+
+        let directories = self.get_project_directories(id)?;
+
+        Ok(Some(ProjectWithDirectories {
+            project,
+            directories,
+        }))
+    }
+
+    /// Find a project by a directory path.
+    ///
+    /// Returns the project and matching directory if the path matches exactly,
+    /// or if the path is a subdirectory of a registered project directory.
+    fn get_project_by_directory(&self, path: &str) -> Result<Option<ProjectWithDirectories>>;
+
+    // ============================================================
+    // Agent Type operations
+    // ============================================================
+
+    fn get_agent_types(&self, project_id: Uuid) -> Result<Vec<AgentTypeConfig>>;
+
+    fn get_agent_type(&self, id: Uuid) -> Result<Option<AgentTypeConfig>>;
+
+    fn create_agent_type(
+        &self,
+        project_id: Uuid,
+        input: CreateAgentTypeInput,
+    ) -> Result<AgentTypeConfig>;
+
+    fn update_agent_type(
+        &self,
+        id: Uuid,
+        input: UpdateAgentTypeInput,
+    ) -> Result<Option<AgentTypeConfig>>;
+
+    /// Unregister an agent type. Existing tasks keep their `agent_type`, but
+    /// no new tasks can be assigned to it.
+    fn delete_agent_type(&self, id: Uuid) -> Result<bool>;
+
+    // ============================================================
+    // Tag operations
+    // ============================================================
+
+    fn get_tags(&self, project_id: Uuid) -> Result<Vec<Tag>>;
+
+    fn get_tag(&self, id: Uuid) -> Result<Option<Tag>>;
+
+    fn create_tag(&self, project_id: Uuid, input: CreateTagInput) -> Result<Tag>;
+
+    fn update_tag(&self, id: Uuid, input: UpdateTagInput) -> Result<Option<Tag>>;
+
+    /// Delete a tag, removing it from every feature that has it.
+    fn delete_tag(&self, id: Uuid) -> Result<bool>;
+
+    // ============================================================
+    // Feature operations
+    // ============================================================
+
+    /// List a page of features, across all projects or in one, filtered and
+    /// sorted in SQL.
+    ///
+    /// An unknown state or sort key is a validation error.
+    fn list_features(
+        &self,
+        project_id: Option<Uuid>,
+        query: &ListFeaturesQuery,
+    ) -> Result<Page<Feature>>;
+
+    /// Get all features, unpaginated.
+    fn get_all_features(&self) -> Result<Vec<Feature>> {
+        Ok(self
+            .list_features(None, &ListFeaturesQuery::default())?
+            .items)
+    }
+
+    /// Get a project's features, unpaginated.
+    fn get_features_by_project(&self, project_id: Uuid) -> Result<Vec<Feature>> {
+        Ok(self
+            .list_features(Some(project_id), &ListFeaturesQuery::default())?
+            .items)
+    }
+
+    /// Resolve a feature reference to an id.
+    ///
+    /// Ids are returned as is, without checking that the feature exists.
+    /// Keys are unique across projects. Paths are looked up among current paths first, then among the paths
+    /// of renamed or moved features. A bare path found in several projects
+    /// is a validation error.
+    fn resolve_feature_ref(&self, reference: &FeatureRef) -> Result<Option<Uuid>>;
+
+    /// Resolve a feature reference within a project, as written in a
+    /// `Feature:` commit trailer: the feature's UUID, its key (`MAN-42`),
+    /// its path (`auth/oauth-login`), or just its slug (`oauth-login`).
+    ///
+    /// A slug that matches several features is a validation error.
+    fn resolve_feature(&self, project_id: Uuid, reference: &str) -> Result<Option<Feature>> {
+        let reference = reference.trim();
+        if let Ok(id) = Uuid::parse_str(reference) {
+            return Ok(self.get_feature(id)?.filter(|f| f.project_id == project_id));
+        }
+        if is_feature_key(reference) {
+            let id = self.resolve_feature_ref(&FeatureRef::Key(reference.to_string()))?;
+            if let Some(id) = id {
+                return Ok(self.get_feature(id)?.filter(|f| f.project_id == project_id));
+            }
+        }
+
+        let slug = reference.trim_matches('/').to_ascii_lowercase();
+        let by_path = FeatureRef::Path {
+            project_id: Some(project_id),
+            path: slug.clone(),
+        };
+        if let Some(id) = self.resolve_feature_ref(&by_path)? {
+            return self.get_feature(id);
+        }
+
+        let mut matches: Vec<Feature> = self
+            .get_features_by_project(project_id)?
+            .into_iter()
+            .filter(|f| f.slug == slug)
+            .collect();
+
+        if matches.len() > 1 {
+            let ids: Vec<String> = matches.iter().map(|f| f.id.to_string()).collect();
+            return Err(ManifestError::validation(format!(
+                "Feature reference '{}' is ambiguous (matches {}). Use the feature id instead",
+                reference,
+                ids.join(", ")
+            ))
+            .into());
+        }
+        Ok(matches.pop())
+    }
+
+    fn get_feature(&self, id: Uuid) -> Result<Option<Feature>>;
+
+    /// Get the diff between current and desired details for a feature.
+    fn get_feature_diff(&self, id: Uuid) -> Result<Option<FeatureDiff>> {
+        let feature = match self.get_feature(id)? {
+            Some(f) => f,
+            None => return Ok(None),
+        };
+
+        let has_changes =
+            feature.desired_details.is_some() && feature.desired_details != feature.details;
+
+        Ok(Some(FeatureDiff {
+            has_changes,
+            current: feature.details,
+            desired: feature.desired_details,
+        }))
+    }
+
+    fn create_feature(&self, project_id: Uuid, input: CreateFeatureInput) -> Result<Feature>;
+
+    /// Create multiple features in a single transaction.
+    /// All features are created atomically - if any fails, all are rolled back.
+    fn create_features_bulk(
+        &self,
+        project_id: Uuid,
+        inputs: Vec<CreateFeatureInput>,
+    ) -> Result<Vec<Feature>>;
+
+    /// Update a feature. Renaming or moving it gives it (and its subtree) new
+    /// paths; the old ones are kept as redirects.
+    fn update_feature(&self, id: Uuid, input: UpdateFeatureInput) -> Result<Option<Feature>>;
+
+    fn delete_feature(&self, id: Uuid) -> Result<bool>;
+
+    fn get_root_features(&self, project_id: Uuid) -> Result<Vec<Feature>>;
+
+    fn get_children(&self, parent_id: Uuid) -> Result<Vec<Feature>>;
+
+    fn is_leaf(&self, feature_id: Uuid) -> Result<bool>;
+
+    /// Search features by key, title and details, optionally limited to a
+    /// project and to features having all of `tags`.
+    /// Returns a page of summaries ranked by relevance (an exact key match
+    /// first, then title matches, then details matches), 10 unless
+    /// `page.limit` says otherwise.
+    fn search_features(
+        &self,
+        query: &str,
+        project_id: Option<Uuid>,
+        tags: &[String],
+        page: &PageQuery,
+    ) -> Result<Page<FeatureSummary>>;
+
+    fn get_feature_tree(&self, project_id: Uuid) -> Result<Vec<FeatureTreeNode>> {
+        let features = self.get_features_by_project(project_id)?;
+
+        // Group features by parent_id
+        let mut children_map: HashMap<Option<Uuid>, Vec<Feature>> = HashMap::new();
+        for feature in features {
+            children_map
+                .entry(feature.parent_id)
+                .or_default()
+                .push(feature);
+        }
+
+        // Recursively build tree starting from roots (parent_id = None)
+        fn build_subtree(
+            parent_id: Option<Uuid>,
+            children_map: &HashMap<Option<Uuid>, Vec<Feature>>,
+        ) -> Vec<FeatureTreeNode> {
+            children_map
+                .get(&parent_id)
+                .map(|features| {
+                    features
+                        .iter()
+                        .map(|f| FeatureTreeNode {
+                            feature: f.clone(),
+                            children: build_subtree(Some(f.id), children_map),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        Ok(build_subtree(None, &children_map))
+    }
+
+    // ============================================================
+    // Code Link operations
+    // ============================================================
+
+    fn get_code_links(&self, feature_id: Uuid) -> Result<Vec<FeatureCodeLink>>;
+
+    fn get_code_link(&self, id: Uuid) -> Result<Option<FeatureCodeLink>>;
+
+    /// Link a feature to a location in one of its project's directories.
+    fn create_code_link(
+        &self,
+        feature_id: Uuid,
+        input: CreateCodeLinkInput,
+    ) -> Result<FeatureCodeLink>;
+
+    fn update_code_link(
+        &self,
+        id: Uuid,
+        input: UpdateCodeLinkInput,
+    ) -> Result<Option<FeatureCodeLink>>;
+
+    fn delete_code_link(&self, id: Uuid) -> Result<bool>;
+
+    /// Find the features whose code links cover `path`.
+    ///
+    /// A relative path is matched against links in every project directory.
+    /// An absolute path is first made relative to the directory containing it.
+    fn find_features_by_path(&self, project_id: Uuid, path: &str) -> Result<Vec<FeatureCodeMatch>>;
+
+    // ============================================================
+    // Feature Relation operations
+    // ============================================================
+
+    /// Relations from or to a feature, oldest first.
+    fn get_relations(&self, feature_id: Uuid) -> Result<Vec<FeatureRelation>>;
+
+    /// Relate `feature_id` to another feature of its project.
+    ///
+    /// A dependency (`depends_on` or `blocks`) that would close a cycle is a
+    /// validation error; relating the same pair twice with the same kind is
+    /// an invalid state.
+    fn create_relation(
+        &self,
+        feature_id: Uuid,
+        input: CreateRelationInput,
+    ) -> Result<FeatureRelation>;
+
+    fn delete_relation(&self, id: Uuid) -> Result<bool>;
+
+    /// Features a feature depends on (upstream) or that depend on it
+    /// (downstream), directly or transitively, ordered by priority then title.
+    fn get_dependencies(
+        &self,
+        feature_id: Uuid,
+        direction: DependencyDirection,
+    ) -> Result<Vec<Feature>>;
+
+    /// Leaf features that are specified and whose dependencies are all
+    /// implemented (or deprecated), ordered by priority then title.
+    fn get_next_features(&self, project_id: Uuid) -> Result<Vec<Feature>>;
+
+    /// A project's relations and the features they connect.
+    fn get_feature_graph(&self, project_id: Uuid) -> Result<FeatureGraph>;
+
+    // ============================================================
+    // Acceptance Criterion operations
+    // ============================================================
+
+    /// A feature's acceptance criteria, in order.
+    fn get_criteria(&self, feature_id: Uuid) -> Result<Vec<AcceptanceCriterion>>;
+
+    fn get_criterion(&self, id: Uuid) -> Result<Option<AcceptanceCriterion>>;
+
+    /// Add an acceptance criterion to a feature, at the end unless a
+    /// position is given.
+    fn create_criterion(
+        &self,
+        feature_id: Uuid,
+        input: CreateCriterionInput,
+    ) -> Result<AcceptanceCriterion>;
+
+    fn update_criterion(
+        &self,
+        id: Uuid,
+        input: UpdateCriterionInput,
+    ) -> Result<Option<AcceptanceCriterion>>;
+
+    fn delete_criterion(&self, id: Uuid) -> Result<bool>;
+
+    /// Refuse to mark a feature implemented while some of its acceptance
+    /// criteria aren't passing. Features without criteria always pass.
+    fn ensure_criteria_passing(&self, feature_id: Uuid) -> Result<()> {
+        let open: Vec<String> = self
+            .get_criteria(feature_id)?
+            .into_iter()
+            .filter(|c| c.status != CriterionStatus::Passing)
+            .map(|c| format!("#{} {} ({})", c.position + 1, c.text, c.status.as_str()))
+            .collect();
+        if open.is_empty() {
+            return Ok(());
+        }
+        Err(ManifestError::invalid_state(format!(
+            "Cannot mark the feature implemented while acceptance criteria aren't passing: {}. \
+             Verify them first, or set allow_unverified_criteria.",
+            open.join("; ")
+        ))
+        .into())
+    }
+
+    // ============================================================
+    // Test Result operations
+    // ============================================================
+
+    /// Match the test cases of a report to the project's features and store
+    /// them as the features' latest results.
+    ///
+    /// A case matches the acceptance criteria whose `test_name` it ends with,
+    /// which then become `passing` or `failing`, and the features whose key
+    /// or (unambiguous) slug appears in its name. The stored results of every
+    /// matched feature are replaced. Returns `None` if the project doesn't exist.
+    fn record_test_results(
+        &self,
+        project_id: Uuid,
+        cases: Vec<TestCaseResult>,
+    ) -> Result<Option<TestReportResult>>;
+
+    /// Latest test results of the project's features that have any, in
+    /// feature path order.
+    fn get_test_summaries(&self, project_id: Uuid) -> Result<Vec<FeatureTestSummary>>;
+
+    /// A feature's latest test results, failures first.
+    fn get_test_results(&self, feature_id: Uuid) -> Result<Vec<TestResult>>;
+
+    // ============================================================
+    // Snapshot operations
+    // ============================================================
+
+    /// Copy every row of a project, in every table, into a snapshot.
+    /// Returns `None` if the project doesn't exist.
+    fn export_snapshot(&self, project_id: Uuid) -> Result<Option<ProjectSnapshot>>;
+
+    /// Restore a project from a snapshot, keeping its IDs.
+    ///
+    /// Refuses to overwrite an existing project unless `replace` is set, in
+    /// which case all of the project's current rows are deleted first. Rows
+    /// that don't belong to the snapshot's project are rejected.
+    fn import_snapshot(&self, snapshot: &ProjectSnapshot, replace: bool) -> Result<Project>;
+
+    // ============================================================
+    // Session operations
+    // ============================================================
+
+    fn get_session(&self, id: Uuid) -> Result<Option<Session>>;
+
+    /// List a page of a feature's sessions, newest first.
+    fn list_sessions_by_feature(
+        &self,
+        feature_id: Uuid,
+        query: &PageQuery,
+    ) -> Result<Page<Session>>;
+
+    /// Get all sessions for a feature.
+    fn get_sessions_by_feature(&self, feature_id: Uuid) -> Result<Vec<Session>> {
+        Ok(self
+            .list_sessions_by_feature(feature_id, &PageQuery::default())?
+            .items)
+    }
+
+    /// Get the active sessions for a feature, oldest first.
+    fn get_active_sessions(&self, feature_id: Uuid) -> Result<Vec<Session>> {
+        let mut sessions: Vec<Session> = self
+            .get_sessions_by_feature(feature_id)?
+            .into_iter()
+            .filter(|s| s.status == SessionStatus::Active)
+            .collect();
+        sessions.reverse();
+        Ok(sessions)
+    }
+
+    fn create_session(&self, input: CreateSessionInput) -> Result<SessionResponse>;
+
+    fn get_session_status(&self, id: Uuid) -> Result<Option<SessionStatusResponse>> {
+        let session = match self.get_session(id)? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+
+        let feature = self
+            .get_feature(session.feature_id)?
+            .ok_or_else(|| ManifestError::not_found("Feature"))?;
+
+        let tasks = self.get_tasks_by_session(id)?;
+
+        Ok(Some(SessionStatusResponse {
+            session,
+            feature: SessionFeatureSummary {
+                id: feature.id,
+                title: feature.title,
+            },
+            tasks,
+        }))
+    }
+
+    fn complete_session(
+        &self,
+        id: Uuid,
+        input: CompleteSessionInput,
+    ) -> Result<Option<SessionCompletionResult>>;
+
+    // ============================================================
+    // Task operations
+    // ============================================================
+
+    fn get_task(&self, id: Uuid) -> Result<Option<Task>>;
+
+    fn get_tasks_by_session(&self, session_id: Uuid) -> Result<Vec<Task>>;
+
+    fn get_task_children(&self, parent_id: Uuid) -> Result<Vec<Task>>;
+
+    fn create_task(&self, session_id: Uuid, input: CreateTaskInput) -> Result<Task>;
+
+    fn update_task(&self, id: Uuid, input: UpdateTaskInput) -> Result<bool>;
+
+    // ============================================================
+    // Feature History operations
+    // ============================================================
+
+    fn create_history_entry(&self, input: CreateHistoryInput) -> Result<FeatureHistory>;
+
+    /// Record a commit that references a leaf feature.
+    ///
+    /// The commit is attached to the feature's active session, so it lands in
+    /// that session's history entry on completion. With several active
+    /// sessions, the one whose tasks are on the commit's branch wins, else the
+    /// oldest. Without an active session the commit gets its own history
+    /// entry. Recording the same commit twice is a no-op.
+    fn record_commit(&self, feature_id: Uuid, input: RecordCommitInput) -> Result<RecordedCommit>;
+
+    /// List a page of a feature's history, newest first.
+    fn list_feature_history(
+        &self,
+        feature_id: Uuid,
+        query: &PageQuery,
+    ) -> Result<Page<FeatureHistory>>;
+
+    fn get_feature_history(&self, feature_id: Uuid) -> Result<Vec<FeatureHistory>> {
+        Ok(self
+            .list_feature_history(feature_id, &PageQuery::default())?
+            .items)
+    }
+
+    // ============================================================
+    // Metrics
+    // ============================================================
+
+    /// Compute delivery metrics for a project.
+    ///
+    /// Task timings come from live tasks and from the task reports kept in
+    /// history, since tasks themselves are deleted when their session completes.
+    fn get_project_metrics(&self, project_id: Uuid) -> Result<Option<ProjectMetrics>>;
+
+    // ============================================================
+    // Idempotency keys
+    // ============================================================
+
+    /// Claim `key` for `request` (its method and path), unless an earlier
+    /// request holds it. Keys claimed longer than `ttl` ago are forgotten.
+    fn claim_idempotency_key(
+        &self,
+        key: &str,
+        request: &str,
+        ttl: Duration,
+    ) -> Result<IdempotencyClaim>;
+
+    /// Keep the response of the request that claimed `key` for its retries.
+    fn complete_idempotency_key(&self, key: &str, response: &StoredResponse) -> Result<()>;
+
+    /// Forget `key`, so that a retry runs the request again.
+    fn release_idempotency_key(&self, key: &str) -> Result<()>;
+}
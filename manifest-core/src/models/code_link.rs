@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+use super::feature::Feature;
+
+/// Where a feature lives in the source tree.
+///
+/// A link points at a path inside one of the project's directories. The path
+/// is either a glob (`src/api/**/*.rs`) or a single file, optionally narrowed
+/// to a line range and/or a symbol name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureCodeLink {
+    pub id: Uuid,
+    pub feature_id: Uuid,
+    /// The project directory `path` is relative to.
+    pub directory_id: Uuid,
+    /// Path relative to the directory. `*` and `?` match within a path
+    /// segment, `**` matches any number of segments. A path without
+    /// wildcards also covers everything below it.
+    pub path: String,
+    /// First line of the linked range (1-based, inclusive).
+    pub line_start: Option<u32>,
+    /// Last line of the linked range (inclusive).
+    pub line_end: Option<u32>,
+    /// Function, type or module name implementing the feature.
+    pub symbol: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FeatureCodeLink {
+    /// Returns true if this link covers `path` (relative to the link's directory).
+    pub fn matches(&self, path: &str) -> bool {
+        self.matcher().is_match(path)
+    }
+
+    /// The link's path compiled once, for matching many paths.
+    pub fn matcher(&self) -> CodePathMatcher {
+        // Stored paths were validated when the link was saved
+        CodePathMatcher::new(&self.path).unwrap_or(CodePathMatcher::Nothing)
+    }
+}
+
+/// A code link path compiled for matching.
+#[derive(Debug, Clone)]
+pub enum CodePathMatcher {
+    /// A path without wildcards: the path itself and everything below it.
+    Prefix(String),
+    Glob(GlobMatcher),
+    /// An invalid pattern, which matches nothing.
+    Nothing,
+}
+
+impl CodePathMatcher {
+    /// Compile a code link path. Only `*`, `?` and `**` are wildcards; other
+    /// glob syntax (`[...]`, `{...}`) is taken literally.
+    pub fn new(pattern: &str) -> Result<Self, globset::Error> {
+        let pattern = normalize_code_path(pattern);
+        if !pattern.contains(['*', '?']) {
+            return Ok(Self::Prefix(pattern));
+        }
+        let escaped: String = pattern
+            .chars()
+            .map(|c| match c {
+                '[' | ']' | '{' | '}' => format!("[{}]", c),
+                c => c.to_string(),
+            })
+            .collect();
+        let glob = GlobBuilder::new(&escaped).literal_separator(true).build()?;
+        Ok(Self::Glob(glob.compile_matcher()))
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        let path = normalize_code_path(path);
+        match self {
+            Self::Prefix(prefix) => {
+                path == *prefix
+                    || path
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+            Self::Glob(glob) => glob.is_match(&path),
+            Self::Nothing => false,
+        }
+    }
+}
+
+/// Input for linking a feature to code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCodeLinkInput {
+    /// Defaults to the project's primary directory (or its only one).
+    #[serde(default)]
+    pub directory_id: Option<Uuid>,
+    pub path: String,
+    #[serde(default)]
+    pub line_start: Option<u32>,
+    #[serde(default)]
+    pub line_end: Option<u32>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+/// Input for updating a code link. Omitted fields are left unchanged; the
+/// optional ones are cleared by passing `null` (`Some(None)`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCodeLinkInput {
+    pub path: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub line_start: Option<Option<u32>>,
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub line_end: Option<Option<u32>>,
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub symbol: Option<Option<String>>,
+}
+
+/// Tell a field set to `null` (`Some(None)`) from an omitted one (`None`).
+fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A feature found by reverse lookup on a path, with the links that matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureCodeMatch {
+    pub feature: Feature,
+    pub links: Vec<FeatureCodeLink>,
+}
+
+/// Normalize a relative path for matching: forward slashes, no `./` prefix
+/// and no trailing slash.
+pub fn normalize_code_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    path.to_string()
+}
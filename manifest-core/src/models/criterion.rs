@@ -0,0 +1,85 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A checkable statement a feature must satisfy before it is implemented.
+///
+/// Criteria are ordered by `position` within their feature. Marking a feature
+/// implemented is refused while any of its criteria isn't `passing`, unless
+/// the caller explicitly allows it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptanceCriterion {
+    pub id: Uuid,
+    pub feature_id: Uuid,
+    /// 0-based position within the feature.
+    pub position: u32,
+    pub text: String,
+    pub status: CriterionStatus,
+    /// Name of the test that verifies the criterion.
+    pub test_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Verification status of an acceptance criterion.
+///
+/// - `Unverified`: Nobody has checked it yet (the initial status)
+/// - `Passing`: Verified to hold
+/// - `Failing`: Checked and found not to hold
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CriterionStatus {
+    #[default]
+    Unverified,
+    Passing,
+    Failing,
+}
+
+impl CriterionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unverified => "unverified",
+            Self::Passing => "passing",
+            Self::Failing => "failing",
+        }
+    }
+}
+
+impl FromStr for CriterionStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unverified" => Ok(Self::Unverified),
+            "passing" => Ok(Self::Passing),
+            "failing" => Ok(Self::Failing),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Input for adding an acceptance criterion to a feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCriterionInput {
+    pub text: String,
+    /// Defaults to `Unverified`.
+    #[serde(default)]
+    pub status: Option<CriterionStatus>,
+    #[serde(default)]
+    pub test_name: Option<String>,
+    /// Insert at this position, moving later criteria down. Defaults to the end.
+    #[serde(default)]
+    pub position: Option<u32>,
+}
+
+/// Input for updating an acceptance criterion. Omitted fields are left unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateCriterionInput {
+    pub text: Option<String>,
+    pub status: Option<CriterionStatus>,
+    pub test_name: Option<String>,
+    /// Move the criterion to this position.
+    pub position: Option<u32>,
+}
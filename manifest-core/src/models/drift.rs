@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::feature::Feature;
+use super::history::CommitRef;
+
+/// Implemented features whose code changed after their spec was last updated.
+///
+/// A feature's code is the paths it is linked to plus the files touched by
+/// the commits in its history. Commits already in its history don't count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub project_id: Uuid,
+    pub generated_at: DateTime<Utc>,
+    /// Drifted features, stalest spec first.
+    pub features: Vec<FeatureDrift>,
+}
+
+/// A feature and the commits that changed its code since `feature.updated_at`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureDrift {
+    pub feature: Feature,
+    /// Oldest first.
+    pub commits: Vec<DriftCommit>,
+}
+
+/// A commit that touched a drifted feature's code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftCommit {
+    #[serde(flatten)]
+    pub commit: CommitRef,
+    pub committed_at: DateTime<Utc>,
+    /// Project directory the commit was found in.
+    pub directory_id: Uuid,
+    /// The feature's files the commit touched, relative to the directory.
+    pub files: Vec<String>,
+}
@@ -4,6 +4,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::project::is_project_key;
+
 /// A living description of a system capability.
 ///
 /// Unlike traditional issue trackers where items are "closed" and forgotten,
@@ -20,7 +22,15 @@ pub struct Feature {
     pub id: Uuid,
     pub project_id: Uuid,
     pub parent_id: Option<Uuid>,
+    /// Short key made of the project's key prefix and a number allocated at
+    /// creation (`MAN-42`). Never reused or changed.
+    pub key: String,
     pub title: String,
+    /// Title slug, unique among siblings (`oauth-integration`).
+    pub slug: String,
+    /// Slugs from the root down to this feature (`auth/oauth-integration`),
+    /// unique within the project. Accepted wherever a feature id is.
+    pub path: String,
     /// Feature details including user stories, implementation notes, and technical context.
     /// User stories can be embedded here in "As a... I want... So that..." format.
     pub details: Option<String>,
@@ -31,6 +41,8 @@ pub struct Feature {
     /// Priority for ordering features within a parent. Lower values appear first.
     /// Use this to indicate implementation order without polluting feature titles.
     pub priority: i32,
+    /// Names of the feature's tags, sorted.
+    pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -91,6 +103,9 @@ pub struct CreateFeatureInput {
     pub state: Option<FeatureState>,
     /// Priority for ordering within parent. Lower values first. Defaults to 0.
     pub priority: Option<i32>,
+    /// Tag names. Tags the project doesn't have yet are created.
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// Input for updating an existing feature. All fields are optional for partial updates.
@@ -105,6 +120,8 @@ pub struct UpdateFeatureInput {
     pub state: Option<FeatureState>,
     /// Update priority for ordering within parent.
     pub priority: Option<i32>,
+    /// Replace the feature's tags. Tags the project doesn't have yet are created.
+    pub tags: Option<Vec<String>>,
 }
 
 /// A feature with its nested children, used for tree responses.
@@ -137,9 +154,12 @@ pub struct FeatureSummary {
     pub id: Uuid,
     pub project_id: Uuid,
     pub parent_id: Option<Uuid>,
+    pub key: String,
     pub title: String,
+    pub path: String,
     pub state: FeatureState,
     pub priority: i32,
+    pub tags: Vec<String>,
 }
 
 impl From<Feature> for FeatureSummary {
@@ -148,18 +168,179 @@ impl From<Feature> for FeatureSummary {
             id: f.id,
             project_id: f.project_id,
             parent_id: f.parent_id,
+            key: f.key,
             title: f.title,
+            path: f.path,
             state: f.state,
             priority: f.priority,
+            tags: f.tags,
+        }
+    }
+}
+
+/// A reference to a feature: its id, its key or its path.
+///
+/// Keys are unique across projects and are only recognized in upper case
+/// (`MAN-42`), so they can't be confused with slugs. Paths are unique within
+/// a project. A bare path is looked up across all projects; prefix it with
+/// the project id (`<project-id>/auth/oauth`) when several projects have it.
+/// Paths a feature had before being renamed or moved keep resolving to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureRef {
+    Id(Uuid),
+    Key(String),
+    Path {
+        project_id: Option<Uuid>,
+        path: String,
+    },
+}
+
+impl FeatureRef {
+    /// Look a bare path up in `project_id` only.
+    pub fn in_project(self, project_id: Uuid) -> Self {
+        match self {
+            FeatureRef::Path {
+                project_id: None,
+                path,
+            } => FeatureRef::Path {
+                project_id: Some(project_id),
+                path,
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<Uuid> for FeatureRef {
+    fn from(id: Uuid) -> Self {
+        FeatureRef::Id(id)
+    }
+}
+
+impl From<&str> for FeatureRef {
+    fn from(s: &str) -> Self {
+        let s = s.trim().trim_matches('/');
+        if let Ok(id) = Uuid::parse_str(s) {
+            return FeatureRef::Id(id);
+        }
+        if is_feature_key(s) {
+            return FeatureRef::Key(s.to_string());
+        }
+        let (project_id, path) = match s.split_once('/') {
+            Some((first, rest)) => match Uuid::parse_str(first) {
+                Ok(project_id) => (Some(project_id), rest),
+                Err(_) => (None, s),
+            },
+            None => (None, s),
+        };
+        FeatureRef::Path {
+            project_id,
+            path: path.to_ascii_lowercase(),
+        }
+    }
+}
+
+impl From<&String> for FeatureRef {
+    fn from(s: &String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl std::fmt::Display for FeatureRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeatureRef::Id(id) => write!(f, "{}", id),
+            FeatureRef::Key(key) => f.write_str(key),
+            FeatureRef::Path {
+                project_id: Some(project_id),
+                path,
+            } => write!(f, "{}/{}", project_id, path),
+            FeatureRef::Path { path, .. } => f.write_str(path),
+        }
+    }
+}
+
+/// Whether `s` has the form of a feature key: an upper-case project key
+/// prefix, a hyphen and a number (`MAN-42`).
+pub fn is_feature_key(s: &str) -> bool {
+    match s.rsplit_once('-') {
+        Some((prefix, number)) => {
+            is_project_key(prefix)
+                && !number.is_empty()
+                && number.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// Lowercase, hyphen-separated form of a feature title, e.g. `oauth-login`.
+///
+/// Used for feature slugs, and to refer to features by name in commit
+/// trailers and branch names.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
         }
     }
+    slug.trim_end_matches('-').to_string()
 }
 
 /// Query parameters for listing features.
-#[derive(Debug, Clone, Deserialize, Default)]
+///
+/// All filters are optional and combine with AND.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ListFeaturesQuery {
     /// Maximum number of features to return.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<u32>,
-    /// Number of features to skip for pagination.
+    /// Number of features to skip. Prefer `cursor`, which is stable while
+    /// features are added.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub offset: Option<u32>,
+    /// `next_cursor` from the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Comma-separated tag names. Only features with all of them are listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Comma-separated states (`specified,implemented`). Features in any of them are listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// Only direct children of this feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
+    /// Only features at most this many levels below a root (0 lists root features).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+    /// `true` lists only leaf features, `false` only features with children.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf: Option<bool>,
+    /// `true` lists only features with pending changes (`desired_details` set).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_pending_changes: Option<bool>,
+    /// `true` lists only features with an active session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_active_session: Option<bool>,
+    /// Only features updated at or after this time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_since: Option<DateTime<Utc>>,
+    /// `priority` (the default, then title), `title`, `updated_at` or
+    /// `created_at`. Prefix with `-` to sort descending.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+/// Split a comma-separated query parameter (`tag`, `state`) into its values.
+pub fn parse_list_param(param: Option<&str>) -> Vec<String> {
+    param
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
 }
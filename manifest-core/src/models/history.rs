@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::task::{AgentType, TaskResult, TaskStatus};
+
 /// An append-only log entry recording work done on a feature.
 ///
 /// Feature history is like `git log` for a feature—it records what was done
@@ -32,6 +34,24 @@ pub struct HistoryDetails {
     /// Git commits created during this work.
     #[serde(default)]
     pub commits: Vec<CommitRef>,
+    /// Reports from the session's tasks, captured before the tasks are deleted.
+    #[serde(default)]
+    pub task_results: Vec<TaskReport>,
+}
+
+/// A task's final status and result as recorded in a history entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskReport {
+    pub task_id: Uuid,
+    pub title: String,
+    pub status: TaskStatus,
+    pub agent_type: AgentType,
+    /// The agent's report, if one was submitted.
+    pub result: Option<TaskResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
 }
 
 /// A reference to a git commit.
@@ -46,6 +66,15 @@ pub struct CommitRef {
     pub author: Option<String>,
 }
 
+impl CommitRef {
+    /// True if both refer to the same commit, allowing either SHA to be abbreviated.
+    pub fn same_commit(&self, other: &CommitRef) -> bool {
+        !self.sha.is_empty()
+            && !other.sha.is_empty()
+            && (self.sha.starts_with(&other.sha) || other.sha.starts_with(&self.sha))
+    }
+}
+
 /// Input for creating a history entry.
 ///
 /// Typically history entries are created automatically when sessions complete,
@@ -56,3 +85,27 @@ pub struct CreateHistoryInput {
     pub session_id: Option<Uuid>,
     pub details: HistoryDetails,
 }
+
+/// Input for recording a commit that references a feature, e.g. from a git hook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordCommitInput {
+    #[serde(flatten)]
+    pub commit: CommitRef,
+    /// Branch the commit was made on. Used to pick between concurrent
+    /// sessions by matching their tasks' branches.
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+/// Where a recorded commit ended up.
+///
+/// Commits go to the feature's active session if there is one, otherwise
+/// they become a history entry of their own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedCommit {
+    pub feature_id: Uuid,
+    pub session_id: Option<Uuid>,
+    pub history_id: Option<Uuid>,
+    /// True if the commit was already recorded and nothing changed.
+    pub already_recorded: bool,
+}
@@ -0,0 +1,21 @@
+/// A response kept under an idempotency key, replayed to retries of the
+/// request that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// What claiming an idempotency key for a request found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// The key is new: run the request, then store its response.
+    Claimed,
+    /// A request with this key is still running.
+    InProgress,
+    /// The request already ran and this was its response.
+    Completed(StoredResponse),
+    /// The key was used for a different request.
+    Mismatch,
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::task::AgentType;
+
+/// Delivery metrics for a project, computed on request from sessions, tasks
+/// and history. Durations are in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMetrics {
+    pub project_id: Uuid,
+    /// Cycle time of every feature with at least one completed session.
+    pub features: Vec<FeatureCycleTime>,
+    /// Task durations and failure rates grouped by agent type.
+    pub agent_types: Vec<AgentTypeMetrics>,
+    /// Completed work per week, starting Mondays in UTC, oldest first.
+    pub weekly_throughput: Vec<WeeklyThroughput>,
+}
+
+/// Time from the first session on a feature to its latest completed session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureCycleTime {
+    pub feature_id: Uuid,
+    pub title: String,
+    /// Number of completed sessions on the feature.
+    pub sessions: u32,
+    pub first_started_at: DateTime<Utc>,
+    pub last_completed_at: DateTime<Utc>,
+    pub cycle_time_secs: i64,
+}
+
+/// Task outcomes and durations for one agent type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentTypeMetrics {
+    pub agent_type: AgentType,
+    /// Tasks that reached `completed` or `failed`.
+    pub finished: u32,
+    pub failed: u32,
+    /// `failed / finished`, or 0 when nothing has finished.
+    pub failure_rate: f64,
+    /// Distribution of `finished_at - started_at` over tasks with both timestamps.
+    pub duration: DurationStats,
+}
+
+/// Summary statistics of a set of durations, in seconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DurationStats {
+    pub count: u32,
+    pub min_secs: Option<i64>,
+    pub median_secs: Option<i64>,
+    pub p90_secs: Option<i64>,
+    pub max_secs: Option<i64>,
+    pub mean_secs: Option<f64>,
+}
+
+impl DurationStats {
+    /// Compute stats over the given durations. Percentiles use nearest rank.
+    pub fn from_secs(mut secs: Vec<i64>) -> Self {
+        if secs.is_empty() {
+            return Self::default();
+        }
+        secs.sort_unstable();
+
+        let count = secs.len();
+        let rank = |p: f64| secs[((p * count as f64).ceil() as usize).clamp(1, count) - 1];
+
+        Self {
+            count: count as u32,
+            min_secs: secs.first().copied(),
+            median_secs: Some(rank(0.5)),
+            p90_secs: Some(rank(0.9)),
+            max_secs: secs.last().copied(),
+            mean_secs: Some(secs.iter().sum::<i64>() as f64 / count as f64),
+        }
+    }
+}
+
+/// Work finished during one week.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyThroughput {
+    /// Monday of the week (UTC).
+    pub week_start: NaiveDate,
+    pub sessions_completed: u32,
+    pub tasks_completed: u32,
+    pub tasks_failed: u32,
+}
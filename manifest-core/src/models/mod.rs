@@ -6,24 +6,55 @@
 //!
 //! - [`Feature`]: Living documentation of system capabilities, forming a hierarchical tree.
 //!   Any node can have content, but only leaf nodes can have sessions.
+//!   Features are addressed by id, key (`MAN-42`) or slug path ([`FeatureRef`]).
 //! - [`FeatureHistory`]: Append-only log of work done on features (like `git log` for a feature).
 //! - [`Project`]: Top-level container with associated directories and features.
+//! - [`FeatureCodeLink`]: Where in a project directory a feature is implemented.
+//! - [`Tag`]: Project-scoped label grouping features across the tree.
+//! - [`FeatureRelation`]: Typed link between two features (`depends_on`, `blocks`, ...).
+//! - [`AcceptanceCriterion`]: Ordered, checkable statement a feature must satisfy.
+//! - [`TestResult`]: Latest outcome of a test matched to a feature from a CI report.
+//!
+//! [`DriftReport`] is computed on request from code links, history and git.
+//! List endpoints return a [`Page`] and take a cursor from the previous one.
+//! A [`ProjectSnapshot`] copies a whole project between databases.
 //!
 //! ## Ephemeral Entities
 //!
 //! These exist only during active work and are deleted when sessions complete:
 //!
-//! - [`Session`]: Active work session on a leaf feature (one at a time per feature).
+//! - [`Session`]: Active work session on a leaf feature (one at a time per feature unless
+//!   the project allows concurrent sessions).
 //! - [`Task`]: Work unit within a session, assigned to an AI agent.
 
+mod code_link;
+mod criterion;
+mod drift;
 mod feature;
 mod history;
+mod idempotency;
+mod metrics;
+mod page;
 mod project;
+mod relation;
 mod session;
+mod snapshot;
+mod tag;
 mod task;
+mod test_result;
 
+pub use code_link::*;
+pub use criterion::*;
+pub use drift::*;
 pub use feature::*;
 pub use history::*;
+pub use idempotency::*;
+pub use metrics::*;
+pub use page::*;
 pub use project::*;
+pub use relation::*;
 pub use session::*;
+pub use snapshot::*;
+pub use tag::*;
 pub use task::*;
+pub use test_result::*;
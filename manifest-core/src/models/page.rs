@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// One page of a list response.
+///
+/// Pass `next_cursor` back as `cursor` to get the following page; it is
+/// `None` on the last page. Cursors point just past the last item returned,
+/// so rows inserted or deleted between requests don't shift later pages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Number of items across all pages. Only counted for the first page
+    /// (requests without a cursor).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+impl<T> Page<T> {
+    /// Convert the items, keeping the cursor and total.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+/// Query parameters for paginated lists.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PageQuery {
+    /// `next_cursor` from the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Maximum number of items to return. Defaults to no limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::task::AgentType;
+
 /// A project containing features.
 ///
 /// Projects are the top-level organizational unit. Each project can have
@@ -11,9 +13,18 @@ use uuid::Uuid;
 pub struct Project {
     pub id: Uuid,
     pub name: String,
+    /// Prefix of the project's feature keys (`MAN` for `MAN-42`). Unique
+    /// across projects and fixed once the project is created.
+    pub key: String,
     pub description: Option<String>,
     /// Project-wide instructions for AI agents (coding guidelines, conventions, etc.).
     pub instructions: Option<String>,
+    /// How many sessions may be active on one feature at once. Defaults to 1;
+    /// higher values allow parallel sessions of distinct kinds.
+    pub max_concurrent_sessions: u32,
+    /// Whether the server creates a git worktree and branch for each new task
+    /// in the primary directory. Only honored when running in local mode.
+    pub provision_worktrees: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -37,10 +48,33 @@ pub struct ProjectDirectory {
     pub created_at: DateTime<Utc>,
 }
 
+/// An agent type registered for a project.
+///
+/// Tasks can only be assigned to registered agent types. Each type carries
+/// default instructions handed to the agent alongside its task, and an
+/// optional cap on how many of its tasks may run at once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentTypeConfig {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    /// Identifier used in `Task.agent_type` (e.g., `claude`, `local-llama`, `human-review`).
+    pub name: AgentType,
+    /// Human-readable name shown in UIs.
+    pub label: String,
+    /// Default instructions for agents of this type (tool usage, review checklist, etc.).
+    pub instructions: Option<String>,
+    /// Maximum number of running tasks of this type across the project. `None` means unlimited.
+    pub max_concurrency: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Input for creating a new project.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateProjectInput {
     pub name: String,
+    /// Feature key prefix. Derived from the name when omitted.
+    #[serde(default)]
+    pub key: Option<String>,
     pub description: Option<String>,
     pub instructions: Option<String>,
 }
@@ -51,6 +85,8 @@ pub struct UpdateProjectInput {
     pub name: Option<String>,
     pub description: Option<String>,
     pub instructions: Option<String>,
+    pub max_concurrent_sessions: Option<u32>,
+    pub provision_worktrees: Option<bool>,
 }
 
 /// Input for adding a directory to a project.
@@ -72,6 +108,23 @@ pub struct UpdateDirectoryInput {
     pub instructions: Option<String>,
 }
 
+/// Input for registering an agent type with a project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAgentTypeInput {
+    pub name: AgentType,
+    pub label: String,
+    pub instructions: Option<String>,
+    pub max_concurrency: Option<u32>,
+}
+
+/// Input for updating a registered agent type. The name cannot be changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateAgentTypeInput {
+    pub label: Option<String>,
+    pub instructions: Option<String>,
+    pub max_concurrency: Option<u32>,
+}
+
 /// A project with its associated directories, used for detailed responses.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectWithDirectories {
@@ -79,3 +132,12 @@ pub struct ProjectWithDirectories {
     pub project: Project,
     pub directories: Vec<ProjectDirectory>,
 }
+
+/// Whether `s` is a valid project key prefix: an upper-case letter followed
+/// by up to nine upper-case letters or digits (`MAN`, `WEB2`).
+pub fn is_project_key(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && s.len() <= 10
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
@@ -0,0 +1,196 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::feature::FeatureSummary;
+
+/// A typed link from one feature to another in the same project.
+///
+/// Relations cut across the feature tree: "OAuth" can depend on "Session
+/// Management" wherever the two live. `depends_on` and `blocks` are
+/// dependencies and may not form a cycle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureRelation {
+    pub id: Uuid,
+    pub from_feature_id: Uuid,
+    pub to_feature_id: Uuid,
+    pub kind: RelationKind,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FeatureRelation {
+    /// The `(dependent, dependency)` pair, if this relation is a dependency.
+    pub fn dependency(&self) -> Option<(Uuid, Uuid)> {
+        self.kind
+            .dependency(self.from_feature_id, self.to_feature_id)
+    }
+}
+
+/// How the `from` feature relates to the `to` feature.
+///
+/// - `DependsOn`: `from` can't be implemented before `to`
+/// - `Blocks`: `to` can't be implemented before `from` (the reverse of `DependsOn`)
+/// - `RelatesTo`: informational link, no ordering
+/// - `Supersedes`: `from` replaces `to`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    DependsOn,
+    Blocks,
+    RelatesTo,
+    Supersedes,
+}
+
+impl RelationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DependsOn => "depends_on",
+            Self::Blocks => "blocks",
+            Self::RelatesTo => "relates_to",
+            Self::Supersedes => "supersedes",
+        }
+    }
+
+    /// The `(dependent, dependency)` pair for a relation of this kind from
+    /// `from` to `to`, if it is a dependency.
+    pub fn dependency(&self, from: Uuid, to: Uuid) -> Option<(Uuid, Uuid)> {
+        match self {
+            Self::DependsOn => Some((from, to)),
+            Self::Blocks => Some((to, from)),
+            Self::RelatesTo | Self::Supersedes => None,
+        }
+    }
+}
+
+impl FromStr for RelationKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "depends_on" => Ok(Self::DependsOn),
+            "blocks" => Ok(Self::Blocks),
+            "relates_to" => Ok(Self::RelatesTo),
+            "supersedes" => Ok(Self::Supersedes),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Input for relating a feature to another one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateRelationInput {
+    pub to_feature_id: Uuid,
+    pub kind: RelationKind,
+}
+
+/// Which way to follow dependencies from a feature.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyDirection {
+    /// Features it depends on, directly or transitively.
+    #[default]
+    Upstream,
+    /// Features depending on it, directly or transitively.
+    Downstream,
+}
+
+/// A project's features that take part in relations, with the relations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureGraph {
+    pub features: Vec<FeatureSummary>,
+    pub relations: Vec<FeatureRelation>,
+}
+
+impl FeatureGraph {
+    /// Render as a Graphviz `digraph`. Nodes are named by feature key.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph features {\n    rankdir=LR;\n");
+        for feature in &self.features {
+            let label = format!("{} {}", feature.key, feature.title);
+            let _ = writeln!(
+                out,
+                "    \"{}\" [label=\"{}\"];",
+                feature.key,
+                dot_escape(&label)
+            );
+        }
+        for relation in &self.relations {
+            let (Some(from), Some(to)) = (
+                self.key_of(relation.from_feature_id),
+                self.key_of(relation.to_feature_id),
+            ) else {
+                continue;
+            };
+            let style = match relation.kind {
+                RelationKind::DependsOn | RelationKind::Blocks => "",
+                RelationKind::RelatesTo => ", dir=none, style=dashed",
+                RelationKind::Supersedes => ", style=dotted",
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [label=\"{}\"{}];",
+                from,
+                to,
+                relation.kind.as_str(),
+                style
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart. Node ids are feature keys with `-`
+    /// replaced by `_`.
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart LR\n");
+        for feature in &self.features {
+            let label = format!("{} {}", feature.key, feature.title);
+            let _ = writeln!(
+                out,
+                "    {}[\"{}\"]",
+                mermaid_id(&feature.key),
+                label.replace('"', "#quot;")
+            );
+        }
+        for relation in &self.relations {
+            let (Some(from), Some(to)) = (
+                self.key_of(relation.from_feature_id),
+                self.key_of(relation.to_feature_id),
+            ) else {
+                continue;
+            };
+            let arrow = match relation.kind {
+                RelationKind::DependsOn | RelationKind::Blocks => "-->",
+                RelationKind::RelatesTo => "-.-",
+                RelationKind::Supersedes => "-.->",
+            };
+            let _ = writeln!(
+                out,
+                "    {} {}|{}| {}",
+                mermaid_id(from),
+                arrow,
+                relation.kind.as_str(),
+                mermaid_id(to)
+            );
+        }
+        out
+    }
+
+    fn key_of(&self, id: Uuid) -> Option<&str> {
+        self.features
+            .iter()
+            .find(|f| f.id == id)
+            .map(|f| f.key.as_str())
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_id(key: &str) -> String {
+    key.replace('-', "_")
+}
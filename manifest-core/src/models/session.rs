@@ -12,7 +12,9 @@ use super::task::CreateTaskInput;
 ///
 /// Sessions are **ephemeral**—they exist only during active work. When a session
 /// completes, its tasks are summarized into a `FeatureHistory` entry and deleted.
-/// Only one session can be active on a feature at a time.
+/// By default only one session can be active on a feature at a time; projects
+/// can raise `max_concurrent_sessions` to run sessions of different kinds in
+/// parallel (e.g., `docs` alongside `implementation`).
 ///
 /// Sessions can only be created on **leaf features** (features with no children).
 /// This enforces work at the appropriate level of granularity.
@@ -22,7 +24,14 @@ pub struct Session {
     pub feature_id: Uuid,
     /// High-level objective for this work session.
     pub goal: String,
+    /// What sort of work this session does. Concurrent sessions on a feature
+    /// must have distinct kinds.
+    pub kind: Option<String>,
     pub status: SessionStatus,
+    /// Commits recorded while the session is active (e.g. by the git hook).
+    /// They are added to the history entry when the session completes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<super::CommitRef>,
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
 }
@@ -66,12 +75,16 @@ impl FromStr for SessionStatus {
 /// Input for creating a new session.
 ///
 /// Sessions are created with an initial set of tasks. The feature must be a
-/// leaf node (no children) and must not have an active session already.
+/// leaf node (no children) and must have room for another active session
+/// under the project's `max_concurrent_sessions`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateSessionInput {
     pub feature_id: Uuid,
     /// High-level objective for this work session.
     pub goal: String,
+    /// Session kind, required to differ from other active sessions on the feature.
+    #[serde(default)]
+    pub kind: Option<String>,
     /// Initial tasks to create with the session.
     pub tasks: Vec<CreateTaskInput>,
 }
@@ -83,6 +96,9 @@ pub struct CreateSessionInput {
 pub struct CreateFeatureSessionInput {
     /// High-level objective for this work session.
     pub goal: String,
+    /// Session kind, required to differ from other active sessions on the feature.
+    #[serde(default)]
+    pub kind: Option<String>,
     /// Initial tasks to create with the session.
     #[serde(default)]
     pub tasks: Vec<CreateTaskInput>,
@@ -114,14 +130,30 @@ pub struct SessionFeatureSummary {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompleteSessionInput {
     /// Summary of work done, becomes the history entry description.
+    /// If empty, a summary is drafted from the session goal and task results.
+    #[serde(default)]
     pub summary: String,
-    /// Git commits created during this session.
+    /// Git commits created during this session. Commits reported in task
+    /// results are merged in automatically.
     #[serde(default)]
     pub commits: Vec<CommitRef>,
     /// Optionally update the feature's state (e.g., to `Implemented`).
-    /// If not provided, the feature state is not changed.
+    /// If not provided, the feature state is not changed. `Implemented` is
+    /// only accepted from the last active session on the feature, so
+    /// `desired_details` is promoted once all concurrent work is done.
     #[serde(default)]
     pub feature_state: Option<FeatureState>,
+    /// When the server can read the project's git repository, commits made
+    /// on the task branches since the session started are attached
+    /// automatically. If true, only those with a `Feature:` trailer naming
+    /// the feature (by id, key, path or slug) are kept. Without task
+    /// branches, commits on `HEAD` are read and always need the trailer.
+    #[serde(default)]
+    pub require_feature_trailer: bool,
+    /// Mark the feature `Implemented` even if some of its acceptance criteria
+    /// aren't passing. Without it, such a completion is refused.
+    #[serde(default)]
+    pub allow_unverified_criteria: bool,
 }
 
 /// Result of completing a session.
@@ -132,4 +164,8 @@ pub struct CompleteSessionInput {
 pub struct SessionCompletionResult {
     pub session: Session,
     pub history_entry: super::FeatureHistory,
+    /// Things left undone after completing, such as worktrees kept because
+    /// they had uncommitted changes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
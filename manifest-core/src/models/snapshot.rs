@@ -0,0 +1,25 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Version of the snapshot layout. Table rows follow the database schema and
+/// are versioned by `schema_version` instead.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Lossless copy of one project, for moving it between databases or seeding one.
+///
+/// Holds every row of the project in every table, keyed by table name, with
+/// its stored column values, so IDs, keys and timestamps survive the trip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    pub format_version: u32,
+    /// Latest SQLite migration the exporting database's tables match (`022`),
+    /// whichever backend it runs on. A database only imports snapshots from
+    /// its own schema or older ones.
+    pub schema_version: String,
+    pub project_id: Uuid,
+    pub exported_at: DateTime<Utc>,
+    pub tables: BTreeMap<String, Vec<serde_json::Map<String, serde_json::Value>>>,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A project-scoped label for concerns that cut across the feature tree
+/// (`security`, `v2`, `frontend`).
+///
+/// Features refer to tags by name. Names are unique within a project,
+/// ignoring case.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    /// Display color as `#rrggbb`.
+    pub color: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for creating a tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTagInput {
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Input for updating a tag. Omitted fields are left unchanged; renaming a
+/// tag renames it on every feature that has it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTagInput {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
@@ -1,9 +1,12 @@
+use std::fmt;
 use std::str::FromStr;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::history::CommitRef;
+
 /// A unit of work within a session, assigned to an AI agent.
 ///
 /// Tasks are **ephemeral**—they exist only during an active session. When the
@@ -27,7 +30,13 @@ pub struct Task {
     pub worktree_path: Option<String>,
     /// Git branch name for this task's work.
     pub branch: Option<String>,
+    /// Structured report from the agent, set when the task completes or fails.
+    pub result: Option<TaskResult>,
     pub created_at: DateTime<Utc>,
+    /// When the task first moved to `running`.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the task last moved to `completed` or `failed`.
+    pub finished_at: Option<DateTime<Utc>>,
 }
 
 /// The execution status of a task.
@@ -70,36 +79,126 @@ impl FromStr for TaskStatus {
     }
 }
 
-/// The type of AI agent assigned to a task.
+/// The type of agent assigned to a task, e.g. `claude` or `local-llama`.
+///
+/// Agent types are registered per project (see [`AgentTypeConfig`]); this is
+/// just the registered name. Projects start with [`DEFAULT_AGENT_TYPES`].
+///
+/// [`AgentTypeConfig`]: super::AgentTypeConfig
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct AgentType(String);
+
+/// Agent types registered for every new project, as `(name, label)` pairs.
+pub const DEFAULT_AGENT_TYPES: &[(&str, &str)] = &[
+    ("claude", "Anthropic Claude"),
+    ("gemini", "Google Gemini"),
+    ("codex", "OpenAI Codex"),
+];
+
+impl AgentType {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for AgentType {
+    type Err = ();
+
+    /// Accepts any non-empty name made of lowercase letters, digits, `-` and `_`.
+    /// Whether the type is registered for a project is checked by the database.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid = !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if valid {
+            Ok(Self(s.to_string()))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl TryFrom<String> for AgentType {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::from_str(&s).map_err(|_| {
+            format!(
+                "invalid agent type '{}', use lowercase letters, digits, '-' and '_'",
+                s
+            )
+        })
+    }
+}
+
+impl From<AgentType> for String {
+    fn from(agent_type: AgentType) -> Self {
+        agent_type.0
+    }
+}
+
+impl fmt::Display for AgentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// An agent's report of the work done on a task.
+///
+/// Stored as JSON to allow schema evolution without migrations. When the
+/// session completes, results are copied into the history entry so they
+/// survive task deletion.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TaskResult {
+    /// What the agent did (or attempted).
+    #[serde(default)]
+    pub summary: String,
+    /// Paths of files created or modified, relative to the working directory.
+    #[serde(default)]
+    pub files_changed: Vec<String>,
+    /// Git commits created while working on the task.
+    #[serde(default)]
+    pub commits: Vec<CommitRef>,
+    /// Outcome of the agent's test run, if tests were run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_outcome: Option<TestOutcome>,
+    /// Why the task could not be completed. Only set for failed tasks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+}
+
+/// The outcome of running tests for a task.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
-pub enum AgentType {
-    /// Anthropic Claude
-    Claude,
-    /// Google Gemini
-    Gemini,
-    /// OpenAI Codex
-    Codex,
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
 }
 
-impl AgentType {
+impl TestOutcome {
     pub fn as_str(&self) -> &'static str {
         match self {
-            Self::Claude => "claude",
-            Self::Gemini => "gemini",
-            Self::Codex => "codex",
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
         }
     }
 }
 
-impl FromStr for AgentType {
+impl FromStr for TestOutcome {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "claude" => Ok(Self::Claude),
-            "gemini" => Ok(Self::Gemini),
-            "codex" => Ok(Self::Codex),
+            "passed" => Ok(Self::Passed),
+            "failed" => Ok(Self::Failed),
+            "skipped" => Ok(Self::Skipped),
             _ => Err(()),
         }
     }
@@ -113,11 +212,14 @@ pub struct CreateTaskInput {
     pub title: String,
     /// Description of what work is included in this task.
     pub scope: String,
-    /// The AI agent type to assign this task to.
+    /// The agent type to assign this task to. Must be registered for the project.
     pub agent_type: AgentType,
 }
 
 /// Input for updating a task. Used by agents to report progress.
+///
+/// Status changes also stamp the task: moving to `running` sets `started_at`
+/// (once), and moving to `completed` or `failed` sets `finished_at`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateTaskInput {
     pub status: Option<TaskStatus>,
@@ -125,4 +227,6 @@ pub struct UpdateTaskInput {
     pub worktree_path: Option<String>,
     /// Git branch name for this task's work.
     pub branch: Option<String>,
+    /// Structured report of the work done. Replaces any previous result.
+    pub result: Option<TaskResult>,
 }
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::feature::FeatureState;
+use super::task::TestOutcome;
+
+/// One test case read from a test report (JUnit XML or libtest JSON).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCaseResult {
+    /// Fully qualified test name (`auth::login::rejects_wrong_password`).
+    pub name: String,
+    pub outcome: TestOutcome,
+    /// Failure message, for failed tests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// The latest result of a test matched to a feature.
+///
+/// A test matches an acceptance criterion whose `test_name` it ends with, or
+/// a feature whose key (`man_42`) or slug (`password_login`) appears in its
+/// name. Each report replaces the stored results of the features it matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    pub id: Uuid,
+    pub feature_id: Uuid,
+    /// The criterion the test verifies, when matched through one.
+    pub criterion_id: Option<Uuid>,
+    pub test_name: String,
+    pub outcome: TestOutcome,
+    pub message: Option<String>,
+    pub reported_at: DateTime<Utc>,
+}
+
+/// Latest test results of one feature, counted by outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureTestSummary {
+    pub feature_id: Uuid,
+    pub key: String,
+    pub title: String,
+    pub state: FeatureState,
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub reported_at: DateTime<Utc>,
+}
+
+/// What happened to a submitted test report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestReportResult {
+    /// Number of test cases in the report.
+    pub received: u32,
+    /// Number of test cases matched to at least one feature.
+    pub matched: u32,
+    /// Names of the test cases that matched no feature.
+    pub unmatched: Vec<String>,
+    /// Updated summaries of the features the report touched.
+    pub features: Vec<FeatureTestSummary>,
+}
@@ -1,14 +1,17 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
+    extract::{FromRequestParts, OriginalUri, Path, Query, State},
+    http::{header, request::Parts, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::db::{Database, ManifestError};
 use crate::models::*;
+use crate::{drift, git, test_report};
+
+use super::SecurityConfig;
 
 // Import MCP types for bulk feature creation (re-exported from mcp module)
 use crate::mcp::{PlanFeaturesResponse, ProposedFeature};
@@ -46,6 +49,71 @@ fn internal_error(e: anyhow::Error) -> (StatusCode, String) {
     )
 }
 
+// ============================================================
+// Feature References
+// ============================================================
+
+/// Feature id from the `{id}` segment of `/features/{id}/...` routes.
+///
+/// The segment may be a feature key (`/features/MAN-42`) or a feature path
+/// with its slashes percent-encoded (`/features/auth%2Foauth-integration`)
+/// instead of a UUID; see [`FeatureRef`].
+pub struct FeatureId(pub Uuid);
+
+impl FromRequestParts<Database> for FeatureId {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, db: &Database) -> Result<Self, Self::Rejection> {
+        let Path(reference) = Path::<String>::from_request_parts(parts, db)
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?;
+        match db.resolve_feature_ref(&FeatureRef::from(&reference)) {
+            Ok(Some(id)) => Ok(FeatureId(id)),
+            Ok(None) => Err((StatusCode::NOT_FOUND, "Feature not found".to_string())),
+            Err(e) => Err(internal_error(e)),
+        }
+    }
+}
+
+// ============================================================
+// Pagination
+// ============================================================
+
+/// A [`Page`] as the JSON body, with a `Link: <...>; rel="next"` header
+/// repeating the request with the next page's cursor.
+pub struct PageResponse<T> {
+    page: Page<T>,
+    uri: Uri,
+}
+
+impl<T> PageResponse<T> {
+    pub fn new(page: Page<T>, uri: Uri) -> Self {
+        Self { page, uri }
+    }
+}
+
+impl<T: Serialize> IntoResponse for PageResponse<T> {
+    fn into_response(self) -> Response {
+        let next = self.page.next_cursor.as_ref().map(|cursor| {
+            let mut params: Vec<&str> = self
+                .uri
+                .query()
+                .unwrap_or_default()
+                .split('&')
+                .filter(|p| !p.is_empty() && !p.starts_with("cursor="))
+                .collect();
+            let cursor_param = format!("cursor={}", cursor);
+            params.push(&cursor_param);
+            format!("<{}?{}>; rel=\"next\"", self.uri.path(), params.join("&"))
+        });
+        let mut response = Json(self.page).into_response();
+        if let Some(link) = next.and_then(|l| l.parse().ok()) {
+            response.headers_mut().insert(header::LINK, link);
+        }
+        response
+    }
+}
+
 // ============================================================
 // Health
 // ============================================================
@@ -54,14 +122,55 @@ pub async fn health() -> impl IntoResponse {
     Json(serde_json::json!({ "status": "ok" }))
 }
 
+// ============================================================
+// Admin
+// ============================================================
+
+/// Stream a consistent copy of the whole database as a SQLite file.
+pub async fn backup_database(State(db): State<Database>) -> Result<Response, (StatusCode, String)> {
+    let path = std::env::temp_dir().join(format!("manifest-backup-{}.db", Uuid::new_v4()));
+    let backup = path.clone();
+    tokio::task::spawn_blocking(move || db.backup_to(&backup))
+        .await
+        .map_err(|e| internal_error(e.into()))?
+        .map_err(internal_error)?;
+    let file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+    // The open handle keeps the copy readable; drop the name right away so
+    // nothing is left behind however the download ends. Windows can't remove
+    // open files, so the copy stays in its temp directory there.
+    let _ = tokio::fs::remove_file(&path).await;
+
+    let filename = format!(
+        "manifest-{}.db",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/vnd.sqlite3".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(file)),
+    )
+        .into_response())
+}
+
 // ============================================================
 // Projects
 // ============================================================
 
 pub async fn list_projects(
     State(db): State<Database>,
-) -> Result<Json<Vec<Project>>, (StatusCode, String)> {
-    db.get_all_projects().map(Json).map_err(internal_error)
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<PageQuery>,
+) -> Result<PageResponse<Project>, (StatusCode, String)> {
+    db.list_projects(&query)
+        .map(|page| PageResponse::new(page, uri))
+        .map_err(internal_error)
 }
 
 pub async fn get_project(
@@ -105,6 +214,34 @@ pub async fn delete_project(
     }
 }
 
+/// Query parameters for importing a snapshot.
+#[derive(Debug, Deserialize)]
+pub struct ImportSnapshotQuery {
+    /// Overwrite the project if it already exists.
+    #[serde(default)]
+    pub replace: bool,
+}
+
+pub async fn export_project_snapshot(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProjectSnapshot>, (StatusCode, String)> {
+    db.export_snapshot(id)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))
+}
+
+pub async fn import_project_snapshot(
+    State(db): State<Database>,
+    Query(query): Query<ImportSnapshotQuery>,
+    Json(snapshot): Json<ProjectSnapshot>,
+) -> Result<(StatusCode, Json<Project>), (StatusCode, String)> {
+    db.import_snapshot(&snapshot, query.replace)
+        .map(|p| (StatusCode::CREATED, Json(p)))
+        .map_err(internal_error)
+}
+
 // ============================================================
 // Project Directories
 // ============================================================
@@ -139,36 +276,185 @@ pub async fn remove_project_directory(
     }
 }
 
+pub async fn get_project_metrics(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProjectMetrics>, (StatusCode, String)> {
+    db.get_project_metrics(id)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))
+}
+
+/// Implemented features whose linked code changed after their spec did.
+pub async fn get_project_drift(
+    State(db): State<Database>,
+    Extension(config): Extension<SecurityConfig>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DriftReport>, (StatusCode, String)> {
+    if !config.is_local() {
+        return Err(manifest_error(ManifestError::invalid_state(
+            "Drift can only be checked when the server runs in local mode",
+        )));
+    }
+    drift::detect(&db, id)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))
+}
+
+/// Store a JUnit XML or libtest JSON report as the latest test results of
+/// the features its tests match.
+pub async fn report_test_results(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+    report: String,
+) -> Result<Json<TestReportResult>, (StatusCode, String)> {
+    let cases = test_report::parse(&report).map_err(internal_error)?;
+    db.record_test_results(id, cases)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))
+}
+
+pub async fn list_test_summaries(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<FeatureTestSummary>>, (StatusCode, String)> {
+    db.get_project(id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+
+    db.get_test_summaries(id).map(Json).map_err(internal_error)
+}
+
+pub async fn list_feature_test_results(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+) -> Result<Json<Vec<TestResult>>, (StatusCode, String)> {
+    db.get_feature(feature_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Feature not found".to_string()))?;
+
+    db.get_test_results(feature_id)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+// ============================================================
+// Agent Types
+// ============================================================
+
+pub async fn list_agent_types(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<AgentTypeConfig>>, (StatusCode, String)> {
+    db.get_agent_types(project_id)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+pub async fn create_agent_type(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+    Json(input): Json<CreateAgentTypeInput>,
+) -> Result<(StatusCode, Json<AgentTypeConfig>), (StatusCode, String)> {
+    db.create_agent_type(project_id, input)
+        .map(|t| (StatusCode::CREATED, Json(t)))
+        .map_err(internal_error)
+}
+
+pub async fn update_agent_type(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+    Json(input): Json<UpdateAgentTypeInput>,
+) -> Result<Json<AgentTypeConfig>, (StatusCode, String)> {
+    db.update_agent_type(id, input)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Agent type not found".to_string()))
+}
+
+pub async fn delete_agent_type(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if db.delete_agent_type(id).map_err(internal_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Agent type not found".to_string()))
+    }
+}
+
+// ============================================================
+// Tags
+// ============================================================
+
+pub async fn list_tags(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<Tag>>, (StatusCode, String)> {
+    db.get_tags(project_id).map(Json).map_err(internal_error)
+}
+
+pub async fn create_tag(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+    Json(input): Json<CreateTagInput>,
+) -> Result<(StatusCode, Json<Tag>), (StatusCode, String)> {
+    db.create_tag(project_id, input)
+        .map(|t| (StatusCode::CREATED, Json(t)))
+        .map_err(internal_error)
+}
+
+pub async fn update_tag(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+    Json(input): Json<UpdateTagInput>,
+) -> Result<Json<Tag>, (StatusCode, String)> {
+    db.update_tag(id, input)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Tag not found".to_string()))
+}
+
+pub async fn delete_tag(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if db.delete_tag(id).map_err(internal_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Tag not found".to_string()))
+    }
+}
+
 // ============================================================
 // Features
 // ============================================================
 
 pub async fn list_features(
     State(db): State<Database>,
+    OriginalUri(uri): OriginalUri,
     Query(query): Query<ListFeaturesQuery>,
-) -> Result<Json<Vec<FeatureSummary>>, (StatusCode, String)> {
-    // Use SQL-based pagination for efficiency
-    let features = db
-        .get_all_features_paginated(query.limit, query.offset)
-        .map_err(internal_error)?;
+) -> Result<PageResponse<FeatureSummary>, (StatusCode, String)> {
+    let features = db.list_features(None, &query).map_err(internal_error)?;
 
     // Always return summaries only - use get_feature for full details
-    let summaries: Vec<FeatureSummary> = features.into_iter().map(Into::into).collect();
-    Ok(Json(summaries))
+    Ok(PageResponse::new(features.map(Into::into), uri))
 }
 
 pub async fn list_project_features(
     State(db): State<Database>,
     Path(project_id): Path<Uuid>,
+    OriginalUri(uri): OriginalUri,
     Query(query): Query<ListFeaturesQuery>,
-) -> Result<Json<Vec<FeatureSummary>>, (StatusCode, String)> {
-    // Use SQL-based pagination for efficiency
+) -> Result<PageResponse<FeatureSummary>, (StatusCode, String)> {
     let features = db
-        .get_features_by_project_paginated(project_id, query.limit, query.offset)
+        .list_features(Some(project_id), &query)
         .map_err(internal_error)?;
     // Always return summaries only - use get_feature for full details
-    let summaries: Vec<FeatureSummary> = features.into_iter().map(Into::into).collect();
-    Ok(Json(summaries))
+    Ok(PageResponse::new(features.map(Into::into), uri))
 }
 
 pub async fn list_root_features(
@@ -191,17 +477,19 @@ pub async fn get_feature_tree(
 
 pub async fn list_children(
     State(db): State<Database>,
-    Path(parent_id): Path<Uuid>,
+    FeatureId(parent_id): FeatureId,
 ) -> Result<Json<Vec<Feature>>, (StatusCode, String)> {
     db.get_children(parent_id).map(Json).map_err(internal_error)
 }
 
 pub async fn get_feature_history(
     State(db): State<Database>,
-    Path(feature_id): Path<Uuid>,
-) -> Result<Json<Vec<FeatureHistory>>, (StatusCode, String)> {
-    db.get_feature_history(feature_id)
-        .map(Json)
+    FeatureId(feature_id): FeatureId,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<PageQuery>,
+) -> Result<PageResponse<FeatureHistory>, (StatusCode, String)> {
+    db.list_feature_history(feature_id, &query)
+        .map(|page| PageResponse::new(page, uri))
         .map_err(internal_error)
 }
 
@@ -214,6 +502,9 @@ pub struct CreateFeatureHistoryInput {
     /// If true, also update feature state to 'implemented'. Defaults to true.
     #[serde(default = "default_true")]
     pub mark_implemented: bool,
+    /// Mark the feature implemented even if some acceptance criteria aren't passing.
+    #[serde(default)]
+    pub allow_unverified_criteria: bool,
 }
 
 fn default_true() -> bool {
@@ -222,7 +513,8 @@ fn default_true() -> bool {
 
 pub async fn create_feature_history(
     State(db): State<Database>,
-    Path(feature_id): Path<Uuid>,
+    Extension(config): Extension<SecurityConfig>,
+    FeatureId(feature_id): FeatureId,
     Json(input): Json<CreateFeatureHistoryInput>,
 ) -> Result<(StatusCode, Json<FeatureHistory>), (StatusCode, String)> {
     // Verify feature exists
@@ -239,6 +531,29 @@ pub async fn create_feature_history(
         ));
     }
 
+    if input.mark_implemented
+        && feature.state != FeatureState::Implemented
+        && !input.allow_unverified_criteria
+    {
+        db.ensure_criteria_passing(feature_id)
+            .map_err(internal_error)?;
+    }
+
+    // Without a session, pick up commits on HEAD since the last recorded work
+    // that name the feature
+    let mut commits = input.commits;
+    if config.is_local() {
+        let since = db
+            .get_feature_history(feature_id)
+            .map_err(internal_error)?
+            .iter()
+            .map(|h| h.created_at)
+            .max()
+            .unwrap_or(feature.created_at);
+        let discovered = discover_commits(&db, feature_id, &[], since, true);
+        merge_commits(&mut commits, discovered);
+    }
+
     // Create history entry directly (no session)
     let history = db
         .create_history_entry(CreateHistoryInput {
@@ -246,7 +561,8 @@ pub async fn create_feature_history(
             session_id: None,
             details: HistoryDetails {
                 summary: input.summary,
-                commits: input.commits,
+                commits,
+                task_results: vec![],
             },
         })
         .map_err(internal_error)?;
@@ -262,6 +578,7 @@ pub async fn create_feature_history(
                 desired_details: None,
                 state: Some(FeatureState::Implemented),
                 priority: None,
+                tags: None,
             },
         )
         .map_err(internal_error)?;
@@ -272,21 +589,24 @@ pub async fn create_feature_history(
 
 pub async fn list_feature_sessions(
     State(db): State<Database>,
-    Path(feature_id): Path<Uuid>,
-) -> Result<Json<Vec<Session>>, (StatusCode, String)> {
+    FeatureId(feature_id): FeatureId,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<PageQuery>,
+) -> Result<PageResponse<Session>, (StatusCode, String)> {
     // First verify feature exists
     db.get_feature(feature_id)
         .map_err(internal_error)?
         .ok_or((StatusCode::NOT_FOUND, "Feature not found".to_string()))?;
 
-    db.get_sessions_by_feature(feature_id)
-        .map(Json)
+    db.list_sessions_by_feature(feature_id, &query)
+        .map(|page| PageResponse::new(page, uri))
         .map_err(internal_error)
 }
 
 pub async fn create_feature_session(
     State(db): State<Database>,
-    Path(feature_id): Path<Uuid>,
+    Extension(config): Extension<SecurityConfig>,
+    FeatureId(feature_id): FeatureId,
     Json(input): Json<CreateFeatureSessionInput>,
 ) -> Result<(StatusCode, Json<SessionResponse>), (StatusCode, String)> {
     // First verify feature exists
@@ -298,17 +618,18 @@ pub async fn create_feature_session(
     let session_input = CreateSessionInput {
         feature_id,
         goal: input.goal,
+        kind: input.kind,
         tasks: input.tasks,
     };
 
-    db.create_session(session_input)
-        .map(|s| (StatusCode::CREATED, Json(s)))
-        .map_err(internal_error)
+    let mut response = db.create_session(session_input).map_err(internal_error)?;
+    auto_provision_worktrees(&db, &config, feature_id, &mut response.tasks);
+    Ok((StatusCode::CREATED, Json(response)))
 }
 
 pub async fn get_feature(
     State(db): State<Database>,
-    Path(id): Path<Uuid>,
+    FeatureId(id): FeatureId,
 ) -> Result<Json<Feature>, (StatusCode, String)> {
     db.get_feature(id)
         .map_err(internal_error)?
@@ -318,7 +639,7 @@ pub async fn get_feature(
 
 pub async fn get_feature_diff(
     State(db): State<Database>,
-    Path(id): Path<Uuid>,
+    FeatureId(id): FeatureId,
 ) -> Result<Json<FeatureDiff>, (StatusCode, String)> {
     db.get_feature_diff(id)
         .map_err(internal_error)?
@@ -338,7 +659,7 @@ pub async fn create_feature(
 
 pub async fn update_feature(
     State(db): State<Database>,
-    Path(id): Path<Uuid>,
+    FeatureId(id): FeatureId,
     Json(input): Json<UpdateFeatureInput>,
 ) -> Result<Json<Feature>, (StatusCode, String)> {
     db.update_feature(id, input)
@@ -349,7 +670,7 @@ pub async fn update_feature(
 
 pub async fn delete_feature(
     State(db): State<Database>,
-    Path(id): Path<Uuid>,
+    FeatureId(id): FeatureId,
 ) -> Result<StatusCode, (StatusCode, String)> {
     if db.delete_feature(id).map_err(internal_error)? {
         Ok(StatusCode::NO_CONTENT)
@@ -365,32 +686,307 @@ pub struct SearchFeaturesQuery {
     pub q: String,
     /// Optional project UUID to limit search to.
     pub project_id: Option<Uuid>,
+    /// Comma-separated tag names. Only features with all of them are returned.
+    pub tag: Option<String>,
     /// Maximum number of results to return. Defaults to 10.
     pub limit: Option<u32>,
+    /// `next_cursor` from the previous page.
+    pub cursor: Option<String>,
 }
 
 /// Search features by title and details.
 /// Returns summaries ranked by relevance.
 pub async fn search_features(
     State(db): State<Database>,
+    OriginalUri(uri): OriginalUri,
     Query(query): Query<SearchFeaturesQuery>,
-) -> Result<Json<Vec<FeatureSummary>>, (StatusCode, String)> {
-    db.search_features(&query.q, query.project_id, query.limit)
+) -> Result<PageResponse<FeatureSummary>, (StatusCode, String)> {
+    let tags = parse_list_param(query.tag.as_deref());
+    let page = PageQuery {
+        cursor: query.cursor,
+        limit: query.limit,
+    };
+    db.search_features(&query.q, query.project_id, &tags, &page)
+        .map(|page| PageResponse::new(page, uri))
+        .map_err(internal_error)
+}
+
+// ============================================================
+// Commits
+// ============================================================
+
+/// Query parameters for resolving a feature reference.
+#[derive(Debug, Deserialize)]
+pub struct ResolveFeatureQuery {
+    /// Feature UUID, key, path or title slug, as written in a `Feature:` trailer.
+    #[serde(rename = "ref")]
+    pub reference: String,
+}
+
+pub async fn resolve_feature(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<ResolveFeatureQuery>,
+) -> Result<Json<Feature>, (StatusCode, String)> {
+    db.resolve_feature(project_id, &query.reference)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("No feature '{}' in this project", query.reference),
+        ))
+}
+
+pub async fn record_commit(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+    Json(input): Json<RecordCommitInput>,
+) -> Result<Json<RecordedCommit>, (StatusCode, String)> {
+    db.record_commit(feature_id, input)
         .map(Json)
         .map_err(internal_error)
 }
 
+// ============================================================
+// Code Links
+// ============================================================
+
+pub async fn list_code_links(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+) -> Result<Json<Vec<FeatureCodeLink>>, (StatusCode, String)> {
+    db.get_feature(feature_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Feature not found".to_string()))?;
+
+    db.get_code_links(feature_id)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+pub async fn create_code_link(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+    Json(input): Json<CreateCodeLinkInput>,
+) -> Result<(StatusCode, Json<FeatureCodeLink>), (StatusCode, String)> {
+    db.create_code_link(feature_id, input)
+        .map(|l| (StatusCode::CREATED, Json(l)))
+        .map_err(internal_error)
+}
+
+pub async fn update_code_link(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+    Json(input): Json<UpdateCodeLinkInput>,
+) -> Result<Json<FeatureCodeLink>, (StatusCode, String)> {
+    db.update_code_link(id, input)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Code link not found".to_string()))
+}
+
+pub async fn delete_code_link(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if db.delete_code_link(id).map_err(internal_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Code link not found".to_string()))
+    }
+}
+
+/// Query parameters for finding features by source path.
+#[derive(Debug, Deserialize)]
+pub struct FeaturesByPathQuery {
+    /// File path, relative to a project directory or absolute.
+    pub path: String,
+}
+
+/// Reverse lookup: which features are implemented in a file.
+pub async fn get_features_by_path(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<FeaturesByPathQuery>,
+) -> Result<Json<Vec<FeatureCodeMatch>>, (StatusCode, String)> {
+    db.get_project(project_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+
+    db.find_features_by_path(project_id, &query.path)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+// ============================================================
+// Acceptance Criteria
+// ============================================================
+
+pub async fn list_criteria(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+) -> Result<Json<Vec<AcceptanceCriterion>>, (StatusCode, String)> {
+    db.get_feature(feature_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Feature not found".to_string()))?;
+
+    db.get_criteria(feature_id)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+pub async fn create_criterion(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+    Json(input): Json<CreateCriterionInput>,
+) -> Result<(StatusCode, Json<AcceptanceCriterion>), (StatusCode, String)> {
+    db.create_criterion(feature_id, input)
+        .map(|c| (StatusCode::CREATED, Json(c)))
+        .map_err(internal_error)
+}
+
+pub async fn update_criterion(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+    Json(input): Json<UpdateCriterionInput>,
+) -> Result<Json<AcceptanceCriterion>, (StatusCode, String)> {
+    db.update_criterion(id, input)
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Criterion not found".to_string()))
+}
+
+pub async fn delete_criterion(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if db.delete_criterion(id).map_err(internal_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Criterion not found".to_string()))
+    }
+}
+
+// ============================================================
+// Feature Relations
+// ============================================================
+
+pub async fn list_relations(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+) -> Result<Json<Vec<FeatureRelation>>, (StatusCode, String)> {
+    db.get_feature(feature_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Feature not found".to_string()))?;
+
+    db.get_relations(feature_id)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+pub async fn create_relation(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+    Json(input): Json<CreateRelationInput>,
+) -> Result<(StatusCode, Json<FeatureRelation>), (StatusCode, String)> {
+    db.create_relation(feature_id, input)
+        .map(|r| (StatusCode::CREATED, Json(r)))
+        .map_err(internal_error)
+}
+
+pub async fn delete_relation(
+    State(db): State<Database>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if db.delete_relation(id).map_err(internal_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Relation not found".to_string()))
+    }
+}
+
+/// Query parameters for a feature's dependency closure.
+#[derive(Debug, Deserialize)]
+pub struct DependenciesQuery {
+    #[serde(default)]
+    pub direction: DependencyDirection,
+}
+
+pub async fn get_feature_dependencies(
+    State(db): State<Database>,
+    FeatureId(feature_id): FeatureId,
+    Query(query): Query<DependenciesQuery>,
+) -> Result<Json<Vec<FeatureSummary>>, (StatusCode, String)> {
+    db.get_feature(feature_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Feature not found".to_string()))?;
+
+    let features = db
+        .get_dependencies(feature_id, query.direction)
+        .map_err(internal_error)?;
+    Ok(Json(features.into_iter().map(Into::into).collect()))
+}
+
+/// Specified leaf features whose dependencies are all implemented.
+pub async fn list_next_features(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<FeatureSummary>>, (StatusCode, String)> {
+    db.get_project(project_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+
+    let features = db.get_next_features(project_id).map_err(internal_error)?;
+    Ok(Json(features.into_iter().map(Into::into).collect()))
+}
+
+/// Query parameters for exporting the relation graph.
+#[derive(Debug, Deserialize)]
+pub struct GraphQuery {
+    /// `json` (default), `dot` or `mermaid`.
+    pub format: Option<String>,
+}
+
+pub async fn get_feature_graph(
+    State(db): State<Database>,
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    db.get_project(project_id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+
+    let graph = db.get_feature_graph(project_id).map_err(internal_error)?;
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(Json(graph).into_response()),
+        "dot" => Ok((
+            [(header::CONTENT_TYPE, "text/vnd.graphviz")],
+            graph.to_dot(),
+        )
+            .into_response()),
+        "mermaid" => {
+            Ok(([(header::CONTENT_TYPE, "text/plain")], graph.to_mermaid()).into_response())
+        }
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown format '{}'. Expected json, dot or mermaid", other),
+        )),
+    }
+}
+
 // ============================================================
 // Sessions
 // ============================================================
 
 pub async fn create_session(
     State(db): State<Database>,
+    Extension(config): Extension<SecurityConfig>,
     Json(input): Json<CreateSessionInput>,
 ) -> Result<(StatusCode, Json<SessionResponse>), (StatusCode, String)> {
-    db.create_session(input)
-        .map(|s| (StatusCode::CREATED, Json(s)))
-        .map_err(internal_error)
+    let feature_id = input.feature_id;
+    let mut response = db.create_session(input).map_err(internal_error)?;
+    auto_provision_worktrees(&db, &config, feature_id, &mut response.tasks);
+    Ok((StatusCode::CREATED, Json(response)))
 }
 
 pub async fn get_session(
@@ -415,13 +1011,38 @@ pub async fn get_session_status(
 
 pub async fn complete_session(
     State(db): State<Database>,
+    Extension(config): Extension<SecurityConfig>,
     Path(id): Path<Uuid>,
-    Json(input): Json<CompleteSessionInput>,
+    Json(mut input): Json<CompleteSessionInput>,
 ) -> Result<Json<SessionCompletionResult>, (StatusCode, String)> {
-    db.complete_session(id, input)
+    let session = db
+        .get_session(id)
         .map_err(internal_error)?
-        .map(Json)
-        .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))
+        .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+    // Completion removes the tasks, so grab their branches and worktrees first
+    let tasks = db.get_tasks_by_session(id).map_err(internal_error)?;
+
+    if config.is_local() && session.status == SessionStatus::Active {
+        let branches: Vec<&str> = tasks.iter().filter_map(|t| t.branch.as_deref()).collect();
+        let discovered = discover_commits(
+            &db,
+            session.feature_id,
+            &branches,
+            session.created_at,
+            input.require_feature_trailer,
+        );
+        merge_commits(&mut input.commits, discovered);
+    }
+
+    let mut result = db
+        .complete_session(id, input)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+
+    if config.is_local() {
+        result.warnings = cleanup_worktrees(&db, session.feature_id, &tasks);
+    }
+    Ok(Json(result))
 }
 
 // ============================================================
@@ -452,17 +1073,20 @@ pub async fn update_task(
 
 pub async fn create_session_task(
     State(db): State<Database>,
+    Extension(config): Extension<SecurityConfig>,
     Path(session_id): Path<Uuid>,
     Json(input): Json<CreateTaskInput>,
 ) -> Result<(StatusCode, Json<Task>), (StatusCode, String)> {
     // First verify session exists
-    db.get_session(session_id)
+    let session = db
+        .get_session(session_id)
         .map_err(internal_error)?
         .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))?;
 
-    db.create_task(session_id, input)
-        .map(|t| (StatusCode::CREATED, Json(t)))
-        .map_err(internal_error)
+    let task = db.create_task(session_id, input).map_err(internal_error)?;
+    let mut tasks = vec![task];
+    auto_provision_worktrees(&db, &config, session.feature_id, &mut tasks);
+    Ok((StatusCode::CREATED, Json(tasks.remove(0))))
 }
 
 pub async fn list_session_tasks(
@@ -479,6 +1103,254 @@ pub async fn list_session_tasks(
         .map_err(internal_error)
 }
 
+// ============================================================
+// Worktrees
+// ============================================================
+
+/// Create a git worktree and branch for a task in the project's primary directory.
+/// Idempotent: a task whose worktree already exists is returned unchanged.
+pub async fn provision_task_worktree(
+    State(db): State<Database>,
+    Extension(config): Extension<SecurityConfig>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Task>, (StatusCode, String)> {
+    if !config.is_local() {
+        return Err(manifest_error(ManifestError::invalid_state(
+            "Worktrees can only be provisioned when the server runs in local mode",
+        )));
+    }
+
+    let task = db
+        .get_task(id)
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+
+    provision_worktree(&db, task)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// The git repository worktrees are created from: the project's primary
+/// directory, or its only directory when none is marked primary.
+fn project_repo(db: &Database, feature_id: Uuid) -> anyhow::Result<std::path::PathBuf> {
+    let feature = db
+        .get_feature(feature_id)?
+        .ok_or_else(|| ManifestError::not_found("Feature"))?;
+    let directories = db.get_project_directories(feature.project_id)?;
+
+    let directory = match directories.iter().find(|d| d.is_primary) {
+        Some(d) => d,
+        None if directories.len() == 1 => &directories[0],
+        None => {
+            return Err(ManifestError::validation(
+                "Project has no primary directory to create worktrees in",
+            )
+            .into())
+        }
+    };
+
+    let repo = std::path::PathBuf::from(&directory.path);
+    if !git::is_repo(&repo) {
+        return Err(ManifestError::validation(format!(
+            "Primary directory '{}' is not a git repository",
+            directory.path
+        ))
+        .into());
+    }
+    Ok(repo)
+}
+
+fn provision_worktree(db: &Database, task: Task) -> anyhow::Result<Task> {
+    if let Some(path) = &task.worktree_path {
+        if std::path::Path::new(path).exists() {
+            return Ok(task);
+        }
+    }
+
+    let session = db
+        .get_session(task.session_id)?
+        .ok_or_else(|| ManifestError::not_found("Session"))?;
+    let feature = db
+        .get_feature(session.feature_id)?
+        .ok_or_else(|| ManifestError::not_found("Feature"))?;
+    let repo = project_repo(db, feature.id)?;
+
+    let branch = task
+        .branch
+        .clone()
+        .unwrap_or_else(|| git::task_branch_name(&feature.title, &task.title, task.id));
+    // Agents may set the branch themselves
+    git::validate_branch_name(&repo, &branch)
+        .map_err(|e| ManifestError::validation(format!("{:#}", e)))?;
+    let path = git::task_worktree_path(&repo, &branch);
+    git::add_worktree(&repo, &path, &branch)
+        .map_err(|e| ManifestError::invalid_state(format!("Could not create worktree: {}", e)))?;
+
+    db.update_task(
+        task.id,
+        UpdateTaskInput {
+            status: None,
+            worktree_path: Some(path.to_string_lossy().into_owned()),
+            branch: Some(branch),
+            result: None,
+        },
+    )?;
+    db.get_task(task.id)?
+        .ok_or_else(|| ManifestError::not_found("Task").into())
+}
+
+/// Provision worktrees for new tasks when the project opts in.
+///
+/// Best effort: the session or task is already created, so failures are
+/// logged and the task is returned without a worktree.
+fn auto_provision_worktrees(
+    db: &Database,
+    config: &SecurityConfig,
+    feature_id: Uuid,
+    tasks: &mut [Task],
+) {
+    if !config.is_local() || tasks.is_empty() {
+        return;
+    }
+    let enabled = db
+        .get_feature(feature_id)
+        .ok()
+        .flatten()
+        .and_then(|f| db.get_project(f.project_id).ok().flatten())
+        .is_some_and(|p| p.provision_worktrees);
+    if !enabled {
+        return;
+    }
+
+    for task in tasks.iter_mut() {
+        match provision_worktree(db, task.clone()) {
+            Ok(provisioned) => *task = provisioned,
+            Err(e) => tracing::warn!("Skipping worktree for task {}: {}", task.id, e),
+        }
+    }
+}
+
+/// Remove the worktrees Manifest created for a completed session's tasks.
+///
+/// Worktrees with uncommitted changes are kept; each yields a warning for
+/// the caller.
+fn cleanup_worktrees(db: &Database, feature_id: Uuid, tasks: &[Task]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if tasks.iter().all(|t| t.worktree_path.is_none()) {
+        return warnings;
+    }
+    let repo = match project_repo(db, feature_id) {
+        Ok(repo) => repo,
+        Err(e) => {
+            tracing::warn!("Skipping worktree cleanup: {}", e);
+            return warnings;
+        }
+    };
+
+    for task in tasks {
+        let Some(path) = task.worktree_path.as_deref().map(std::path::Path::new) else {
+            continue;
+        };
+        if !git::is_managed_worktree(&repo, path) || !path.exists() {
+            continue;
+        }
+        if git::has_uncommitted_changes(path).unwrap_or(true) {
+            warnings.push(format!(
+                "Kept worktree {} of task '{}': it has uncommitted changes",
+                path.display(),
+                task.title
+            ));
+            continue;
+        }
+        if let Err(e) = git::remove_worktree(&repo, path, task.branch.as_deref()) {
+            tracing::warn!("Failed to remove worktree {}: {}", path.display(), e);
+        }
+    }
+    warnings
+}
+
+// ============================================================
+// Commit Discovery
+// ============================================================
+
+/// Commits on the task `branches` since `since`, read from the project's
+/// repository.
+///
+/// When none of the branches exists, `HEAD` is read instead. It holds other
+/// features' work too, so there only commits whose `Feature:` trailer names
+/// the feature are kept, whatever `require_feature_trailer` says.
+///
+/// Best effort: agents are expected to report their commits, so when the
+/// repository can't be read nothing is discovered and completion goes ahead.
+fn discover_commits(
+    db: &Database,
+    feature_id: Uuid,
+    branches: &[&str],
+    since: chrono::DateTime<chrono::Utc>,
+    require_feature_trailer: bool,
+) -> Vec<CommitRef> {
+    let repo = match project_repo(db, feature_id) {
+        Ok(repo) => repo,
+        Err(e) => {
+            tracing::debug!("Skipping commit discovery: {}", e);
+            return vec![];
+        }
+    };
+
+    // Branches may have been deleted or never created
+    let mut revs: Vec<&str> = branches
+        .iter()
+        .copied()
+        .filter(|branch| git::branch_exists(&repo, branch))
+        .collect();
+    let require_feature_trailer = require_feature_trailer || revs.is_empty();
+    if revs.is_empty() {
+        revs.push("HEAD");
+    }
+
+    // Trailers may name the feature by id, key, path or slug
+    let project_id = db
+        .get_feature(feature_id)
+        .ok()
+        .flatten()
+        .map(|f| f.project_id);
+    let mut resolved = std::collections::HashMap::new();
+    let mut names_feature = |trailer: &str| {
+        *resolved.entry(trailer.to_string()).or_insert_with(|| {
+            project_id.is_some_and(|project_id| {
+                db.resolve_feature(project_id, trailer)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|f| f.id == feature_id)
+            })
+        })
+    };
+    match git::commits_since(&repo, &revs, since) {
+        Ok(entries) => entries
+            .into_iter()
+            .filter(|c| !require_feature_trailer || c.features.iter().any(|f| names_feature(f)))
+            .map(|c| CommitRef {
+                sha: c.sha,
+                message: c.subject,
+                author: Some(c.author),
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Commit discovery failed: {}", e);
+            vec![]
+        }
+    }
+}
+
+/// Append discovered commits that weren't already reported.
+fn merge_commits(commits: &mut Vec<CommitRef>, discovered: Vec<CommitRef>) {
+    for commit in discovered {
+        if !commits.iter().any(|c| c.same_commit(&commit)) {
+            commits.push(commit);
+        }
+    }
+}
+
 // ============================================================
 // Project by Directory (for MCP get_project_context)
 // ============================================================
@@ -515,6 +1387,9 @@ pub async fn get_project_by_directory(
 pub struct BulkCreateFeaturesInput {
     /// The proposed feature tree.
     pub features: Vec<ProposedFeature>,
+    /// Create the root features under this feature instead of at the top level.
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
     /// If true, creates the features in the database. If false, returns preview only.
     #[serde(default)]
     pub confirm: bool,
@@ -533,6 +1408,18 @@ pub async fn bulk_create_features(
     db.get_project(project_id)
         .map_err(internal_error)?
         .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))?;
+    if let Some(parent_id) = input.parent_id {
+        let parent = db.get_feature(parent_id).map_err(internal_error)?.ok_or((
+            StatusCode::NOT_FOUND,
+            "Parent feature not found".to_string(),
+        ))?;
+        if parent.project_id != project_id {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Parent feature belongs to another project".to_string(),
+            ));
+        }
+    }
 
     let mut created_ids = Vec::new();
 
@@ -540,13 +1427,32 @@ pub async fn bulk_create_features(
         // Flatten the tree into a list of inputs with pre-generated UUIDs
         // This allows us to use the transactional bulk insert
         let mut feature_inputs = Vec::new();
+        let mut criteria = Vec::new();
         for feature in &input.features {
-            flatten_feature_tree(None, feature, &mut feature_inputs, &mut created_ids);
+            flatten_feature_tree(
+                input.parent_id,
+                feature,
+                &mut feature_inputs,
+                &mut criteria,
+                &mut created_ids,
+            );
         }
 
         // Create all features in a single transaction
         db.create_features_bulk(project_id, feature_inputs)
             .map_err(internal_error)?;
+        for (feature_id, text) in criteria {
+            db.create_criterion(
+                feature_id,
+                CreateCriterionInput {
+                    text,
+                    status: None,
+                    test_name: None,
+                    position: None,
+                },
+            )
+            .map_err(internal_error)?;
+        }
     }
 
     Ok(Json(PlanFeaturesResponse {
@@ -556,12 +1462,14 @@ pub async fn bulk_create_features(
     }))
 }
 
-/// Flatten a ProposedFeature tree into a list of CreateFeatureInput.
+/// Flatten a ProposedFeature tree into a list of CreateFeatureInput, and
+/// its acceptance criteria into `(feature id, text)` pairs.
 /// Pre-generates UUIDs so parent-child relationships can be established.
 fn flatten_feature_tree(
     parent_id: Option<Uuid>,
     proposed: &ProposedFeature,
     inputs: &mut Vec<CreateFeatureInput>,
+    criteria: &mut Vec<(Uuid, String)>,
     created_ids: &mut Vec<String>,
 ) -> Uuid {
     let id = Uuid::new_v4();
@@ -574,11 +1482,18 @@ fn flatten_feature_tree(
         details: proposed.details.clone(),
         state: Some(FeatureState::Specified),
         priority: Some(proposed.priority),
+        tags: proposed.tags.clone(),
     });
+    criteria.extend(
+        proposed
+            .acceptance_criteria
+            .iter()
+            .map(|text| (id, text.clone())),
+    );
 
     // Recursively flatten children with this feature's ID as parent
     for child in &proposed.children {
-        flatten_feature_tree(Some(id), child, inputs, created_ids);
+        flatten_feature_tree(Some(id), child, inputs, criteria, created_ids);
     }
 
     id
@@ -1,11 +1,12 @@
-//! Security middleware for API authentication and rate limiting.
+//! Security middleware for API authentication and rate limiting, and
+//! idempotent retries of POST requests.
 
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
+    http::{header, Method, Request, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use std::{
     collections::HashMap,
@@ -14,6 +15,15 @@ use std::{
     time::{Duration, Instant},
 };
 
+use crate::db::Database;
+use crate::models::{IdempotencyClaim, StoredResponse};
+
+/// Header that makes a POST safe to retry.
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// How long a response is replayed to retries of its request.
+const IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
 /// Security configuration loaded from environment variables.
 #[derive(Clone, Debug)]
 pub struct SecurityConfig {
@@ -53,6 +63,12 @@ impl SecurityConfig {
         }
     }
 
+    /// Local mode: no API key, so the server runs next to the project
+    /// directories and may touch them (e.g. to provision git worktrees).
+    pub fn is_local(&self) -> bool {
+        self.api_key.is_none()
+    }
+
     /// Create a config with no authentication (for local development/testing).
     pub fn disabled() -> Self {
         Self {
@@ -235,6 +251,147 @@ fn extract_client_ip(request: &Request<Body>) -> IpAddr {
     "127.0.0.1".parse().unwrap()
 }
 
+/// Answer a POST whose `Idempotency-Key` was seen before with the response
+/// to the first request, instead of running it again.
+///
+/// Only successful responses are kept, so a request that was refused or
+/// failed can be retried with the same key once the cause is fixed. A retry
+/// arriving while the first request still runs gets 409, and a key reused
+/// for a different request (another path or body) gets 422.
+pub async fn idempotency_middleware(
+    State(db): State<Database>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= 255 => key.to_string(),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Idempotency-Key must be 1 to 255 visible ASCII characters",
+            )
+                .into_response()
+        }
+    };
+    let (parts, body) = request.into_parts();
+    // Handlers still apply their own limits to the rebuilt body
+    let body = match axum::body::to_bytes(body, super::SNAPSHOT_LIMIT).await {
+        Ok(body) => body,
+        Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response(),
+    };
+    let fingerprint = format!(
+        "{} {} {:016x}",
+        parts.method,
+        parts.uri.path(),
+        body_hash(&body)
+    );
+
+    match db.claim_idempotency_key(&key, &fingerprint, IDEMPOTENCY_TTL) {
+        Ok(IdempotencyClaim::Claimed) => {}
+        Ok(IdempotencyClaim::Completed(stored)) => return replay(stored),
+        Ok(IdempotencyClaim::InProgress) => {
+            return (
+                StatusCode::CONFLICT,
+                "A request with this Idempotency-Key is still running",
+            )
+                .into_response()
+        }
+        Ok(IdempotencyClaim::Mismatch) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used for a different request",
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to claim idempotency key: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response();
+        }
+    }
+    // Dropped without completing (refused, failed or cancelled) releases the key
+    let claim = ClaimedKey { db, key: Some(key) };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to read response body: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response();
+        }
+    };
+    claim.complete(&StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        body: body.to_vec(),
+    });
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// FNV-1a hash of a request body, so a key reused with another body is told
+/// apart from a retry.
+fn body_hash(body: &[u8]) -> u64 {
+    body.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// An idempotency key claimed for a running request. Released on drop unless
+/// the response was stored, so a request that ends early (e.g. the client
+/// disconnected) doesn't hold the key until it expires.
+struct ClaimedKey {
+    db: Database,
+    key: Option<String>,
+}
+
+impl ClaimedKey {
+    fn complete(mut self, stored: &StoredResponse) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        if let Err(e) = self.db.complete_idempotency_key(&key, stored) {
+            tracing::warn!("Failed to store response for idempotency key: {:?}", e);
+            self.key = Some(key);
+        }
+    }
+}
+
+impl Drop for ClaimedKey {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            if let Err(e) = self.db.release_idempotency_key(&key) {
+                tracing::warn!("Failed to release idempotency key: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Rebuild a stored response, marked as a replay.
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = stored.content_type.and_then(|v| v.parse().ok()) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert("idempotent-replayed", "true".parse().unwrap());
+    response
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -280,6 +437,36 @@ mod tests {
         assert!(!limiter.check(ip2));
     }
 
+    #[test]
+    fn claimed_key_is_released_unless_completed() {
+        let db = Database::open_memory().unwrap();
+        db.migrate().unwrap();
+        let claim = |key: &str| {
+            db.claim_idempotency_key(key, "POST /api/v1/tags 0", IDEMPOTENCY_TTL)
+                .unwrap()
+        };
+        let stored = StoredResponse {
+            status: 201,
+            content_type: None,
+            body: b"{}".to_vec(),
+        };
+
+        assert!(matches!(claim("dropped"), IdempotencyClaim::Claimed));
+        drop(ClaimedKey {
+            db: db.clone(),
+            key: Some("dropped".to_string()),
+        });
+        assert!(matches!(claim("completed"), IdempotencyClaim::Claimed));
+        ClaimedKey {
+            db: db.clone(),
+            key: Some("completed".to_string()),
+        }
+        .complete(&stored);
+
+        assert!(matches!(claim("dropped"), IdempotencyClaim::Claimed));
+        assert!(matches!(claim("completed"), IdempotencyClaim::Completed(_)));
+    }
+
     #[test]
     fn security_config_disabled_has_no_auth() {
         let config = SecurityConfig::disabled();
@@ -2,15 +2,22 @@ mod handlers;
 mod middleware;
 
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{delete, get, post, put},
-    Router,
+    Extension, Router,
 };
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
 use crate::db::Database;
 use crate::mcp;
 
-pub use middleware::SecurityConfig;
+pub use middleware::{SecurityConfig, IDEMPOTENCY_KEY};
+
+/// Test reports of large suites exceed axum's default 2 MB body limit.
+const TEST_REPORT_LIMIT: usize = 32 * 1024 * 1024;
+
+/// Snapshots carry a project's whole history.
+const SNAPSHOT_LIMIT: usize = 256 * 1024 * 1024;
 
 /// Build CORS layer based on configuration
 fn build_cors_layer(config: &SecurityConfig) -> CorsLayer {
@@ -28,7 +35,11 @@ fn build_cors_layer(config: &SecurityConfig) -> CorsLayer {
                 Method::DELETE,
                 Method::OPTIONS,
             ])
-            .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                header::HeaderName::from_static(middleware::IDEMPOTENCY_KEY),
+            ])
     } else {
         CorsLayer::permissive()
     }
@@ -54,6 +65,14 @@ pub fn create_router_with_config(db: Database, config: SecurityConfig) -> Router
         .route("/projects/{id}", get(handlers::get_project))
         .route("/projects/{id}", put(handlers::update_project))
         .route("/projects/{id}", delete(handlers::delete_project))
+        .route(
+            "/projects/{id}/snapshot",
+            get(handlers::export_project_snapshot),
+        )
+        .route(
+            "/snapshots",
+            post(handlers::import_project_snapshot).layer(DefaultBodyLimit::max(SNAPSHOT_LIMIT)),
+        )
         .route(
             "/projects/{id}/directories",
             get(handlers::list_project_directories),
@@ -62,6 +81,22 @@ pub fn create_router_with_config(db: Database, config: SecurityConfig) -> Router
             "/projects/{id}/directories",
             post(handlers::add_project_directory),
         )
+        .route("/projects/{id}/metrics", get(handlers::get_project_metrics))
+        .route("/projects/{id}/drift", get(handlers::get_project_drift))
+        .route(
+            "/projects/{id}/test-results",
+            get(handlers::list_test_summaries)
+                .post(handlers::report_test_results)
+                .layer(DefaultBodyLimit::max(TEST_REPORT_LIMIT)),
+        )
+        .route(
+            "/projects/{id}/agent-types",
+            get(handlers::list_agent_types).post(handlers::create_agent_type),
+        )
+        .route(
+            "/projects/{id}/tags",
+            get(handlers::list_tags).post(handlers::create_tag),
+        )
         .route(
             "/projects/{id}/features",
             get(handlers::list_project_features),
@@ -75,15 +110,50 @@ pub fn create_router_with_config(db: Database, config: SecurityConfig) -> Router
             "/projects/{id}/features/roots",
             get(handlers::list_root_features),
         )
+        .route(
+            "/projects/{id}/features/next",
+            get(handlers::list_next_features),
+        )
+        .route("/projects/{id}/graph", get(handlers::get_feature_graph))
         .route(
             "/projects/{id}/features/tree",
             get(handlers::get_feature_tree),
         )
+        .route(
+            "/projects/{id}/features/by-path",
+            get(handlers::get_features_by_path),
+        )
+        .route(
+            "/projects/{id}/features/resolve",
+            get(handlers::resolve_feature),
+        )
         // Directories (for delete by directory id)
         .route(
             "/directories/{id}",
             delete(handlers::remove_project_directory),
         )
+        // Agent types (by agent type id)
+        .route(
+            "/agent-types/{id}",
+            put(handlers::update_agent_type).delete(handlers::delete_agent_type),
+        )
+        // Tags (by tag id)
+        .route(
+            "/tags/{id}",
+            put(handlers::update_tag).delete(handlers::delete_tag),
+        )
+        // Code links (by code link id)
+        .route(
+            "/code-links/{id}",
+            put(handlers::update_code_link).delete(handlers::delete_code_link),
+        )
+        // Relations (by relation id)
+        .route("/relations/{id}", delete(handlers::delete_relation))
+        // Acceptance criteria (by criterion id)
+        .route(
+            "/criteria/{id}",
+            put(handlers::update_criterion).delete(handlers::delete_criterion),
+        )
         // Features (by feature id)
         .route("/features", get(handlers::list_features))
         .route("/features/search", get(handlers::search_features))
@@ -96,6 +166,27 @@ pub fn create_router_with_config(db: Database, config: SecurityConfig) -> Router
             "/features/{id}/history",
             get(handlers::get_feature_history).post(handlers::create_feature_history),
         )
+        .route("/features/{id}/commits", post(handlers::record_commit))
+        .route(
+            "/features/{id}/code-links",
+            get(handlers::list_code_links).post(handlers::create_code_link),
+        )
+        .route(
+            "/features/{id}/criteria",
+            get(handlers::list_criteria).post(handlers::create_criterion),
+        )
+        .route(
+            "/features/{id}/test-results",
+            get(handlers::list_feature_test_results),
+        )
+        .route(
+            "/features/{id}/relations",
+            get(handlers::list_relations).post(handlers::create_relation),
+        )
+        .route(
+            "/features/{id}/dependencies",
+            get(handlers::get_feature_dependencies),
+        )
         .route(
             "/features/{id}/sessions",
             get(handlers::list_feature_sessions).post(handlers::create_feature_session),
@@ -111,7 +202,20 @@ pub fn create_router_with_config(db: Database, config: SecurityConfig) -> Router
         )
         // Tasks
         .route("/tasks/{id}", get(handlers::get_task))
-        .route("/tasks/{id}", put(handlers::update_task));
+        .route("/tasks/{id}", put(handlers::update_task))
+        .route(
+            "/tasks/{id}/worktree",
+            post(handlers::provision_task_worktree),
+        )
+        // Inside auth, so a replay needs the same credentials
+        .layer(axum::middleware::from_fn_with_state(
+            db.clone(),
+            middleware::idempotency_middleware,
+        ))
+        // Admin: a backup is safe to repeat and too large to keep for one
+        .route("/admin/backup", post(handlers::backup_database))
+        // Handlers that touch the local file system check for local mode
+        .layer(Extension(config.clone()));
 
     // Apply auth middleware to protected routes if API key is configured
     let protected_api = if config.api_key.is_some() {
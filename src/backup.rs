@@ -0,0 +1,136 @@
+//! Scheduled database backups.
+//!
+//! `mfst serve` backs the database up on a timer when `MANIFEST_BACKUP_DIR`
+//! is set: once at startup, then every `MANIFEST_BACKUP_INTERVAL_HOURS`
+//! (default 24), keeping the newest `MANIFEST_BACKUP_KEEP` files (default 7).
+//! Backups go through [`Database::backup_to`], so they are consistent while
+//! the server keeps writing.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::db::Database;
+
+const DEFAULT_INTERVAL_HOURS: u64 = 24;
+const DEFAULT_KEEP: usize = 7;
+
+/// Backups written by the schedule are named `manifest-<UTC timestamp>.db`,
+/// which sorts them oldest first.
+const FILE_PREFIX: &str = "manifest-";
+const FILE_SUFFIX: &str = ".db";
+
+/// Backup schedule loaded from environment variables.
+#[derive(Debug, Clone)]
+pub struct BackupSchedule {
+    /// Directory backups are written to (from MANIFEST_BACKUP_DIR)
+    pub dir: PathBuf,
+    /// Time between backups (from MANIFEST_BACKUP_INTERVAL_HOURS)
+    pub interval: Duration,
+    /// Number of backups kept (from MANIFEST_BACKUP_KEEP)
+    pub keep: usize,
+}
+
+impl BackupSchedule {
+    /// Load the schedule, or `None` if MANIFEST_BACKUP_DIR isn't set.
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var("MANIFEST_BACKUP_DIR").ok()?;
+
+        let hours = std::env::var("MANIFEST_BACKUP_INTERVAL_HOURS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|&h| h > 0)
+            .unwrap_or(DEFAULT_INTERVAL_HOURS);
+
+        let keep = std::env::var("MANIFEST_BACKUP_KEEP")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|&k| k > 0)
+            .unwrap_or(DEFAULT_KEEP);
+
+        Some(Self {
+            dir: PathBuf::from(dir),
+            interval: Duration::from_secs(hours * 60 * 60),
+            keep,
+        })
+    }
+
+    /// Run the schedule in the background for as long as the server runs.
+    pub fn spawn(self, db: Database) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(self.interval);
+            loop {
+                timer.tick().await;
+                let (db, dir, keep) = (db.clone(), self.dir.clone(), self.keep);
+                match tokio::task::spawn_blocking(move || backup_now(&db, &dir, keep)).await {
+                    Ok(Ok(path)) => tracing::info!("Backed up database to {}", path.display()),
+                    Ok(Err(e)) => tracing::error!("Scheduled backup failed: {:#}", e),
+                    Err(e) => tracing::error!("Scheduled backup panicked: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Write a timestamped backup into `dir`, then delete all but the newest `keep`.
+pub fn backup_now(db: &Database, dir: &Path, keep: usize) -> Result<PathBuf> {
+    let name = format!(
+        "{}{}{}",
+        FILE_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        FILE_SUFFIX
+    );
+    let path = dir.join(name);
+    db.backup_to(&path)?;
+    prune(dir, keep)?;
+    Ok(path)
+}
+
+/// Delete the oldest scheduled backups in `dir` beyond `keep`, leaving any
+/// other files alone. Returns the deleted paths.
+fn prune(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(FILE_PREFIX) && n.ends_with(FILE_SUFFIX))
+        })
+        .collect();
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = backups.into_iter().take(excess).collect();
+    for path in &removed {
+        std::fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_keeps_the_newest_backups_only() {
+        let dir = std::env::temp_dir().join(format!("manifest-prune-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "manifest-20260101T000000Z.db",
+            "manifest-20260102T000000Z.db",
+            "manifest-20260103T000000Z.db",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let removed = prune(&dir, 2).unwrap();
+
+        assert_eq!(removed, vec![dir.join("manifest-20260101T000000Z.db")]);
+        assert!(dir.join("manifest-20260103T000000Z.db").exists());
+        assert!(dir.join("notes.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+//! Drift detection: implemented features whose code moved on without them.
+//!
+//! The server builds a [`DriftReport`] by reading each project directory's
+//! git log since the stalest implemented spec. A commit counts against a
+//! feature when it was made after `feature.updated_at`, isn't already in the
+//! feature's history, and touched a path the feature is linked to or a file
+//! one of its recorded commits touched.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::git;
+use crate::mcp::client::ClientError;
+use crate::mcp::ManifestClient;
+use crate::models::*;
+
+/// What is known about one implemented feature's code.
+struct Tracked {
+    feature: Feature,
+    links: Vec<FeatureCodeLink>,
+    recorded: Vec<CommitRef>,
+}
+
+/// Build the drift report for a project. Returns `None` if it doesn't exist.
+///
+/// Directories that aren't git repositories are skipped.
+pub fn detect(db: &Database, project_id: Uuid) -> Result<Option<DriftReport>> {
+    if db.get_project(project_id)?.is_none() {
+        return Ok(None);
+    }
+
+    let mut tracked = Vec::new();
+    for feature in db.get_features_by_project(project_id)? {
+        if feature.state != FeatureState::Implemented {
+            continue;
+        }
+        let links = db.get_code_links(feature.id)?;
+        let recorded: Vec<CommitRef> = db
+            .get_feature_history(feature.id)?
+            .into_iter()
+            .flat_map(|h| h.details.commits)
+            .collect();
+        if !links.is_empty() || !recorded.is_empty() {
+            tracked.push(Tracked {
+                feature,
+                links,
+                recorded,
+            });
+        }
+    }
+
+    let mut drift: Vec<FeatureDrift> = tracked
+        .iter()
+        .map(|t| FeatureDrift {
+            feature: t.feature.clone(),
+            commits: vec![],
+        })
+        .collect();
+
+    if let Some(since) = tracked.iter().map(|t| t.feature.updated_at).min() {
+        for dir in db.get_project_directories(project_id)? {
+            let path = Path::new(&dir.path);
+            if !git::is_repo(path) {
+                tracing::debug!(
+                    "Skipping drift check for {}: not a git repository",
+                    dir.path
+                );
+                continue;
+            }
+            let changes = match git::changes_since(path, since) {
+                Ok(changes) => changes,
+                Err(e) => {
+                    tracing::warn!("Drift check failed for {}: {}", dir.path, e);
+                    continue;
+                }
+            };
+            // Recorded commits from other repositories simply aren't found here
+            let shas: Vec<&str> = tracked
+                .iter()
+                .flat_map(|t| t.recorded.iter().map(|c| c.sha.as_str()))
+                .collect();
+            let recorded = match git::commits_with_files(path, &shas) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    tracing::warn!("Drift check failed for {}: {}", dir.path, e);
+                    continue;
+                }
+            };
+            for (t, d) in tracked.iter().zip(drift.iter_mut()) {
+                d.commits
+                    .extend(drifted_commits(t, dir.id, &recorded, &changes));
+            }
+        }
+    }
+
+    drift.retain(|d| !d.commits.is_empty());
+    for d in &mut drift {
+        d.commits.sort_by_key(|c| c.committed_at);
+    }
+    drift.sort_by_key(|d| d.feature.updated_at);
+
+    Ok(Some(DriftReport {
+        project_id,
+        generated_at: Utc::now(),
+        features: drift,
+    }))
+}
+
+/// Commits in `changes` that drifted `t`. `recorded` holds the recorded
+/// commits of every feature found in the same directory, with their files.
+fn drifted_commits(
+    t: &Tracked,
+    directory_id: Uuid,
+    recorded: &[git::LogEntry],
+    changes: &[git::LogEntry],
+) -> Vec<DriftCommit> {
+    let links: Vec<CodePathMatcher> = t
+        .links
+        .iter()
+        .filter(|l| l.directory_id == directory_id)
+        .map(FeatureCodeLink::matcher)
+        .collect();
+    let files: HashSet<&String> = recorded
+        .iter()
+        .filter(|c| t.recorded.iter().any(|r| r.same_commit(&commit_ref(c))))
+        .flat_map(|c| &c.files)
+        .collect();
+
+    changes
+        .iter()
+        .filter(|c| c.committed_at > t.feature.updated_at)
+        .filter(|c| !t.recorded.iter().any(|r| r.same_commit(&commit_ref(c))))
+        .filter_map(|c| {
+            let touched: Vec<String> = c
+                .files
+                .iter()
+                .filter(|f| files.contains(*f) || links.iter().any(|l| l.is_match(f)))
+                .cloned()
+                .collect();
+            (!touched.is_empty()).then(|| DriftCommit {
+                commit: commit_ref(c),
+                committed_at: c.committed_at,
+                directory_id,
+                files: touched,
+            })
+        })
+        .collect()
+}
+
+fn commit_ref(entry: &git::LogEntry) -> CommitRef {
+    CommitRef {
+        sha: entry.sha.clone(),
+        message: entry.subject.clone(),
+        author: Some(entry.author.clone()),
+    }
+}
+
+/// Fetch the drift report for the project registered at `dir` (`mfst drift`).
+pub async fn report(client: &ManifestClient, dir: &Path) -> Result<DriftReport> {
+    let repo = git::main_worktree(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let project = match client
+        .get_project_by_directory(&repo.to_string_lossy())
+        .await
+    {
+        Ok(p) => p.project,
+        Err(ClientError::NotFound(_)) => {
+            bail!("{} is not a registered project directory", repo.display())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    Ok(client.get_drift(project.id).await?)
+}
+
+/// Render a report for the terminal.
+pub fn format_report(report: &DriftReport) -> String {
+    if report.features.is_empty() {
+        return "No drift: every implemented feature is up to date with its code.\n".to_string();
+    }
+
+    let mut out = format!(
+        "{} implemented feature(s) changed in code since their spec was updated:\n",
+        report.features.len()
+    );
+    for d in &report.features {
+        out.push_str(&format!(
+            "\n{} ({})\n  spec updated {}\n",
+            d.feature.title,
+            d.feature.id,
+            d.feature.updated_at.format("%Y-%m-%d %H:%M")
+        ));
+        for c in &d.commits {
+            out.push_str(&format!(
+                "  {} {} ({})\n",
+                &c.commit.sha[..c.commit.sha.len().min(8)],
+                c.commit.message,
+                c.committed_at.format("%Y-%m-%d %H:%M")
+            ));
+            for file in &c.files {
+                out.push_str(&format!("      {}\n", file));
+            }
+        }
+    }
+    out
+}
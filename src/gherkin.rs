@@ -0,0 +1,631 @@
+//! Gherkin `.feature` files.
+//!
+//! `mfst import --format gherkin` reads each file's `Feature:` into a feature,
+//! its `Rule:`s into child features and its scenarios into acceptance
+//! criteria (or sections of the details), then sends the tree to the bulk
+//! create endpoint: without `--confirm` that only returns the preview.
+//! `mfst export --format gherkin` writes a feature subtree back out, with
+//! deeper descendants as rules titled by their path (`Login / Lockout`),
+//! which import nests again.
+//!
+//! Only English keywords are read. Lines starting with `#` are comments in
+//! Gherkin, so Markdown headings in exported details don't survive a round
+//! trip.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use uuid::Uuid;
+
+use crate::db::ManifestError;
+use crate::git;
+use crate::mcp::client::ClientError;
+use crate::mcp::{ManifestClient, PlanFeaturesResponse, ProposedFeature};
+use crate::models::{AcceptanceCriterion, FeatureRef, FeatureTreeNode, Project};
+
+/// Where imported scenarios go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScenarioMapping {
+    /// One acceptance criterion per scenario: its name, then its steps.
+    Criteria,
+    /// A `Scenario:` section appended to the feature's details.
+    Details,
+}
+
+const SCENARIO_KEYWORDS: &[&str] = &[
+    "Scenario",
+    "Scenario Outline",
+    "Scenario Template",
+    "Example",
+];
+
+/// Separates the titles of nested features in a `Rule:` line.
+const RULE_PATH_SEPARATOR: &str = " / ";
+
+/// A `Background:` or scenario being read: its name, then its other lines.
+struct Block {
+    background: bool,
+    lines: Vec<String>,
+}
+
+/// Read one `.feature` file into a proposed feature tree.
+pub fn parse(text: &str, mapping: ScenarioMapping) -> Result<ProposedFeature> {
+    let mut root: Option<ProposedFeature> = None;
+    // Child indexes leading from the root to the current rule's feature
+    let mut rule: Vec<usize> = Vec::new();
+    let mut block: Option<Block> = None;
+    let mut tags: Vec<String> = Vec::new();
+    // Closing delimiter of the doc string being read
+    let mut doc_string: Option<&str> = None;
+    // A blank line separates the next description line from the last
+    let mut paragraph = false;
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if let Some(delimiter) = doc_string {
+            if line == delimiter {
+                doc_string = None;
+            }
+            if let Some(block) = block.as_mut() {
+                block.lines.push(line.to_string());
+            }
+            continue;
+        }
+        if line.is_empty() {
+            paragraph = true;
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('@') {
+            tags.extend(
+                line.split_whitespace()
+                    .take_while(|t| !t.starts_with('#'))
+                    .filter_map(|t| t.strip_prefix('@'))
+                    .map(String::from),
+            );
+            continue;
+        }
+
+        let keyword = line
+            .split_once(':')
+            .map(|(keyword, rest)| (keyword.trim(), rest.trim()));
+        let Some(feature) = root.as_mut() else {
+            match keyword {
+                Some(("Feature", title)) => {
+                    root = Some(ProposedFeature {
+                        title: title.to_string(),
+                        details: None,
+                        priority: 0,
+                        tags: std::mem::take(&mut tags),
+                        acceptance_criteria: vec![],
+                        children: vec![],
+                    });
+                    paragraph = false;
+                    continue;
+                }
+                _ => {
+                    return Err(ManifestError::validation(format!(
+                        "line {}: expected `Feature:`",
+                        index + 1
+                    ))
+                    .into())
+                }
+            }
+        };
+
+        match keyword {
+            Some(("Feature", _)) => {
+                return Err(ManifestError::validation(format!(
+                    "line {}: a file can only have one `Feature:`",
+                    index + 1
+                ))
+                .into());
+            }
+            Some(("Rule", title)) => {
+                finish_block(feature, &rule, block.take(), mapping);
+                rule = rule_path(feature, title);
+                nested(feature, &rule)
+                    .tags
+                    .extend(std::mem::take(&mut tags));
+                paragraph = false;
+            }
+            Some(("Background", name)) => {
+                finish_block(feature, &rule, block.take(), mapping);
+                block = Some(Block {
+                    background: true,
+                    lines: vec![name.to_string()],
+                });
+            }
+            Some((keyword, name)) if SCENARIO_KEYWORDS.contains(&keyword) => {
+                finish_block(feature, &rule, block.take(), mapping);
+                // Scenario tags have nowhere to go
+                tags.clear();
+                block = Some(Block {
+                    background: false,
+                    lines: vec![name.to_string()],
+                });
+            }
+            _ => match block.as_mut() {
+                Some(block) => {
+                    // Tags before `Examples:` belong to the examples table
+                    tags.clear();
+                    if line.starts_with("\"\"\"") || line.starts_with("```") {
+                        doc_string = Some(&line[..3]);
+                    }
+                    block.lines.push(line.to_string());
+                }
+                None => {
+                    let target = nested(feature, &rule);
+                    let details = target.details.get_or_insert_with(String::new);
+                    if !details.is_empty() {
+                        details.push_str(if paragraph { "\n\n" } else { "\n" });
+                    }
+                    details.push_str(line);
+                    paragraph = false;
+                }
+            },
+        }
+    }
+
+    let Some(mut feature) = root else {
+        return Err(ManifestError::validation("No `Feature:` found").into());
+    };
+    finish_block(&mut feature, &rule, block, mapping);
+    if feature.title.is_empty() {
+        return Err(ManifestError::validation("The `Feature:` has no title").into());
+    }
+    Ok(feature)
+}
+
+/// The feature `path` leads to from `root`.
+fn nested<'a>(root: &'a mut ProposedFeature, path: &[usize]) -> &'a mut ProposedFeature {
+    path.iter()
+        .fold(root, |feature, &index| &mut feature.children[index])
+}
+
+/// Find or add the features named by a rule title, returning their path.
+fn rule_path(root: &mut ProposedFeature, title: &str) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut feature = root;
+    for segment in title.split(RULE_PATH_SEPARATOR).map(str::trim) {
+        let index = match feature.children.iter().position(|c| c.title == segment) {
+            Some(index) => index,
+            None => {
+                feature.children.push(ProposedFeature {
+                    title: segment.to_string(),
+                    details: None,
+                    priority: feature.children.len() as i32,
+                    tags: vec![],
+                    acceptance_criteria: vec![],
+                    children: vec![],
+                });
+                feature.children.len() - 1
+            }
+        };
+        path.push(index);
+        feature = &mut feature.children[index];
+    }
+    path
+}
+
+/// Add a finished background or scenario to the feature `path` leads to.
+fn finish_block(
+    root: &mut ProposedFeature,
+    path: &[usize],
+    block: Option<Block>,
+    mapping: ScenarioMapping,
+) {
+    let Some(block) = block else {
+        return;
+    };
+    let feature = nested(root, path);
+    if !block.background && mapping == ScenarioMapping::Criteria {
+        let text = block
+            .lines
+            .iter()
+            .filter(|line| !line.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.is_empty() {
+            feature.acceptance_criteria.push(text);
+        }
+        return;
+    }
+
+    let keyword = if block.background {
+        "Background"
+    } else {
+        "Scenario"
+    };
+    let mut section = format!("{}: {}", keyword, block.lines[0])
+        .trim_end()
+        .to_string();
+    for line in &block.lines[1..] {
+        section.push_str("\n  ");
+        section.push_str(line);
+    }
+    let details = feature.details.get_or_insert_with(String::new);
+    if !details.is_empty() {
+        details.push_str("\n\n");
+    }
+    details.push_str(&section);
+}
+
+/// Write a feature subtree as a `.feature` file.
+///
+/// `criteria` holds the acceptance criteria of the subtree's features.
+pub fn render(
+    root: &FeatureTreeNode,
+    criteria: &HashMap<Uuid, Vec<AcceptanceCriterion>>,
+) -> String {
+    let mut out = String::new();
+    render_feature(&mut out, root, "Feature", &root.feature.title, 0, criteria);
+    let mut rules = Vec::new();
+    collect_rules(&root.children, &[], &mut rules);
+    for (title, node) in rules {
+        out.push('\n');
+        render_feature(&mut out, node, "Rule", &title, 2, criteria);
+    }
+    out
+}
+
+/// Descendants in depth-first order, titled by their path below the root.
+fn collect_rules<'a>(
+    nodes: &'a [FeatureTreeNode],
+    parents: &[&str],
+    rules: &mut Vec<(String, &'a FeatureTreeNode)>,
+) {
+    for node in nodes {
+        let mut path = parents.to_vec();
+        path.push(&node.feature.title);
+        rules.push((path.join(RULE_PATH_SEPARATOR), node));
+        collect_rules(&node.children, &path, rules);
+    }
+}
+
+fn render_feature(
+    out: &mut String,
+    node: &FeatureTreeNode,
+    keyword: &str,
+    title: &str,
+    indent: usize,
+    criteria: &HashMap<Uuid, Vec<AcceptanceCriterion>>,
+) {
+    let pad = " ".repeat(indent);
+    let feature = &node.feature;
+    if !feature.tags.is_empty() {
+        let tags: Vec<String> = feature
+            .tags
+            .iter()
+            .map(|t| format!("@{}", t.replace(char::is_whitespace, "_")))
+            .collect();
+        out.push_str(&format!("{}{}\n", pad, tags.join(" ")));
+    }
+    out.push_str(&format!("{}{}: {}\n", pad, keyword, title));
+    if let Some(details) = feature.details.as_deref().filter(|d| !d.trim().is_empty()) {
+        for line in details.trim().lines() {
+            match line.trim_end() {
+                "" => out.push('\n'),
+                line => out.push_str(&format!("{}  {}\n", pad, line)),
+            }
+        }
+    }
+    for criterion in criteria.get(&feature.id).into_iter().flatten() {
+        let mut lines = criterion.text.lines().map(str::trim);
+        out.push_str(&format!(
+            "\n{}  Scenario: {}\n",
+            pad,
+            lines.next().unwrap_or_default()
+        ));
+        for line in lines.filter(|l| !l.is_empty()) {
+            out.push_str(&format!("{}    {}\n", pad, line));
+        }
+    }
+}
+
+/// The project registered at `dir`.
+async fn project_at(client: &ManifestClient, dir: &Path) -> Result<Project> {
+    let repo = git::main_worktree(dir).unwrap_or_else(|_| dir.to_path_buf());
+    match client
+        .get_project_by_directory(&repo.to_string_lossy())
+        .await
+    {
+        Ok(p) => Ok(p.project),
+        Err(ClientError::NotFound(_)) => {
+            bail!("{} is not a registered project directory", repo.display())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `.feature` files in `paths`, searching directories recursively.
+fn feature_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries = std::fs::read_dir(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        let (dirs, found): (Vec<_>, Vec<_>) = entries.into_iter().partition(|p| p.is_dir());
+        files.extend(
+            found
+                .into_iter()
+                .filter(|p| p.extension().is_some_and(|e| e == "feature")),
+        );
+        files.extend(feature_files(&dirs)?);
+    }
+    Ok(files)
+}
+
+/// Import `.feature` files into the project registered at `dir` (`mfst import`).
+///
+/// Only previews the features unless `confirm` is set.
+pub async fn import(
+    client: &ManifestClient,
+    dir: &Path,
+    paths: &[PathBuf],
+    parent: Option<&str>,
+    mapping: ScenarioMapping,
+    confirm: bool,
+) -> Result<PlanFeaturesResponse> {
+    let files = feature_files(paths)?;
+    if files.is_empty() {
+        bail!("No .feature files found");
+    }
+    let mut features = Vec::new();
+    for (priority, file) in files.iter().enumerate() {
+        let text = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let mut feature = parse(&text, mapping)
+            .with_context(|| format!("Invalid Gherkin in {}", file.display()))?;
+        feature.priority = priority as i32;
+        features.push(feature);
+    }
+
+    let project = project_at(client, dir).await?;
+    let parent_id = match parent {
+        Some(reference) => Some(
+            client
+                .get_feature(FeatureRef::from(reference).in_project(project.id))
+                .await?
+                .id,
+        ),
+        None => None,
+    };
+    Ok(client
+        .bulk_create_features(project.id, &features, parent_id, confirm)
+        .await?)
+}
+
+/// Render the subtree rooted at `feature` in the project registered at `dir`
+/// (`mfst export`).
+pub async fn export(client: &ManifestClient, dir: &Path, feature: &str) -> Result<String> {
+    let project = project_at(client, dir).await?;
+    let feature = client
+        .get_feature(FeatureRef::from(feature).in_project(project.id))
+        .await?;
+    let tree = client.get_feature_tree(feature.project_id).await?;
+    let Some(root) = find_node(&tree, feature.id) else {
+        bail!("Feature {} is missing from its project's tree", feature.id);
+    };
+
+    let mut criteria = HashMap::new();
+    let mut pending = vec![root];
+    while let Some(node) = pending.pop() {
+        criteria.insert(node.feature.id, client.get_criteria(node.feature.id).await?);
+        pending.extend(&node.children);
+    }
+    Ok(render(root, &criteria))
+}
+
+fn find_node(nodes: &[FeatureTreeNode], id: Uuid) -> Option<&FeatureTreeNode> {
+    nodes.iter().find_map(|node| {
+        if node.feature.id == id {
+            Some(node)
+        } else {
+            find_node(&node.children, id)
+        }
+    })
+}
+
+/// Render the outcome of an import for the terminal.
+pub fn format_import(result: &PlanFeaturesResponse) -> String {
+    fn count(feature: &ProposedFeature) -> usize {
+        1 + feature.children.iter().map(count).sum::<usize>()
+    }
+    fn write(out: &mut String, feature: &ProposedFeature, depth: usize) {
+        out.push_str(&format!("{}- {}", "  ".repeat(depth), feature.title));
+        if !feature.tags.is_empty() {
+            out.push_str(&format!(" [{}]", feature.tags.join(", ")));
+        }
+        match feature.acceptance_criteria.len() {
+            0 => {}
+            1 => out.push_str(" (1 criterion)"),
+            n => out.push_str(&format!(" ({} criteria)", n)),
+        }
+        out.push('\n');
+        for child in &feature.children {
+            write(out, child, depth + 1);
+        }
+    }
+
+    let total: usize = result.proposed_features.iter().map(count).sum();
+    let mut out = if result.created {
+        format!("Created {} feature(s):\n", total)
+    } else {
+        format!(
+            "Would create {} feature(s); run again with --confirm to create them:\n",
+            total
+        )
+    };
+    for feature in &result.proposed_features {
+        write(&mut out, feature, 0);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CriterionStatus, Feature, FeatureState};
+    use chrono::Utc;
+
+    const LOGIN: &str = r#"# language: en
+@auth @web
+Feature: Password login
+  Users sign in with their email and password.
+
+  Sessions last a day.
+
+  Background:
+    Given a registered user
+
+  Scenario: Accepts a valid password
+    When they sign in with the right password
+    Then they see the dashboard
+
+  @slow
+  Rule: Lockout
+    Scenario Outline: Locks after repeated failures
+      When they fail to sign in <times> times
+      Then the account is locked
+      Examples:
+        | times |
+        | 5     |
+
+  Rule: Lockout / Unlock
+    Scenario: Unlocks by email
+      Given a locked account
+      When they follow the unlock link
+      Then they can sign in again
+"#;
+
+    #[test]
+    fn parse_maps_rules_and_scenarios() {
+        let feature = parse(LOGIN, ScenarioMapping::Criteria).unwrap();
+
+        assert_eq!(feature.title, "Password login");
+        assert_eq!(feature.tags, vec!["auth", "web"]);
+        assert_eq!(
+            feature.details.as_deref(),
+            Some(
+                "Users sign in with their email and password.\n\n\
+                 Sessions last a day.\n\n\
+                 Background:\n  Given a registered user"
+            )
+        );
+        assert_eq!(
+            feature.acceptance_criteria,
+            vec!["Accepts a valid password\nWhen they sign in with the right password\nThen they see the dashboard"]
+        );
+
+        assert_eq!(feature.children.len(), 1);
+        let lockout = &feature.children[0];
+        assert_eq!(lockout.title, "Lockout");
+        assert_eq!(lockout.tags, vec!["slow"]);
+        assert_eq!(lockout.acceptance_criteria.len(), 1);
+        assert!(lockout.acceptance_criteria[0].ends_with("| 5     |"));
+        assert_eq!(lockout.children[0].title, "Unlock");
+        assert_eq!(lockout.children[0].acceptance_criteria.len(), 1);
+    }
+
+    #[test]
+    fn parse_can_put_scenarios_in_details() {
+        let feature = parse(LOGIN, ScenarioMapping::Details).unwrap();
+
+        assert!(feature.acceptance_criteria.is_empty());
+        assert!(feature.details.unwrap().ends_with(
+            "Scenario: Accepts a valid password\n  \
+             When they sign in with the right password\n  \
+             Then they see the dashboard"
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_text_without_a_feature() {
+        assert!(parse(
+            "Scenario: Orphan\n  Given nothing",
+            ScenarioMapping::Criteria
+        )
+        .is_err());
+        assert!(parse("Feature: One\nFeature: Two", ScenarioMapping::Criteria).is_err());
+    }
+
+    fn node(title: &str, tags: &[&str], children: Vec<FeatureTreeNode>) -> FeatureTreeNode {
+        FeatureTreeNode {
+            feature: Feature {
+                id: Uuid::new_v4(),
+                project_id: Uuid::nil(),
+                parent_id: None,
+                key: "MAN-1".to_string(),
+                title: title.to_string(),
+                slug: String::new(),
+                path: String::new(),
+                details: None,
+                desired_details: None,
+                state: FeatureState::Specified,
+                priority: 0,
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            children,
+        }
+    }
+
+    #[test]
+    fn render_round_trips_through_parse() {
+        let mut root = node(
+            "Password login",
+            &["auth"],
+            vec![node("Lockout", &[], vec![node("Unlock", &[], vec![])])],
+        );
+        root.feature.details = Some("Users sign in.".to_string());
+        let unlock_id = root.children[0].children[0].feature.id;
+        let criterion = |feature_id, text: &str| AcceptanceCriterion {
+            id: Uuid::new_v4(),
+            feature_id,
+            position: 0,
+            text: text.to_string(),
+            status: CriterionStatus::Unverified,
+            test_name: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let criteria = HashMap::from([
+            (
+                root.feature.id,
+                vec![criterion(
+                    root.feature.id,
+                    "Signs in\nThen they see the dashboard",
+                )],
+            ),
+            (unlock_id, vec![criterion(unlock_id, "Unlocks by email")]),
+        ]);
+
+        let text = render(&root, &criteria);
+        let parsed = parse(&text, ScenarioMapping::Criteria).unwrap();
+
+        assert!(text.contains("  Rule: Lockout / Unlock\n"));
+        assert_eq!(parsed.title, "Password login");
+        assert_eq!(parsed.tags, vec!["auth"]);
+        assert_eq!(parsed.details.as_deref(), Some("Users sign in."));
+        assert_eq!(
+            parsed.acceptance_criteria,
+            vec!["Signs in\nThen they see the dashboard"]
+        );
+        assert_eq!(
+            parsed.children[0].children[0].acceptance_criteria,
+            vec!["Unlocks by email"]
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! Thin wrapper around the `git` command line for local repositories.
+//!
+//! Used by the server in local mode, where project directories live on the
+//! same machine. Every function shells out to `git -C <repo> ...` and turns a
+//! non-zero exit status into an error carrying git's stderr.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Prefix for branches created by Manifest, so they are easy to spot and clean up.
+pub const BRANCH_PREFIX: &str = "manifest/";
+
+/// Commit-message trailer that ties a commit to a feature, e.g. `Feature: <id>`.
+pub const FEATURE_TRAILER: &str = "Feature";
+
+/// A commit read from `git log`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub sha: String,
+    /// First line of the commit message.
+    pub subject: String,
+    pub author: String,
+    pub committed_at: DateTime<Utc>,
+    /// Values of the commit's `Feature:` trailers.
+    pub features: Vec<String>,
+    /// Paths the commit touched, relative to the repository directory.
+    /// Only filled in by [`changes_since`].
+    pub files: Vec<String>,
+}
+
+/// Run `git -C <repo> <args>` and return trimmed stdout.
+pub fn run(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Returns true if `path` is inside a git work tree.
+pub fn is_repo(path: &Path) -> bool {
+    run(path, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out == "true")
+}
+
+/// Slug of `text` suitable for branch and directory names.
+pub fn slugify(text: &str) -> String {
+    // Keep names readable in `git branch` output
+    crate::models::slugify(text)
+        .chars()
+        .take(40)
+        .collect::<String>()
+        .trim_end_matches('-')
+        .to_string()
+}
+
+/// Branch name for a task, e.g. `manifest/oauth-login/add-callback-route-1a2b3c4d`.
+///
+/// The task id suffix keeps names unique when titles repeat.
+pub fn task_branch_name(feature_title: &str, task_title: &str, task_id: Uuid) -> String {
+    let short_id = &task_id.simple().to_string()[..8];
+    format!(
+        "{}{}/{}-{}",
+        BRANCH_PREFIX,
+        slugify(feature_title),
+        slugify(task_title),
+        short_id
+    )
+}
+
+/// Directory holding the worktrees Manifest creates for `repo`:
+/// a `<repo>.worktrees` sibling of the repository.
+pub fn worktrees_root(repo: &Path) -> PathBuf {
+    let name = repo
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "repo".to_string());
+    repo.with_file_name(format!("{}.worktrees", name))
+}
+
+/// Check that `branch` is safe to hand to `git worktree add` and to name a
+/// directory after: a valid branch name that can't be read as an option or
+/// climb out of the worktrees directory.
+pub fn validate_branch_name(repo: &Path, branch: &str) -> Result<()> {
+    if branch.starts_with('-') || branch.contains("..") {
+        bail!("Invalid branch name '{}'", branch);
+    }
+    run(repo, &["check-ref-format", "--branch", branch])
+        .map(|_| ())
+        .with_context(|| format!("Invalid branch name '{}'", branch))
+}
+
+/// Worktree location for a branch checked by [`validate_branch_name`]: the
+/// whole branch name as a path under [`worktrees_root`], so every branch gets
+/// a directory of its own.
+pub fn task_worktree_path(repo: &Path, branch: &str) -> PathBuf {
+    worktrees_root(repo).join(branch)
+}
+
+/// Returns true if `path` is a worktree Manifest created for `repo`.
+///
+/// Only these are removed on cleanup; worktrees agents set up by hand are left alone.
+pub fn is_managed_worktree(repo: &Path, path: &Path) -> bool {
+    path.starts_with(worktrees_root(repo))
+}
+
+/// Returns true if the local branch `branch` exists.
+pub fn branch_exists(repo: &Path, branch: &str) -> bool {
+    run(
+        repo,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{}", branch),
+        ],
+    )
+    .is_ok()
+}
+
+/// Create a worktree at `path` on a new branch `branch` from the current `HEAD`.
+///
+/// If the branch already exists it is checked out instead of created.
+pub fn add_worktree(repo: &Path, path: &Path, branch: &str) -> Result<()> {
+    let path_str = path.to_string_lossy();
+
+    if branch_exists(repo, branch) {
+        run(repo, &["worktree", "add", &path_str, branch])?;
+    } else {
+        run(repo, &["worktree", "add", "-b", branch, &path_str])?;
+    }
+    Ok(())
+}
+
+/// Returns true if the worktree at `path` has uncommitted changes or
+/// untracked files.
+pub fn has_uncommitted_changes(path: &Path) -> Result<bool> {
+    Ok(!run(path, &["status", "--porcelain"])?.is_empty())
+}
+
+/// Remove the worktree at `path` and delete its branch if it has been merged.
+///
+/// Git refuses to remove a worktree with uncommitted changes, and unmerged
+/// branches are kept, so no work is lost.
+pub fn remove_worktree(repo: &Path, path: &Path, branch: Option<&str>) -> Result<()> {
+    run(repo, &["worktree", "remove", &path.to_string_lossy()])?;
+
+    // Drop the directories left empty by branch names with slashes
+    let root = worktrees_root(repo);
+    for dir in path.ancestors().skip(1).take_while(|dir| *dir != root) {
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+
+    if let Some(branch) = branch {
+        if let Err(e) = run(repo, &["branch", "-d", branch]) {
+            tracing::debug!("Keeping branch {}: {}", branch, e);
+        }
+    }
+    Ok(())
+}
+
+/// Commits reachable from `revs` and committed at or after `since`, oldest first.
+pub fn commits_since(repo: &Path, revs: &[&str], since: DateTime<Utc>) -> Result<Vec<LogEntry>> {
+    let format = log_format();
+    let since = format!("--since={}", since.format("%Y-%m-%d %H:%M:%S +0000"));
+
+    let mut args = vec!["log", "--reverse", format.as_str(), since.as_str()];
+    args.extend_from_slice(revs);
+    args.push("--");
+
+    Ok(parse_log(&run(repo, &args)?))
+}
+
+/// Commits on `HEAD` since `since` that touched files below `dir`, oldest
+/// first, with the paths they touched relative to `dir`.
+pub fn changes_since(dir: &Path, since: DateTime<Utc>) -> Result<Vec<LogEntry>> {
+    let format = log_format();
+    let since = format!("--since={}", since.format("%Y-%m-%d %H:%M:%S +0000"));
+    let args = [
+        "log",
+        "--reverse",
+        "--name-only",
+        "--relative",
+        format.as_str(),
+        since.as_str(),
+        "HEAD",
+        "--",
+        ".",
+    ];
+    Ok(parse_log(&run(dir, &args)?))
+}
+
+/// The commits named by `shas` that exist in the repository at `dir`,
+/// with the paths they touched relative to `dir`. Other names are skipped.
+pub fn commits_with_files(dir: &Path, shas: &[&str]) -> Result<Vec<LogEntry>> {
+    // Anything else could be read as an option
+    let shas: Vec<&str> = shas
+        .iter()
+        .copied()
+        .filter(|sha| !sha.is_empty() && sha.chars().all(|c| c.is_ascii_hexdigit()))
+        .collect();
+    if shas.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let format = log_format();
+    let mut args = vec![
+        "log",
+        "--no-walk=unsorted",
+        "--ignore-missing",
+        "--name-only",
+        "--relative",
+        format.as_str(),
+    ];
+    args.extend_from_slice(&shas);
+    args.push("--");
+    Ok(parse_log(&run(dir, &args)?))
+}
+
+/// The commit `HEAD` points at.
+pub fn head_commit(repo: &Path) -> Result<LogEntry> {
+    let format = log_format();
+    parse_log(&run(repo, &["log", "-1", format.as_str(), "HEAD"])?)
+        .pop()
+        .context("HEAD has no commits")
+}
+
+/// `git log --format` argument producing records for [`parse_log`].
+///
+/// Fields are separated by US (0x1f) and records start with RS (0x1e) so
+/// that subjects and trailers can contain anything printable. The header
+/// fits on one line; `--name-only` file lists follow it.
+fn log_format() -> String {
+    format!(
+        "--format=%x1e%H%x1f%s%x1f%an%x1f%cI%x1f%(trailers:key={},valueonly,separator=%x1f)",
+        FEATURE_TRAILER
+    )
+}
+
+fn parse_log(output: &str) -> Vec<LogEntry> {
+    output
+        .split('\x1e')
+        .map(str::trim)
+        .filter(|record| !record.is_empty())
+        .filter_map(|record| {
+            let (header, files) = record.split_once('\n').unwrap_or((record, ""));
+            let mut fields = header.split('\x1f');
+            Some(LogEntry {
+                sha: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                committed_at: DateTime::parse_from_rfc3339(fields.next()?)
+                    .ok()?
+                    .with_timezone(&Utc),
+                features: fields
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+                    .collect(),
+                files: files
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(String::from)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Values of the `Feature:` trailers in a commit message file.
+pub fn message_feature_trailers(repo: &Path, message_file: &Path) -> Result<Vec<String>> {
+    let output = run(
+        repo,
+        &[
+            "interpret-trailers",
+            "--parse",
+            &message_file.to_string_lossy(),
+        ],
+    )?;
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case(FEATURE_TRAILER))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect())
+}
+
+/// Name of the checked-out branch, or `None` on a detached `HEAD`.
+pub fn current_branch(repo: &Path) -> Option<String> {
+    run(repo, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok()
+}
+
+/// Root of the main working tree, also when `path` is inside a linked worktree.
+///
+/// Project directories are registered by their main checkout, so this is
+/// what to look projects up by.
+pub fn main_worktree(path: &Path) -> Result<PathBuf> {
+    let common_dir = PathBuf::from(run(
+        path,
+        &["rev-parse", "--path-format=absolute", "--git-common-dir"],
+    )?);
+    match common_dir.file_name() {
+        Some(name) if name == ".git" => common_dir
+            .parent()
+            .map(Path::to_path_buf)
+            .context("git directory has no parent"),
+        // Bare repositories have no working tree of their own
+        _ => Ok(common_dir),
+    }
+}
+
+/// Directory git runs hooks from, honoring `core.hooksPath`.
+pub fn hooks_dir(repo: &Path) -> Result<PathBuf> {
+    Ok(PathBuf::from(run(
+        repo,
+        &["rev-parse", "--path-format=absolute", "--git-path", "hooks"],
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_collapses_punctuation_and_case() {
+        assert_eq!(slugify("OAuth Login (Google)"), "oauth-login-google");
+        assert_eq!(slugify("  --Add  callback route!  "), "add-callback-route");
+    }
+
+    #[test]
+    fn task_branch_name_includes_feature_task_and_short_id() {
+        let id = Uuid::parse_str("1a2b3c4d-0000-4000-8000-000000000000").unwrap();
+        assert_eq!(
+            task_branch_name("OAuth Login", "Add callback route", id),
+            "manifest/oauth-login/add-callback-route-1a2b3c4d"
+        );
+    }
+
+    #[test]
+    fn managed_worktrees_live_next_to_the_repo() {
+        let repo = Path::new("/src/app");
+        let path = task_worktree_path(repo, "manifest/login/add-route-1a2b3c4d");
+        assert_eq!(
+            path,
+            PathBuf::from("/src/app.worktrees/manifest/login/add-route-1a2b3c4d")
+        );
+        assert!(is_managed_worktree(repo, &path));
+        assert!(!is_managed_worktree(repo, Path::new("/src/app/tmp")));
+    }
+
+    #[test]
+    fn branches_with_the_same_last_segment_get_distinct_worktrees() {
+        let repo = Path::new("/src/app");
+        assert_ne!(
+            task_worktree_path(repo, "a/x"),
+            task_worktree_path(repo, "b/x")
+        );
+    }
+
+    #[test]
+    fn validate_branch_name_rejects_options_and_parent_components() {
+        let repo = std::env::temp_dir();
+        assert!(validate_branch_name(&repo, "manifest/login/add-route-1a2b3c4d").is_ok());
+        assert!(validate_branch_name(&repo, "-f").is_err());
+        assert!(validate_branch_name(&repo, "manifest/../escape").is_err());
+        assert!(validate_branch_name(&repo, "with space").is_err());
+    }
+
+    #[test]
+    fn parse_log_reads_trailers_and_file_lists() {
+        let output = "\x1eaaa\x1fAdd route\x1fAgent\x1f2026-01-02T03:04:05+01:00\x1fsearch\x1f\n\n\
+                      src/api.rs\nsrc/lib.rs\n\
+                      \x1ebbb\x1fFix typo\x1fAgent\x1f2026-01-03T00:00:00Z\x1f";
+
+        let entries = parse_log(output);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].features, vec!["search"]);
+        assert_eq!(entries[0].files, vec!["src/api.rs", "src/lib.rs"]);
+        assert_eq!(
+            entries[0].committed_at.to_rfc3339(),
+            "2026-01-02T02:04:05+00:00"
+        );
+        assert!(entries[1].features.is_empty());
+        assert!(entries[1].files.is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+//! Git hooks that link commits to features through `Feature:` trailers.
+//!
+//! `mfst hook install` writes `commit-msg` and `post-commit` scripts into a
+//! registered project directory. The scripts call back into `mfst`:
+//!
+//! - `commit-msg` rejects the commit if a `Feature: <uuid|slug>` trailer
+//!   does not resolve to a feature of the project.
+//! - `post-commit` records the new commit on each referenced feature, in its
+//!   active session if there is one and in its history otherwise.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::git;
+use crate::mcp::client::ClientError;
+use crate::mcp::ManifestClient;
+use crate::models::*;
+
+/// Hooks installed by [`install`].
+pub const HOOKS: &[&str] = &["commit-msg", "post-commit"];
+
+/// Marks hook scripts as ours, so reinstalling can replace them safely.
+const MARKER: &str = "# Installed by `mfst hook install`";
+
+/// Install the hooks into the git repository at `dir`, which must be a
+/// registered project directory. Existing hooks that Manifest did not write
+/// are only replaced with `force`.
+pub async fn install(client: &ManifestClient, dir: &Path, force: bool) -> Result<Vec<PathBuf>> {
+    let repo = git::main_worktree(dir)
+        .with_context(|| format!("{} is not a git repository", dir.display()))?;
+    match client.get_project_by_directory(&repo.to_string_lossy()).await {
+        Ok(_) => {}
+        Err(ClientError::NotFound(_)) => bail!(
+            "{} is not a registered project directory. Register it with add_project_directory first",
+            repo.display()
+        ),
+        Err(e) => return Err(e.into()),
+    }
+
+    let hooks_dir = git::hooks_dir(&repo)?;
+    std::fs::create_dir_all(&hooks_dir)?;
+    let exe = std::env::current_exe().context("Could not locate the mfst executable")?;
+
+    let mut installed = Vec::new();
+    for hook in HOOKS {
+        let path = hooks_dir.join(hook);
+        if let Ok(existing) = std::fs::read_to_string(&path) {
+            if !existing.contains(MARKER) && !force {
+                bail!(
+                    "{} already exists. Re-run with --force to replace it",
+                    path.display()
+                );
+            }
+        }
+        std::fs::write(&path, hook_script(hook, &exe))?;
+        make_executable(&path)?;
+        installed.push(path);
+    }
+    Ok(installed)
+}
+
+fn hook_script(hook: &str, exe: &Path) -> String {
+    let exe = exe.display();
+    match hook {
+        "commit-msg" => format!("#!/bin/sh\n{MARKER}\nexec \"{exe}\" hook commit-msg \"$1\"\n"),
+        // Recording is best effort; never make a finished commit look failed
+        _ => format!("#!/bin/sh\n{MARKER}\n\"{exe}\" hook {hook} || true\n"),
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Check the `Feature:` trailers of the message in `message_file`.
+///
+/// Commits are let through if the server cannot be reached, so the hook
+/// never blocks offline work.
+pub async fn commit_msg(client: &ManifestClient, dir: &Path, message_file: &Path) -> Result<()> {
+    let references = git::message_feature_trailers(dir, message_file)?;
+    if references.is_empty() {
+        return Ok(());
+    }
+
+    let Some(project) = project_for(client, dir).await? else {
+        return Ok(());
+    };
+
+    let mut problems = Vec::new();
+    for reference in &references {
+        match client.resolve_feature(project.id, reference).await {
+            Ok(_) => {}
+            Err(ClientError::NotFound(_)) => problems.push(format!(
+                "Feature: {} does not match any feature in project '{}'",
+                reference, project.name
+            )),
+            Err(ClientError::BadRequest(message)) => problems.push(message),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!("{}", problems.join("\n"));
+    }
+    Ok(())
+}
+
+/// Record the commit at `HEAD` on every feature its trailers reference.
+///
+/// Returns what was recorded. Failures for one feature are reported on
+/// stderr and do not stop the others.
+pub async fn post_commit(client: &ManifestClient, dir: &Path) -> Result<Vec<RecordedCommit>> {
+    let entry = git::head_commit(dir)?;
+    if entry.features.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let Some(project) = project_for(client, dir).await? else {
+        return Ok(vec![]);
+    };
+
+    let input = RecordCommitInput {
+        commit: CommitRef {
+            sha: entry.sha.clone(),
+            message: entry.subject.clone(),
+            author: Some(entry.author.clone()),
+        },
+        branch: git::current_branch(dir),
+    };
+
+    let mut recorded = Vec::new();
+    for reference in &entry.features {
+        let result = match client.resolve_feature(project.id, reference).await {
+            Ok(feature) => client.record_commit(feature.id, &input).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(r) => recorded.push(r),
+            Err(e) => eprintln!(
+                "manifest: could not record {} on '{}': {}",
+                &entry.sha[..entry.sha.len().min(8)],
+                reference,
+                e
+            ),
+        }
+    }
+    Ok(recorded)
+}
+
+/// The project whose registered directory contains `dir`.
+///
+/// Returns `None` (after a warning) when the server is unreachable.
+async fn project_for(client: &ManifestClient, dir: &Path) -> Result<Option<Project>> {
+    let repo = git::main_worktree(dir)?;
+    match client
+        .get_project_by_directory(&repo.to_string_lossy())
+        .await
+    {
+        Ok(p) => Ok(Some(p.project)),
+        Err(ClientError::Http(e)) => {
+            eprintln!(
+                "manifest: server unreachable, skipping feature trailers: {}",
+                e
+            );
+            Ok(None)
+        }
+        Err(ClientError::NotFound(_)) => {
+            bail!("{} is not a registered project directory", repo.display())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
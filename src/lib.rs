@@ -1,5 +1,12 @@
 pub mod api;
+pub mod backup;
+pub mod drift;
+pub mod gherkin;
+pub mod git;
+pub mod hook;
 pub mod mcp;
+pub mod snapshot;
+pub mod test_report;
 
 // Re-export from manifest-core for convenience
 pub use manifest_core::db;
@@ -1,8 +1,9 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::io::Write;
+use std::path::PathBuf;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use manifest::{api, db, mcp};
+use manifest::{api, backup, db, drift, gherkin, hook, mcp, snapshot, test_report};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -48,11 +49,239 @@ enum Commands {
         daemon: bool,
     },
     /// Start MCP server via stdio (for Claude Code integration)
-    Mcp,
+    Mcp {
+        /// Use the local database directly instead of a running server
+        /// (ignored when MANIFEST_URL is set)
+        #[arg(long)]
+        local: bool,
+    },
     /// Check server status
     Status,
     /// Stop the daemon
     Stop,
+    /// List implemented features whose code changed after their spec
+    Drift {
+        /// Registered project directory
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+
+        /// Exit with status 1 if any feature has drifted
+        #[arg(long)]
+        check: bool,
+    },
+    /// Push a JUnit XML or libtest JSON test report (for CI)
+    ReportTests {
+        /// Report file
+        file: PathBuf,
+
+        /// Registered project directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Import features from Gherkin `.feature` files (previews unless --confirm)
+    Import {
+        /// Files, or directories searched for `.feature` files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        #[arg(long, value_enum)]
+        format: ExchangeFormat,
+
+        /// Registered project directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Create the features under this feature (id, key or path)
+        #[arg(long)]
+        parent: Option<String>,
+
+        /// Put scenarios in the feature details instead of acceptance criteria
+        #[arg(long)]
+        scenarios_in_details: bool,
+
+        /// Create the features instead of previewing them
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Export a feature subtree as a Gherkin `.feature` file
+    Export {
+        /// Root feature of the subtree (id, key or path)
+        feature: String,
+
+        #[arg(long, value_enum)]
+        format: ExchangeFormat,
+
+        /// Registered project directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Back up, restore or migrate the database
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+    /// Export or import a lossless project snapshot (JSON or YAML)
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+    /// Manage git hooks that link commits to features
+    Hook {
+        #[command(subcommand)]
+        command: HookCommands,
+    },
+}
+
+/// File formats features are imported from and exported to.
+#[derive(Clone, Copy, ValueEnum)]
+enum ExchangeFormat {
+    Gherkin,
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Copy the database to a file (safe while the server is running)
+    Backup {
+        /// Backup file to write
+        path: PathBuf,
+    },
+    /// Replace the database with a backup, saving the current one first
+    Restore {
+        /// Backup file to restore
+        path: PathBuf,
+    },
+    /// List applied and pending migrations
+    Status,
+    /// Apply pending migrations (the server does this on startup)
+    Migrate {
+        /// Print the SQL that would run instead of running it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Revert migrations newer than a version with their down scripts,
+    /// saving the current database first
+    Rollback {
+        /// Version to roll back to (e.g. 019)
+        version: String,
+
+        /// Print the SQL that would run instead of running it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    /// Write a snapshot of a project to stdout
+    Export {
+        /// Project id, or a registered project directory
+        #[arg(default_value = ".")]
+        project: String,
+
+        #[arg(long, value_enum, default_value = "json")]
+        format: SnapshotFileFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Restore a project, with its IDs, from a JSON or YAML snapshot
+    Import {
+        /// Snapshot file
+        file: PathBuf,
+
+        /// Overwrite the project if it already exists
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SnapshotFileFormat {
+    Json,
+    Yaml,
+}
+
+#[derive(Subcommand)]
+enum HookCommands {
+    /// Install commit-msg and post-commit hooks in a registered project directory
+    Install {
+        /// Repository to install into
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+
+        /// Replace existing hooks not installed by mfst
+        #[arg(long)]
+        force: bool,
+    },
+    /// Validate `Feature:` trailers (run by the commit-msg hook)
+    #[command(hide = true)]
+    CommitMsg {
+        /// File holding the commit message
+        file: PathBuf,
+    },
+    /// Record the new commit on its features (run by the post-commit hook)
+    #[command(hide = true)]
+    PostCommit,
+}
+
+/// Start backing the database up in the background if MANIFEST_BACKUP_DIR is set
+fn start_scheduled_backups(db: &db::Database) {
+    if let Some(schedule) = backup::BackupSchedule::from_env() {
+        if db.backend() == db::Backend::Postgres {
+            tracing::warn!(
+                "MANIFEST_BACKUP_DIR is ignored for PostgreSQL; back it up with pg_dump"
+            );
+            return;
+        }
+        tracing::info!(
+            "Backing up to {} every {}h, keeping {}",
+            schedule.dir.display(),
+            schedule.interval.as_secs() / 3600,
+            schedule.keep
+        );
+        schedule.spawn(db.clone());
+    }
+}
+
+/// Render `mfst db status` as a table.
+fn format_migration_status(statuses: &[db::MigrationStatus]) -> String {
+    let mut out = String::new();
+    for s in statuses {
+        let state = match s.state {
+            db::MigrationState::Pending => "pending",
+            db::MigrationState::Applied => "applied",
+            db::MigrationState::Modified => "EDITED",
+            db::MigrationState::Unknown => "UNKNOWN",
+        };
+        out.push_str(&format!(
+            "{}  {:<32} {:<8} {}{}\n",
+            s.version,
+            s.name,
+            state,
+            s.applied_at.as_deref().unwrap_or("-"),
+            if s.reversible { "  (reversible)" } else { "" }
+        ));
+    }
+    let count = |state| statuses.iter().filter(|s| s.state == state).count();
+    out.push_str(&format!(
+        "{} applied, {} pending\n",
+        count(db::MigrationState::Applied) + count(db::MigrationState::Modified),
+        count(db::MigrationState::Pending)
+    ));
+    if count(db::MigrationState::Modified) > 0 {
+        out.push_str("EDITED migrations changed after they were applied; the server won't start until their SQL is restored.\n");
+    }
+    if count(db::MigrationState::Unknown) > 0 {
+        out.push_str(
+            "UNKNOWN migrations come from a newer mfst; the server won't start on this database.\n",
+        );
+    }
+    out
 }
 
 /// Initialize tracing with output to stderr (for MCP mode) or stdout
@@ -79,8 +308,20 @@ fn init_tracing(use_stderr: bool) {
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    // MCP mode needs stderr for logging since stdout is the protocol channel
-    let use_stderr = matches!(cli.command, Some(Commands::Mcp));
+    // MCP mode needs stderr for logging since stdout is the protocol channel;
+    // client commands log there too so their output stays readable
+    let use_stderr = matches!(
+        cli.command,
+        Some(
+            Commands::Mcp { .. }
+                | Commands::Drift { .. }
+                | Commands::ReportTests { .. }
+                | Commands::Import { .. }
+                | Commands::Export { .. }
+                | Commands::Snapshot { .. }
+                | Commands::Hook { .. }
+        )
+    );
     init_tracing(use_stderr);
 
     match cli.command {
@@ -100,6 +341,7 @@ async fn main() -> anyhow::Result<()> {
 
             let db = db::Database::open_default()?;
             db.migrate()?;
+            start_scheduled_backups(&db);
 
             let app = api::create_router(db);
 
@@ -108,16 +350,30 @@ async fn main() -> anyhow::Result<()> {
 
             axum::serve(listener, app).await?;
         }
-        Some(Commands::Mcp) => {
-            // MCP server uses HTTP client to connect to the API
-            // No local database needed - configure via MANIFEST_URL env var
+        Some(Commands::Mcp { local }) => {
+            // By default the MCP server talks to a running server (MANIFEST_URL);
+            // --local opens the database in this process instead
+            let client = if local {
+                mcp::ManifestClient::local_from_env()?
+            } else {
+                let client = mcp::ManifestClient::from_env();
+                // Keep answering from the cache, and queue writes, while
+                // the server is down
+                match mcp::offline::OfflineStore::open_default() {
+                    Ok(store) => client.with_offline_store(std::sync::Arc::new(store)),
+                    Err(e) => {
+                        tracing::warn!("Offline cache unavailable: {:#}", e);
+                        client
+                    }
+                }
+            };
             let mode = if mcp::is_ide_mode() {
                 "MCP (IDE)"
             } else {
                 "MCP (CLI)"
             };
             print_banner(std::io::stderr(), mode);
-            mcp::run_stdio_server().await?;
+            mcp::run_stdio_server(client).await?;
         }
         Some(Commands::Status) => {
             println!("Checking Manifest server status...");
@@ -127,6 +383,155 @@ async fn main() -> anyhow::Result<()> {
             println!("Stopping Manifest server...");
             // TODO: Stop daemon
         }
+        Some(Commands::Drift { dir, check }) => {
+            let client = mcp::ManifestClient::from_env();
+            let report = drift::report(&client, &dir).await?;
+            print!("{}", drift::format_report(&report));
+            if check && !report.features.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::ReportTests { file, dir }) => {
+            let client = mcp::ManifestClient::from_env();
+            let result = test_report::report(&client, &dir, &file).await?;
+            print!("{}", test_report::format_report(&result));
+        }
+        Some(Commands::Import {
+            paths,
+            format: ExchangeFormat::Gherkin,
+            dir,
+            parent,
+            scenarios_in_details,
+            confirm,
+        }) => {
+            let client = mcp::ManifestClient::from_env();
+            let mapping = if scenarios_in_details {
+                gherkin::ScenarioMapping::Details
+            } else {
+                gherkin::ScenarioMapping::Criteria
+            };
+            let result =
+                gherkin::import(&client, &dir, &paths, parent.as_deref(), mapping, confirm).await?;
+            print!("{}", gherkin::format_import(&result));
+        }
+        Some(Commands::Export {
+            feature,
+            format: ExchangeFormat::Gherkin,
+            dir,
+            output,
+        }) => {
+            let client = mcp::ManifestClient::from_env();
+            let text = gherkin::export(&client, &dir, &feature).await?;
+            match output {
+                Some(path) => std::fs::write(&path, text)?,
+                None => print!("{}", text),
+            }
+        }
+        Some(Commands::Db { command }) => {
+            let db = db::Database::open_default()?;
+            // Restoring and rolling back keep a copy of a SQLite database
+            // first; a PostgreSQL server has backups of its own
+            let save_copy = |name: &str| -> anyhow::Result<()> {
+                if db.backend() == db::Backend::Sqlite {
+                    let saved = db::Database::default_path()?.with_file_name(name);
+                    db.backup_to(&saved)?;
+                    println!("Saved the current database to {}", saved.display());
+                }
+                Ok(())
+            };
+            match command {
+                DbCommands::Backup { path } => {
+                    db.backup_to(&path)?;
+                    println!("Backed up database to {}", path.display());
+                }
+                DbCommands::Restore { path: backup } => {
+                    save_copy("manifest-before-restore.db")?;
+                    let version = db.restore_from(&backup)?;
+                    println!(
+                        "Restored {} (schema {}) and migrated it",
+                        backup.display(),
+                        version
+                    );
+                }
+                DbCommands::Status => {
+                    print!("{}", format_migration_status(&db.migration_status()?))
+                }
+                DbCommands::Migrate { dry_run } => {
+                    let pending = db.pending_migrations()?;
+                    if dry_run {
+                        println!("Would apply {} migration(s)", pending.len());
+                        for m in &pending {
+                            println!("\n-- {} {}\n{}", m.version, m.name, m.sql.trim_end());
+                        }
+                    } else {
+                        db.migrate()?;
+                        println!("Applied {} migration(s)", pending.len());
+                    }
+                }
+                DbCommands::Rollback { version, dry_run } => {
+                    let plan = db.plan_rollback(&version)?;
+                    if dry_run {
+                        println!("Would revert {} migration(s)", plan.len());
+                        for m in &plan {
+                            println!("\n-- {} {}\n{}", m.version, m.name, m.sql.trim_end());
+                        }
+                    } else {
+                        save_copy("manifest-before-rollback.db")?;
+                        for reverted in db.rollback_migrations(&version)? {
+                            println!("Reverted migration {}", reverted);
+                        }
+                    }
+                }
+            }
+        }
+        Some(Commands::Snapshot { command }) => {
+            let client = mcp::ManifestClient::from_env();
+            match command {
+                SnapshotCommands::Export {
+                    project,
+                    format,
+                    output,
+                } => {
+                    let format = match format {
+                        SnapshotFileFormat::Json => snapshot::SnapshotFormat::Json,
+                        SnapshotFileFormat::Yaml => snapshot::SnapshotFormat::Yaml,
+                    };
+                    let text = snapshot::export(&client, &project, format).await?;
+                    match output {
+                        Some(path) => std::fs::write(&path, text)?,
+                        None => print!("{}", text),
+                    }
+                }
+                SnapshotCommands::Import { file, replace } => {
+                    let project = snapshot::import(&client, &file, replace).await?;
+                    println!("Imported project {} ({})", project.name, project.id);
+                }
+            }
+        }
+        Some(Commands::Hook { command }) => {
+            let client = mcp::ManifestClient::from_env();
+            let cwd = std::env::current_dir()?;
+            match command {
+                HookCommands::Install { dir, force } => {
+                    for path in hook::install(&client, &dir, force).await? {
+                        println!("Installed {}", path.display());
+                    }
+                }
+                HookCommands::CommitMsg { file } => {
+                    hook::commit_msg(&client, &cwd, &file).await?;
+                }
+                HookCommands::PostCommit => {
+                    for recorded in hook::post_commit(&client, &cwd).await? {
+                        let target = match recorded.session_id {
+                            _ if recorded.already_recorded => "already recorded".to_string(),
+                            Some(id) => format!("session {}", id),
+                            None => "history".to_string(),
+                        };
+                        eprintln!("manifest: feature {} ({})", recorded.feature_id, target);
+                    }
+                }
+            }
+        }
         None => {
             // Default: start server
             let bind_addr =
@@ -144,6 +549,7 @@ async fn main() -> anyhow::Result<()> {
 
             let db = db::Database::open_default()?;
             db.migrate()?;
+            start_scheduled_backups(&db);
 
             let app = api::create_router(db);
 
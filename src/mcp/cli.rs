@@ -1,9 +1,9 @@
 //! CLI mode MCP server - simplified tools for single-agent CLI workflows.
 //!
-//! This mode exposes 12 tools optimized for CLI agents like Claude Code:
-//! - Discovery: get_project_context, list_features, search_features, get_feature, get_feature_history, render_feature_tree
-//! - Setup: create_project, add_project_directory, create_feature, plan_features
-//! - Work: start_feature, complete_feature
+//! This mode exposes 15 tools optimized for CLI agents like Claude Code:
+//! - Discovery: get_project_context, list_features, search_features, get_feature, get_feature_history, render_feature_tree, get_next_features
+//! - Setup: create_project, add_project_directory, create_feature, plan_features, relate_features
+//! - Work: start_feature, verify_criterion, complete_feature
 
 use std::str::FromStr;
 
@@ -11,7 +11,7 @@ use rmcp::{
     handler::server::{tool::ToolRouter, wrapper::Parameters},
     model::{CallToolResult, Content, ServerInfo},
     schemars::JsonSchema,
-    tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler,
+    tool, tool_router, ErrorData as McpError, ServerHandler,
 };
 use serde::Deserialize;
 use uuid::Uuid;
@@ -27,13 +27,15 @@ use crate::models::*;
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct StartFeatureRequest {
-    #[schemars(description = "The UUID of the feature to start working on")]
+    #[schemars(
+        description = "The UUID, key (e.g. MAN-42) or path of the feature to start working on"
+    )]
     pub feature_id: String,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct CompleteFeatureRequest {
-    #[schemars(description = "The UUID of the feature to complete")]
+    #[schemars(description = "The UUID, key (e.g. MAN-42) or path of the feature to complete")]
     pub feature_id: String,
     #[schemars(description = "Summary of work done - becomes the history entry")]
     pub summary: String,
@@ -43,6 +45,11 @@ pub struct CompleteFeatureRequest {
     #[schemars(description = "Whether to mark the feature as 'implemented'. Defaults to true.")]
     #[serde(default = "default_true")]
     pub mark_implemented: bool,
+    #[schemars(
+        description = "Mark the feature implemented even if some acceptance criteria aren't passing. Defaults to false, which refuses the completion until every criterion is passing."
+    )]
+    #[serde(default)]
+    pub allow_unverified_criteria: bool,
 }
 
 fn default_true() -> bool {
@@ -57,6 +64,7 @@ fn default_true() -> bool {
 pub struct CliMcpServer {
     client: ManifestClient,
     tool_router: ToolRouter<Self>,
+    call_keys: super::ToolCallKeys,
 }
 
 impl CliMcpServer {
@@ -64,6 +72,7 @@ impl CliMcpServer {
         Self {
             client,
             tool_router: Self::tool_router(),
+            call_keys: Default::default(),
         }
     }
 
@@ -76,6 +85,19 @@ impl CliMcpServer {
             .map_err(|e| McpError::invalid_params(format!("Invalid UUID: {}", e), None))
     }
 
+    /// Feature id for a feature id or path (`auth/oauth-integration`).
+    async fn resolve_feature_id(&self, reference: impl Into<FeatureRef>) -> Result<Uuid, McpError> {
+        match reference.into() {
+            FeatureRef::Id(id) => Ok(id),
+            path => self
+                .client
+                .get_feature(path)
+                .await
+                .map(|f| f.id)
+                .map_err(Self::client_err),
+        }
+    }
+
     fn client_err(e: super::client::ClientError) -> McpError {
         match e {
             super::client::ClientError::NotFound(msg) => McpError::invalid_params(msg, None),
@@ -117,7 +139,7 @@ impl CliMcpServer {
     }
 
     #[tool(
-        description = "List features, optionally filtered by project or state. Returns summaries only (id, title, state, priority, parent_id). Use get_feature for full details of a specific feature."
+        description = "List features, optionally filtered by project, state, tags, parent, depth, leaf-only, pending changes, active sessions or last update, and sorted. Returns summaries only (id, key, title, state, priority, parent_id, tags). Use get_feature for full details of a specific feature."
     )]
     async fn list_features(
         &self,
@@ -130,23 +152,54 @@ impl CliMcpServer {
             None => None,
         };
 
+        let parent_id = match req.parent_id {
+            Some(ref pid) => Some(Self::parse_uuid(pid)?),
+            None => None,
+        };
+        let updated_since = match req.updated_since {
+            Some(ref since) => Some(since.parse().map_err(|e| {
+                McpError::invalid_params(format!("Invalid updated_since: {}", e), None)
+            })?),
+            None => None,
+        };
+        let query = ListFeaturesQuery {
+            limit: req.limit,
+            offset: req.offset,
+            cursor: req.cursor,
+            tag: req.tag,
+            state: req.state,
+            parent_id,
+            depth: req.depth,
+            leaf: req.leaf,
+            has_pending_changes: req.has_pending_changes,
+            has_active_session: req.has_active_session,
+            updated_since,
+            sort: req.sort,
+        };
+
         let features = self
             .client
-            .list_features(project_id, req.state.as_deref(), req.limit, req.offset)
+            .list_features(project_id, &query)
             .await
             .map_err(Self::client_err)?;
 
         let result = FeatureListSummaryResponse {
             features: features
+                .items
                 .into_iter()
                 .map(|f| FeatureSummaryInfo {
                     id: f.id.to_string(),
+                    key: f.key,
                     title: f.title,
+                    path: f.path,
                     state: f.state.as_str().to_string(),
                     priority: f.priority,
                     parent_id: f.parent_id.map(|id| id.to_string()),
+                    tags: f.tags,
                 })
                 .collect(),
+            next_cursor: features.next_cursor,
+            total: features.total,
         };
 
         let json = serde_json::to_string_pretty(&result)
@@ -156,7 +209,7 @@ impl CliMcpServer {
     }
 
     #[tool(
-        description = "Search features by title or content. Use this to find specific features without listing all of them. Returns summaries ranked by relevance. Use get_feature for full details."
+        description = "Search features by key (e.g. MAN-42), title or content. Use this to find specific features without listing all of them. Returns summaries ranked by relevance. Use get_feature for full details."
     )]
     async fn search_features(
         &self,
@@ -171,7 +224,7 @@ impl CliMcpServer {
 
         let features = self
             .client
-            .search_features(&req.query, project_id, req.limit)
+            .search_features(&req.query, project_id, req.tag.as_deref(), req.limit)
             .await
             .map_err(Self::client_err)?;
 
@@ -180,12 +233,17 @@ impl CliMcpServer {
                 .into_iter()
                 .map(|f| FeatureSummaryInfo {
                     id: f.id.to_string(),
+                    key: f.key,
                     title: f.title,
+                    path: f.path,
                     state: f.state.as_str().to_string(),
                     priority: f.priority,
                     parent_id: f.parent_id.map(|id| id.to_string()),
+                    tags: f.tags,
                 })
                 .collect(),
+            next_cursor: None,
+            total: None,
         };
 
         let json = serde_json::to_string_pretty(&result)
@@ -202,7 +260,7 @@ impl CliMcpServer {
         params: Parameters<GetFeatureRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         let feature = self
             .client
@@ -226,7 +284,7 @@ impl CliMcpServer {
         params: Parameters<GetFeatureHistoryRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         let history = self
             .client
@@ -264,7 +322,7 @@ impl CliMcpServer {
     }
 
     #[tool(
-        description = "Render a project's feature tree as ASCII art with status symbols. Returns a visual tree showing feature hierarchy and states (◇ proposed, ○ specified, ● implemented, ✗ deprecated)."
+        description = "Render a project's feature tree as ASCII art with status symbols. Returns a visual tree showing feature keys, hierarchy and states (◇ proposed, ○ specified, ● implemented, ✗ deprecated). Features whose latest reported tests failed are marked [tests failing]."
     )]
     async fn render_feature_tree(
         &self,
@@ -279,11 +337,144 @@ impl CliMcpServer {
             .await
             .map_err(Self::client_err)?;
 
-        let rendered = tree_render::render_tree(&tree);
+        let failing: Vec<Uuid> = self
+            .client
+            .get_test_summaries(project_id)
+            .await
+            .map_err(Self::client_err)?
+            .into_iter()
+            .filter(|s| s.failed > 0)
+            .map(|s| s.feature_id)
+            .collect();
+
+        let rendered = tree_render::render_tree(&tree, &failing);
 
         Ok(CallToolResult::success(vec![Content::text(rendered)]))
     }
 
+    #[tool(
+        description = "Relate two features of a project: 'depends_on' when feature_id can't be built before to_feature_id, 'blocks' for the reverse, 'relates_to' or 'supersedes'. Dependencies that would form a cycle are rejected. get_next_features uses dependencies to pick what to build next."
+    )]
+    async fn relate_features(
+        &self,
+        params: Parameters<RelateFeaturesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
+        let to_feature_id = self.resolve_feature_id(&req.to_feature_id).await?;
+        let kind = RelationKind::from_str(&req.kind).map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid kind: {}. Must be one of: depends_on, blocks, relates_to, supersedes",
+                    req.kind
+                ),
+                None,
+            )
+        })?;
+
+        let relation = self
+            .client
+            .create_relation(
+                feature_id,
+                &CreateRelationInput {
+                    to_feature_id,
+                    kind,
+                },
+            )
+            .await
+            .map_err(Self::client_err)?;
+
+        let result = RelationInfo {
+            id: relation.id.to_string(),
+            from_feature_id: relation.from_feature_id.to_string(),
+            to_feature_id: relation.to_feature_id.to_string(),
+            kind: relation.kind.as_str().to_string(),
+        };
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "List the features that can be built next in a project: specified leaf features whose dependencies are all implemented, ordered by priority. Returns summaries only."
+    )]
+    async fn get_next_features(
+        &self,
+        params: Parameters<GetNextFeaturesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let project_id = Self::parse_uuid(&req.project_id)?;
+
+        let features = self
+            .client
+            .get_next_features(project_id)
+            .await
+            .map_err(Self::client_err)?;
+
+        let result = FeatureListSummaryResponse {
+            features: features
+                .into_iter()
+                .map(|f| FeatureSummaryInfo {
+                    id: f.id.to_string(),
+                    key: f.key,
+                    title: f.title,
+                    path: f.path,
+                    state: f.state.as_str().to_string(),
+                    priority: f.priority,
+                    parent_id: f.parent_id.map(|id| id.to_string()),
+                    tags: f.tags,
+                })
+                .collect(),
+            next_cursor: None,
+            total: None,
+        };
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Record whether an acceptance criterion holds, optionally naming the test that verifies it. Acceptance criteria come with the feature from start_feature. Marking a feature implemented is refused until all its criteria are passing."
+    )]
+    async fn verify_criterion(
+        &self,
+        params: Parameters<VerifyCriterionRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let criterion_id = Self::parse_uuid(&req.criterion_id)?;
+        let status = CriterionStatus::from_str(&req.status).map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid status: {}. Must be one of: passing, failing, unverified",
+                    req.status
+                ),
+                None,
+            )
+        })?;
+
+        let criterion = self
+            .client
+            .update_criterion(
+                criterion_id,
+                &UpdateCriterionInput {
+                    status: Some(status),
+                    test_name: req.test_name,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(Self::client_err)?;
+
+        let json = serde_json::to_string_pretty(&ManifestClient::criterion_to_info(&criterion))
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     // ============================================================
     // Setup Tools
     // ============================================================
@@ -301,6 +492,7 @@ impl CliMcpServer {
             .client
             .create_project(&CreateProjectInput {
                 name: req.name,
+                key: req.key,
                 description: req.description,
                 instructions: req.instructions,
             })
@@ -310,6 +502,7 @@ impl CliMcpServer {
         let result = ProjectInfo {
             id: project.id.to_string(),
             name: project.name,
+            key: project.key,
             description: project.description,
             instructions: project.instructions,
         };
@@ -359,7 +552,7 @@ impl CliMcpServer {
     }
 
     #[tool(
-        description = "Create a feature (system capability) within a project. Name by capability, not by task - e.g., 'Router' not 'Implement Routing'. Use parent_id for domain grouping. Use priority field for sequencing."
+        description = "Create a feature (system capability) within a project. Name by capability, not by task - e.g., 'Router' not 'Implement Routing'. Use parent_id for domain grouping. Use priority field for sequencing and tags for cross-cutting concerns (security, v2, frontend)."
     )]
     async fn create_feature(
         &self,
@@ -368,7 +561,10 @@ impl CliMcpServer {
         let req = params.0;
         let project_id = Self::parse_uuid(&req.project_id)?;
         let parent_id = match req.parent_id {
-            Some(pid) => Some(Self::parse_uuid(&pid)?),
+            Some(pid) => Some(
+                self.resolve_feature_id(FeatureRef::from(&pid).in_project(project_id))
+                    .await?,
+            ),
             None => None,
         };
         let state = FeatureState::from_str(&req.state).map_err(|_| {
@@ -392,6 +588,7 @@ impl CliMcpServer {
                     details: req.details,
                     state: Some(state),
                     priority: req.priority,
+                    tags: req.tags,
                 },
             )
             .await
@@ -417,7 +614,7 @@ impl CliMcpServer {
 
         let response = self
             .client
-            .bulk_create_features(project_id, &req.features, req.confirm)
+            .bulk_create_features(project_id, &req.features, None, req.confirm)
             .await
             .map_err(Self::client_err)?;
 
@@ -432,14 +629,14 @@ impl CliMcpServer {
     // ============================================================
 
     #[tool(
-        description = "Signal that you are starting work on a feature. Sets state to 'specified' if currently 'proposed'. Returns the feature details so you know what to implement."
+        description = "Signal that you are starting work on a feature. Sets state to 'specified' if currently 'proposed'. Returns the feature details and acceptance criteria so you know what to implement. Verify each criterion with verify_criterion before complete_feature."
     )]
     async fn start_feature(
         &self,
         params: Parameters<StartFeatureRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         // Get current feature
         let feature = self
@@ -460,6 +657,7 @@ impl CliMcpServer {
                         desired_details: None,
                         state: Some(FeatureState::Specified),
                         priority: None,
+                        tags: None,
                     },
                 )
                 .await
@@ -468,7 +666,15 @@ impl CliMcpServer {
             feature
         };
 
-        let result = ManifestClient::feature_to_info(&feature);
+        let mut result = ManifestClient::feature_to_info(&feature);
+        result.acceptance_criteria = self
+            .client
+            .get_criteria(feature_id)
+            .await
+            .map_err(Self::client_err)?
+            .iter()
+            .map(ManifestClient::criterion_to_info)
+            .collect();
 
         let json = serde_json::to_string_pretty(&result)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -477,14 +683,14 @@ impl CliMcpServer {
     }
 
     #[tool(
-        description = "Complete work on a feature. Creates a history entry with your summary and commits, then marks the feature as 'implemented'. Call this when work is done and verified."
+        description = "Complete work on a feature. Creates a history entry with your summary and commits, then marks the feature as 'implemented'. When the server runs locally, commits made since the feature's last history entry whose 'Feature:' trailer names it (id, key, path or slug) are discovered from git and attached too. Call this when work is done and verified."
     )]
     async fn complete_feature(
         &self,
         params: Parameters<CompleteFeatureRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         // Convert commits
         let commits: Vec<CommitRef> = req
@@ -500,7 +706,13 @@ impl CliMcpServer {
         // Create history entry directly (no session)
         let history = self
             .client
-            .create_feature_history(feature_id, &req.summary, &commits, req.mark_implemented)
+            .create_feature_history(
+                feature_id,
+                &req.summary,
+                &commits,
+                req.mark_implemented,
+                req.allow_unverified_criteria,
+            )
             .await
             .map_err(Self::client_err)?;
 
@@ -527,8 +739,31 @@ impl CliMcpServer {
     }
 }
 
-#[tool_handler]
 impl ServerHandler for CliMcpServer {
+    async fn call_tool(
+        &self,
+        request: rmcp::model::CallToolRequestParam,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let call = self.call_keys.start(&request);
+        let tcc = rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        let result = super::client::with_tool_call_key(call.key, self.tool_router.call(tcc)).await;
+        call.finish(&result);
+        Ok(super::add_connection_notice(&self.client, result?))
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<rmcp::model::PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<rmcp::model::ListToolsResult, McpError> {
+        Ok(rmcp::model::ListToolsResult {
+            tools: self.tool_router.list_all(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             server_info: rmcp::model::Implementation {
@@ -574,14 +809,16 @@ WORKFLOW:
 
 2. START work:
    - start_feature: Transitions proposed → specified
-   - Returns feature details for implementation
+   - Returns feature details and acceptance criteria for implementation
 
 3. IMPLEMENT:
    - Write code, run tests, verify
    - The feature details are your specification
+   - verify_criterion: Record each acceptance criterion as passing or failing
 
 4. COMPLETE work:
    - complete_feature: Records summary + commits, marks implemented
+   - Refuses to mark implemented while acceptance criteria aren't passing
    - Creates history entry for future reference
 
 SETUP (one-time):
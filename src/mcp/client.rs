@@ -4,23 +4,73 @@
 //! Configuration is via environment variables:
 //! - `MANIFEST_URL` - Base URL (default: `http://localhost:17010/api/v1`)
 //! - `MANIFEST_API_KEY` - API key for authentication (optional for local)
+//!
+//! [`ManifestClient::local`] skips the server: requests go to the API
+//! router in-process, on a [`Database`] the caller opened.
+//!
+//! [`ManifestClient::with_offline_store`] keeps a client of a remote server
+//! working while the server is unreachable; see [`super::offline`].
 
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
+use axum::Router;
 use reqwest::{Client, StatusCode};
 use serde::de::DeserializeOwned;
+use serde::Serialize;
 use thiserror::Error;
+use tower::ServiceExt;
 use uuid::Uuid;
 
+use crate::api::{self, SecurityConfig, IDEMPOTENCY_KEY};
+use crate::db::Database;
+use crate::mcp::offline::{OfflineStore, QueuedWrite, RefusedWrite};
 use crate::mcp::{
-    DirectoryInfo, FeatureInfo, PlanFeaturesResponse, ProjectContextResponse, ProjectInfo,
-    ProposedFeature,
+    CommitInfo, CriterionInfo, DirectoryInfo, FeatureInfo, PlanFeaturesResponse,
+    ProjectContextResponse, ProjectInfo, ProposedFeature, SessionInfo, TaskInfo, TaskResultInfo,
 };
 use crate::models::*;
 
 /// Default URL for local development.
 const DEFAULT_URL: &str = "http://localhost:17010/api/v1";
 
+/// Base URL of an in-process client. Only the path reaches the router.
+const IN_PROCESS_URL: &str = "http://in-process/api/v1";
+
+tokio::task_local! {
+    /// The tool call being handled; see [`with_tool_call_key`].
+    static TOOL_CALL: ToolCall;
+}
+
+struct ToolCall {
+    key: Uuid,
+    /// POSTs sent so far.
+    posts: AtomicU32,
+}
+
+/// Send the POSTs `f` makes with idempotency keys derived from `key`: the
+/// first POST to a path gets the same key every time, so running `f` again
+/// under the same key gets the first run's responses.
+pub async fn with_tool_call_key<F: Future>(key: Uuid, f: F) -> F::Output {
+    let call = ToolCall {
+        key,
+        posts: AtomicU32::new(0),
+    };
+    TOOL_CALL.scope(call, f).await
+}
+
+/// The key for the next POST to `path` in the current tool call, if any.
+fn tool_call_idempotency_key(path: &str) -> Option<Uuid> {
+    TOOL_CALL
+        .try_with(|call| {
+            let n = call.posts.fetch_add(1, Ordering::Relaxed);
+            Uuid::new_v5(&call.key, format!("{} {}", n, path).as_bytes())
+        })
+        .ok()
+}
+
 /// HTTP client errors.
 #[derive(Debug, Error)]
 pub enum ClientError {
@@ -40,12 +90,290 @@ pub enum ClientError {
     Server(String),
 }
 
+/// `{id}` route segment for a feature reference, with path slashes encoded.
+fn feature_segment(feature: impl Into<FeatureRef>) -> String {
+    feature.into().to_string().replace('/', "%2F")
+}
+
+/// Percent-encode the characters that would break a query string value.
+fn encode_query_value(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            ' ' => "%20".to_string(),
+            '&' => "%26".to_string(),
+            '=' => "%3D".to_string(),
+            '?' => "%3F".to_string(),
+            '#' => "%23".to_string(),
+            '%' => "%25".to_string(),
+            '+' => "%2B".to_string(),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+/// A request being built, sent over HTTP or to the in-process router.
+struct Request<'a> {
+    builder: reqwest::RequestBuilder,
+    client: &'a ManifestClient,
+    /// Whether the request may be queued while the server is unreachable.
+    queueable: bool,
+}
+
+impl Request<'_> {
+    fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        Self {
+            builder: self.builder.json(json),
+            ..self
+        }
+    }
+
+    fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        Self {
+            builder: self.builder.query(query),
+            ..self
+        }
+    }
+
+    fn body(self, body: impl Into<reqwest::Body>) -> Self {
+        Self {
+            builder: self.builder.body(body),
+            ..self
+        }
+    }
+
+    /// Let the request wait in the offline queue, if the client has one,
+    /// while the server can't be reached. A queued request gets a
+    /// `202 Accepted` with its idempotency key; see [`queued_key`].
+    fn queue_offline(self) -> Self {
+        Self {
+            queueable: true,
+            ..self
+        }
+    }
+
+    async fn send(self) -> Result<reqwest::Response, ClientError> {
+        let client = self.client;
+        let mut request = self.builder.build()?;
+        let idempotency_key = if request.method() == reqwest::Method::POST {
+            tool_call_idempotency_key(&client.relative_path(request.url()))
+        } else {
+            None
+        };
+        if let Some(key) = idempotency_key {
+            request.headers_mut().insert(
+                IDEMPOTENCY_KEY,
+                key.to_string()
+                    .parse()
+                    .expect("a UUID is a valid header value"),
+            );
+        }
+        if let Some(router) = &client.router {
+            return send_in_process(router, request).await;
+        }
+        let Some(offline) = &client.offline else {
+            return Ok(client.client.execute(request).await?);
+        };
+
+        client.replay_queued(offline).await;
+        let path = client.relative_path(request.url());
+        let is_read = request.method() == reqwest::Method::GET;
+        let write = QueuedWrite {
+            idempotency_key: idempotency_key.unwrap_or_else(Uuid::new_v4),
+            method: request.method().to_string(),
+            path: path.clone(),
+            content_type: request
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| bytes.to_vec()),
+            queued_at: chrono::Utc::now(),
+        };
+        // Writes queued earlier go first
+        if self.queueable && offline.queue_len().map_err(store_error)? > 0 {
+            return enqueue(offline, write);
+        }
+
+        let sent = client.client.execute(request).await;
+        match sent {
+            Ok(response) if !is_unreachable_status(response.status()) => {
+                offline.set_offline(false);
+                if !(is_read && response.status().is_success()) {
+                    return Ok(response);
+                }
+                let status = response.status();
+                let headers = response.headers().clone();
+                let body = response.bytes().await?;
+                if let Err(e) = offline.cache(&path, &body) {
+                    tracing::warn!("Failed to cache {}: {:#}", path, e);
+                }
+                Ok(rebuild_response(status, headers, body))
+            }
+            // Errors other than a failed connection say nothing about the server
+            Err(e) if !is_unreachable(&e) => Err(e.into()),
+            unreachable => {
+                offline.set_offline(true);
+                if is_read {
+                    if let Some(body) = offline.cached(&path).map_err(store_error)? {
+                        return Ok(rebuild_response(
+                            StatusCode::OK,
+                            Default::default(),
+                            body.into(),
+                        ));
+                    }
+                } else if self.queueable {
+                    return enqueue(offline, write);
+                }
+                Ok(unreachable?)
+            }
+        }
+    }
+}
+
+/// Hand `request` to the API router, as a server would.
+async fn send_in_process(
+    router: &Router,
+    request: reqwest::Request,
+) -> Result<reqwest::Response, ClientError> {
+    let mut local = axum::http::Request::builder()
+        .method(request.method().clone())
+        .uri(request.url().as_str());
+    for (name, value) in request.headers() {
+        local = local.header(name, value);
+    }
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|bytes| bytes.to_vec())
+        .unwrap_or_default();
+    let local = local
+        .body(axum::body::Body::from(body))
+        .map_err(|e| ClientError::Server(e.to_string()))?;
+
+    let response = router
+        .clone()
+        .oneshot(local)
+        .await
+        .unwrap_or_else(|never| match never {});
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ClientError::Server(e.to_string()))?;
+    Ok(axum::http::Response::from_parts(parts, body).into())
+}
+
+/// Statuses a proxy in front of a hosted server answers with while the
+/// server itself is down.
+fn is_unreachable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_unreachable(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+fn rebuild_response(
+    status: StatusCode,
+    headers: reqwest::header::HeaderMap,
+    body: axum::body::Bytes,
+) -> reqwest::Response {
+    let mut response = axum::http::Response::new(body);
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response.into()
+}
+
+fn enqueue(offline: &OfflineStore, write: QueuedWrite) -> Result<reqwest::Response, ClientError> {
+    offline.enqueue(&write).map_err(store_error)?;
+    tracing::info!(
+        "Queued {} {} until the server is back",
+        write.method,
+        write.path
+    );
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        IDEMPOTENCY_KEY,
+        write
+            .idempotency_key
+            .to_string()
+            .parse()
+            .expect("a UUID is a valid header value"),
+    );
+    Ok(rebuild_response(
+        StatusCode::ACCEPTED,
+        headers,
+        axum::body::Bytes::new(),
+    ))
+}
+
+/// The idempotency key of a request that was queued instead of sent.
+fn queued_key(response: &reqwest::Response) -> Option<Uuid> {
+    if response.status() != StatusCode::ACCEPTED {
+        return None;
+    }
+    response
+        .headers()
+        .get(IDEMPOTENCY_KEY)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn store_error(e: anyhow::Error) -> ClientError {
+    ClientError::Server(format!("Offline store failed: {:#}", e))
+}
+
+/// How a client's requests are getting through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Online,
+    /// The server answers, but writes queued while it didn't haven't all
+    /// been accepted yet.
+    Degraded {
+        queued: usize,
+    },
+    /// The server can't be reached. Reads come from the cache and task
+    /// updates and history entries are queued.
+    Offline {
+        queued: usize,
+    },
+}
+
+impl ConnectionStatus {
+    /// A line for agents to see in tool results, unless online.
+    pub fn notice(&self) -> Option<String> {
+        match self {
+            ConnectionStatus::Online => None,
+            ConnectionStatus::Degraded { queued } => Some(format!(
+                "[manifest: degraded] {} queued write(s) not yet accepted by the server; they will be retried",
+                queued
+            )),
+            ConnectionStatus::Offline { queued } => Some(format!(
+                "[manifest: offline] Server unreachable. Results may be stale; {} write(s) queued until it is back",
+                queued
+            )),
+        }
+    }
+}
+
 /// HTTP client for Manifest API.
 #[derive(Debug, Clone)]
 pub struct ManifestClient {
     base_url: String,
     api_key: Option<String>,
     client: Client,
+    /// Set for in-process clients, which send requests here instead.
+    router: Option<Router>,
+    /// Cache and write queue for when the server can't be reached.
+    offline: Option<Arc<OfflineStore>>,
 }
 
 impl ManifestClient {
@@ -68,17 +396,166 @@ impl ManifestClient {
             base_url: base_url.into(),
             api_key,
             client,
+            router: None,
+            offline: None,
         }
     }
 
+    /// Keep working through outages of the server with `store`; see
+    /// [`offline`](super::offline).
+    pub fn with_offline_store(self, store: Arc<OfflineStore>) -> Self {
+        Self {
+            offline: Some(store),
+            ..self
+        }
+    }
+
+    pub fn connection_status(&self) -> ConnectionStatus {
+        let Some(offline) = &self.offline else {
+            return ConnectionStatus::Online;
+        };
+        let queued = offline.queue_len().unwrap_or_default();
+        if offline.is_offline() {
+            ConnectionStatus::Offline { queued }
+        } else if queued > 0 {
+            ConnectionStatus::Degraded { queued }
+        } else {
+            ConnectionStatus::Online
+        }
+    }
+
+    /// Lines for agents to see in tool results: the connection status unless
+    /// online, then each queued write the server refused since the last
+    /// call. Refused writes are only reported once.
+    pub fn notices(&self) -> Vec<String> {
+        let mut notices: Vec<String> = self.connection_status().notice().into_iter().collect();
+        if let Some(offline) = &self.offline {
+            match offline.take_refused() {
+                Ok(refused) => notices.extend(refused.iter().map(RefusedWrite::notice)),
+                Err(e) => tracing::warn!("Failed to read refused writes: {:#}", e),
+            }
+        }
+        notices
+    }
+
+    /// Serve requests in-process from `db`, which should be migrated. Nothing
+    /// needs to be listening; the API runs as it would for a local server.
+    ///
+    /// Other processes may share the database file. A file lock only keeps
+    /// migrations and rollbacks from running at once; concurrent writes wait
+    /// on SQLite's 10 second busy timeout and fail if it runs out.
+    pub fn local(db: Database) -> Self {
+        Self {
+            router: Some(api::create_router_with_config(
+                db,
+                SecurityConfig::disabled(),
+            )),
+            ..Self::new(IN_PROCESS_URL, None)
+        }
+    }
+
+    /// Open the default database and serve requests from it in-process,
+    /// unless `MANIFEST_URL` names a server to use instead.
+    pub fn local_from_env() -> anyhow::Result<Self> {
+        if std::env::var("MANIFEST_URL").is_ok() {
+            return Ok(Self::from_env());
+        }
+        let db = Database::open_default()?;
+        db.migrate()?;
+        Ok(Self::local(db))
+    }
+
     /// Build a request with optional auth header.
-    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+    fn request(&self, method: reqwest::Method, path: &str) -> Request<'_> {
         let url = format!("{}{}", self.base_url, path);
-        let mut req = self.client.request(method, &url);
+        let mut builder = self.client.request(method, &url);
         if let Some(ref key) = self.api_key {
-            req = req.bearer_auth(key);
+            builder = builder.bearer_auth(key);
+        }
+        Request {
+            builder,
+            client: self,
+            queueable: false,
+        }
+    }
+
+    /// `url` relative to the base URL, as the offline store keys it.
+    fn relative_path(&self, url: &reqwest::Url) -> String {
+        let url = url.as_str();
+        url.strip_prefix(self.base_url.as_str())
+            .unwrap_or(url)
+            .to_string()
+    }
+
+    /// Send the writes queued while the server was unreachable, oldest
+    /// first, each with its idempotency key. Stops at the first that can't
+    /// be delivered, to keep them in order.
+    async fn replay_queued(&self, offline: &OfflineStore) {
+        let queued = match offline.queued() {
+            Ok(queued) => queued,
+            Err(e) => {
+                tracing::warn!("Failed to read the offline queue: {:#}", e);
+                return;
+            }
+        };
+        for write in queued {
+            let Ok(method) = write.method.parse::<reqwest::Method>() else {
+                continue;
+            };
+            let url = format!("{}{}", self.base_url, write.path);
+            let mut request = self
+                .client
+                .request(method, &url)
+                .header(IDEMPOTENCY_KEY, write.idempotency_key.to_string());
+            if let Some(ref key) = self.api_key {
+                request = request.bearer_auth(key);
+            }
+            if let Some(content_type) = &write.content_type {
+                request = request.header(reqwest::header::CONTENT_TYPE, content_type);
+            }
+            if let Some(body) = write.body.clone() {
+                request = request.body(body);
+            }
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(e) if is_unreachable(&e) => {
+                    offline.set_offline(true);
+                    return;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to replay {} {}: {}", write.method, write.path, e);
+                    return;
+                }
+            };
+            let status = response.status();
+            if is_unreachable_status(status) {
+                offline.set_offline(true);
+                return;
+            }
+            offline.set_offline(false);
+            if status.is_server_error() {
+                // Try again with the next request
+                return;
+            }
+            let done = if status.is_success() {
+                offline.dequeue(write.idempotency_key)
+            } else {
+                let message = response.text().await.unwrap_or_default();
+                tracing::warn!(
+                    "The server refused queued {} {}: {} {}",
+                    write.method,
+                    write.path,
+                    status,
+                    message
+                );
+                offline.refuse(&write, status.as_u16(), &message)
+            };
+            if let Err(e) = done {
+                tracing::warn!("Failed to update the offline queue: {:#}", e);
+                return;
+            }
         }
-        req
     }
 
     /// Handle response, converting HTTP errors to ClientError.
@@ -134,6 +611,7 @@ impl ManifestClient {
         let response = self
             .request(reqwest::Method::PUT, &format!("/tasks/{}", id))
             .json(input)
+            .queue_offline()
             .send()
             .await?;
         self.handle_empty_response(response).await
@@ -155,41 +633,46 @@ impl ManifestClient {
     /// Get sessions for a feature.
     pub async fn get_sessions_by_feature(
         &self,
-        feature_id: Uuid,
+        feature: impl Into<FeatureRef>,
     ) -> Result<Vec<Session>, ClientError> {
         let response = self
             .request(
                 reqwest::Method::GET,
-                &format!("/features/{}/sessions", feature_id),
+                &format!("/features/{}/sessions", feature_segment(feature)),
             )
             .send()
             .await?;
-        self.handle_response(response).await
+        let page: Page<Session> = self.handle_response(response).await?;
+        Ok(page.items)
     }
 
     /// Create a new session on a feature.
     pub async fn create_session(
         &self,
-        feature_id: Uuid,
+        feature: impl Into<FeatureRef>,
         goal: &str,
+        kind: Option<&str>,
     ) -> Result<SessionResponse, ClientError> {
-        self.create_session_with_tasks(feature_id, goal, &[]).await
+        self.create_session_with_tasks(feature, goal, kind, &[])
+            .await
     }
 
     /// Create a new session on a feature with initial tasks.
     pub async fn create_session_with_tasks(
         &self,
-        feature_id: Uuid,
+        feature: impl Into<FeatureRef>,
         goal: &str,
+        kind: Option<&str>,
         tasks: &[CreateTaskInput],
     ) -> Result<SessionResponse, ClientError> {
         let response = self
             .request(
                 reqwest::Method::POST,
-                &format!("/features/{}/sessions", feature_id),
+                &format!("/features/{}/sessions", feature_segment(feature)),
             )
             .json(&serde_json::json!({
                 "goal": goal,
+                "kind": kind,
                 "tasks": tasks
             }))
             .send()
@@ -248,42 +731,157 @@ impl ManifestClient {
     // ============================================================
 
     /// Get a feature by ID.
-    pub async fn get_feature(&self, id: Uuid) -> Result<Feature, ClientError> {
+    pub async fn get_feature(
+        &self,
+        feature: impl Into<FeatureRef>,
+    ) -> Result<Feature, ClientError> {
         let response = self
-            .request(reqwest::Method::GET, &format!("/features/{}", id))
+            .request(
+                reqwest::Method::GET,
+                &format!("/features/{}", feature_segment(feature)),
+            )
             .send()
             .await?;
         self.handle_response(response).await
     }
 
     /// Get history for a feature.
-    pub async fn get_feature_history(&self, id: Uuid) -> Result<Vec<FeatureHistory>, ClientError> {
+    pub async fn get_feature_history(
+        &self,
+        feature: impl Into<FeatureRef>,
+    ) -> Result<Vec<FeatureHistory>, ClientError> {
         let response = self
-            .request(reqwest::Method::GET, &format!("/features/{}/history", id))
+            .request(
+                reqwest::Method::GET,
+                &format!("/features/{}/history", feature_segment(feature)),
+            )
             .send()
             .await?;
-        self.handle_response(response).await
+        let page: Page<FeatureHistory> = self.handle_response(response).await?;
+        Ok(page.items)
     }
 
     /// Create a history entry directly on a feature (CLI mode).
     /// Optionally marks the feature as implemented.
     pub async fn create_feature_history(
         &self,
-        feature_id: Uuid,
+        feature: impl Into<FeatureRef>,
         summary: &str,
         commits: &[CommitRef],
         mark_implemented: bool,
+        allow_unverified_criteria: bool,
     ) -> Result<FeatureHistory, ClientError> {
-        let response = self
+        let feature = feature.into();
+        // Only an ID says which feature a queued entry belongs to
+        let feature_id = match feature {
+            FeatureRef::Id(id) => Some(id),
+            _ => None,
+        };
+        let mut request = self
             .request(
                 reqwest::Method::POST,
-                &format!("/features/{}/history", feature_id),
+                &format!("/features/{}/history", feature_segment(feature)),
             )
             .json(&serde_json::json!({
                 "summary": summary,
                 "commits": commits,
-                "mark_implemented": mark_implemented
-            }))
+                "mark_implemented": mark_implemented,
+                "allow_unverified_criteria": allow_unverified_criteria
+            }));
+        if feature_id.is_some() {
+            request = request.queue_offline();
+        }
+        let response = request.send().await?;
+        if let (Some(key), Some(feature_id)) = (queued_key(&response), feature_id) {
+            // Stands in for the entry the server will create on replay
+            return Ok(FeatureHistory {
+                id: key,
+                feature_id,
+                session_id: None,
+                details: HistoryDetails {
+                    summary: summary.to_string(),
+                    commits: commits.to_vec(),
+                    ..Default::default()
+                },
+                created_at: chrono::Utc::now(),
+            });
+        }
+        self.handle_response(response).await
+    }
+
+    /// Resolve a feature UUID, path or slug within a project.
+    pub async fn resolve_feature(
+        &self,
+        project_id: Uuid,
+        reference: &str,
+    ) -> Result<Feature, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/features/resolve", project_id),
+            )
+            .query(&[("ref", reference)])
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Implemented features whose code changed after their spec was updated.
+    pub async fn get_drift(&self, project_id: Uuid) -> Result<DriftReport, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/drift", project_id),
+            )
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Push a JUnit XML or libtest JSON report for a project.
+    pub async fn report_test_results(
+        &self,
+        project_id: Uuid,
+        report: String,
+    ) -> Result<TestReportResult, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/projects/{}/test-results", project_id),
+            )
+            .body(report)
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Latest test results of a project's features, counted by outcome.
+    pub async fn get_test_summaries(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<FeatureTestSummary>, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/test-results", project_id),
+            )
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Record a commit against a feature's active session or history.
+    pub async fn record_commit(
+        &self,
+        feature: impl Into<FeatureRef>,
+        input: &RecordCommitInput,
+    ) -> Result<RecordedCommit, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/features/{}/commits", feature_segment(feature)),
+            )
+            .json(input)
             .send()
             .await?;
         self.handle_response(response).await
@@ -304,67 +902,44 @@ impl ManifestClient {
         self.handle_response(response).await
     }
 
-    /// List all features with optional filtering.
+    /// List a page of features with optional filtering.
     /// Always returns summaries only - use get_feature for full details.
     pub async fn list_features(
         &self,
         project_id: Option<Uuid>,
-        state: Option<&str>,
-        limit: Option<u32>,
-        offset: Option<u32>,
-    ) -> Result<Vec<FeatureSummary>, ClientError> {
-        let mut url = match project_id {
+        query: &ListFeaturesQuery,
+    ) -> Result<Page<FeatureSummary>, ClientError> {
+        let url = match project_id {
             Some(pid) => format!("/projects/{}/features", pid),
             None => "/features".to_string(),
         };
 
-        // Build query string
-        let mut params = vec![];
-        if let Some(s) = state {
-            params.push(format!("state={}", s));
-        }
-        if let Some(l) = limit {
-            params.push(format!("limit={}", l));
-        }
-        if let Some(o) = offset {
-            params.push(format!("offset={}", o));
-        }
-        if !params.is_empty() {
-            url.push('?');
-            url.push_str(&params.join("&"));
-        }
-
-        let response = self.request(reqwest::Method::GET, &url).send().await?;
+        let response = self
+            .request(reqwest::Method::GET, &url)
+            .query(query)
+            .send()
+            .await?;
         self.handle_response(response).await
     }
 
-    /// Search features by title and details.
+    /// Search features by key, title and details.
     /// Returns summaries ranked by relevance.
     pub async fn search_features(
         &self,
         query: &str,
         project_id: Option<Uuid>,
+        tag: Option<&str>,
         limit: Option<u32>,
     ) -> Result<Vec<FeatureSummary>, ClientError> {
         let mut url = "/features/search".to_string();
 
-        // Build query string with manual percent-encoding for the query
-        let encoded_query: String = query
-            .chars()
-            .map(|c| match c {
-                ' ' => "%20".to_string(),
-                '&' => "%26".to_string(),
-                '=' => "%3D".to_string(),
-                '?' => "%3F".to_string(),
-                '#' => "%23".to_string(),
-                '%' => "%25".to_string(),
-                _ => c.to_string(),
-            })
-            .collect();
-        let mut params = vec![format!("q={}", encoded_query)];
+        let mut params = vec![format!("q={}", encode_query_value(query))];
         if let Some(pid) = project_id {
             params.push(format!("project_id={}", pid));
         }
+        if let Some(t) = tag {
+            params.push(format!("tag={}", encode_query_value(t)));
+        }
         if let Some(l) = limit {
             params.push(format!("limit={}", l));
         }
@@ -372,17 +947,21 @@ impl ManifestClient {
         url.push_str(&params.join("&"));
 
         let response = self.request(reqwest::Method::GET, &url).send().await?;
-        self.handle_response(response).await
+        let page: Page<FeatureSummary> = self.handle_response(response).await?;
+        Ok(page.items)
     }
 
     /// Update a feature.
     pub async fn update_feature(
         &self,
-        id: Uuid,
+        feature: impl Into<FeatureRef>,
         input: &UpdateFeatureInput,
     ) -> Result<Feature, ClientError> {
         let response = self
-            .request(reqwest::Method::PUT, &format!("/features/{}", id))
+            .request(
+                reqwest::Method::PUT,
+                &format!("/features/{}", feature_segment(feature)),
+            )
             .json(input)
             .send()
             .await?;
@@ -411,6 +990,7 @@ impl ManifestClient {
         &self,
         project_id: Uuid,
         features: &[ProposedFeature],
+        parent_id: Option<Uuid>,
         confirm: bool,
     ) -> Result<PlanFeaturesResponse, ClientError> {
         let response = self
@@ -420,6 +1000,7 @@ impl ManifestClient {
             )
             .json(&serde_json::json!({
                 "features": features,
+                "parent_id": parent_id,
                 "confirm": confirm
             }))
             .send()
@@ -440,17 +1021,45 @@ impl ManifestClient {
         self.handle_response(response).await
     }
 
+    /// Get a lossless snapshot of a project.
+    pub async fn export_snapshot(&self, project_id: Uuid) -> Result<ProjectSnapshot, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/snapshot", project_id),
+            )
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Restore a project from a snapshot, overwriting it if `replace` is set.
+    pub async fn import_snapshot(
+        &self,
+        snapshot: &ProjectSnapshot,
+        replace: bool,
+    ) -> Result<Project, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/snapshots?replace={}", replace),
+            )
+            .json(snapshot)
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
     /// Get project by directory path.
     pub async fn get_project_by_directory(
         &self,
         path: &str,
     ) -> Result<ProjectWithDirectories, ClientError> {
-        let url = format!("{}/projects/by-directory", self.base_url);
-        let mut req = self.client.get(&url).query(&[("path", path)]);
-        if let Some(ref key) = self.api_key {
-            req = req.bearer_auth(key);
-        }
-        let response = req.send().await?;
+        let response = self
+            .request(reqwest::Method::GET, "/projects/by-directory")
+            .query(&[("path", path)])
+            .send()
+            .await?;
         self.handle_response(response).await
     }
 
@@ -464,6 +1073,98 @@ impl ManifestClient {
         self.handle_response(response).await
     }
 
+    /// List the agent types registered for a project.
+    pub async fn get_agent_types(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<AgentTypeConfig>, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/agent-types", project_id),
+            )
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Link a feature to a location in the source tree.
+    pub async fn create_code_link(
+        &self,
+        feature: impl Into<FeatureRef>,
+        input: &CreateCodeLinkInput,
+    ) -> Result<FeatureCodeLink, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/features/{}/code-links", feature_segment(feature)),
+            )
+            .json(input)
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Relate a feature to another one.
+    pub async fn create_relation(
+        &self,
+        feature: impl Into<FeatureRef>,
+        input: &CreateRelationInput,
+    ) -> Result<FeatureRelation, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/features/{}/relations", feature_segment(feature)),
+            )
+            .json(input)
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// A feature's acceptance criteria, in order.
+    pub async fn get_criteria(
+        &self,
+        feature: impl Into<FeatureRef>,
+    ) -> Result<Vec<AcceptanceCriterion>, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/features/{}/criteria", feature_segment(feature)),
+            )
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    pub async fn update_criterion(
+        &self,
+        id: Uuid,
+        input: &UpdateCriterionInput,
+    ) -> Result<AcceptanceCriterion, ClientError> {
+        let response = self
+            .request(reqwest::Method::PUT, &format!("/criteria/{}", id))
+            .json(input)
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// Specified leaf features whose dependencies are all implemented.
+    pub async fn get_next_features(
+        &self,
+        project_id: Uuid,
+    ) -> Result<Vec<FeatureSummary>, ClientError> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/projects/{}/features/next", project_id),
+            )
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
     /// Add a directory to a project.
     pub async fn add_project_directory(
         &self,
@@ -507,6 +1208,7 @@ impl ManifestClient {
             project: ProjectInfo {
                 id: project_with_dirs.project.id.to_string(),
                 name: project_with_dirs.project.name,
+                key: project_with_dirs.project.key,
                 description: project_with_dirs.project.description,
                 instructions: project_with_dirs.project.instructions,
             },
@@ -524,11 +1226,64 @@ impl ManifestClient {
     pub fn feature_to_info(feature: &Feature) -> FeatureInfo {
         FeatureInfo {
             id: feature.id.to_string(),
+            key: feature.key.clone(),
             title: feature.title.clone(),
+            path: feature.path.clone(),
             details: feature.details.clone(),
             desired_details: feature.desired_details.clone(),
             state: feature.state.as_str().to_string(),
             priority: feature.priority,
+            tags: feature.tags.clone(),
+            acceptance_criteria: vec![],
+        }
+    }
+
+    /// Convert AcceptanceCriterion to CriterionInfo for MCP response.
+    pub fn criterion_to_info(criterion: &AcceptanceCriterion) -> CriterionInfo {
+        CriterionInfo {
+            id: criterion.id.to_string(),
+            text: criterion.text.clone(),
+            status: criterion.status.as_str().to_string(),
+            test_name: criterion.test_name.clone(),
+        }
+    }
+
+    /// Convert Session to SessionInfo for MCP response.
+    pub fn session_to_info(session: &Session) -> SessionInfo {
+        SessionInfo {
+            id: session.id.to_string(),
+            feature_id: session.feature_id.to_string(),
+            goal: session.goal.clone(),
+            kind: session.kind.clone(),
+            status: session.status.as_str().to_string(),
+        }
+    }
+
+    /// Convert Task to TaskInfo for MCP response, including its result report.
+    pub fn task_to_info(task: &Task) -> TaskInfo {
+        TaskInfo {
+            id: task.id.to_string(),
+            title: task.title.clone(),
+            scope: task.scope.clone(),
+            status: task.status.as_str().to_string(),
+            agent_type: task.agent_type.as_str().to_string(),
+            worktree_path: task.worktree_path.clone(),
+            branch: task.branch.clone(),
+            result: task.result.as_ref().map(|r| TaskResultInfo {
+                summary: r.summary.clone(),
+                files_changed: r.files_changed.clone(),
+                commits: r
+                    .commits
+                    .iter()
+                    .map(|c| CommitInfo {
+                        sha: c.sha.clone(),
+                        message: c.message.clone(),
+                        author: c.author.clone(),
+                    })
+                    .collect(),
+                test_outcome: r.test_outcome.map(|o| o.as_str().to_string()),
+                failure_reason: r.failure_reason.clone(),
+            }),
         }
     }
 }
@@ -1,37 +1,108 @@
 //! MCP server for AI-assisted feature development.
 //!
 //! Supports two modes:
-//! - CLI mode (default): 12 tools optimized for single-agent CLI workflows
-//! - IDE mode: 21 tools for multi-agent orchestration and IDE integration
+//! - CLI mode (default): 15 tools optimized for single-agent CLI workflows
+//! - IDE mode: 26 tools for multi-agent orchestration and IDE integration
 //!
 //! Set `MANIFEST_MODE=ide` to use IDE mode.
 
 mod cli;
 pub mod client;
+pub mod offline;
 mod tree_render;
 mod types;
 
 pub use cli::CliMcpServer;
 
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 pub use client::ManifestClient;
 pub use types::*;
 
 use rmcp::{
     handler::server::{tool::ToolRouter, wrapper::Parameters},
-    model::{CallToolResult, Content, ServerInfo},
-    tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler, ServiceExt,
+    model::{CallToolRequestParam, CallToolResult, Content, ServerInfo},
+    tool, tool_router, ErrorData as McpError, ServerHandler, ServiceExt,
 };
 use uuid::Uuid;
 
 use crate::models::*;
 use client::ClientError;
 
+/// How long a tool call that didn't return a result can be retried under
+/// its key.
+const RETRY_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Keys of tool calls that haven't returned a result: ones still running,
+/// given up on by the agent (it timed out), or failed. An agent retrying
+/// such a call sends the same arguments again; the retry gets the first
+/// call's key, so the server answers the writes that already went through
+/// with their first results instead of writing twice. A call that returns a
+/// result drops its key, so calling the tool again later is a new call.
+#[derive(Clone, Default)]
+struct ToolCallKeys(Arc<Mutex<HashMap<String, (Uuid, Instant)>>>);
+
+impl ToolCallKeys {
+    fn start(&self, request: &CallToolRequestParam) -> PendingCall {
+        let call = format!(
+            "{}\n{}",
+            request.name,
+            serde_json::to_string(&request.arguments).unwrap_or_default()
+        );
+        let now = Instant::now();
+        let mut pending = self.0.lock().expect("tool call keys lock poisoned");
+        pending.retain(|_, (_, started)| now.duration_since(*started) < RETRY_WINDOW);
+        let (key, started) = pending.entry(call.clone()).or_insert((Uuid::new_v4(), now));
+        *started = now;
+        PendingCall {
+            keys: self.clone(),
+            call,
+            key: *key,
+        }
+    }
+}
+
+/// A tool call started through [`ToolCallKeys::start`].
+struct PendingCall {
+    keys: ToolCallKeys,
+    call: String,
+    key: Uuid,
+}
+
+impl PendingCall {
+    /// Drop the call's key if it returned a result. Errors keep it for a retry.
+    fn finish(self, result: &Result<CallToolResult, McpError>) {
+        if !matches!(result, Ok(result) if result.is_error != Some(true)) {
+            return;
+        }
+        let mut pending = self.keys.0.lock().expect("tool call keys lock poisoned");
+        if pending
+            .get(&self.call)
+            .is_some_and(|(key, _)| *key == self.key)
+        {
+            pending.remove(&self.call);
+        }
+    }
+}
+
+/// Append the client's notices to a tool result: its connection status,
+/// while it isn't online, so the agent knows the data may be stale or its
+/// writes still queued, and queued writes the server refused.
+fn add_connection_notice(client: &ManifestClient, mut result: CallToolResult) -> CallToolResult {
+    for notice in client.notices() {
+        result.content.push(Content::text(notice));
+    }
+    result
+}
+
 #[derive(Clone)]
 pub struct McpServer {
     client: ManifestClient,
     tool_router: ToolRouter<Self>,
+    call_keys: ToolCallKeys,
 }
 
 impl McpServer {
@@ -39,6 +110,7 @@ impl McpServer {
         Self {
             client,
             tool_router: Self::tool_router(),
+            call_keys: Default::default(),
         }
     }
 
@@ -52,6 +124,19 @@ impl McpServer {
             .map_err(|e| McpError::invalid_params(format!("Invalid UUID: {}", e), None))
     }
 
+    /// Feature id for a feature id or path (`auth/oauth-integration`).
+    async fn resolve_feature_id(&self, reference: impl Into<FeatureRef>) -> Result<Uuid, McpError> {
+        match reference.into() {
+            FeatureRef::Id(id) => Ok(id),
+            path => self
+                .client
+                .get_feature(path)
+                .await
+                .map(|f| f.id)
+                .map_err(Self::client_err),
+        }
+    }
+
     /// Convert ClientError to McpError.
     fn client_err(e: ClientError) -> McpError {
         match e {
@@ -64,6 +149,44 @@ impl McpServer {
             ClientError::Server(msg) => McpError::internal_error(msg, None),
         }
     }
+
+    /// Build a TaskResult from the fields agents report via complete_task/fail_task.
+    fn task_result(
+        summary: Option<String>,
+        files_changed: Vec<String>,
+        commits: Vec<CommitRefInput>,
+        test_outcome: Option<String>,
+        failure_reason: Option<String>,
+    ) -> Result<TaskResult, McpError> {
+        let test_outcome = test_outcome
+            .map(|o| {
+                TestOutcome::from_str(&o).map_err(|_| {
+                    McpError::invalid_params(
+                        format!(
+                            "Invalid test_outcome '{}'. Must be: passed, failed, or skipped",
+                            o
+                        ),
+                        None,
+                    )
+                })
+            })
+            .transpose()?;
+
+        Ok(TaskResult {
+            summary: summary.unwrap_or_default(),
+            files_changed,
+            commits: commits
+                .into_iter()
+                .map(|c| CommitRef {
+                    sha: c.sha,
+                    message: c.message,
+                    author: c.author,
+                })
+                .collect(),
+            test_outcome,
+            failure_reason,
+        })
+    }
 }
 
 #[tool_router]
@@ -73,7 +196,7 @@ impl McpServer {
     // ============================================================
 
     #[tool(
-        description = "Retrieve your assigned task with full feature context. Call this FIRST when starting work. Returns: task details (id, title, scope, status), feature specification (title, details), and session goal. Use this information to understand what to implement before writing any code."
+        description = "Retrieve your assigned task with full feature context. Call this FIRST when starting work. Returns: task details (id, title, scope, status, and worktree_path/branch when the server provisioned a git worktree), feature specification (title, details), session goal, and default instructions registered for your agent type. Use this information to understand what to implement before writing any code."
     )]
     async fn get_task_context(
         &self,
@@ -98,16 +221,30 @@ impl McpServer {
             .await
             .map_err(Self::client_err)?;
 
+        let agent_instructions = self
+            .client
+            .get_agent_types(feature.project_id)
+            .await
+            .map_err(Self::client_err)?
+            .into_iter()
+            .find(|t| t.name == task.agent_type)
+            .and_then(|t| t.instructions);
+
+        let mut feature_info = ManifestClient::feature_to_info(&feature);
+        feature_info.acceptance_criteria = self
+            .client
+            .get_criteria(feature.id)
+            .await
+            .map_err(Self::client_err)?
+            .iter()
+            .map(ManifestClient::criterion_to_info)
+            .collect();
+
         let context = TaskContextResponse {
-            task: TaskInfo {
-                id: task.id.to_string(),
-                title: task.title,
-                scope: task.scope,
-                status: task.status.as_str().to_string(),
-                agent_type: task.agent_type.as_str().to_string(),
-            },
-            feature: ManifestClient::feature_to_info(&feature),
+            task: ManifestClient::task_to_info(&task),
+            feature: feature_info,
             session_goal: session.goal,
+            agent_instructions,
         };
 
         let json = serde_json::to_string_pretty(&context)
@@ -133,6 +270,7 @@ impl McpServer {
                     status: Some(TaskStatus::Running),
                     worktree_path: None,
                     branch: None,
+                    result: None,
                 },
             )
             .await
@@ -144,7 +282,7 @@ impl McpServer {
     }
 
     #[tool(
-        description = "Signal that your task is finished. Call this ONLY when all work is done and verified. Before calling: ensure code compiles, tests pass, and implementation matches the task scope. After calling: your work is recorded and you should stop making changes. Side effect: updates task.status to 'completed'."
+        description = "Signal that your task is finished. Call this ONLY when all work is done and verified. Before calling: ensure code compiles, tests pass, and implementation matches the task scope. Report what you did in summary, files_changed, commits, and test_outcome - the orchestrator uses these to write the session summary. After calling: your work is recorded and you should stop making changes. Side effect: updates task.status to 'completed'."
     )]
     async fn complete_task(
         &self,
@@ -153,6 +291,14 @@ impl McpServer {
         let req = params.0;
         let task_id = Self::parse_uuid(&req.task_id)?;
 
+        let result = Self::task_result(
+            req.summary,
+            req.files_changed,
+            req.commits,
+            req.test_outcome,
+            None,
+        )?;
+
         self.client
             .update_task(
                 task_id,
@@ -160,6 +306,7 @@ impl McpServer {
                     status: Some(TaskStatus::Completed),
                     worktree_path: None,
                     branch: None,
+                    result: Some(result),
                 },
             )
             .await
@@ -170,32 +317,63 @@ impl McpServer {
         )]))
     }
 
+    #[tool(
+        description = "Signal that your task could not be completed. Call this instead of complete_task when you are blocked, the scope is wrong, or verification fails and you cannot fix it. Explain why in failure_reason so the orchestrator can decide whether to retry, re-scope, or escalate. Side effect: updates task.status to 'failed'."
+    )]
+    async fn fail_task(
+        &self,
+        params: Parameters<FailTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let task_id = Self::parse_uuid(&req.task_id)?;
+
+        let result = Self::task_result(
+            req.summary,
+            req.files_changed,
+            req.commits,
+            req.test_outcome,
+            Some(req.failure_reason),
+        )?;
+
+        self.client
+            .update_task(
+                task_id,
+                &UpdateTaskInput {
+                    status: Some(TaskStatus::Failed),
+                    worktree_path: None,
+                    branch: None,
+                    result: Some(result),
+                },
+            )
+            .await
+            .map_err(Self::client_err)?;
+
+        Ok(CallToolResult::success(vec![Content::text(
+            "Task marked as failed",
+        )]))
+    }
+
     // ============================================================
     // Orchestrator Tools - Used to manage sessions and tasks
     // ============================================================
 
     #[tool(
-        description = "Start a new implementation session on a feature. Only one active session per feature is allowed. Use this to begin work on a feature, then create tasks within the session for agents to execute. The goal should describe the overall objective. Constraint: feature must be a leaf (no children). Side effect: creates session with status 'active'."
+        description = "Start a new implementation session on a feature. Only one active session per feature is allowed unless the project sets max_concurrent_sessions, in which case parallel sessions need distinct kinds. Use this to begin work on a feature, then create tasks within the session for agents to execute. The goal should describe the overall objective. Constraint: feature must be a leaf (no children). Side effect: creates session with status 'active'."
     )]
     async fn create_session(
         &self,
         params: Parameters<CreateSessionRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         let response = self
             .client
-            .create_session(feature_id, &req.goal)
+            .create_session(feature_id, &req.goal, req.kind.as_deref())
             .await
             .map_err(Self::client_err)?;
 
-        let result = SessionInfo {
-            id: response.session.id.to_string(),
-            feature_id: response.session.feature_id.to_string(),
-            goal: response.session.goal,
-            status: response.session.status.as_str().to_string(),
-        };
+        let result = ManifestClient::session_to_info(&response.session);
 
         let json = serde_json::to_string_pretty(&result)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -204,14 +382,14 @@ impl McpServer {
     }
 
     #[tool(
-        description = "Get the active session for a feature, if one exists. Use this to check if a feature already has work in progress before attempting to create a new session. Returns the session details if an active session exists, or null if no active session. This is useful for recovering from 'already has an active session' errors."
+        description = "Get the active session(s) for a feature, if any exist. Use this to check if a feature already has work in progress before attempting to create a new session. Returns the session details if an active session exists, or null if no active session. This is useful for recovering from 'already has an active session' errors."
     )]
     async fn get_feature_session(
         &self,
         params: Parameters<GetFeatureSessionRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         // Verify feature exists
         self.client
@@ -226,25 +404,28 @@ impl McpServer {
             .await
             .map_err(Self::client_err)?;
 
-        // Find the active session (if any)
-        let active_session = sessions
-            .into_iter()
-            .find(|s| s.status == SessionStatus::Active);
-        let has_active = active_session.is_some();
+        // Collect the active sessions, oldest first
+        let active_sessions: Vec<SessionInfo> = sessions
+            .iter()
+            .rev()
+            .filter(|s| s.status == SessionStatus::Active)
+            .map(ManifestClient::session_to_info)
+            .collect();
 
         let result = FeatureSessionResponse {
             feature_id: feature_id.to_string(),
-            session: active_session.map(|s| SessionInfo {
-                id: s.id.to_string(),
-                feature_id: s.feature_id.to_string(),
-                goal: s.goal,
-                status: s.status.as_str().to_string(),
-            }),
-            message: if has_active {
+            session: active_sessions.first().cloned(),
+            message: if active_sessions.len() > 1 {
+                format!(
+                    "Feature has {} active sessions. Use one of them, or start another with a distinct kind if the project allows it.",
+                    active_sessions.len()
+                )
+            } else if !active_sessions.is_empty() {
                 "Feature has an active session. Use this session or complete it before starting a new one.".to_string()
             } else {
                 "No active session. You can create a new session with create_session or breakdown_feature.".to_string()
             },
+            sessions: active_sessions,
         };
 
         let json = serde_json::to_string_pretty(&result)
@@ -266,7 +447,7 @@ impl McpServer {
         let agent_type = AgentType::from_str(&req.agent_type).map_err(|_| {
             McpError::invalid_params(
                 format!(
-                    "Invalid agent_type '{}'. Must be: claude, gemini, or codex",
+                    "Invalid agent_type '{}'. Use lowercase letters, digits, '-' and '_'",
                     req.agent_type
                 ),
                 None,
@@ -287,13 +468,7 @@ impl McpServer {
             .await
             .map_err(Self::client_err)?;
 
-        let result = TaskInfo {
-            id: task.id.to_string(),
-            title: task.title,
-            scope: task.scope,
-            status: task.status.as_str().to_string(),
-            agent_type: task.agent_type.as_str().to_string(),
-        };
+        let result = ManifestClient::task_to_info(&task);
 
         let json = serde_json::to_string_pretty(&result)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -309,7 +484,7 @@ impl McpServer {
         params: Parameters<BreakdownFeatureRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         // Convert TaskInputItem to CreateTaskInput
         let tasks: Result<Vec<CreateTaskInput>, McpError> = req
@@ -319,7 +494,7 @@ impl McpServer {
                 let agent_type = AgentType::from_str(&t.agent_type).map_err(|_| {
                     McpError::invalid_params(
                         format!(
-                            "Invalid agent_type '{}'. Must be: claude, gemini, or codex",
+                            "Invalid agent_type '{}'. Use lowercase letters, digits, '-' and '_'",
                             t.agent_type
                         ),
                         None,
@@ -337,27 +512,16 @@ impl McpServer {
 
         let response = self
             .client
-            .create_session_with_tasks(feature_id, &req.goal, &tasks)
+            .create_session_with_tasks(feature_id, &req.goal, req.kind.as_deref(), &tasks)
             .await
             .map_err(Self::client_err)?;
 
         let result = BreakdownFeatureResponse {
-            session: SessionInfo {
-                id: response.session.id.to_string(),
-                feature_id: response.session.feature_id.to_string(),
-                goal: response.session.goal,
-                status: response.session.status.as_str().to_string(),
-            },
+            session: ManifestClient::session_to_info(&response.session),
             tasks: response
                 .tasks
                 .into_iter()
-                .map(|t| TaskInfo {
-                    id: t.id.to_string(),
-                    title: t.title,
-                    scope: t.scope,
-                    status: t.status.as_str().to_string(),
-                    agent_type: t.agent_type.as_str().to_string(),
-                })
+                .map(|t| ManifestClient::task_to_info(&t))
                 .collect(),
         };
 
@@ -387,13 +551,7 @@ impl McpServer {
             session_id: session_id.to_string(),
             tasks: tasks
                 .into_iter()
-                .map(|t| TaskInfo {
-                    id: t.id.to_string(),
-                    title: t.title,
-                    scope: t.scope,
-                    status: t.status.as_str().to_string(),
-                    agent_type: t.agent_type.as_str().to_string(),
-                })
+                .map(|t| ManifestClient::task_to_info(&t))
                 .collect(),
         };
 
@@ -404,7 +562,7 @@ impl McpServer {
     }
 
     #[tool(
-        description = "Complete a session after all tasks are done. Call this when all tasks are completed to finalize the session. Creates a history entry summarizing the work and optionally marks the feature as 'implemented'. IMPORTANT: By default, this marks the feature as implemented. Set mark_implemented=false if the work is partial. Side effects: creates feature_history entry, deletes task records, updates session status to 'completed', optionally updates feature state to 'implemented'."
+        description = "Complete a session after all tasks are done. Call this when all tasks are completed to finalize the session. Creates a history entry summarizing the work and optionally marks the feature as 'implemented'. If summary is omitted, it is drafted from the results agents reported with complete_task/fail_task, and their commits are attached automatically. When the server runs locally, commits made on the task branches since the session started are also discovered from git, so listing commits is optional. IMPORTANT: By default, this marks the feature as implemented. Set mark_implemented=false if the work is partial. Marking implemented is refused while acceptance criteria aren't passing (see verify_criterion) unless allow_unverified_criteria=true. Side effects: creates feature_history entry, deletes task records, updates session status to 'completed', optionally updates feature state to 'implemented'."
     )]
     async fn complete_session(
         &self,
@@ -439,6 +597,8 @@ impl McpServer {
                     summary: req.summary,
                     commits,
                     feature_state,
+                    require_feature_trailer: req.require_feature_trailer,
+                    allow_unverified_criteria: req.allow_unverified_criteria,
                 },
             )
             .await
@@ -454,6 +614,7 @@ impl McpServer {
             }
             .to_string(),
             history_entry_id: result.history_entry.id.to_string(),
+            warnings: result.warnings,
         };
 
         let json = serde_json::to_string_pretty(&response)
@@ -467,7 +628,7 @@ impl McpServer {
     // ============================================================
 
     #[tool(
-        description = "List features, optionally filtered by project or state. Returns summaries only (id, title, state, priority, parent_id). Use get_feature for full details of a specific feature."
+        description = "List features, optionally filtered by project, state, tags, parent, depth, leaf-only, pending changes, active sessions or last update, and sorted. Returns summaries only (id, key, title, state, priority, parent_id, tags). Use get_feature for full details of a specific feature."
     )]
     async fn list_features(
         &self,
@@ -481,25 +642,56 @@ impl McpServer {
             None => None,
         };
 
+        let parent_id = match req.parent_id {
+            Some(ref pid) => Some(Self::parse_uuid(pid)?),
+            None => None,
+        };
+        let updated_since = match req.updated_since {
+            Some(ref since) => Some(since.parse().map_err(|e| {
+                McpError::invalid_params(format!("Invalid updated_since: {}", e), None)
+            })?),
+            None => None,
+        };
+        let query = ListFeaturesQuery {
+            limit: req.limit,
+            offset: req.offset,
+            cursor: req.cursor,
+            tag: req.tag,
+            state: req.state,
+            parent_id,
+            depth: req.depth,
+            leaf: req.leaf,
+            has_pending_changes: req.has_pending_changes,
+            has_active_session: req.has_active_session,
+            updated_since,
+            sort: req.sort,
+        };
+
         // Get features via HTTP client (always returns summaries)
         let features = self
             .client
-            .list_features(project_id, req.state.as_deref(), req.limit, req.offset)
+            .list_features(project_id, &query)
             .await
             .map_err(Self::client_err)?;
 
         // Always return summaries only
         let result = FeatureListSummaryResponse {
             features: features
+                .items
                 .into_iter()
                 .map(|f| FeatureSummaryInfo {
                     id: f.id.to_string(),
+                    key: f.key,
                     title: f.title,
+                    path: f.path,
                     state: f.state.as_str().to_string(),
                     priority: f.priority,
                     parent_id: f.parent_id.map(|id| id.to_string()),
+                    tags: f.tags,
                 })
                 .collect(),
+            next_cursor: features.next_cursor,
+            total: features.total,
         };
 
         let json = serde_json::to_string_pretty(&result)
@@ -509,7 +701,7 @@ impl McpServer {
     }
 
     #[tool(
-        description = "Search features by title or content. Use this to find specific features without listing all of them. Returns summaries ranked by relevance. Use get_feature for full details."
+        description = "Search features by key (e.g. MAN-42), title or content. Use this to find specific features without listing all of them. Returns summaries ranked by relevance. Use get_feature for full details."
     )]
     async fn search_features(
         &self,
@@ -526,7 +718,7 @@ impl McpServer {
         // Get features via HTTP client
         let features = self
             .client
-            .search_features(&req.query, project_id, req.limit)
+            .search_features(&req.query, project_id, req.tag.as_deref(), req.limit)
             .await
             .map_err(Self::client_err)?;
 
@@ -535,12 +727,17 @@ impl McpServer {
                 .into_iter()
                 .map(|f| FeatureSummaryInfo {
                     id: f.id.to_string(),
+                    key: f.key,
                     title: f.title,
+                    path: f.path,
                     state: f.state.as_str().to_string(),
                     priority: f.priority,
                     parent_id: f.parent_id.map(|id| id.to_string()),
+                    tags: f.tags,
                 })
                 .collect(),
+            next_cursor: None,
+            total: None,
         };
 
         let json = serde_json::to_string_pretty(&result)
@@ -557,7 +754,7 @@ impl McpServer {
         params: Parameters<GetFeatureRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         let feature = self
             .client
@@ -581,7 +778,7 @@ impl McpServer {
         params: Parameters<GetFeatureHistoryRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         let history = self
             .client
@@ -640,7 +837,7 @@ impl McpServer {
     }
 
     #[tool(
-        description = "Render a project's feature tree as ASCII art with status symbols. Returns a visual tree showing feature hierarchy and states (◇ proposed, ○ specified, ● implemented, ✗ deprecated)."
+        description = "Render a project's feature tree as ASCII art with status symbols. Returns a visual tree showing feature keys, hierarchy and states (◇ proposed, ○ specified, ● implemented, ✗ deprecated). Features whose latest reported tests failed are marked [tests failing]."
     )]
     async fn render_feature_tree(
         &self,
@@ -655,7 +852,17 @@ impl McpServer {
             .await
             .map_err(Self::client_err)?;
 
-        let rendered = tree_render::render_tree(&tree);
+        let failing: Vec<Uuid> = self
+            .client
+            .get_test_summaries(project_id)
+            .await
+            .map_err(Self::client_err)?
+            .into_iter()
+            .filter(|s| s.failed > 0)
+            .map(|s| s.feature_id)
+            .collect();
+
+        let rendered = tree_render::render_tree(&tree, &failing);
 
         Ok(CallToolResult::success(vec![Content::text(rendered)]))
     }
@@ -702,19 +909,20 @@ impl McpServer {
     }
 
     #[tool(
-        description = "Update a feature's state, title, or details. Use this to transition features through their lifecycle (proposed → specified → implemented → deprecated) or to update living documentation when implementation reveals new information. At least one field (state, title, or details) must be provided."
+        description = "Update a feature's state, title, details, or tags. Use this to transition features through their lifecycle (proposed → specified → implemented → deprecated) or to update living documentation when implementation reveals new information. At least one field (state, title, details, or tags) must be provided."
     )]
     async fn update_feature_state(
         &self,
         params: Parameters<UpdateFeatureStateRequest>,
     ) -> Result<CallToolResult, McpError> {
         let req = params.0;
-        let feature_id = Self::parse_uuid(&req.feature_id)?;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
 
         // Validate at least one field is provided
-        if req.state.is_none() && req.title.is_none() && req.details.is_none() {
+        if req.state.is_none() && req.title.is_none() && req.details.is_none() && req.tags.is_none()
+        {
             return Err(McpError::invalid_params(
-                "At least one of state, title, or details must be provided",
+                "At least one of state, title, details, or tags must be provided",
                 None,
             ));
         }
@@ -746,6 +954,7 @@ impl McpServer {
                     desired_details: None,
                     state: new_state,
                     priority: None,
+                    tags: req.tags,
                 },
             )
             .await
@@ -759,6 +968,175 @@ impl McpServer {
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    #[tool(
+        description = "Record where a feature is implemented in the codebase: a file, directory, or glob, optionally narrowed to a line range or symbol. Call this after implementing a feature so agents editing those files later can find the feature via GET /projects/{id}/features/by-path. The path is relative to the project's primary directory unless directory_id is given."
+    )]
+    async fn link_code(
+        &self,
+        params: Parameters<LinkCodeRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
+        let directory_id = req
+            .directory_id
+            .as_deref()
+            .map(Self::parse_uuid)
+            .transpose()?;
+
+        let link = self
+            .client
+            .create_code_link(
+                feature_id,
+                &CreateCodeLinkInput {
+                    directory_id,
+                    path: req.path,
+                    line_start: req.line_start,
+                    line_end: req.line_end,
+                    symbol: req.symbol,
+                },
+            )
+            .await
+            .map_err(Self::client_err)?;
+
+        let result = CodeLinkInfo {
+            id: link.id.to_string(),
+            feature_id: link.feature_id.to_string(),
+            directory_id: link.directory_id.to_string(),
+            path: link.path,
+            line_start: link.line_start,
+            line_end: link.line_end,
+            symbol: link.symbol,
+        };
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Relate two features of a project: 'depends_on' when feature_id can't be built before to_feature_id, 'blocks' for the reverse, 'relates_to' or 'supersedes'. Dependencies that would form a cycle are rejected. get_next_features uses dependencies to pick what to build next."
+    )]
+    async fn relate_features(
+        &self,
+        params: Parameters<RelateFeaturesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let feature_id = self.resolve_feature_id(&req.feature_id).await?;
+        let to_feature_id = self.resolve_feature_id(&req.to_feature_id).await?;
+        let kind = RelationKind::from_str(&req.kind).map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid kind: {}. Must be one of: depends_on, blocks, relates_to, supersedes",
+                    req.kind
+                ),
+                None,
+            )
+        })?;
+
+        let relation = self
+            .client
+            .create_relation(
+                feature_id,
+                &CreateRelationInput {
+                    to_feature_id,
+                    kind,
+                },
+            )
+            .await
+            .map_err(Self::client_err)?;
+
+        let result = RelationInfo {
+            id: relation.id.to_string(),
+            from_feature_id: relation.from_feature_id.to_string(),
+            to_feature_id: relation.to_feature_id.to_string(),
+            kind: relation.kind.as_str().to_string(),
+        };
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "List the features that can be built next in a project: specified leaf features whose dependencies are all implemented, ordered by priority. Returns summaries only."
+    )]
+    async fn get_next_features(
+        &self,
+        params: Parameters<GetNextFeaturesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let project_id = Self::parse_uuid(&req.project_id)?;
+
+        let features = self
+            .client
+            .get_next_features(project_id)
+            .await
+            .map_err(Self::client_err)?;
+
+        let result = FeatureListSummaryResponse {
+            features: features
+                .into_iter()
+                .map(|f| FeatureSummaryInfo {
+                    id: f.id.to_string(),
+                    key: f.key,
+                    title: f.title,
+                    path: f.path,
+                    state: f.state.as_str().to_string(),
+                    priority: f.priority,
+                    parent_id: f.parent_id.map(|id| id.to_string()),
+                    tags: f.tags,
+                })
+                .collect(),
+            next_cursor: None,
+            total: None,
+        };
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Record whether an acceptance criterion holds, optionally naming the test that verifies it. Acceptance criteria come with the feature from get_task_context. Marking a feature implemented is refused until all its criteria are passing."
+    )]
+    async fn verify_criterion(
+        &self,
+        params: Parameters<VerifyCriterionRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let req = params.0;
+        let criterion_id = Self::parse_uuid(&req.criterion_id)?;
+        let status = CriterionStatus::from_str(&req.status).map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid status: {}. Must be one of: passing, failing, unverified",
+                    req.status
+                ),
+                None,
+            )
+        })?;
+
+        let criterion = self
+            .client
+            .update_criterion(
+                criterion_id,
+                &UpdateCriterionInput {
+                    status: Some(status),
+                    test_name: req.test_name,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(Self::client_err)?;
+
+        let json = serde_json::to_string_pretty(&ManifestClient::criterion_to_info(&criterion))
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     // ============================================================
     // Setup Tools - Create projects, directories, and features
     // ============================================================
@@ -776,6 +1154,7 @@ impl McpServer {
             .client
             .create_project(&CreateProjectInput {
                 name: req.name,
+                key: req.key,
                 description: req.description,
                 instructions: req.instructions,
             })
@@ -785,6 +1164,7 @@ impl McpServer {
         let result = ProjectInfo {
             id: project.id.to_string(),
             name: project.name,
+            key: project.key,
             description: project.description,
             instructions: project.instructions,
         };
@@ -834,7 +1214,7 @@ impl McpServer {
     }
 
     #[tool(
-        description = "Create a feature (system capability) within a project. Name by capability, not by phase or task - e.g., 'Router' not 'Phase 1: Implement Routing'. Use parent_id for domain grouping (e.g., 'Authentication' parent with 'OAuth' and 'Password Login' children). Only leaf features can have implementation sessions. Use priority field for sequencing."
+        description = "Create a feature (system capability) within a project. Name by capability, not by phase or task - e.g., 'Router' not 'Phase 1: Implement Routing'. Use parent_id for domain grouping (e.g., 'Authentication' parent with 'OAuth' and 'Password Login' children). Only leaf features can have implementation sessions. Use priority field for sequencing and tags for cross-cutting concerns (security, v2, frontend)."
     )]
     async fn create_feature(
         &self,
@@ -843,7 +1223,10 @@ impl McpServer {
         let req = params.0;
         let project_id = Self::parse_uuid(&req.project_id)?;
         let parent_id = match req.parent_id {
-            Some(pid) => Some(Self::parse_uuid(&pid)?),
+            Some(pid) => Some(
+                self.resolve_feature_id(FeatureRef::from(&pid).in_project(project_id))
+                    .await?,
+            ),
             None => None,
         };
         let state = FeatureState::from_str(&req.state).map_err(|_| {
@@ -867,6 +1250,7 @@ impl McpServer {
                     details: req.details,
                     state: Some(state),
                     priority: req.priority,
+                    tags: req.tags,
                 },
             )
             .await
@@ -893,7 +1277,7 @@ impl McpServer {
         // Use HTTP client to bulk create features
         let response = self
             .client
-            .bulk_create_features(project_id, &req.features, req.confirm)
+            .bulk_create_features(project_id, &req.features, None, req.confirm)
             .await
             .map_err(Self::client_err)?;
 
@@ -904,8 +1288,31 @@ impl McpServer {
     }
 }
 
-#[tool_handler]
 impl ServerHandler for McpServer {
+    async fn call_tool(
+        &self,
+        request: rmcp::model::CallToolRequestParam,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let call = self.call_keys.start(&request);
+        let tcc = rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        let result = client::with_tool_call_key(call.key, self.tool_router.call(tcc)).await;
+        call.finish(&result);
+        Ok(add_connection_notice(&self.client, result?))
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<rmcp::model::PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<rmcp::model::ListToolsResult, McpError> {
+        Ok(rmcp::model::ListToolsResult {
+            tools: self.tool_router.list_all(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             server_info: rmcp::model::Implementation {
@@ -973,8 +1380,10 @@ Test: "Can a user observe or interact with this?" If yes, it can be a feature.
 
 FEATURE FIELDS:
 - title: Short capability name (2-5 words). What users can DO.
-- details: Feature specification including user stories, technical notes, constraints, acceptance criteria.
+- details: Feature specification including user stories, technical notes, constraints.
           User stories can follow "As a [user], I can [capability] so that [benefit]" format.
+- acceptance criteria: Ordered, checkable statements kept apart from details, each
+          unverified, passing or failing. get_task_context returns them with the feature.
 - state: Auto-managed lifecycle (proposed → specified → implemented → deprecated)
 - priority: Lower number = implement first. Use for sequencing.
 
@@ -1010,10 +1419,14 @@ DISCOVERY (find what to work on):
 - get_feature: Get full details of a feature before starting work
 
 AGENT WORKFLOW (when assigned a task_id):
-1. Call get_task_context with your task_id to understand your assignment
+1. Call get_task_context with your task_id to understand your assignment.
+   If it returns a worktree_path, make all changes there on the given branch
 2. Call start_task to signal you're beginning work
-3. Implement the task scope - write code, run tests, verify
-4. Call complete_task when done and verified
+3. Implement the task scope - write code, run tests, verify. Call verify_criterion
+   for each acceptance criterion you checked, naming the test that covers it
+4. Call complete_task when done and verified, reporting summary, files_changed,
+   commits, and test_outcome
+5. If you cannot finish, call fail_task with a specific failure_reason instead
 
 INSTRUCTION PRIORITY:
 Task scope > Project instructions > These defaults
@@ -1058,8 +1471,8 @@ ORCHESTRATOR WORKFLOW (when managing a feature):
 3. Call create_session on a leaf feature to start work
 4. Call create_task to break down work into agent-sized units
 5. Spawn agents with their task_ids
-6. Call list_session_tasks to monitor progress
-7. Call complete_session when all tasks are done
+6. Call list_session_tasks to monitor progress and read task results
+7. Call complete_session when all tasks are done (omit summary to draft it from task results)
 
 IMPORTANT:
 - Read feature details carefully before coding
@@ -1080,18 +1493,19 @@ pub fn is_ide_mode() -> bool {
         .unwrap_or(false)
 }
 
-pub async fn run_stdio_server() -> anyhow::Result<()> {
+/// Serve MCP over stdio, with `client` reaching Manifest.
+pub async fn run_stdio_server(client: ManifestClient) -> anyhow::Result<()> {
     use tokio::io::{stdin, stdout};
 
     if is_ide_mode() {
         tracing::info!("Starting MCP server via stdio (IDE mode)");
-        let service = McpServer::from_env();
+        let service = McpServer::new(client);
         let server = service.serve((stdin(), stdout())).await?;
         let quit_reason = server.waiting().await?;
         tracing::info!("MCP server stopped: {:?}", quit_reason);
     } else {
         tracing::info!("Starting MCP server via stdio (CLI mode)");
-        let service = CliMcpServer::from_env();
+        let service = CliMcpServer::new(client);
         let server = service.serve((stdin(), stdout())).await?;
         let quit_reason = server.waiting().await?;
         tracing::info!("MCP server stopped: {:?}", quit_reason);
@@ -1128,3 +1542,41 @@ pub fn streamable_http_router() -> axum::Router {
         axum::Router::new().fallback_service(service)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: &'static str) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: name.into(),
+            arguments: serde_json::json!({ "feature_id": "auth" })
+                .as_object()
+                .cloned(),
+        }
+    }
+
+    #[test]
+    fn retries_of_unfinished_or_failed_calls_reuse_the_key() {
+        let keys = ToolCallKeys::default();
+        let first = keys.start(&request("start_feature"));
+        let key = first.key;
+        let retry = keys.start(&request("start_feature"));
+        assert_eq!(retry.key, key);
+
+        retry.finish(&Err(McpError::internal_error("offline", None)));
+        first.finish(&Ok(CallToolResult::error(vec![])));
+        assert_eq!(keys.start(&request("start_feature")).key, key);
+        assert_ne!(keys.start(&request("complete_session")).key, key);
+    }
+
+    #[test]
+    fn calls_after_a_result_get_a_new_key() {
+        let keys = ToolCallKeys::default();
+        let first = keys.start(&request("start_feature"));
+        let key = first.key;
+        first.finish(&Ok(CallToolResult::success(vec![])));
+
+        assert_ne!(keys.start(&request("start_feature")).key, key);
+    }
+}
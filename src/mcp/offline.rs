@@ -0,0 +1,293 @@
+//! Offline support for a [`ManifestClient`](super::ManifestClient) of a
+//! remote server.
+//!
+//! The client keeps a small SQLite file of its own next to the local
+//! database. Successful reads are cached there and answered from it while
+//! the server can't be reached. Task updates and history entries made in
+//! the meantime are queued, each under an idempotency key, and replayed in
+//! order once the server answers again. Writes the server refuses on replay
+//! are kept until an agent has been told about them.
+//!
+//! The file is a cache: it has no migrations, and deleting it only loses
+//! writes that were never sent.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+use crate::db::Database;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS cached_responses (
+        path TEXT PRIMARY KEY,
+        body BLOB NOT NULL,
+        fetched_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS queued_writes (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        idempotency_key TEXT NOT NULL UNIQUE,
+        method TEXT NOT NULL,
+        path TEXT NOT NULL,
+        content_type TEXT,
+        body BLOB,
+        queued_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS refused_writes (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        method TEXT NOT NULL,
+        path TEXT NOT NULL,
+        status INTEGER NOT NULL,
+        message TEXT NOT NULL,
+        refused_at TEXT NOT NULL
+    );
+";
+
+/// Most responses kept in the cache. Caching another drops the one fetched
+/// longest ago.
+pub const MAX_CACHED_RESPONSES: usize = 1000;
+
+/// A write waiting for the server. `path` is relative to the API base URL.
+#[derive(Debug, Clone)]
+pub struct QueuedWrite {
+    pub idempotency_key: Uuid,
+    pub method: String,
+    pub path: String,
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// A queued write the server refused when it was replayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefusedWrite {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    /// The server's explanation, from the response body.
+    pub message: String,
+}
+
+impl RefusedWrite {
+    /// A line for agents to see in tool results.
+    pub fn notice(&self) -> String {
+        format!(
+            "[manifest: refused] The server refused {} {}, queued while offline ({}): {}",
+            self.method, self.path, self.status, self.message
+        )
+    }
+}
+
+/// Cached reads and queued writes, shared by the clients of one process.
+#[derive(Debug)]
+pub struct OfflineStore {
+    conn: Mutex<Connection>,
+    /// Whether the last request failed to reach the server.
+    offline: AtomicBool,
+}
+
+impl OfflineStore {
+    /// Open (creating it if needed) the store at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        // Several MCP servers may share the file
+        conn.busy_timeout(Duration::from_secs(10))?;
+        Self::with_connection(conn)
+    }
+
+    /// An empty store that lasts as long as the process.
+    pub fn open_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    /// Open the store at `MANIFEST_OFFLINE_CACHE`, or `offline-cache.db`
+    /// next to the local database.
+    pub fn open_default() -> Result<Self> {
+        let path = match std::env::var("MANIFEST_OFFLINE_CACHE") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => Database::default_path()?.with_file_name("offline-cache.db"),
+        };
+        Self::open(&path)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            offline: AtomicBool::new(false),
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().expect("offline store lock poisoned")
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        if self.offline.swap(offline, Ordering::Relaxed) != offline {
+            if offline {
+                tracing::warn!("Manifest server unreachable; working offline");
+            } else {
+                tracing::info!("Manifest server reachable again");
+            }
+        }
+    }
+
+    /// The body of the last successful GET of `path`.
+    pub fn cached(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .conn()
+            .query_row(
+                "SELECT body FROM cached_responses WHERE path = ?",
+                [path],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Cache `body` as the latest response for `path`, dropping the oldest
+    /// responses beyond [`MAX_CACHED_RESPONSES`].
+    pub fn cache(&self, path: &str, body: &[u8]) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        // Replacing a row gives it a new rowid, so rowids follow fetch order
+        tx.execute(
+            "INSERT OR REPLACE INTO cached_responses (path, body, fetched_at) VALUES (?, ?, ?)",
+            params![path, body, Utc::now().to_rfc3339()],
+        )?;
+        tx.execute(
+            "DELETE FROM cached_responses WHERE rowid NOT IN
+                (SELECT rowid FROM cached_responses ORDER BY rowid DESC LIMIT ?)",
+            [MAX_CACHED_RESPONSES as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Queue `write`, unless a write with its key (a retry of the same tool
+    /// call) is queued already.
+    pub fn enqueue(&self, write: &QueuedWrite) -> Result<()> {
+        self.conn().execute(
+            "INSERT OR IGNORE INTO queued_writes (idempotency_key, method, path, content_type, body, queued_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                write.idempotency_key.to_string(),
+                write.method,
+                write.path,
+                write.content_type,
+                write.body,
+                write.queued_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Queued writes, oldest first. Fails on a row that can't be read back,
+    /// rather than replaying it under another key.
+    pub fn queued(&self) -> Result<Vec<QueuedWrite>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT seq, idempotency_key, method, path, content_type, body, queued_at
+             FROM queued_writes ORDER BY seq",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut writes = Vec::new();
+        while let Some(row) = rows.next()? {
+            let seq: i64 = row.get(0)?;
+            let key: String = row.get(1)?;
+            let queued_at: String = row.get(6)?;
+            writes.push(QueuedWrite {
+                idempotency_key: key.parse().with_context(|| {
+                    format!(
+                        "Queued write {} has an invalid idempotency key '{}'",
+                        seq, key
+                    )
+                })?,
+                method: row.get(2)?,
+                path: row.get(3)?,
+                content_type: row.get(4)?,
+                body: row.get(5)?,
+                queued_at: queued_at.parse().with_context(|| {
+                    format!(
+                        "Queued write {} has an invalid timestamp '{}'",
+                        seq, queued_at
+                    )
+                })?,
+            });
+        }
+        Ok(writes)
+    }
+
+    pub fn queue_len(&self) -> Result<usize> {
+        let count: i64 =
+            self.conn()
+                .query_row("SELECT COUNT(*) FROM queued_writes", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Move a queued write the server refused with `status` to the refused
+    /// writes, which [`take_refused`](Self::take_refused) reports.
+    pub fn refuse(&self, write: &QueuedWrite, status: u16, message: &str) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO refused_writes (method, path, status, message, refused_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                write.method,
+                write.path,
+                status,
+                message,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM queued_writes WHERE idempotency_key = ?",
+            [write.idempotency_key.to_string()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Refused writes not reported yet, oldest first. They are forgotten
+    /// once returned.
+    pub fn take_refused(&self) -> Result<Vec<RefusedWrite>> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let refused = tx
+            .prepare("SELECT method, path, status, message FROM refused_writes ORDER BY seq")?
+            .query_map([], |row| {
+                Ok(RefusedWrite {
+                    method: row.get(0)?,
+                    path: row.get(1)?,
+                    status: row.get(2)?,
+                    message: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        tx.execute("DELETE FROM refused_writes", [])?;
+        tx.commit()?;
+        Ok(refused)
+    }
+
+    /// Drop a write once the server has answered it.
+    pub fn dequeue(&self, idempotency_key: Uuid) -> Result<()> {
+        self.conn().execute(
+            "DELETE FROM queued_writes WHERE idempotency_key = ?",
+            [idempotency_key.to_string()],
+        )?;
+        Ok(())
+    }
+}
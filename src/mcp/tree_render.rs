@@ -1,5 +1,7 @@
 //! ASCII tree rendering for feature hierarchies.
 
+use uuid::Uuid;
+
 use crate::models::{FeatureState, FeatureTreeNode};
 
 const PROPOSED: char = '◇';
@@ -17,22 +19,25 @@ fn state_symbol(state: FeatureState) -> char {
     }
 }
 
-/// Render a feature tree as ASCII art with status symbols.
+/// Render a feature tree as ASCII art with status symbols and feature keys.
 ///
 /// Example output:
 /// ```text
-/// Authentication
-/// ├── ● Password Login
-/// ├── ○ OAuth Integration
-/// │   ├── • Google Provider
-/// │   └── • GitHub Provider
-/// └── ✗ Legacy Basic Auth
+/// MAN-1 Authentication
+/// ├── ● MAN-2 Password Login
+/// ├── ○ MAN-3 OAuth Integration
+/// │   ├── • MAN-4 Google Provider
+/// │   └── • MAN-5 GitHub Provider
+/// └── ✗ MAN-6 Legacy Basic Auth
 /// ```
-pub fn render_tree(nodes: &[FeatureTreeNode]) -> String {
+///
+/// Features in `failing` are flagged as having failing tests in the latest
+/// test report.
+pub fn render_tree(nodes: &[FeatureTreeNode], failing: &[Uuid]) -> String {
     let mut output = String::new();
     for (i, node) in nodes.iter().enumerate() {
         let is_last = i == nodes.len() - 1;
-        render_node(&mut output, node, "", is_last, true);
+        render_node(&mut output, node, failing, "", is_last, true);
     }
     output
 }
@@ -41,26 +46,30 @@ pub fn render_tree(nodes: &[FeatureTreeNode]) -> String {
 fn render_node(
     output: &mut String,
     node: &FeatureTreeNode,
+    failing: &[Uuid],
     prefix: &str,
     is_last: bool,
     is_root: bool,
 ) {
     let symbol = state_symbol(node.feature.state);
 
-    if is_root {
-        // Root nodes: just title (no branch characters)
-        output.push_str(&node.feature.title);
-        output.push('\n');
-    } else {
-        // Child nodes: branch + symbol + title
+    if !is_root {
+        // Child nodes: branch + symbol before the key and title
         let branch = if is_last { "└── " } else { "├── " };
         output.push_str(prefix);
         output.push_str(branch);
         output.push(symbol);
         output.push(' ');
-        output.push_str(&node.feature.title);
-        output.push('\n');
     }
+    if !node.feature.key.is_empty() {
+        output.push_str(&node.feature.key);
+        output.push(' ');
+    }
+    output.push_str(&node.feature.title);
+    if failing.contains(&node.feature.id) {
+        output.push_str(" [tests failing]");
+    }
+    output.push('\n');
 
     // Calculate prefix for children
     let child_prefix = if is_root {
@@ -73,16 +82,15 @@ fn render_node(
     // Render children
     for (i, child) in node.children.iter().enumerate() {
         let child_is_last = i == node.children.len() - 1;
-        render_node(output, child, &child_prefix, child_is_last, false);
+        render_node(output, child, failing, &child_prefix, child_is_last, false);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::Feature;
+    use crate::models::{slugify, Feature};
     use chrono::Utc;
-    use uuid::Uuid;
 
     fn make_node(
         title: &str,
@@ -94,11 +102,15 @@ mod tests {
                 id: Uuid::new_v4(),
                 project_id: Uuid::new_v4(),
                 parent_id: None,
+                key: String::new(),
                 title: title.to_string(),
+                slug: slugify(title),
+                path: slugify(title),
                 details: None,
                 desired_details: None,
                 state,
                 priority: 0,
+                tags: vec![],
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             },
@@ -109,7 +121,7 @@ mod tests {
     #[test]
     fn test_single_root() {
         let tree = vec![make_node("Authentication", FeatureState::Proposed, vec![])];
-        let output = render_tree(&tree);
+        let output = render_tree(&tree, &[]);
         assert_eq!(output, "Authentication\n");
     }
 
@@ -123,7 +135,7 @@ mod tests {
                 make_node("OAuth", FeatureState::Specified, vec![]),
             ],
         )];
-        let output = render_tree(&tree);
+        let output = render_tree(&tree, &[]);
         assert_eq!(
             output,
             "Authentication\n├── ● Password Login\n└── ○ OAuth\n"
@@ -148,8 +160,30 @@ mod tests {
                 make_node("Legacy Basic Auth", FeatureState::Deprecated, vec![]),
             ],
         )];
-        let output = render_tree(&tree);
+        let output = render_tree(&tree, &[]);
         let expected = "Authentication\n├── ● Password Login\n├── ○ OAuth Integration\n│   ├── ◇ Google Provider\n│   └── ◇ GitHub Provider\n└── ✗ Legacy Basic Auth\n";
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_keys_precede_titles() {
+        let mut login = make_node("Password Login", FeatureState::Implemented, vec![]);
+        login.feature.key = "MAN-2".to_string();
+        let mut root = make_node("Authentication", FeatureState::Proposed, vec![login]);
+        root.feature.key = "MAN-1".to_string();
+        let output = render_tree(&[root], &[]);
+        assert_eq!(output, "MAN-1 Authentication\n└── ● MAN-2 Password Login\n");
+    }
+
+    #[test]
+    fn test_failing_tests_are_flagged() {
+        let login = make_node("Password Login", FeatureState::Implemented, vec![]);
+        let failing = vec![login.feature.id];
+        let root = make_node("Authentication", FeatureState::Proposed, vec![login]);
+        let output = render_tree(&[root], &failing);
+        assert_eq!(
+            output,
+            "Authentication\n└── ● Password Login [tests failing]\n"
+        );
+    }
 }
@@ -23,18 +23,59 @@ pub struct StartTaskRequest {
 pub struct CompleteTaskRequest {
     #[schemars(description = "The UUID of the task to mark as complete")]
     pub task_id: String,
+    #[schemars(
+        description = "What you did - becomes part of the session summary when the session completes"
+    )]
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[schemars(description = "Paths of files you created or modified")]
+    #[serde(default)]
+    pub files_changed: Vec<String>,
+    #[schemars(description = "Git commits you created while working on the task")]
+    #[serde(default)]
+    pub commits: Vec<CommitRefInput>,
+    #[schemars(description = "Outcome of your test run: 'passed', 'failed', or 'skipped'")]
+    #[serde(default)]
+    pub test_outcome: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FailTaskRequest {
+    #[schemars(description = "The UUID of the task that could not be completed")]
+    pub task_id: String,
+    #[schemars(
+        description = "Why the task could not be completed - be specific so the orchestrator can decide what to do next"
+    )]
+    pub failure_reason: String,
+    #[schemars(description = "What you attempted before giving up")]
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[schemars(description = "Paths of files you created or modified")]
+    #[serde(default)]
+    pub files_changed: Vec<String>,
+    #[schemars(description = "Git commits you created while working on the task")]
+    #[serde(default)]
+    pub commits: Vec<CommitRefInput>,
+    #[schemars(description = "Outcome of your test run: 'passed', 'failed', or 'skipped'")]
+    #[serde(default)]
+    pub test_outcome: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateSessionRequest {
     #[schemars(
-        description = "The UUID of the feature to start a session on (must be a leaf feature with no children)"
+        description = "The UUID, key (e.g. MAN-42) or path of the feature to start a session on (must be a leaf feature with no children)"
     )]
     pub feature_id: String,
     #[schemars(
         description = "The goal of this session - what will be accomplished when the session ends"
     )]
     pub goal: String,
+    #[schemars(
+        description = "Optional session kind (e.g. 'docs', 'implementation'). Needed to run sessions in parallel on one feature when the project allows it: concurrent sessions must have distinct kinds."
+    )]
+    #[serde(default)]
+    pub kind: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -48,7 +89,7 @@ pub struct CreateTaskRequest {
     )]
     pub scope: String,
     #[schemars(
-        description = "Which agent type should handle this task: 'claude', 'gemini', or 'codex'"
+        description = "Which agent type should handle this task. Must be registered for the project; every project starts with 'claude', 'gemini', and 'codex'"
     )]
     pub agent_type: String,
 }
@@ -61,7 +102,9 @@ pub struct ListSessionTasksRequest {
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetFeatureSessionRequest {
-    #[schemars(description = "The UUID of the feature to get the active session for")]
+    #[schemars(
+        description = "The UUID, key (e.g. MAN-42) or path of the feature to get the active session for"
+    )]
     pub feature_id: String,
 }
 
@@ -70,8 +113,9 @@ pub struct CompleteSessionRequest {
     #[schemars(description = "The UUID of the session to complete")]
     pub session_id: String,
     #[schemars(
-        description = "Summary of work done during this session - becomes the feature history entry"
+        description = "Summary of work done during this session - becomes the feature history entry. If omitted, a summary is drafted from the task results."
     )]
+    #[serde(default)]
     pub summary: String,
     #[schemars(description = "Git commits created during this session")]
     #[serde(default)]
@@ -81,6 +125,16 @@ pub struct CompleteSessionRequest {
     )]
     #[serde(default = "default_true")]
     pub mark_implemented: bool,
+    #[schemars(
+        description = "Only attach discovered commits whose message has a 'Feature:' trailer naming this feature (id, key, path or slug). Defaults to false."
+    )]
+    #[serde(default)]
+    pub require_feature_trailer: bool,
+    #[schemars(
+        description = "Mark the feature implemented even if some acceptance criteria aren't passing. Defaults to false, which refuses the completion until every criterion is passing."
+    )]
+    #[serde(default)]
+    pub allow_unverified_criteria: bool,
 }
 
 /// A reference to a git commit for MCP input.
@@ -104,13 +158,52 @@ pub struct ListFeaturesRequest {
     #[schemars(description = "Optional project UUID to filter features by project")]
     pub project_id: Option<String>,
     #[schemars(
-        description = "Optional state filter: 'proposed', 'specified', 'implemented', or 'deprecated'"
+        description = "Optional comma-separated states: 'proposed', 'specified', 'implemented', or 'deprecated'"
     )]
     pub state: Option<String>,
     #[schemars(description = "Maximum number of features to return. Defaults to no limit.")]
     pub limit: Option<u32>,
     #[schemars(description = "Number of features to skip for pagination. Defaults to 0.")]
     pub offset: Option<u32>,
+    #[schemars(
+        description = "Optional next_cursor from a previous list_features call, to get the following page."
+    )]
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[schemars(
+        description = "Optional comma-separated tag names (e.g. 'security,v2'). Only features with all of them are listed."
+    )]
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[schemars(description = "Optional parent feature UUID. Only its direct children are listed.")]
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[schemars(description = "Optional maximum depth below the root. 0 lists only root features.")]
+    #[serde(default)]
+    pub depth: Option<u32>,
+    #[schemars(
+        description = "Optional. true lists only leaf features (the ones sessions can be started on), false only features with children."
+    )]
+    #[serde(default)]
+    pub leaf: Option<bool>,
+    #[schemars(
+        description = "Optional. true lists only features with pending changes (desired_details set)."
+    )]
+    #[serde(default)]
+    pub has_pending_changes: Option<bool>,
+    #[schemars(description = "Optional. true lists only features with an active session.")]
+    #[serde(default)]
+    pub has_active_session: Option<bool>,
+    #[schemars(
+        description = "Optional RFC 3339 timestamp. Only features updated at or after it are listed."
+    )]
+    #[serde(default)]
+    pub updated_since: Option<String>,
+    #[schemars(
+        description = "Optional sort: 'priority' (default), 'title', 'updated_at' or 'created_at'. Prefix with '-' for descending."
+    )]
+    #[serde(default)]
+    pub sort: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -119,19 +212,26 @@ pub struct SearchFeaturesRequest {
     pub query: String,
     #[schemars(description = "Optional project UUID to limit search to a specific project")]
     pub project_id: Option<String>,
+    #[schemars(
+        description = "Optional comma-separated tag names. Only features with all of them are returned."
+    )]
+    #[serde(default)]
+    pub tag: Option<String>,
     #[schemars(description = "Maximum number of results to return. Defaults to 10.")]
     pub limit: Option<u32>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetFeatureRequest {
-    #[schemars(description = "The UUID of the feature to retrieve")]
+    #[schemars(description = "The UUID, key (e.g. MAN-42) or path of the feature to retrieve")]
     pub feature_id: String,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetFeatureHistoryRequest {
-    #[schemars(description = "The UUID of the feature to get history for")]
+    #[schemars(
+        description = "The UUID, key (e.g. MAN-42) or path of the feature to get history for"
+    )]
     pub feature_id: String,
 }
 
@@ -151,7 +251,7 @@ pub struct GetActiveFeatureRequest {}
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct UpdateFeatureStateRequest {
-    #[schemars(description = "The UUID of the feature to update")]
+    #[schemars(description = "The UUID, key (e.g. MAN-42) or path of the feature to update")]
     pub feature_id: String,
     #[schemars(
         description = "The new state: 'proposed', 'specified', 'implemented', or 'deprecated'"
@@ -166,12 +266,79 @@ pub struct UpdateFeatureStateRequest {
     )]
     #[serde(default)]
     pub details: Option<String>,
+    #[schemars(
+        description = "Replace the feature's tags (e.g. ['security', 'v2']). Missing tags are created."
+    )]
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RelateFeaturesRequest {
+    #[schemars(
+        description = "The UUID, key (e.g. MAN-42) or path of the feature the relation starts from"
+    )]
+    pub feature_id: String,
+    #[schemars(description = "The UUID, key or path of the related feature")]
+    pub to_feature_id: String,
+    #[schemars(
+        description = "'depends_on' (feature_id needs to_feature_id first), 'blocks' (to_feature_id needs feature_id first), 'relates_to' or 'supersedes'"
+    )]
+    pub kind: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetNextFeaturesRequest {
+    #[schemars(description = "The UUID of the project")]
+    pub project_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct VerifyCriterionRequest {
+    #[schemars(description = "The UUID of the acceptance criterion")]
+    pub criterion_id: String,
+    #[schemars(description = "'passing', 'failing' or 'unverified'")]
+    pub status: String,
+    #[schemars(description = "Name of the test that verifies the criterion")]
+    #[serde(default)]
+    pub test_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LinkCodeRequest {
+    #[schemars(
+        description = "The UUID, key (e.g. MAN-42) or path of the feature the code implements"
+    )]
+    pub feature_id: String,
+    #[schemars(
+        description = "Path relative to the project directory. A file ('src/auth/oauth.rs'), a directory ('src/auth'), or a glob ('src/auth/**/*.rs')"
+    )]
+    pub path: String,
+    #[schemars(
+        description = "UUID of the project directory the path is relative to. Defaults to the primary directory."
+    )]
+    #[serde(default)]
+    pub directory_id: Option<String>,
+    #[schemars(description = "First line of the implementing code (1-based)")]
+    #[serde(default)]
+    pub line_start: Option<u32>,
+    #[schemars(description = "Last line of the implementing code (inclusive)")]
+    #[serde(default)]
+    pub line_end: Option<u32>,
+    #[schemars(description = "Function, type, or module name that implements the feature")]
+    #[serde(default)]
+    pub symbol: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateProjectRequest {
     #[schemars(description = "The project name (e.g., 'RocketShip', 'MyApp')")]
     pub name: String,
+    #[schemars(
+        description = "Optional feature key prefix, e.g. 'RS' for keys like RS-42. Derived from the name if omitted"
+    )]
+    #[serde(default)]
+    pub key: Option<String>,
     #[schemars(description = "Optional description of the project")]
     #[serde(default)]
     pub description: Option<String>,
@@ -207,7 +374,9 @@ pub struct AddProjectDirectoryRequest {
 pub struct CreateFeatureRequest {
     #[schemars(description = "The UUID of the project this feature belongs to")]
     pub project_id: String,
-    #[schemars(description = "Optional parent feature UUID for hierarchical features")]
+    #[schemars(
+        description = "Optional parent feature (UUID, key or path) for hierarchical features"
+    )]
     #[serde(default)]
     pub parent_id: Option<String>,
     #[schemars(description = "Short title for the feature (e.g., 'User Authentication')")]
@@ -227,6 +396,11 @@ pub struct CreateFeatureRequest {
     )]
     #[serde(default)]
     pub priority: Option<i32>,
+    #[schemars(
+        description = "Optional tags for cross-cutting concerns (e.g. ['security', 'frontend']). Missing tags are created."
+    )]
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 fn default_proposed() -> String {
@@ -260,6 +434,9 @@ pub struct TaskContextResponse {
     pub feature: FeatureInfo,
     /// The session goal describing the overall objective
     pub session_goal: String,
+    /// Default instructions registered for your agent type, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_instructions: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -269,12 +446,34 @@ pub struct TaskInfo {
     pub scope: String,
     pub status: String,
     pub agent_type: String,
+    /// Git worktree to do this task's work in, when one was provisioned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_path: Option<String>,
+    /// Branch checked out in the worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// The agent's report, present once the task has completed or failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<TaskResultInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TaskResultInfo {
+    pub summary: String,
+    pub files_changed: Vec<String>,
+    pub commits: Vec<CommitInfo>,
+    pub test_outcome: Option<String>,
+    pub failure_reason: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FeatureInfo {
     pub id: String,
+    /// Short key (e.g. `MAN-42`), usable wherever a feature UUID is.
+    pub key: String,
     pub title: String,
+    /// Slug path (e.g. `auth/oauth-login`), usable wherever a feature UUID is.
+    pub path: String,
     /// Feature details including user stories, implementation notes, and technical context.
     pub details: Option<String>,
     /// Desired details for pending changes. When non-null, indicates edits awaiting implementation.
@@ -282,13 +481,30 @@ pub struct FeatureInfo {
     pub state: String,
     /// Priority for ordering within parent. Lower values appear first.
     pub priority: i32,
+    pub tags: Vec<String>,
+    /// Acceptance criteria, in order. Only filled in where work on the feature starts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub acceptance_criteria: Vec<CriterionInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CriterionInfo {
+    pub id: String,
+    pub text: String,
+    /// 'unverified', 'passing' or 'failing'
+    pub status: String,
+    /// Test that verifies the criterion, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SessionInfo {
     pub id: String,
     pub feature_id: String,
     pub goal: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
     pub status: String,
 }
 
@@ -301,8 +517,11 @@ pub struct TaskListResponse {
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FeatureSessionResponse {
     pub feature_id: String,
-    /// The active session for this feature, or null if no active session exists
+    /// The oldest active session for this feature, or null if no active session exists
     pub session: Option<SessionInfo>,
+    /// All active sessions, oldest first. More than one only when the project
+    /// allows concurrent sessions.
+    pub sessions: Vec<SessionInfo>,
     /// Message explaining the result
     pub message: String,
 }
@@ -313,6 +532,9 @@ pub struct CompleteSessionResponse {
     pub feature_id: String,
     pub feature_state: String,
     pub history_entry_id: String,
+    /// Worktrees kept because they had uncommitted changes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -347,16 +569,25 @@ pub struct CommitInfo {
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FeatureSummaryInfo {
     pub id: String,
+    pub key: String,
     pub title: String,
+    pub path: String,
     pub state: String,
     pub priority: i32,
     pub parent_id: Option<String>,
+    pub tags: Vec<String>,
 }
 
 /// Response for list_features in summary mode (default).
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FeatureListSummaryResponse {
     pub features: Vec<FeatureSummaryInfo>,
+    /// Pass as `cursor` to get the next page. Absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Number of matching features across all pages, on the first page only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -369,6 +600,8 @@ pub struct ProjectContextResponse {
 pub struct ProjectInfo {
     pub id: String,
     pub name: String,
+    /// Prefix of the project's feature keys (e.g. `MAN`).
+    pub key: String,
     pub description: Option<String>,
     /// Project-wide instructions for AI agents (coding guidelines, conventions).
     pub instructions: Option<String>,
@@ -384,6 +617,28 @@ pub struct DirectoryInfo {
     pub instructions: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RelationInfo {
+    pub id: String,
+    pub from_feature_id: String,
+    pub to_feature_id: String,
+    pub kind: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CodeLinkInfo {
+    pub id: String,
+    pub feature_id: String,
+    pub directory_id: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_start: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_end: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PlanFeaturesResponse {
     /// The proposed feature tree. Review before confirming.
@@ -406,6 +661,12 @@ pub struct ProposedFeature {
     /// Priority for ordering. Lower values = implement first.
     #[serde(default)]
     pub priority: i32,
+    /// Tag names. Tags the project doesn't have yet are created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Acceptance criteria, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub acceptance_criteria: Vec<String>,
     /// Child features (for hierarchical structure)
     #[serde(default)]
     pub children: Vec<ProposedFeature>,
@@ -417,12 +678,19 @@ pub struct ProposedFeature {
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct BreakdownFeatureRequest {
-    #[schemars(description = "The UUID of the feature to break down into tasks")]
+    #[schemars(
+        description = "The UUID, key (e.g. MAN-42) or path of the feature to break down into tasks"
+    )]
     pub feature_id: String,
     #[schemars(
         description = "The session goal - what will be accomplished when all tasks are complete"
     )]
     pub goal: String,
+    #[schemars(
+        description = "Optional session kind (e.g. 'docs', 'implementation'). Concurrent sessions on one feature must have distinct kinds."
+    )]
+    #[serde(default)]
+    pub kind: Option<String>,
     #[schemars(
         description = "The tasks to create. Each task should be completable by one agent (1-3 story points)."
     )]
@@ -439,7 +707,7 @@ pub struct TaskInputItem {
     )]
     pub scope: String,
     #[schemars(
-        description = "Which agent type should handle this task: 'claude', 'gemini', or 'codex'. Defaults to 'claude'."
+        description = "Which agent type should handle this task. Must be registered for the project; every project starts with 'claude', 'gemini', and 'codex'. Defaults to 'claude'."
     )]
     #[serde(default = "default_claude")]
     pub agent_type: String,
@@ -0,0 +1,121 @@
+//! Project snapshots on the command line (`mfst snapshot export/import`).
+//!
+//! The server builds and restores [`ProjectSnapshot`]s; this module picks the
+//! project and converts between the wire format (JSON) and the file format,
+//! JSON or YAML.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use uuid::Uuid;
+
+use crate::db::ManifestError;
+use crate::git;
+use crate::mcp::client::ClientError;
+use crate::mcp::ManifestClient;
+use crate::models::{Project, ProjectSnapshot};
+
+/// File formats a snapshot is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    Json,
+    Yaml,
+}
+
+/// Write `snapshot` in `format`.
+pub fn serialize(snapshot: &ProjectSnapshot, format: SnapshotFormat) -> Result<String> {
+    Ok(match format {
+        SnapshotFormat::Json => serde_json::to_string_pretty(snapshot)? + "\n",
+        SnapshotFormat::Yaml => serde_yaml::to_string(snapshot)?,
+    })
+}
+
+/// Read a JSON or YAML snapshot, telling them apart by the first character.
+pub fn deserialize(text: &str) -> Result<ProjectSnapshot> {
+    let snapshot = if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(text).map_err(|e| e.to_string())
+    };
+    snapshot.map_err(|e| ManifestError::validation(format!("Invalid snapshot: {}", e)).into())
+}
+
+/// The project `project` names: a project id, or a registered directory.
+async fn resolve_project(client: &ManifestClient, project: &str) -> Result<Uuid> {
+    if let Ok(id) = Uuid::parse_str(project) {
+        return Ok(id);
+    }
+    let dir = Path::new(project);
+    let repo = git::main_worktree(dir).unwrap_or_else(|_| dir.to_path_buf());
+    match client
+        .get_project_by_directory(&repo.to_string_lossy())
+        .await
+    {
+        Ok(p) => Ok(p.project.id),
+        Err(ClientError::NotFound(_)) => bail!(
+            "{} is neither a project id nor a registered project directory",
+            project
+        ),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Fetch a snapshot of `project` and write it in `format`.
+pub async fn export(
+    client: &ManifestClient,
+    project: &str,
+    format: SnapshotFormat,
+) -> Result<String> {
+    let id = resolve_project(client, project).await?;
+    serialize(&client.export_snapshot(id).await?, format)
+}
+
+/// Restore the project in the snapshot `file`.
+pub async fn import(client: &ManifestClient, file: &Path, replace: bool) -> Result<Project> {
+    let text = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let snapshot = deserialize(&text)?;
+    Ok(client.import_snapshot(&snapshot, replace).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SNAPSHOT_FORMAT_VERSION;
+    use chrono::Utc;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn yaml_and_json_round_trip_rows_unchanged() {
+        let row = serde_json::json!({
+            "id": "6f1c2a4e-0000-4000-8000-000000000001",
+            "priority": -3,
+            "details": null,
+            "files_changed": "[\"src/main.rs\"]"
+        });
+        let snapshot = ProjectSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            schema_version: "021".to_string(),
+            project_id: Uuid::new_v4(),
+            exported_at: Utc::now(),
+            tables: BTreeMap::from([(
+                "features".to_string(),
+                vec![row.as_object().unwrap().clone()],
+            )]),
+        };
+
+        for format in [SnapshotFormat::Json, SnapshotFormat::Yaml] {
+            let text = serialize(&snapshot, format).unwrap();
+            let read = deserialize(&text).unwrap();
+            assert_eq!(read.project_id, snapshot.project_id);
+            assert_eq!(read.exported_at, snapshot.exported_at);
+            assert_eq!(read.tables, snapshot.tables);
+        }
+    }
+
+    #[test]
+    fn deserialize_rejects_other_documents() {
+        assert!(deserialize("{\"format_version\": 1}").is_err());
+        assert!(deserialize("- just\n- a list\n").is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! Test reports: JUnit XML and libtest JSON.
+//!
+//! CI pushes the raw report to `POST /projects/{id}/test-results` with
+//! `mfst report-tests`; the server reads the test cases out of it here and
+//! matches them to features in the database. The format is told apart by
+//! the first character: JUnit reports are XML, libtest (`cargo test --
+//! -Z unstable-options --format json`) writes one JSON object per line.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::db::ManifestError;
+use crate::git;
+use crate::mcp::client::ClientError;
+use crate::mcp::ManifestClient;
+use crate::models::{FeatureState, TestCaseResult, TestOutcome, TestReportResult};
+
+/// Read the test cases of a JUnit XML or libtest JSON report.
+///
+/// A report that is neither, or has no test cases, is a validation error.
+pub fn parse(report: &str) -> Result<Vec<TestCaseResult>> {
+    let cases = if report.trim_start().starts_with('<') {
+        parse_junit(report)?
+    } else {
+        parse_libtest(report)
+    };
+    if cases.is_empty() {
+        return Err(ManifestError::validation(
+            "No test cases found; expected a JUnit XML or libtest JSON report",
+        )
+        .into());
+    }
+    Ok(cases)
+}
+
+fn parse_junit(report: &str) -> Result<Vec<TestCaseResult>> {
+    let invalid =
+        |e: quick_xml::Error| ManifestError::validation(format!("Invalid JUnit XML: {}", e));
+    let mut reader = Reader::from_str(report);
+    reader.config_mut().trim_text(true);
+
+    let mut cases = Vec::new();
+    // The test case being read, and whether its element is still open
+    let mut current: Option<TestCaseResult> = None;
+    let mut in_failure = false;
+    loop {
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(e) if e.name().as_ref() == b"testcase" => {
+                current = Some(test_case(&e)?);
+            }
+            Event::Empty(e) if e.name().as_ref() == b"testcase" => {
+                cases.push(test_case(&e)?);
+            }
+            Event::Start(e) | Event::Empty(e) => {
+                let Some(case) = current.as_mut() else {
+                    continue;
+                };
+                match e.name().as_ref() {
+                    b"failure" | b"error" => {
+                        case.outcome = TestOutcome::Failed;
+                        case.message = attribute(&e, "message")?;
+                        in_failure = true;
+                    }
+                    b"skipped" if case.outcome != TestOutcome::Failed => {
+                        case.outcome = TestOutcome::Skipped;
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(text) if in_failure => {
+                if let Some(case) = current.as_mut().filter(|c| c.message.is_none()) {
+                    case.message = Some(text.unescape().map_err(invalid)?.into_owned());
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"testcase" => cases.extend(current.take()),
+                b"failure" | b"error" => in_failure = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(cases)
+}
+
+/// A passing test case from a `<testcase>` element's `classname` and `name`.
+fn test_case(element: &BytesStart) -> Result<TestCaseResult> {
+    let name = attribute(element, "name")?.unwrap_or_default();
+    let name = match attribute(element, "classname")? {
+        Some(class) if !class.is_empty() && !name.starts_with(&class) => {
+            // Java-style reports separate classes with dots
+            let separator = if class.contains('.') && !class.contains("::") {
+                "."
+            } else {
+                "::"
+            };
+            format!("{}{}{}", class, separator, name)
+        }
+        _ => name,
+    };
+    Ok(TestCaseResult {
+        name,
+        outcome: TestOutcome::Passed,
+        message: None,
+    })
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>> {
+    let invalid =
+        |e: quick_xml::Error| ManifestError::validation(format!("Invalid JUnit XML: {}", e));
+    let Some(attr) = element
+        .try_get_attribute(name)
+        .map_err(|e| invalid(e.into()))?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(attr.unescape_value().map_err(invalid)?.into_owned()))
+}
+
+/// Read the finished `test` events of libtest JSON output. Lines that aren't
+/// JSON objects (compiler output mixed in) are skipped.
+fn parse_libtest(report: &str) -> Vec<TestCaseResult> {
+    report
+        .lines()
+        .filter(|line| line.trim_start().starts_with('{'))
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|event| event["type"] == "test")
+        .filter_map(|event| {
+            let outcome = match event["event"].as_str()? {
+                "ok" => TestOutcome::Passed,
+                "failed" | "timeout" => TestOutcome::Failed,
+                "ignored" => TestOutcome::Skipped,
+                _ => return None,
+            };
+            let message = match outcome {
+                TestOutcome::Failed => event["message"]
+                    .as_str()
+                    .or(event["stdout"].as_str())
+                    .map(|m| m.trim().to_string()),
+                _ => None,
+            };
+            Some(TestCaseResult {
+                name: event["name"].as_str()?.to_string(),
+                outcome,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Push the report in `file` for the project registered at `dir` (`mfst report-tests`).
+pub async fn report(client: &ManifestClient, dir: &Path, file: &Path) -> Result<TestReportResult> {
+    let report = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let repo = git::main_worktree(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let project = match client
+        .get_project_by_directory(&repo.to_string_lossy())
+        .await
+    {
+        Ok(p) => p.project,
+        Err(ClientError::NotFound(_)) => {
+            bail!("{} is not a registered project directory", repo.display())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    Ok(client.report_test_results(project.id, report).await?)
+}
+
+/// Render the outcome of a pushed report for the terminal.
+pub fn format_report(result: &TestReportResult) -> String {
+    let mut out = format!(
+        "{} of {} test(s) matched a feature.\n",
+        result.matched, result.received
+    );
+    for s in &result.features {
+        out.push_str(&format!(
+            "  {} {}: {} passed, {} failed, {} skipped\n",
+            s.key, s.title, s.passed, s.failed, s.skipped
+        ));
+    }
+    let failing: Vec<String> = result
+        .features
+        .iter()
+        .filter(|s| s.failed > 0 && s.state == FeatureState::Implemented)
+        .map(|s| s.key.clone())
+        .collect();
+    if !failing.is_empty() {
+        out.push_str(&format!(
+            "Implemented features with failing tests: {}\n",
+            failing.join(", ")
+        ));
+    }
+    if !result.unmatched.is_empty() {
+        out.push_str(&format!(
+            "{} test(s) matched no feature; name a feature key or slug in them, \
+             or set an acceptance criterion's test_name.\n",
+            result.unmatched.len()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_junit_reads_outcomes_and_messages() {
+        let report = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="api_spec" tests="3">
+    <testcase classname="api_spec" name="login::accepts_valid_password" time="0.01"/>
+    <testcase classname="api_spec" name="login::rejects_wrong_password">
+      <failure message="assertion failed: status == 401">backtrace</failure>
+    </testcase>
+    <testcase classname="com.example.LoginTest" name="locksAccount">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>"#;
+
+        let cases = parse(report).unwrap();
+
+        let names: Vec<&str> = cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "api_spec::login::accepts_valid_password",
+                "api_spec::login::rejects_wrong_password",
+                "com.example.LoginTest.locksAccount"
+            ]
+        );
+        assert_eq!(cases[0].outcome, TestOutcome::Passed);
+        assert_eq!(cases[1].outcome, TestOutcome::Failed);
+        assert_eq!(
+            cases[1].message.as_deref(),
+            Some("assertion failed: status == 401")
+        );
+        assert_eq!(cases[2].outcome, TestOutcome::Skipped);
+    }
+
+    #[test]
+    fn parse_libtest_skips_non_test_lines() {
+        let report = "   Compiling manifest v0.1.0\n\
+            { \"type\": \"suite\", \"event\": \"started\", \"test_count\": 2 }\n\
+            { \"type\": \"test\", \"event\": \"started\", \"name\": \"login::accepts\" }\n\
+            { \"type\": \"test\", \"name\": \"login::accepts\", \"event\": \"ok\" }\n\
+            { \"type\": \"test\", \"name\": \"login::locks\", \"event\": \"failed\", \"stdout\": \"panicked\\n\" }\n";
+
+        let cases = parse(report).unwrap();
+
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].outcome, TestOutcome::Passed);
+        assert_eq!(cases[1].outcome, TestOutcome::Failed);
+        assert_eq!(cases[1].message.as_deref(), Some("panicked"));
+    }
+
+    #[test]
+    fn parse_rejects_reports_without_test_cases() {
+        assert!(parse("not a report").is_err());
+        assert!(parse("<testsuites></testsuites>").is_err());
+    }
+}
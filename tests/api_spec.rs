@@ -16,6 +16,7 @@ async fn create_test_project(server: &TestServer) -> Project {
         .post("/api/v1/projects")
         .json(&CreateProjectInput {
             name: "Test Project".to_string(),
+            key: None,
             description: None,
             instructions: None,
         })
@@ -56,6 +57,7 @@ mod feature_roots {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -71,6 +73,7 @@ mod feature_roots {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await;
 
@@ -104,6 +107,7 @@ mod feature_children {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -132,6 +136,7 @@ mod feature_children {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -146,6 +151,7 @@ mod feature_children {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await;
 
@@ -159,6 +165,7 @@ mod feature_children {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await;
 
@@ -188,6 +195,7 @@ mod feature_children {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -202,6 +210,7 @@ mod feature_children {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -216,6 +225,7 @@ mod feature_children {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await;
 
@@ -248,6 +258,7 @@ mod feature_hierarchy_create {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -262,6 +273,7 @@ mod feature_hierarchy_create {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await;
 
@@ -286,6 +298,7 @@ mod feature_hierarchy_create {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -300,6 +313,7 @@ mod feature_hierarchy_create {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -314,6 +328,7 @@ mod feature_hierarchy_create {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -345,6 +360,7 @@ mod feature_cascade_delete {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -359,6 +375,7 @@ mod feature_cascade_delete {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -395,6 +412,7 @@ mod feature_history {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -404,7 +422,7 @@ mod feature_history {
             .await;
 
         response.assert_status_ok();
-        let history: Vec<FeatureHistory> = response.json();
+        let history = response.json::<Page<FeatureHistory>>().items;
         assert!(history.is_empty());
     }
 }
@@ -427,6 +445,7 @@ mod session_leaf_validation {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -436,6 +455,7 @@ mod session_leaf_validation {
             .json(&CreateSessionInput {
                 feature_id: leaf.id,
                 goal: "Implement feature".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await;
@@ -458,6 +478,7 @@ mod session_leaf_validation {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -472,6 +493,7 @@ mod session_leaf_validation {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await;
 
@@ -480,6 +502,7 @@ mod session_leaf_validation {
             .json(&CreateSessionInput {
                 feature_id: parent.id,
                 goal: "Implement feature".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await;
@@ -503,6 +526,7 @@ mod session_leaf_validation {
                 state: None,
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -513,6 +537,7 @@ mod session_leaf_validation {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "First session".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await;
@@ -524,6 +549,7 @@ mod session_leaf_validation {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Second session".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await;
@@ -552,6 +578,7 @@ mod session_completion {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -561,11 +588,12 @@ mod session_completion {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Implement feature".to_string(),
+                kind: None,
                 tasks: vec![CreateTaskInput {
                     parent_id: None,
                     title: "Task".to_string(),
                     scope: "Scope".to_string(),
-                    agent_type: AgentType::Claude,
+                    agent_type: AgentType::new("claude"),
                 }],
             })
             .await
@@ -577,6 +605,8 @@ mod session_completion {
                 summary: "Feature implemented".to_string(),
                 commits: vec![],
                 feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
             })
             .await;
 
@@ -602,6 +632,7 @@ mod session_completion {
 
                 details: None,
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -611,6 +642,7 @@ mod session_completion {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Goal".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await
@@ -622,6 +654,8 @@ mod session_completion {
                 summary: "Work completed".to_string(),
                 commits: vec![],
                 feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
             })
             .await;
 
@@ -629,7 +663,7 @@ mod session_completion {
         let response = server
             .get(&format!("/api/v1/features/{}/history", feature.id))
             .await;
-        let history: Vec<FeatureHistory> = response.json();
+        let history = response.json::<Page<FeatureHistory>>().items;
         assert_eq!(history.len(), 1);
         assert_eq!(history[0].details.summary, "Work completed");
     }
@@ -645,6 +679,8 @@ mod session_completion {
                 summary: "Done".to_string(),
                 commits: vec![],
                 feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
             })
             .await;
 
@@ -683,7 +719,7 @@ mod projects {
         let response = server.get("/api/v1/projects").await;
 
         response.assert_status_ok();
-        let projects: Vec<Project> = response.json();
+        let projects = response.json::<Page<Project>>().items;
         assert!(projects.is_empty());
     }
 
@@ -695,6 +731,7 @@ mod projects {
             .post("/api/v1/projects")
             .json(&CreateProjectInput {
                 name: "Zebra Project".to_string(),
+                key: None,
                 description: None,
                 instructions: None,
             })
@@ -704,6 +741,7 @@ mod projects {
             .post("/api/v1/projects")
             .json(&CreateProjectInput {
                 name: "Alpha Project".to_string(),
+                key: None,
                 description: None,
                 instructions: None,
             })
@@ -712,7 +750,7 @@ mod projects {
         let response = server.get("/api/v1/projects").await;
 
         response.assert_status_ok();
-        let projects: Vec<Project> = response.json();
+        let projects = response.json::<Page<Project>>().items;
         assert_eq!(projects.len(), 2);
         assert_eq!(projects[0].name, "Alpha Project");
         assert_eq!(projects[1].name, "Zebra Project");
@@ -726,6 +764,7 @@ mod projects {
             .post("/api/v1/projects")
             .json(&CreateProjectInput {
                 name: "New Project".to_string(),
+                key: None,
                 description: Some("A description".to_string()),
                 instructions: Some("Build with cargo".to_string()),
             })
@@ -774,6 +813,8 @@ mod projects {
                 name: Some("Updated Name".to_string()),
                 description: Some("New description".to_string()),
                 instructions: None,
+                max_concurrent_sessions: None,
+                provision_worktrees: None,
             })
             .await;
 
@@ -783,6 +824,48 @@ mod projects {
         assert_eq!(updated.description, Some("New description".to_string()));
     }
 
+    #[tokio::test]
+    async fn update_sets_max_concurrent_sessions() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        assert_eq!(project.max_concurrent_sessions, 1);
+
+        let response = server
+            .put(&format!("/api/v1/projects/{}", project.id))
+            .json(&UpdateProjectInput {
+                name: None,
+                description: None,
+                instructions: None,
+                max_concurrent_sessions: Some(3),
+                provision_worktrees: None,
+            })
+            .await;
+
+        response.assert_status_ok();
+        let updated: Project = response.json();
+        assert_eq!(updated.max_concurrent_sessions, 3);
+        assert_eq!(updated.name, "Test Project");
+    }
+
+    #[tokio::test]
+    async fn update_rejects_zero_max_concurrent_sessions() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        let response = server
+            .put(&format!("/api/v1/projects/{}", project.id))
+            .json(&UpdateProjectInput {
+                name: None,
+                description: None,
+                instructions: None,
+                max_concurrent_sessions: Some(0),
+                provision_worktrees: None,
+            })
+            .await;
+
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn update_returns_not_found_for_nonexistent_project() {
         let server = setup();
@@ -794,6 +877,8 @@ mod projects {
                 name: Some("Name".to_string()),
                 description: None,
                 instructions: None,
+                max_concurrent_sessions: None,
+                provision_worktrees: None,
             })
             .await;
 
@@ -904,10 +989,338 @@ mod project_directories {
     }
 }
 
+mod project_metrics {
+    use super::*;
+
+    #[tokio::test]
+    async fn returns_empty_metrics_for_new_project() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        let response = server
+            .get(&format!("/api/v1/projects/{}/metrics", project.id))
+            .await;
+
+        response.assert_status_ok();
+        let metrics: ProjectMetrics = response.json();
+        assert_eq!(metrics.project_id, project.id);
+        assert!(metrics.features.is_empty());
+        assert!(metrics.agent_types.is_empty());
+        assert!(metrics.weekly_throughput.is_empty());
+    }
+
+    #[tokio::test]
+    async fn returns_not_found_for_nonexistent_project() {
+        let server = setup();
+        let fake_id = uuid::Uuid::new_v4();
+
+        let response = server
+            .get(&format!("/api/v1/projects/{}/metrics", fake_id))
+            .await;
+
+        response.assert_status_not_found();
+    }
+}
+
+mod agent_types {
+    use super::*;
+
+    #[tokio::test]
+    async fn list_returns_default_agent_types() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        let response = server
+            .get(&format!("/api/v1/projects/{}/agent-types", project.id))
+            .await;
+
+        response.assert_status_ok();
+        let types: Vec<AgentTypeConfig> = response.json();
+        assert_eq!(types.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn create_and_update_agent_type() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        let response = server
+            .post(&format!("/api/v1/projects/{}/agent-types", project.id))
+            .json(&CreateAgentTypeInput {
+                name: AgentType::new("local-llama"),
+                label: "Local Llama".to_string(),
+                instructions: None,
+                max_concurrency: Some(2),
+            })
+            .await;
+
+        response.assert_status(StatusCode::CREATED);
+        let agent_type: AgentTypeConfig = response.json();
+        assert_eq!(agent_type.name.as_str(), "local-llama");
+        assert_eq!(agent_type.max_concurrency, Some(2));
+
+        let response = server
+            .put(&format!("/api/v1/agent-types/{}", agent_type.id))
+            .json(&UpdateAgentTypeInput {
+                label: None,
+                instructions: Some("Keep changes small".to_string()),
+                max_concurrency: None,
+            })
+            .await;
+
+        response.assert_status_ok();
+        let updated: AgentTypeConfig = response.json();
+        assert_eq!(updated.label, "Local Llama");
+        assert_eq!(updated.instructions, Some("Keep changes small".to_string()));
+        assert_eq!(updated.max_concurrency, Some(2));
+    }
+
+    #[tokio::test]
+    async fn create_rejects_invalid_name() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        let response = server
+            .post(&format!("/api/v1/projects/{}/agent-types", project.id))
+            .json(&CreateAgentTypeInput {
+                name: AgentType::new("Local Llama"),
+                label: "Local Llama".to_string(),
+                instructions: None,
+                max_concurrency: None,
+            })
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn task_creation_rejects_invalid_agent_type_names() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let feature = server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>();
+
+        let response = server
+            .post(&format!("/api/v1/features/{}/sessions", feature.id))
+            .json(&serde_json::json!({
+                "goal": "Goal",
+                "tasks": [{"title": "Task", "scope": "Scope", "agent_type": "../Claude"}]
+            }))
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(response.text().contains("invalid agent type '../Claude'"));
+    }
+
+    #[tokio::test]
+    async fn delete_unregisters_agent_type() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        let types = server
+            .get(&format!("/api/v1/projects/{}/agent-types", project.id))
+            .await
+            .json::<Vec<AgentTypeConfig>>();
+        let codex = types
+            .iter()
+            .find(|t| t.name.as_str() == "codex")
+            .expect("codex should be registered");
+
+        let response = server
+            .delete(&format!("/api/v1/agent-types/{}", codex.id))
+            .await;
+        response.assert_status(StatusCode::NO_CONTENT);
+
+        let feature = server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>();
+
+        let response = server
+            .post(&format!("/api/v1/features/{}/sessions", feature.id))
+            .json(&CreateFeatureSessionInput {
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![CreateTaskInput {
+                    parent_id: None,
+                    title: "Task".to_string(),
+                    scope: "Scope".to_string(),
+                    agent_type: AgentType::new("codex"),
+                }],
+            })
+            .await;
+
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+}
+
 // ============================================================
 // Feature CRUD
 // ============================================================
 
+mod code_links {
+    use super::*;
+
+    async fn create_linked_feature(server: &TestServer) -> (Project, Feature) {
+        let project = create_test_project(server).await;
+        server
+            .post(&format!("/api/v1/projects/{}/directories", project.id))
+            .json(&AddDirectoryInput {
+                path: "/home/user/project".to_string(),
+                git_remote: None,
+                is_primary: true,
+                instructions: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let feature = server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: "Routing".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>();
+        (project, feature)
+    }
+
+    #[tokio::test]
+    async fn create_update_and_delete_code_link() {
+        let server = setup();
+        let (_, feature) = create_linked_feature(&server).await;
+
+        let response = server
+            .post(&format!("/api/v1/features/{}/code-links", feature.id))
+            .json(&CreateCodeLinkInput {
+                directory_id: None,
+                path: "src/api/mod.rs".to_string(),
+                line_start: Some(40),
+                line_end: None,
+                symbol: Some("create_router".to_string()),
+            })
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let link: FeatureCodeLink = response.json();
+
+        let updated: FeatureCodeLink = server
+            .put(&format!("/api/v1/code-links/{}", link.id))
+            .json(&UpdateCodeLinkInput {
+                path: None,
+                line_start: None,
+                line_end: Some(Some(120)),
+                symbol: None,
+            })
+            .await
+            .json();
+        assert_eq!(updated.line_start, Some(40));
+        assert_eq!(updated.line_end, Some(120));
+        assert_eq!(updated.symbol.as_deref(), Some("create_router"));
+
+        let cleared: FeatureCodeLink = server
+            .put(&format!("/api/v1/code-links/{}", link.id))
+            .json(&serde_json::json!({"line_end": null, "symbol": null}))
+            .await
+            .json();
+        assert_eq!(cleared.line_start, Some(40));
+        assert_eq!(cleared.line_end, None);
+        assert_eq!(cleared.symbol, None);
+
+        let links: Vec<FeatureCodeLink> = server
+            .get(&format!("/api/v1/features/{}/code-links", feature.id))
+            .await
+            .json();
+        assert_eq!(links.len(), 1);
+
+        server
+            .delete(&format!("/api/v1/code-links/{}", link.id))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .delete(&format!("/api/v1/code-links/{}", link.id))
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn create_rejects_line_end_without_start() {
+        let server = setup();
+        let (_, feature) = create_linked_feature(&server).await;
+
+        let response = server
+            .post(&format!("/api/v1/features/{}/code-links", feature.id))
+            .json(&CreateCodeLinkInput {
+                directory_id: None,
+                path: "src/lib.rs".to_string(),
+                line_start: None,
+                line_end: Some(10),
+                symbol: None,
+            })
+            .await;
+
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn by_path_returns_features_with_matching_links() {
+        let server = setup();
+        let (project, feature) = create_linked_feature(&server).await;
+        server
+            .post(&format!("/api/v1/features/{}/code-links", feature.id))
+            .json(&CreateCodeLinkInput {
+                directory_id: None,
+                path: "src/api/**".to_string(),
+                line_start: None,
+                line_end: None,
+                symbol: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let response = server
+            .get(&format!("/api/v1/projects/{}/features/by-path", project.id))
+            .add_query_param("path", "src/api/handlers/mod.rs")
+            .await;
+
+        response.assert_status_ok();
+        let matches: Vec<FeatureCodeMatch> = response.json();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].feature.id, feature.id);
+        assert_eq!(matches[0].links[0].path, "src/api/**");
+
+        let none: Vec<FeatureCodeMatch> = server
+            .get(&format!("/api/v1/projects/{}/features/by-path", project.id))
+            .add_query_param("path", "src/main.rs")
+            .await
+            .json();
+        assert!(none.is_empty());
+    }
+}
+
 mod features {
     use super::*;
 
@@ -926,6 +1339,7 @@ mod features {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -939,6 +1353,7 @@ mod features {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -946,7 +1361,7 @@ mod features {
         let response = server.get("/api/v1/features").await;
 
         response.assert_status_ok();
-        let features: Vec<FeatureSummary> = response.json();
+        let features = response.json::<Page<FeatureSummary>>().items;
         assert_eq!(features.len(), 2);
     }
 
@@ -964,6 +1379,7 @@ mod features {
                 details: Some("As a user... Implementation details".to_string()),
                 state: Some(FeatureState::Specified),
                 priority: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1004,6 +1420,7 @@ mod features {
                 details: None,
                 priority: None,
                 state: Some(FeatureState::Proposed),
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1017,6 +1434,7 @@ mod features {
                 desired_details: None,
                 priority: None,
                 state: Some(FeatureState::Implemented),
+                tags: None,
             })
             .await;
 
@@ -1041,6 +1459,7 @@ mod features {
                 desired_details: None,
                 priority: None,
                 state: None,
+                tags: None,
             })
             .await;
 
@@ -1069,13 +1488,14 @@ mod feature_search {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
         let response = server.get("/api/v1/features/search?q=nonexistent").await;
 
         response.assert_status_ok();
-        let features: Vec<FeatureSummary> = response.json();
+        let features = response.json::<Page<FeatureSummary>>().items;
         assert!(features.is_empty());
     }
 
@@ -1093,6 +1513,7 @@ mod feature_search {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -1105,13 +1526,14 @@ mod feature_search {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
         let response = server.get("/api/v1/features/search?q=user").await;
 
         response.assert_status_ok();
-        let features: Vec<FeatureSummary> = response.json();
+        let features = response.json::<Page<FeatureSummary>>().items;
         assert_eq!(features.len(), 1);
         assert_eq!(features[0].title, "User Authentication");
     }
@@ -1130,13 +1552,14 @@ mod feature_search {
                 details: Some("Implement Google OAuth using PKCE flow".to_string()),
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
         let response = server.get("/api/v1/features/search?q=PKCE").await;
 
         response.assert_status_ok();
-        let features: Vec<FeatureSummary> = response.json();
+        let features = response.json::<Page<FeatureSummary>>().items;
         assert_eq!(features.len(), 1);
         assert_eq!(features[0].title, "OAuth Integration");
     }
@@ -1156,6 +1579,7 @@ mod feature_search {
                     details: None,
                     priority: None,
                     state: None,
+                    tags: vec![],
                 })
                 .await;
         }
@@ -1165,7 +1589,7 @@ mod feature_search {
             .await;
 
         response.assert_status_ok();
-        let features: Vec<FeatureSummary> = response.json();
+        let features = response.json::<Page<FeatureSummary>>().items;
         assert_eq!(features.len(), 2);
     }
 
@@ -1177,6 +1601,7 @@ mod feature_search {
             .post("/api/v1/projects")
             .json(&CreateProjectInput {
                 name: "Project 1".to_string(),
+                key: None,
                 description: None,
                 instructions: None,
             })
@@ -1187,6 +1612,7 @@ mod feature_search {
             .post("/api/v1/projects")
             .json(&CreateProjectInput {
                 name: "Project 2".to_string(),
+                key: None,
                 description: None,
                 instructions: None,
             })
@@ -1202,6 +1628,7 @@ mod feature_search {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -1214,6 +1641,7 @@ mod feature_search {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -1225,7 +1653,7 @@ mod feature_search {
             .await;
 
         response.assert_status_ok();
-        let features: Vec<FeatureSummary> = response.json();
+        let features = response.json::<Page<FeatureSummary>>().items;
         assert_eq!(features.len(), 1);
         assert_eq!(features[0].title, "Auth in Project 1");
     }
@@ -1252,6 +1680,7 @@ mod feature_diff {
                 details: Some("Current details".to_string()),
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1281,6 +1710,7 @@ mod feature_diff {
                 details: Some("Current".to_string()),
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1295,6 +1725,7 @@ mod feature_diff {
                 desired_details: Some("Desired".to_string()),
                 priority: None,
                 state: None,
+                tags: None,
             })
             .await;
 
@@ -1344,6 +1775,7 @@ mod feature_tree {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1358,6 +1790,7 @@ mod feature_tree {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -1371,6 +1804,7 @@ mod feature_tree {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -1408,6 +1842,7 @@ mod sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1417,6 +1852,7 @@ mod sessions {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Implement the feature".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await
@@ -1458,6 +1894,7 @@ mod sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1467,11 +1904,12 @@ mod sessions {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Goal".to_string(),
+                kind: None,
                 tasks: vec![CreateTaskInput {
                     parent_id: None,
                     title: "Task 1".to_string(),
                     scope: "Scope".to_string(),
-                    agent_type: AgentType::Claude,
+                    agent_type: AgentType::new("claude"),
                 }],
             })
             .await
@@ -1515,6 +1953,7 @@ mod tasks {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1524,11 +1963,12 @@ mod tasks {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Goal".to_string(),
+                kind: None,
                 tasks: vec![CreateTaskInput {
                     parent_id: None,
                     title: "My Task".to_string(),
                     scope: "Task scope".to_string(),
-                    agent_type: AgentType::Gemini,
+                    agent_type: AgentType::new("gemini"),
                 }],
             })
             .await
@@ -1543,7 +1983,7 @@ mod tasks {
         assert_eq!(task.id, task_id);
         assert_eq!(task.title, "My Task");
         assert_eq!(task.scope, "Task scope");
-        assert_eq!(task.agent_type, AgentType::Gemini);
+        assert_eq!(task.agent_type, AgentType::new("gemini"));
     }
 
     #[tokio::test]
@@ -1571,6 +2011,7 @@ mod tasks {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1580,11 +2021,12 @@ mod tasks {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Goal".to_string(),
+                kind: None,
                 tasks: vec![CreateTaskInput {
                     parent_id: None,
                     title: "Task".to_string(),
                     scope: "Scope".to_string(),
-                    agent_type: AgentType::Claude,
+                    agent_type: AgentType::new("claude"),
                 }],
             })
             .await
@@ -1598,6 +2040,7 @@ mod tasks {
                 status: Some(TaskStatus::Running),
                 worktree_path: Some("/tmp/worktree".to_string()),
                 branch: Some("feature-branch".to_string()),
+                result: None,
             })
             .await;
 
@@ -1614,32 +2057,7 @@ mod tasks {
     }
 
     #[tokio::test]
-    async fn update_returns_not_found_for_nonexistent_task() {
-        let server = setup();
-        let fake_id = uuid::Uuid::new_v4();
-
-        let response = server
-            .put(&format!("/api/v1/tasks/{}", fake_id))
-            .json(&UpdateTaskInput {
-                status: Some(TaskStatus::Running),
-                worktree_path: None,
-                branch: None,
-            })
-            .await;
-
-        response.assert_status_not_found();
-    }
-}
-
-// ============================================================
-// Session Tasks
-// ============================================================
-
-mod session_tasks {
-    use super::*;
-
-    #[tokio::test]
-    async fn create_task_in_session() {
+    async fn update_stores_task_result() {
         let server = setup();
         let project = create_test_project(&server).await;
 
@@ -1652,6 +2070,7 @@ mod session_tasks {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1661,7 +2080,100 @@ mod session_tasks {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Goal".to_string(),
-                tasks: vec![],
+                kind: None,
+                tasks: vec![CreateTaskInput {
+                    parent_id: None,
+                    title: "Task".to_string(),
+                    scope: "Scope".to_string(),
+                    agent_type: AgentType::new("claude"),
+                }],
+            })
+            .await
+            .json::<SessionResponse>();
+
+        let task_id = session_response.tasks[0].id;
+
+        let response = server
+            .put(&format!("/api/v1/tasks/{}", task_id))
+            .json(&serde_json::json!({
+                "status": "failed",
+                "result": {
+                    "summary": "Tried to add migration",
+                    "files_changed": ["migrations/010.sql"],
+                    "test_outcome": "failed",
+                    "failure_reason": "Schema conflicts with existing index"
+                }
+            }))
+            .await;
+
+        response.assert_status_ok();
+
+        let fetched = server
+            .get(&format!("/api/v1/tasks/{}", task_id))
+            .await
+            .json::<Task>();
+        assert_eq!(fetched.status, TaskStatus::Failed);
+        let result = fetched.result.expect("Expected task result");
+        assert_eq!(result.files_changed, vec!["migrations/010.sql"]);
+        assert_eq!(result.test_outcome, Some(TestOutcome::Failed));
+        assert_eq!(
+            result.failure_reason,
+            Some("Schema conflicts with existing index".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn update_returns_not_found_for_nonexistent_task() {
+        let server = setup();
+        let fake_id = uuid::Uuid::new_v4();
+
+        let response = server
+            .put(&format!("/api/v1/tasks/{}", fake_id))
+            .json(&UpdateTaskInput {
+                status: Some(TaskStatus::Running),
+                worktree_path: None,
+                branch: None,
+                result: None,
+            })
+            .await;
+
+        response.assert_status_not_found();
+    }
+}
+
+// ============================================================
+// Session Tasks
+// ============================================================
+
+mod session_tasks {
+    use super::*;
+
+    #[tokio::test]
+    async fn create_task_in_session() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        let feature = server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>();
+
+        let session_response = server
+            .post("/api/v1/sessions")
+            .json(&CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![],
             })
             .await
             .json::<SessionResponse>();
@@ -1675,7 +2187,7 @@ mod session_tasks {
                 parent_id: None,
                 title: "New Task".to_string(),
                 scope: "Task scope".to_string(),
-                agent_type: AgentType::Claude,
+                agent_type: AgentType::new("claude"),
             })
             .await;
 
@@ -1683,7 +2195,7 @@ mod session_tasks {
         let task: Task = response.json();
         assert_eq!(task.title, "New Task");
         assert_eq!(task.scope, "Task scope");
-        assert_eq!(task.agent_type, AgentType::Claude);
+        assert_eq!(task.agent_type, AgentType::new("claude"));
         assert_eq!(task.status, TaskStatus::Pending);
     }
 
@@ -1698,7 +2210,7 @@ mod session_tasks {
                 parent_id: None,
                 title: "Task".to_string(),
                 scope: "Scope".to_string(),
-                agent_type: AgentType::Claude,
+                agent_type: AgentType::new("claude"),
             })
             .await;
 
@@ -1719,6 +2231,7 @@ mod session_tasks {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1728,18 +2241,19 @@ mod session_tasks {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "Goal".to_string(),
+                kind: None,
                 tasks: vec![
                     CreateTaskInput {
                         parent_id: None,
                         title: "Task 1".to_string(),
                         scope: "Scope 1".to_string(),
-                        agent_type: AgentType::Claude,
+                        agent_type: AgentType::new("claude"),
                     },
                     CreateTaskInput {
                         parent_id: None,
                         title: "Task 2".to_string(),
                         scope: "Scope 2".to_string(),
-                        agent_type: AgentType::Gemini,
+                        agent_type: AgentType::new("gemini"),
                     },
                 ],
             })
@@ -1792,6 +2306,7 @@ mod feature_sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1801,7 +2316,7 @@ mod feature_sessions {
             .await;
 
         response.assert_status_ok();
-        let sessions: Vec<Session> = response.json();
+        let sessions = response.json::<Page<Session>>().items;
         assert!(sessions.is_empty());
     }
 
@@ -1819,6 +2334,7 @@ mod feature_sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1829,6 +2345,7 @@ mod feature_sessions {
             .json(&CreateSessionInput {
                 feature_id: feature.id,
                 goal: "First session".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await
@@ -1839,7 +2356,7 @@ mod feature_sessions {
             .await;
 
         response.assert_status_ok();
-        let sessions: Vec<Session> = response.json();
+        let sessions = response.json::<Page<Session>>().items;
         assert_eq!(sessions.len(), 1);
         assert_eq!(sessions[0].id, session_response.session.id);
         assert_eq!(sessions[0].goal, "First session");
@@ -1871,6 +2388,7 @@ mod feature_sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1879,6 +2397,7 @@ mod feature_sessions {
             .post(&format!("/api/v1/features/{}/sessions", feature.id))
             .json(&CreateFeatureSessionInput {
                 goal: "RESTful session".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await;
@@ -1904,6 +2423,7 @@ mod feature_sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1912,11 +2432,12 @@ mod feature_sessions {
             .post(&format!("/api/v1/features/{}/sessions", feature.id))
             .json(&CreateFeatureSessionInput {
                 goal: "Session with tasks".to_string(),
+                kind: None,
                 tasks: vec![CreateTaskInput {
                     parent_id: None,
                     title: "Task 1".to_string(),
                     scope: "Scope".to_string(),
-                    agent_type: AgentType::Claude,
+                    agent_type: AgentType::new("claude"),
                 }],
             })
             .await;
@@ -1936,6 +2457,7 @@ mod feature_sessions {
             .post(&format!("/api/v1/features/{}/sessions", fake_id))
             .json(&CreateFeatureSessionInput {
                 goal: "Goal".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await;
@@ -1957,6 +2479,7 @@ mod feature_sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await
             .json::<Feature>();
@@ -1971,6 +2494,7 @@ mod feature_sessions {
                 details: None,
                 priority: None,
                 state: None,
+                tags: vec![],
             })
             .await;
 
@@ -1978,6 +2502,7 @@ mod feature_sessions {
             .post(&format!("/api/v1/features/{}/sessions", parent.id))
             .json(&CreateFeatureSessionInput {
                 goal: "Goal".to_string(),
+                kind: None,
                 tasks: vec![],
             })
             .await;
@@ -1988,106 +2513,1993 @@ mod feature_sessions {
     }
 }
 
-// ============================================================
-// Security - API Key Authentication
-// ============================================================
-
-mod security_auth {
+mod worktrees {
     use super::*;
-    use manifest::api::{create_router_with_config, SecurityConfig};
+    use std::process::Command;
+
+    /// Initialize a git repo with one commit so worktrees have a HEAD to branch from.
+    pub(super) fn init_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let repo = dir.path().join("app");
+        std::fs::create_dir(&repo).unwrap();
+        for args in [
+            vec!["init", "-q"],
+            vec![
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "Initial commit",
+            ],
+        ] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(&args)
+                // History that predates any session
+                .env("GIT_AUTHOR_DATE", "2020-01-01T00:00:00Z")
+                .env("GIT_COMMITTER_DATE", "2020-01-01T00:00:00Z")
+                .status()
+                .expect("Failed to run git");
+            assert!(status.success());
+        }
+        dir
+    }
 
-    fn setup_with_auth(api_key: &str) -> TestServer {
-        let db = Database::open_memory().expect("Failed to create database");
-        db.migrate().expect("Failed to migrate");
-        let config = SecurityConfig::with_api_key(api_key);
-        let app = create_router_with_config(db, config);
-        TestServer::new(app).expect("Failed to create test server")
+    pub(super) async fn create_session_in_repo(
+        server: &TestServer,
+        repo: &std::path::Path,
+        provision_worktrees: bool,
+    ) -> SessionResponse {
+        let project = create_test_project(server).await;
+        server
+            .post(&format!("/api/v1/projects/{}/directories", project.id))
+            .json(&AddDirectoryInput {
+                path: repo.to_string_lossy().into_owned(),
+                git_remote: None,
+                is_primary: true,
+                instructions: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .put(&format!("/api/v1/projects/{}", project.id))
+            .json(&UpdateProjectInput {
+                name: None,
+                description: None,
+                instructions: None,
+                max_concurrent_sessions: None,
+                provision_worktrees: Some(provision_worktrees),
+            })
+            .await
+            .assert_status_ok();
+
+        let feature = server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: "OAuth Login".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>();
+
+        server
+            .post("/api/v1/sessions")
+            .json(&CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![CreateTaskInput {
+                    parent_id: None,
+                    title: "Add callback route".to_string(),
+                    scope: "Scope".to_string(),
+                    agent_type: AgentType::new("claude"),
+                }],
+            })
+            .await
+            .json::<SessionResponse>()
     }
 
     #[tokio::test]
-    async fn health_endpoint_is_accessible_without_auth() {
-        let server = setup_with_auth("test-secret-key");
+    async fn provision_creates_branch_and_worktree() {
+        let server = setup();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let session = create_session_in_repo(&server, &repo, false).await;
+        let task = &session.tasks[0];
+        assert!(task.worktree_path.is_none());
 
-        let response = server.get("/api/v1/health").await;
+        let response = server
+            .post(&format!("/api/v1/tasks/{}/worktree", task.id))
+            .await;
 
         response.assert_status_ok();
+        let task: Task = response.json();
+        let branch = task.branch.expect("branch should be set");
+        assert!(branch.starts_with("manifest/oauth-login/add-callback-route-"));
+        let path = task.worktree_path.expect("worktree_path should be set");
+        assert!(std::path::Path::new(&path).join(".git").exists());
+        assert!(path.starts_with(&*dir.path().join("app.worktrees").to_string_lossy()));
+
+        // Provisioning again returns the same worktree
+        let again: Task = server
+            .post(&format!("/api/v1/tasks/{}/worktree", task.id))
+            .await
+            .json();
+        assert_eq!(again.worktree_path, Some(path));
     }
 
     #[tokio::test]
-    async fn protected_endpoint_requires_auth() {
-        let server = setup_with_auth("test-secret-key");
+    async fn provision_rejects_directory_that_is_not_a_repo() {
+        let server = setup();
+        let dir = tempfile::tempdir().unwrap();
+        let session = create_session_in_repo(&server, dir.path(), false).await;
 
-        let response = server.get("/api/v1/projects").await;
+        let response = server
+            .post(&format!("/api/v1/tasks/{}/worktree", session.tasks[0].id))
+            .await;
 
-        response.assert_status(StatusCode::UNAUTHORIZED);
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("not a git repository"));
     }
 
     #[tokio::test]
-    async fn protected_endpoint_accepts_valid_bearer_token() {
-        let server = setup_with_auth("test-secret-key");
+    async fn session_completion_removes_provisioned_worktrees() {
+        let server = setup();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let session = create_session_in_repo(&server, &repo, true).await;
 
-        let response = server
-            .get("/api/v1/projects")
-            .add_header("Authorization", "Bearer test-secret-key")
-            .await;
+        // The project opted in, so the task came back with a worktree
+        let path = session.tasks[0]
+            .worktree_path
+            .clone()
+            .expect("worktree should be provisioned on session creation");
+        assert!(std::path::Path::new(&path).exists());
 
-        response.assert_status_ok();
+        server
+            .post(&format!("/api/v1/sessions/{}/complete", session.session.id))
+            .json(&CompleteSessionInput {
+                summary: "Done".to_string(),
+                commits: vec![],
+                feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
+            })
+            .await
+            .assert_status_ok();
+
+        assert!(!std::path::Path::new(&path).exists());
+        // The directories of the branch name's other segments go too
+        assert!(!dir.path().join("app.worktrees/manifest").exists());
     }
 
     #[tokio::test]
-    async fn protected_endpoint_rejects_invalid_bearer_token() {
-        let server = setup_with_auth("test-secret-key");
+    async fn session_completion_keeps_worktrees_with_uncommitted_changes() {
+        let server = setup();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let session = create_session_in_repo(&server, &repo, true).await;
+        let path = session.tasks[0].worktree_path.clone().unwrap();
+        std::fs::write(std::path::Path::new(&path).join("wip.rs"), "fn main() {}").unwrap();
 
-        let response = server
-            .get("/api/v1/projects")
-            .add_header("Authorization", "Bearer wrong-key")
-            .await;
+        let result: SessionCompletionResult = server
+            .post(&format!("/api/v1/sessions/{}/complete", session.session.id))
+            .json(&CompleteSessionInput {
+                summary: "Done".to_string(),
+                commits: vec![],
+                feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
+            })
+            .await
+            .json();
 
-        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert!(std::path::Path::new(&path).join("wip.rs").exists());
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("uncommitted changes"));
     }
 
     #[tokio::test]
-    async fn protected_endpoint_rejects_malformed_auth_header() {
-        let server = setup_with_auth("test-secret-key");
+    async fn provision_rejects_unsafe_branch_names() {
+        let server = setup();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let session = create_session_in_repo(&server, &repo, false).await;
+        let task = &session.tasks[0];
 
-        let response = server
-            .get("/api/v1/projects")
-            .add_header("Authorization", "Basic dXNlcjpwYXNz")
-            .await;
+        for branch in ["--orphan", "manifest/../../escape", "bad name"] {
+            server
+                .put(&format!("/api/v1/tasks/{}", task.id))
+                .json(&UpdateTaskInput {
+                    status: None,
+                    worktree_path: None,
+                    branch: Some(branch.to_string()),
+                    result: None,
+                })
+                .await
+                .assert_status_success();
 
-        response.assert_status(StatusCode::UNAUTHORIZED);
+            let response = server
+                .post(&format!("/api/v1/tasks/{}/worktree", task.id))
+                .await;
+
+            response.assert_status(StatusCode::BAD_REQUEST);
+            assert!(response.text().contains("Invalid branch name"));
+        }
+        assert!(!dir.path().join("escape").exists());
+    }
+}
+
+mod commit_discovery {
+    use super::worktrees::{create_session_in_repo, init_repo};
+    use super::*;
+    use std::process::Command;
+
+    fn commit(dir: &str, message: &str) {
+        let status = Command::new("git")
+            .args([
+                "-C",
+                dir,
+                "-c",
+                "user.name=Agent",
+                "-c",
+                "user.email=agent@example.com",
+            ])
+            .args(["commit", "-q", "--allow-empty", "-m", message])
+            .status()
+            .expect("Failed to run git");
+        assert!(status.success());
+    }
+
+    async fn complete(
+        server: &TestServer,
+        session_id: uuid::Uuid,
+        trailer: bool,
+    ) -> FeatureHistory {
+        server
+            .post(&format!("/api/v1/sessions/{}/complete", session_id))
+            .json(&CompleteSessionInput {
+                summary: "Done".to_string(),
+                commits: vec![],
+                feature_state: None,
+                require_feature_trailer: trailer,
+                allow_unverified_criteria: false,
+            })
+            .await
+            .json::<SessionCompletionResult>()
+            .history_entry
     }
 
     #[tokio::test]
-    async fn post_endpoint_requires_auth() {
-        let server = setup_with_auth("test-secret-key");
+    async fn completion_attaches_commits_from_task_branches() {
+        let server = setup();
+        let dir = init_repo();
+        let session = create_session_in_repo(&server, &dir.path().join("app"), true).await;
+        let worktree = session.tasks[0].worktree_path.clone().unwrap();
+
+        commit(&worktree, "Add callback route");
+        commit(&worktree, "Handle token refresh");
+
+        let history = complete(&server, session.session.id, false).await;
+
+        let messages: Vec<&str> = history
+            .details
+            .commits
+            .iter()
+            .map(|c| c.message.as_str())
+            .collect();
+        assert_eq!(messages, vec!["Add callback route", "Handle token refresh"]);
+        assert_eq!(history.details.commits[0].author.as_deref(), Some("Agent"));
+        assert_eq!(history.details.commits[0].sha.len(), 40);
+    }
 
-        let response = server
-            .post("/api/v1/projects")
-            .json(&CreateProjectInput {
-                name: "Test".to_string(),
-                description: None,
+    #[tokio::test]
+    async fn completion_can_require_feature_trailer() {
+        let server = setup();
+        let dir = init_repo();
+        let session = create_session_in_repo(&server, &dir.path().join("app"), true).await;
+        let worktree = session.tasks[0].worktree_path.clone().unwrap();
+
+        commit(&worktree, "Unrelated cleanup");
+        commit(
+            &worktree,
+            &format!(
+                "Add callback route\n\nFeature: {}",
+                session.session.feature_id
+            ),
+        );
+
+        let history = complete(&server, session.session.id, true).await;
+
+        assert_eq!(history.details.commits.len(), 1);
+        assert_eq!(history.details.commits[0].message, "Add callback route");
+    }
+
+    #[tokio::test]
+    async fn required_trailers_may_name_the_feature_by_slug() {
+        let server = setup();
+        let dir = init_repo();
+        let session = create_session_in_repo(&server, &dir.path().join("app"), true).await;
+        let worktree = session.tasks[0].worktree_path.clone().unwrap();
+
+        commit(&worktree, "Add callback route\n\nFeature: oauth-login");
+        commit(&worktree, "Add invoices\n\nFeature: billing");
+
+        let history = complete(&server, session.session.id, true).await;
+
+        assert_eq!(history.details.commits.len(), 1);
+        assert_eq!(history.details.commits[0].message, "Add callback route");
+    }
+
+    #[tokio::test]
+    async fn completion_without_task_branches_keeps_only_head_commits_naming_the_feature() {
+        let server = setup();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let session = create_session_in_repo(&server, &repo, false).await;
+        let repo = repo.to_string_lossy();
+
+        commit(&repo, "Add invoices");
+        commit(&repo, "Add callback route\n\nFeature: oauth-login");
+
+        let history = complete(&server, session.session.id, false).await;
+
+        assert_eq!(history.details.commits.len(), 1);
+        assert_eq!(history.details.commits[0].message, "Add callback route");
+    }
+
+    #[tokio::test]
+    async fn history_without_a_session_keeps_only_head_commits_naming_the_feature() {
+        let server = setup();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let session = create_session_in_repo(&server, &repo, false).await;
+        let repo = repo.to_string_lossy();
+
+        commit(&repo, "Add invoices");
+        commit(&repo, "Add callback route\n\nFeature: oauth-login");
+
+        let history: FeatureHistory = server
+            .post(&format!(
+                "/api/v1/features/{}/history",
+                session.session.feature_id
+            ))
+            .json(&serde_json::json!({
+                "summary": "Done",
+                "mark_implemented": false
+            }))
+            .await
+            .json();
+
+        assert_eq!(history.details.commits.len(), 1);
+        assert_eq!(history.details.commits[0].message, "Add callback route");
+    }
+}
+
+mod commit_trailers {
+    use super::worktrees::init_repo;
+    use super::*;
+    use manifest::hook;
+    use manifest::mcp::ManifestClient;
+    use std::path::Path;
+    use std::process::Command;
+
+    async fn create_repo_feature(server: &TestServer, repo: &Path) -> (Project, Feature) {
+        let project = create_test_project(server).await;
+        server
+            .post(&format!("/api/v1/projects/{}/directories", project.id))
+            .json(&AddDirectoryInput {
+                path: repo.to_string_lossy().into_owned(),
+                git_remote: None,
+                is_primary: true,
                 instructions: None,
             })
-            .await;
+            .await
+            .assert_status(StatusCode::CREATED);
+        let feature = server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: "OAuth Login".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>();
+        (project, feature)
+    }
 
-        response.assert_status(StatusCode::UNAUTHORIZED);
+    /// A server on a real port, for exercising the hooks through the HTTP client.
+    pub(super) fn setup_http() -> (TestServer, ManifestClient) {
+        let db = Database::open_memory().expect("Failed to create database");
+        db.migrate().expect("Failed to migrate");
+        let server = TestServer::builder()
+            .http_transport()
+            .build(create_router(db))
+            .expect("Failed to create test server");
+        let url = server
+            .server_address()
+            .expect("server should have an address");
+        let client = ManifestClient::new(format!("{}api/v1", url), None);
+        (server, client)
     }
 
     #[tokio::test]
-    async fn post_endpoint_works_with_valid_auth() {
-        let server = setup_with_auth("test-secret-key");
+    async fn resolves_feature_by_slug() {
+        let server = setup();
+        let dir = tempfile::tempdir().unwrap();
+        let (project, feature) = create_repo_feature(&server, dir.path()).await;
 
         let response = server
+            .get(&format!("/api/v1/projects/{}/features/resolve", project.id))
+            .add_query_param("ref", "oauth-login")
+            .await;
+        let missing = server
+            .get(&format!("/api/v1/projects/{}/features/resolve", project.id))
+            .add_query_param("ref", "billing")
+            .await;
+
+        response.assert_status_ok();
+        assert_eq!(response.json::<Feature>().id, feature.id);
+        missing.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn records_commit_in_feature_history() {
+        let server = setup();
+        let dir = tempfile::tempdir().unwrap();
+        let (_, feature) = create_repo_feature(&server, dir.path()).await;
+        let input = RecordCommitInput {
+            commit: CommitRef {
+                sha: "abc123def456".to_string(),
+                message: "Add callback route".to_string(),
+                author: None,
+            },
+            branch: None,
+        };
+
+        let response = server
+            .post(&format!("/api/v1/features/{}/commits", feature.id))
+            .json(&input)
+            .await;
+        let again: RecordedCommit = server
+            .post(&format!("/api/v1/features/{}/commits", feature.id))
+            .json(&input)
+            .await
+            .json();
+
+        response.assert_status_ok();
+        let recorded: RecordedCommit = response.json();
+        assert!(recorded.history_id.is_some());
+        assert!(again.already_recorded);
+        assert_eq!(again.history_id, recorded.history_id);
+    }
+
+    #[tokio::test]
+    async fn install_refuses_to_replace_foreign_hooks() {
+        let (server, client) = setup_http();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        create_repo_feature(&server, &repo).await;
+        let hooks_dir = repo.join(".git/hooks");
+        std::fs::write(hooks_dir.join("commit-msg"), "#!/bin/sh\nexit 0\n").unwrap();
+
+        let refused = hook::install(&client, &repo, false).await;
+        let installed = hook::install(&client, &repo, true)
+            .await
+            .expect("Failed to install");
+
+        assert!(refused.unwrap_err().to_string().contains("--force"));
+        assert_eq!(installed.len(), hook::HOOKS.len());
+        let script = std::fs::read_to_string(hooks_dir.join("post-commit")).unwrap();
+        assert!(script.contains("hook post-commit"));
+        // Our own hooks are replaced without --force
+        hook::install(&client, &repo, false)
+            .await
+            .expect("Failed to reinstall");
+    }
+
+    #[tokio::test]
+    async fn hooks_validate_trailers_and_record_commits() {
+        let (server, client) = setup_http();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let (_, feature) = create_repo_feature(&server, &repo).await;
+
+        let message = dir.path().join("COMMIT_EDITMSG");
+        std::fs::write(&message, "Add billing\n\nFeature: billing\n").unwrap();
+        let rejected = hook::commit_msg(&client, &repo, &message).await;
+        std::fs::write(&message, "Add callback route\n\nFeature: oauth-login\n").unwrap();
+        hook::commit_msg(&client, &repo, &message)
+            .await
+            .expect("Trailer should resolve");
+
+        assert!(rejected
+            .unwrap_err()
+            .to_string()
+            .contains("Feature: billing"));
+
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args([
+                "-c",
+                "user.name=Agent",
+                "-c",
+                "user.email=agent@example.com",
+            ])
+            .args(["commit", "-q", "--allow-empty", "-F"])
+            .arg(&message)
+            .status()
+            .expect("Failed to run git");
+        assert!(status.success());
+
+        let recorded = hook::post_commit(&client, &repo)
+            .await
+            .expect("Failed to record");
+
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].feature_id, feature.id);
+        let history = server
+            .get(&format!("/api/v1/features/{}/history", feature.id))
+            .await
+            .json::<Page<FeatureHistory>>()
+            .items;
+        assert_eq!(history[0].details.commits[0].message, "Add callback route");
+        assert_eq!(
+            history[0].details.commits[0].author.as_deref(),
+            Some("Agent")
+        );
+    }
+}
+
+mod drift {
+    use super::worktrees::init_repo;
+    use super::*;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Commit changes to `files` at `date` and return the commit's SHA.
+    fn commit_files(repo: &Path, files: &[&str], message: &str, date: &str) -> String {
+        for file in files {
+            let path = repo.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, message).unwrap();
+        }
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args([
+                    "-c",
+                    "user.name=Agent",
+                    "-c",
+                    "user.email=agent@example.com",
+                ])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .output()
+                .expect("Failed to run git");
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", message]);
+        git(&["rev-parse", "HEAD"])
+    }
+
+    async fn create_feature(
+        server: &TestServer,
+        project: &Project,
+        title: &str,
+        state: FeatureState,
+    ) -> Feature {
+        server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: Some(state),
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>()
+    }
+
+    async fn link(server: &TestServer, feature: &Feature, path: &str) {
+        server
+            .post(&format!("/api/v1/features/{}/code-links", feature.id))
+            .json(&CreateCodeLinkInput {
+                directory_id: None,
+                path: path.to_string(),
+                line_start: None,
+                line_end: None,
+                symbol: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    async fn record(server: &TestServer, feature: &Feature, sha: &str) {
+        server
+            .post(&format!("/api/v1/features/{}/commits", feature.id))
+            .json(&RecordCommitInput {
+                commit: CommitRef {
+                    sha: sha.to_string(),
+                    message: "Recorded".to_string(),
+                    author: None,
+                },
+                branch: None,
+            })
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn reports_commits_to_linked_files_after_spec_update() {
+        let server = setup();
+        let dir = init_repo();
+        let repo = dir.path().join("app");
+        let project = create_test_project(&server).await;
+        server
+            .post(&format!("/api/v1/projects/{}/directories", project.id))
+            .json(&AddDirectoryInput {
+                path: repo.to_string_lossy().into_owned(),
+                git_remote: None,
+                is_primary: true,
+                instructions: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let auth = create_feature(&server, &project, "Auth", FeatureState::Implemented).await;
+        let planned = create_feature(&server, &project, "SSO", FeatureState::Specified).await;
+        link(&server, &auth, "src/auth").await;
+        link(&server, &planned, "src/auth").await;
+
+        // The original implementation, recorded before the spec was last touched
+        let original = commit_files(
+            &repo,
+            &["src/auth/login.rs", "src/session.rs"],
+            "Implement login",
+            "2021-01-01T00:00:00Z",
+        );
+        record(&server, &auth, &original).await;
+
+        let later = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        let token = commit_files(&repo, &["src/auth/token.rs"], "Add tokens", &later);
+        commit_files(&repo, &["docs/intro.md"], "Write docs", &later);
+        let session = commit_files(&repo, &["src/session.rs"], "Tweak sessions", &later);
+        let recorded = commit_files(&repo, &["src/auth/login.rs"], "Fix login", &later);
+        record(&server, &auth, &recorded).await;
+
+        let response = server
+            .get(&format!("/api/v1/projects/{}/drift", project.id))
+            .await;
+
+        response.assert_status_ok();
+        let report: DriftReport = response.json();
+        assert_eq!(report.features.len(), 1);
+        assert_eq!(report.features[0].feature.id, auth.id);
+        let commits: Vec<(&str, &[String])> = report.features[0]
+            .commits
+            .iter()
+            .map(|c| (c.commit.sha.as_str(), c.files.as_slice()))
+            .collect();
+        assert_eq!(
+            commits,
+            vec![
+                (token.as_str(), &["src/auth/token.rs".to_string()][..]),
+                (session.as_str(), &["src/session.rs".to_string()][..]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_404_for_unknown_project() {
+        let server = setup();
+
+        let response = server
+            .get(&format!("/api/v1/projects/{}/drift", uuid::Uuid::new_v4()))
+            .await;
+
+        response.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn is_refused_outside_local_mode() {
+        let db = Database::open_memory().expect("Failed to create database");
+        db.migrate().expect("Failed to migrate");
+        let config = manifest::api::SecurityConfig::with_api_key("key");
+        let server = TestServer::new(manifest::api::create_router_with_config(db, config)).unwrap();
+
+        let response = server
+            .get(&format!("/api/v1/projects/{}/drift", uuid::Uuid::new_v4()))
+            .add_header("Authorization", "Bearer key")
+            .await;
+
+        response.assert_status(StatusCode::CONFLICT);
+    }
+}
+
+mod feature_paths {
+    use super::commit_trailers::setup_http;
+    use super::*;
+
+    pub(super) async fn create_feature(
+        server: &TestServer,
+        project: &Project,
+        parent_id: Option<uuid::Uuid>,
+        title: &str,
+    ) -> Feature {
+        server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            })
+            .await
+            .json::<Feature>()
+    }
+
+    #[tokio::test]
+    async fn feature_routes_accept_encoded_paths() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let auth = create_feature(&server, &project, None, "Auth").await;
+        let oauth = create_feature(&server, &project, Some(auth.id), "OAuth Integration").await;
+        assert_eq!(oauth.path, "auth/oauth-integration");
+
+        let response = server
+            .get("/api/v1/features/auth%2Foauth-integration")
+            .await;
+        let history = server
+            .get("/api/v1/features/auth%2Foauth-integration/history")
+            .await;
+        let missing = server.get("/api/v1/features/auth%2Fmissing").await;
+
+        response.assert_status_ok();
+        assert_eq!(response.json::<Feature>().id, oauth.id);
+        history.assert_status_ok();
+        missing.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn renamed_feature_keeps_answering_on_its_old_path() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let auth = create_feature(&server, &project, None, "Auth").await;
+
+        server
+            .put("/api/v1/features/auth")
+            .json(&UpdateFeatureInput {
+                parent_id: None,
+                title: Some("Identity".to_string()),
+                details: None,
+                desired_details: None,
+                state: None,
+                priority: None,
+                tags: None,
+            })
+            .await
+            .assert_status_ok();
+
+        let old = server.get("/api/v1/features/auth").await.json::<Feature>();
+        assert_eq!(old.id, auth.id);
+        assert_eq!(old.path, "identity");
+    }
+
+    #[tokio::test]
+    async fn client_accepts_paths_and_project_prefixes() {
+        let (server, client) = setup_http();
+        let project = create_test_project(&server).await;
+        let auth = create_feature(&server, &project, None, "Auth").await;
+        let oauth = create_feature(&server, &project, Some(auth.id), "OAuth").await;
+
+        let by_path = client
+            .get_feature("auth/oauth")
+            .await
+            .expect("Lookup failed");
+        let qualified = client
+            .get_feature(format!("{}/auth/oauth", project.id).as_str())
+            .await
+            .expect("Lookup failed");
+        let history = client
+            .get_feature_history("auth/oauth")
+            .await
+            .expect("Lookup failed");
+
+        assert_eq!(by_path.id, oauth.id);
+        assert_eq!(qualified.id, oauth.id);
+        assert!(history.is_empty());
+    }
+}
+
+mod feature_keys {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    #[tokio::test]
+    async fn feature_routes_accept_keys() {
+        let server = setup();
+        let project = server
             .post("/api/v1/projects")
-            .add_header("Authorization", "Bearer test-secret-key")
             .json(&CreateProjectInput {
-                name: "Test".to_string(),
+                name: "Rocket Ship".to_string(),
+                key: Some("app".to_string()),
                 description: None,
                 instructions: None,
             })
+            .await
+            .json::<Project>();
+        assert_eq!(project.key, "APP");
+        let auth = create_feature(&server, &project, None, "Auth").await;
+        assert_eq!(auth.key, "APP-1");
+
+        let response = server.get("/api/v1/features/APP-1").await;
+        let history = server.get("/api/v1/features/APP-1/history").await;
+        let missing = server.get("/api/v1/features/APP-2").await;
+
+        response.assert_status_ok();
+        assert_eq!(response.json::<Feature>().id, auth.id);
+        history.assert_status_ok();
+        missing.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn duplicate_project_key_is_rejected() {
+        let server = setup();
+        let input = CreateProjectInput {
+            name: "Rocket Ship".to_string(),
+            key: Some("RS".to_string()),
+            description: None,
+            instructions: None,
+        };
+        server
+            .post("/api/v1/projects")
+            .json(&input)
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let response = server.post("/api/v1/projects").json(&input).await;
+
+        response.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn search_finds_features_by_key() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        create_feature(&server, &project, None, "Auth").await;
+        let billing = create_feature(&server, &project, None, "Billing").await;
+
+        let response = server
+            .get("/api/v1/features/search")
+            .add_query_param("q", &billing.key)
             .await;
 
-        response.assert_status(StatusCode::CREATED);
+        response.assert_status_ok();
+        let found = response.json::<Page<FeatureSummary>>().items;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].key, "TP-2");
+    }
+}
+
+mod tags {
+    use super::*;
+
+    async fn create_tagged(server: &TestServer, project: &Project, title: &str, tags: &[&str]) {
+        server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&CreateFeatureInput {
+                id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn tag_crud_routes() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let tags_url = format!("/api/v1/projects/{}/tags", project.id);
+        let input = CreateTagInput {
+            name: "security".to_string(),
+            color: Some("#cc0000".to_string()),
+        };
+
+        let created = server.post(&tags_url).json(&input).await;
+        created.assert_status(StatusCode::CREATED);
+        let tag = created.json::<Tag>();
+        server
+            .post(&tags_url)
+            .json(&input)
+            .await
+            .assert_status(StatusCode::CONFLICT);
+
+        let updated = server
+            .put(&format!("/api/v1/tags/{}", tag.id))
+            .json(&UpdateTagInput {
+                name: Some("sec".to_string()),
+                color: None,
+            })
+            .await;
+        updated.assert_status_ok();
+        assert_eq!(updated.json::<Tag>().color.as_deref(), Some("#cc0000"));
+
+        let listed = server.get(&tags_url).await.json::<Vec<Tag>>();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "sec");
+
+        server
+            .delete(&format!("/api/v1/tags/{}", tag.id))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .delete(&format!("/api/v1/tags/{}", tag.id))
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn list_and_search_filter_by_tag() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        create_tagged(&server, &project, "Login", &["security", "v2"]).await;
+        create_tagged(&server, &project, "Login audit", &["security"]).await;
+        create_tagged(&server, &project, "Dashboard", &["frontend"]).await;
+
+        let listed = server
+            .get(&format!("/api/v1/projects/{}/features", project.id))
+            .add_query_param("tag", "security")
+            .await
+            .json::<Page<FeatureSummary>>()
+            .items;
+        let searched = server
+            .get("/api/v1/features/search")
+            .add_query_param("q", "login")
+            .add_query_param("tag", "security,v2")
+            .await
+            .json::<Page<FeatureSummary>>()
+            .items;
+
+        assert_eq!(listed.len(), 2);
+        assert!(listed
+            .iter()
+            .all(|f| f.tags.contains(&"security".to_string())));
+        assert_eq!(searched.len(), 1);
+        assert_eq!(searched[0].title, "Login");
+    }
+}
+
+mod feature_filters {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    #[tokio::test]
+    async fn list_all_features_filters_by_states() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let login = create_feature(&server, &project, None, "Login").await;
+        create_feature(&server, &project, None, "Audit").await;
+        server
+            .put(&format!("/api/v1/features/{}", login.id))
+            .json(&UpdateFeatureInput {
+                parent_id: None,
+                title: None,
+                details: None,
+                desired_details: None,
+                state: Some(FeatureState::Implemented),
+                priority: None,
+                tags: None,
+            })
+            .await
+            .assert_status_ok();
+
+        let implemented = server
+            .get("/api/v1/features")
+            .add_query_param("state", "implemented,deprecated")
+            .await
+            .json::<Page<FeatureSummary>>()
+            .items;
+
+        assert_eq!(implemented.len(), 1);
+        assert_eq!(implemented[0].id, login.id);
+        server
+            .get("/api/v1/features")
+            .add_query_param("state", "done")
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn project_features_filter_by_tree_shape_and_sort() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let auth = create_feature(&server, &project, None, "Auth").await;
+        create_feature(&server, &project, Some(auth.id), "OAuth").await;
+        create_feature(&server, &project, Some(auth.id), "Password").await;
+        let url = format!("/api/v1/projects/{}/features", project.id);
+
+        let leaves = server
+            .get(&url)
+            .add_query_param("leaf", "true")
+            .add_query_param("sort", "-title")
+            .await
+            .json::<Page<FeatureSummary>>()
+            .items;
+        let roots = server
+            .get(&url)
+            .add_query_param("depth", "0")
+            .await
+            .json::<Page<FeatureSummary>>()
+            .items;
+
+        let titles: Vec<&str> = leaves.iter().map(|f| f.title.as_str()).collect();
+        assert_eq!(titles, vec!["Password", "OAuth"]);
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].id, auth.id);
+        server
+            .get(&url)
+            .add_query_param("sort", "size")
+            .await
+            .assert_status_bad_request();
+    }
+}
+
+mod pagination {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    #[tokio::test]
+    async fn list_returns_envelope_and_link_to_next_page() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        for title in ["A", "B", "C"] {
+            create_feature(&server, &project, None, title).await;
+        }
+        let url = format!("/api/v1/projects/{}/features", project.id);
+
+        let response = server.get(&url).add_query_param("limit", "2").await;
+        let first = response.json::<Page<FeatureSummary>>();
+        let cursor = first.next_cursor.clone().expect("Missing cursor");
+        let link = response.header("link");
+
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.total, Some(3));
+        assert_eq!(
+            link.to_str().unwrap(),
+            format!("<{}?limit=2&cursor={}>; rel=\"next\"", url, cursor)
+        );
+
+        let response = server
+            .get(&url)
+            .add_query_param("limit", "2")
+            .add_query_param("cursor", &cursor)
+            .await;
+        let last = response.json::<Page<FeatureSummary>>();
+        assert_eq!(last.items.len(), 1);
+        assert_eq!(last.items[0].title, "C");
+        assert!(last.next_cursor.is_none());
+        assert!(response.maybe_header("link").is_none());
+    }
+
+    #[tokio::test]
+    async fn invalid_cursor_is_bad_request() {
+        let server = setup();
+        create_test_project(&server).await;
+
+        server
+            .get("/api/v1/projects")
+            .add_query_param("cursor", "not-a-cursor")
+            .await
+            .assert_status_bad_request();
+    }
+}
+
+mod feature_relations {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    async fn relate(
+        server: &TestServer,
+        from: &Feature,
+        to: &Feature,
+        kind: RelationKind,
+    ) -> axum_test::TestResponse {
+        server
+            .post(&format!("/api/v1/features/{}/relations", from.key))
+            .json(&CreateRelationInput {
+                to_feature_id: to.id,
+                kind,
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn relation_routes_reject_cycles_and_duplicates() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let oauth = create_feature(&server, &project, None, "OAuth").await;
+        let sessions = create_feature(&server, &project, None, "Sessions").await;
+
+        let created = relate(&server, &oauth, &sessions, RelationKind::DependsOn).await;
+        created.assert_status(StatusCode::CREATED);
+        let relation = created.json::<FeatureRelation>();
+        relate(&server, &oauth, &sessions, RelationKind::DependsOn)
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        relate(&server, &sessions, &oauth, RelationKind::DependsOn)
+            .await
+            .assert_status_bad_request();
+
+        let upstream = server
+            .get(&format!("/api/v1/features/{}/dependencies", oauth.id))
+            .await
+            .json::<Vec<FeatureSummary>>();
+        let downstream = server
+            .get(&format!("/api/v1/features/{}/dependencies", sessions.id))
+            .add_query_param("direction", "downstream")
+            .await
+            .json::<Vec<FeatureSummary>>();
+        assert_eq!(upstream.len(), 1);
+        assert_eq!(upstream[0].id, sessions.id);
+        assert_eq!(downstream[0].id, oauth.id);
+
+        server
+            .delete(&format!("/api/v1/relations/{}", relation.id))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        let relations = server
+            .get(&format!("/api/v1/features/{}/relations", oauth.id))
+            .await
+            .json::<Vec<FeatureRelation>>();
+        assert!(relations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn graph_exports_dot_and_mermaid() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let oauth = create_feature(&server, &project, None, "OAuth").await;
+        let sessions = create_feature(&server, &project, None, "Sessions").await;
+        create_feature(&server, &project, None, "Unrelated").await;
+        relate(&server, &oauth, &sessions, RelationKind::DependsOn)
+            .await
+            .assert_status(StatusCode::CREATED);
+        let url = format!("/api/v1/projects/{}/graph", project.id);
+
+        let graph = server.get(&url).await.json::<FeatureGraph>();
+        let dot = server
+            .get(&url)
+            .add_query_param("format", "dot")
+            .await
+            .text();
+        let mermaid = server
+            .get(&url)
+            .add_query_param("format", "mermaid")
+            .await
+            .text();
+
+        assert_eq!(graph.features.len(), 2);
+        assert!(dot.starts_with("digraph features {"));
+        assert!(dot.contains("\"TP-1\" [label=\"TP-1 OAuth\"];"));
+        assert!(dot.contains("\"TP-1\" -> \"TP-2\" [label=\"depends_on\"];"));
+        assert!(mermaid.contains("TP_1 -->|depends_on| TP_2"));
+        server
+            .get(&url)
+            .add_query_param("format", "png")
+            .await
+            .assert_status_bad_request();
+    }
+}
+
+// ============================================================
+// Security - API Key Authentication
+// ============================================================
+
+mod security_auth {
+    use super::*;
+    use manifest::api::{create_router_with_config, SecurityConfig};
+
+    fn setup_with_auth(api_key: &str) -> TestServer {
+        let db = Database::open_memory().expect("Failed to create database");
+        db.migrate().expect("Failed to migrate");
+        let config = SecurityConfig::with_api_key(api_key);
+        let app = create_router_with_config(db, config);
+        TestServer::new(app).expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn health_endpoint_is_accessible_without_auth() {
+        let server = setup_with_auth("test-secret-key");
+
+        let response = server.get("/api/v1/health").await;
+
+        response.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn protected_endpoint_requires_auth() {
+        let server = setup_with_auth("test-secret-key");
+
+        let response = server.get("/api/v1/projects").await;
+
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn protected_endpoint_accepts_valid_bearer_token() {
+        let server = setup_with_auth("test-secret-key");
+
+        let response = server
+            .get("/api/v1/projects")
+            .add_header("Authorization", "Bearer test-secret-key")
+            .await;
+
+        response.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn protected_endpoint_rejects_invalid_bearer_token() {
+        let server = setup_with_auth("test-secret-key");
+
+        let response = server
+            .get("/api/v1/projects")
+            .add_header("Authorization", "Bearer wrong-key")
+            .await;
+
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn protected_endpoint_rejects_malformed_auth_header() {
+        let server = setup_with_auth("test-secret-key");
+
+        let response = server
+            .get("/api/v1/projects")
+            .add_header("Authorization", "Basic dXNlcjpwYXNz")
+            .await;
+
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn post_endpoint_requires_auth() {
+        let server = setup_with_auth("test-secret-key");
+
+        let response = server
+            .post("/api/v1/projects")
+            .json(&CreateProjectInput {
+                name: "Test".to_string(),
+                key: None,
+                description: None,
+                instructions: None,
+            })
+            .await;
+
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn post_endpoint_works_with_valid_auth() {
+        let server = setup_with_auth("test-secret-key");
+
+        let response = server
+            .post("/api/v1/projects")
+            .add_header("Authorization", "Bearer test-secret-key")
+            .json(&CreateProjectInput {
+                name: "Test".to_string(),
+                key: None,
+                description: None,
+                instructions: None,
+            })
+            .await;
+
+        response.assert_status(StatusCode::CREATED);
+    }
+}
+
+mod acceptance_criteria {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    #[tokio::test]
+    async fn criterion_routes_create_update_and_delete() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let feature = create_feature(&server, &project, None, "Login").await;
+        let url = format!("/api/v1/features/{}/criteria", feature.key);
+
+        let created = server
+            .post(&url)
+            .json(&CreateCriterionInput {
+                text: "Accepts a valid password".to_string(),
+                status: None,
+                test_name: None,
+                position: None,
+            })
+            .await;
+        created.assert_status(StatusCode::CREATED);
+        let criterion = created.json::<AcceptanceCriterion>();
+        assert_eq!(criterion.status, CriterionStatus::Unverified);
+
+        let updated = server
+            .put(&format!("/api/v1/criteria/{}", criterion.id))
+            .json(&UpdateCriterionInput {
+                status: Some(CriterionStatus::Failing),
+                test_name: Some("login_accepts_valid_password".to_string()),
+                ..Default::default()
+            })
+            .await
+            .json::<AcceptanceCriterion>();
+        assert_eq!(updated.status, CriterionStatus::Failing);
+        assert_eq!(
+            updated.test_name.as_deref(),
+            Some("login_accepts_valid_password")
+        );
+
+        server
+            .delete(&format!("/api/v1/criteria/{}", criterion.id))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        let criteria = server.get(&url).await.json::<Vec<AcceptanceCriterion>>();
+        assert!(criteria.is_empty());
+    }
+
+    #[tokio::test]
+    async fn history_refuses_implemented_with_unverified_criteria() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let feature = create_feature(&server, &project, None, "Login").await;
+        server
+            .post(&format!("/api/v1/features/{}/criteria", feature.id))
+            .json(&CreateCriterionInput {
+                text: "Accepts a valid password".to_string(),
+                status: None,
+                test_name: None,
+                position: None,
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let history_url = format!("/api/v1/features/{}/history", feature.id);
+
+        server
+            .post(&history_url)
+            .json(&serde_json::json!({ "summary": "Built login" }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        server
+            .post(&history_url)
+            .json(&serde_json::json!({
+                "summary": "Built login",
+                "allow_unverified_criteria": true
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+}
+
+mod test_results {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    #[tokio::test]
+    async fn junit_reports_update_feature_results() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let login = create_feature(&server, &project, None, "Password Login").await;
+        let report = r#"<testsuites>
+  <testsuite name="auth">
+    <testcase classname="auth::password_login" name="accepts_valid_password"/>
+    <testcase classname="auth::password_login" name="rejects_wrong_password">
+      <failure message="expected 401"/>
+    </testcase>
+    <testcase classname="billing" name="charges_card"/>
+  </testsuite>
+</testsuites>"#;
+        let url = format!("/api/v1/projects/{}/test-results", project.id);
+
+        let result = server
+            .post(&url)
+            .text(report)
+            .await
+            .json::<TestReportResult>();
+
+        assert_eq!(result.matched, 2);
+        assert_eq!(result.unmatched, vec!["billing::charges_card"]);
+        let summaries = server.get(&url).await.json::<Vec<FeatureTestSummary>>();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!((summaries[0].passed, summaries[0].failed), (1, 1));
+        let results = server
+            .get(&format!("/api/v1/features/{}/test-results", login.key))
+            .await
+            .json::<Vec<TestResult>>();
+        assert_eq!(results[0].outcome, TestOutcome::Failed);
+        assert_eq!(results[0].message.as_deref(), Some("expected 401"));
+    }
+
+    #[tokio::test]
+    async fn unreadable_reports_are_rejected() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        server
+            .post(&format!("/api/v1/projects/{}/test-results", project.id))
+            .text("all tests passed")
+            .await
+            .assert_status_bad_request();
+    }
+}
+
+mod bulk_import {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    #[tokio::test]
+    async fn bulk_create_adds_tags_and_criteria_under_parent() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let parent = create_feature(&server, &project, None, "Accounts").await;
+
+        let created = server
+            .post(&format!("/api/v1/projects/{}/features/bulk", project.id))
+            .json(&serde_json::json!({
+                "features": [{
+                    "title": "Password login",
+                    "tags": ["auth"],
+                    "acceptance_criteria": [
+                        "Accepts a valid password",
+                        "Rejects a wrong password"
+                    ]
+                }],
+                "parent_id": parent.id,
+                "confirm": true
+            }))
+            .await
+            .json::<serde_json::Value>();
+        assert_eq!(created["created"], true);
+        let id = created["created_feature_ids"][0].as_str().unwrap();
+
+        let feature = server
+            .get(&format!("/api/v1/features/{}", id))
+            .await
+            .json::<Feature>();
+        assert_eq!(feature.parent_id, Some(parent.id));
+        assert_eq!(feature.tags, vec!["auth"]);
+        let criteria = server
+            .get(&format!("/api/v1/features/{}/criteria", id))
+            .await
+            .json::<Vec<AcceptanceCriterion>>();
+        let texts: Vec<&str> = criteria.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Accepts a valid password", "Rejects a wrong password"]
+        );
+    }
+
+    #[tokio::test]
+    async fn bulk_create_rejects_parent_from_another_project() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let other = create_test_project(&server).await;
+        let parent = create_feature(&server, &other, None, "Accounts").await;
+
+        server
+            .post(&format!("/api/v1/projects/{}/features/bulk", project.id))
+            .json(&serde_json::json!({
+                "features": [{ "title": "Password login" }],
+                "parent_id": parent.id
+            }))
+            .await
+            .assert_status_bad_request();
+    }
+}
+
+mod snapshots {
+    use super::feature_paths::create_feature;
+    use super::*;
+
+    #[tokio::test]
+    async fn snapshot_restores_a_deleted_project() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let feature = create_feature(&server, &project, None, "Login").await;
+        let snapshot = server
+            .get(&format!("/api/v1/projects/{}/snapshot", project.id))
+            .await
+            .json::<ProjectSnapshot>();
+        assert_eq!(snapshot.format_version, SNAPSHOT_FORMAT_VERSION);
+        server
+            .delete(&format!("/api/v1/projects/{}", project.id))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        server
+            .post("/api/v1/snapshots")
+            .json(&snapshot)
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let restored = server
+            .get(&format!("/api/v1/features/{}", feature.key))
+            .await
+            .json::<Feature>();
+        assert_eq!(restored.id, feature.id);
+        server
+            .post("/api/v1/snapshots")
+            .json(&snapshot)
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        server
+            .post("/api/v1/snapshots?replace=true")
+            .json(&snapshot)
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn snapshot_import_rejects_unknown_tables() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let mut snapshot = server
+            .get(&format!("/api/v1/projects/{}/snapshot", project.id))
+            .await
+            .json::<ProjectSnapshot>();
+        snapshot
+            .tables
+            .insert("schema_migrations".to_string(), vec![]);
+
+        server
+            .post("/api/v1/snapshots?replace=true")
+            .json(&snapshot)
+            .await
+            .assert_status_bad_request();
+    }
+}
+
+mod admin {
+    use super::*;
+
+    #[tokio::test]
+    async fn backup_streams_a_sqlite_database() {
+        let server = setup();
+        create_test_project(&server).await;
+
+        let response = server.post("/api/v1/admin/backup").await;
+
+        response.assert_status_ok();
+        assert_eq!(response.header("content-type"), "application/vnd.sqlite3");
+        assert!(response.as_bytes().starts_with(b"SQLite format 3\0"));
+    }
+}
+
+mod idempotency_keys {
+    use super::*;
+    use manifest::mcp::client::with_tool_call_key;
+    use manifest::mcp::ManifestClient;
+
+    fn feature_input(title: &str) -> CreateFeatureInput {
+        CreateFeatureInput {
+            id: None,
+            parent_id: None,
+            title: title.to_string(),
+            details: None,
+            state: None,
+            priority: None,
+            tags: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_the_first_response_to_a_retried_post() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let path = format!("/api/v1/projects/{}/features", project.id);
+
+        let first = server
+            .post(&path)
+            .add_header("Idempotency-Key", "create-auth")
+            .json(&feature_input("Auth"))
+            .await;
+        let retry = server
+            .post(&path)
+            .add_header("Idempotency-Key", "create-auth")
+            .json(&feature_input("Auth"))
+            .await;
+        let features: Page<FeatureSummary> = server.get(&path).await.json();
+
+        first.assert_status(StatusCode::CREATED);
+        retry.assert_status(StatusCode::CREATED);
+        assert_eq!(retry.header("idempotent-replayed"), "true");
+        assert_eq!(retry.json::<Feature>().id, first.json::<Feature>().id);
+        assert_eq!(features.items.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_a_key_reused_for_another_request() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+
+        server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .add_header("Idempotency-Key", "reused")
+            .json(&feature_input("Auth"))
+            .await;
+        let other = server
+            .post(&format!("/api/v1/projects/{}/tags", project.id))
+            .add_header("Idempotency-Key", "reused")
+            .json(&serde_json::json!({"name": "backend"}))
+            .await;
+
+        other.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn refuses_a_key_reused_with_another_body() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let path = format!("/api/v1/projects/{}/features", project.id);
+
+        server
+            .post(&path)
+            .add_header("Idempotency-Key", "create")
+            .json(&feature_input("Auth"))
+            .await;
+        let other = server
+            .post(&path)
+            .add_header("Idempotency-Key", "create")
+            .json(&feature_input("Billing"))
+            .await;
+
+        other.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn runs_a_refused_request_again_on_retry() {
+        let server = setup();
+        let project = create_test_project(&server).await;
+        let feature = server
+            .post(&format!("/api/v1/projects/{}/features", project.id))
+            .json(&feature_input("Auth"))
+            .await
+            .json::<Feature>();
+        let session_input = |goal: &str| CreateSessionInput {
+            feature_id: feature.id,
+            goal: goal.to_string(),
+            kind: None,
+            tasks: vec![],
+        };
+        let active = server
+            .post("/api/v1/sessions")
+            .json(&session_input("First"))
+            .await
+            .json::<SessionResponse>();
+
+        let refused = server
+            .post("/api/v1/sessions")
+            .add_header("Idempotency-Key", "second-session")
+            .json(&session_input("Second"))
+            .await;
+        server
+            .post(&format!("/api/v1/sessions/{}/complete", active.session.id))
+            .json(&CompleteSessionInput {
+                summary: "Done".to_string(),
+                commits: vec![],
+                feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
+            })
+            .await
+            .assert_status_ok();
+        let retry = server
+            .post("/api/v1/sessions")
+            .add_header("Idempotency-Key", "second-session")
+            .json(&session_input("Second"))
+            .await;
+
+        refused.assert_status(StatusCode::CONFLICT);
+        retry.assert_status(StatusCode::CREATED);
+        assert!(retry.maybe_header("idempotent-replayed").is_none());
+    }
+
+    #[tokio::test]
+    async fn retried_tool_calls_get_the_first_calls_results() {
+        let db = Database::open_memory().expect("Failed to create database");
+        db.migrate().expect("Failed to migrate");
+        let client = ManifestClient::local(db.clone());
+        let project = client
+            .create_project(&CreateProjectInput {
+                name: "Retries".to_string(),
+                key: None,
+                description: None,
+                instructions: None,
+            })
+            .await
+            .unwrap();
+        let call = uuid::Uuid::new_v4();
+
+        let first = with_tool_call_key(
+            call,
+            client.create_feature(project.id, &feature_input("Auth")),
+        )
+        .await
+        .unwrap();
+        let retry = with_tool_call_key(
+            call,
+            client.create_feature(project.id, &feature_input("Auth")),
+        )
+        .await
+        .unwrap();
+        let other_call = with_tool_call_key(
+            uuid::Uuid::new_v4(),
+            client.create_feature(project.id, &feature_input("Billing")),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(retry.id, first.id);
+        assert_ne!(other_call.id, first.id);
+        assert_eq!(db.get_features_by_project(project.id).unwrap().len(), 2);
+    }
+}
+
+mod local_client {
+    use super::*;
+    use manifest::mcp::client::ClientError;
+    use manifest::mcp::ManifestClient;
+
+    fn local() -> (Database, ManifestClient) {
+        let db = Database::open_memory().expect("Failed to create database");
+        db.migrate().expect("Failed to migrate");
+        (db.clone(), ManifestClient::local(db))
+    }
+
+    #[tokio::test]
+    async fn works_on_the_database_without_a_server() {
+        let (db, client) = local();
+
+        let project = client
+            .create_project(&CreateProjectInput {
+                name: "Local".to_string(),
+                key: None,
+                description: None,
+                instructions: None,
+            })
+            .await
+            .expect("Failed to create project");
+        client
+            .add_project_directory(
+                project.id,
+                &AddDirectoryInput {
+                    path: "/work/local app".to_string(),
+                    git_remote: None,
+                    is_primary: true,
+                    instructions: None,
+                },
+            )
+            .await
+            .expect("Failed to add directory");
+        let feature = client
+            .create_feature(
+                project.id,
+                &CreateFeatureInput {
+                    id: None,
+                    parent_id: None,
+                    title: "Offline Mode".to_string(),
+                    details: None,
+                    state: None,
+                    priority: None,
+                    tags: vec![],
+                },
+            )
+            .await
+            .expect("Failed to create feature");
+
+        let context = client
+            .get_project_context("/work/local app/src")
+            .await
+            .expect("Failed to get context");
+        let by_path = client
+            .get_feature(&format!("{}/offline-mode", project.id))
+            .await
+            .expect("Failed to get feature");
+
+        assert_eq!(context.project.id, project.id.to_string());
+        assert_eq!(by_path.id, feature.id);
+        assert!(db.get_feature(feature.id).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn maps_error_statuses_like_the_http_client() {
+        let (_db, client) = local();
+
+        let missing = client.get_feature(uuid::Uuid::new_v4()).await.unwrap_err();
+
+        assert!(matches!(missing, ClientError::NotFound(_)));
+    }
+}
+
+mod offline_client {
+    use super::*;
+    use manifest::mcp::client::ConnectionStatus;
+    use manifest::mcp::offline::OfflineStore;
+    use manifest::mcp::ManifestClient;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn answers_from_the_cache_and_replays_queued_writes() {
+        let (_server, client) = super::commit_trailers::setup_http();
+        let store = Arc::new(OfflineStore::open_memory().unwrap());
+        let up = client.with_offline_store(store.clone());
+        // Nothing listens on the discard port
+        let down = ManifestClient::new("http://127.0.0.1:9/api/v1".to_string(), None)
+            .with_offline_store(store.clone());
+        let project = up
+            .create_project(&CreateProjectInput {
+                name: "Offline".to_string(),
+                key: None,
+                description: None,
+                instructions: None,
+            })
+            .await
+            .unwrap();
+        let feature = up
+            .create_feature(
+                project.id,
+                &CreateFeatureInput {
+                    id: None,
+                    parent_id: None,
+                    title: "Sync".to_string(),
+                    details: None,
+                    state: None,
+                    priority: None,
+                    tags: vec![],
+                },
+            )
+            .await
+            .unwrap();
+        let session = up
+            .create_session_with_tasks(
+                feature.id,
+                "Sync",
+                None,
+                &[CreateTaskInput {
+                    parent_id: None,
+                    title: "Replay".to_string(),
+                    scope: "Replay writes".to_string(),
+                    agent_type: AgentType::new("claude"),
+                }],
+            )
+            .await
+            .unwrap();
+        let task_id = session.tasks[0].id;
+        up.get_task(task_id).await.unwrap();
+
+        let cached = down.get_task(task_id).await.unwrap();
+        let offline_status = down.connection_status();
+        down.update_task(
+            task_id,
+            &UpdateTaskInput {
+                status: Some(TaskStatus::Running),
+                worktree_path: None,
+                branch: None,
+                result: None,
+            },
+        )
+        .await
+        .unwrap();
+        let queued = store.queue_len().unwrap();
+        let replayed = up.get_task(task_id).await.unwrap();
+
+        assert_eq!(cached.id, task_id);
+        assert!(matches!(offline_status, ConnectionStatus::Offline { .. }));
+        assert!(offline_status.notice().is_some());
+        assert_eq!(queued, 1);
+        assert_eq!(replayed.status, TaskStatus::Running);
+        assert_eq!(store.queue_len().unwrap(), 0);
+        assert_eq!(up.connection_status(), ConnectionStatus::Online);
+    }
+
+    #[tokio::test]
+    async fn reports_queued_writes_the_server_refused_once() {
+        let (_server, client) = super::commit_trailers::setup_http();
+        let store = Arc::new(OfflineStore::open_memory().unwrap());
+        let up = client.with_offline_store(store.clone());
+        let down = ManifestClient::new("http://127.0.0.1:9/api/v1".to_string(), None)
+            .with_offline_store(store.clone());
+        let missing = uuid::Uuid::new_v4();
+
+        down.update_task(
+            missing,
+            &UpdateTaskInput {
+                status: Some(TaskStatus::Running),
+                worktree_path: None,
+                branch: None,
+                result: None,
+            },
+        )
+        .await
+        .unwrap();
+        up.get_task(missing).await.unwrap_err();
+        let notices = up.notices();
+
+        assert_eq!(store.queue_len().unwrap(), 0);
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].contains(&format!("/tasks/{}", missing)));
+        assert!(notices[0].contains("404"));
+        assert!(up.notices().is_empty());
+    }
+
+    #[test]
+    fn refuses_to_replay_corrupt_queued_writes() {
+        let path =
+            std::env::temp_dir().join(format!("manifest-offline-{}.db", uuid::Uuid::new_v4()));
+        let store = OfflineStore::open(&path).unwrap();
+        store
+            .enqueue(&manifest::mcp::offline::QueuedWrite {
+                idempotency_key: uuid::Uuid::new_v4(),
+                method: "PUT".to_string(),
+                path: "/tasks/1".to_string(),
+                content_type: None,
+                body: None,
+                queued_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute("UPDATE queued_writes SET idempotency_key = 'corrupt'", [])
+            .unwrap();
+
+        let err = store.queued().unwrap_err();
+
+        assert!(err.to_string().contains("invalid idempotency key"));
+        drop(store);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn keeps_only_the_latest_cached_responses() {
+        use manifest::mcp::offline::MAX_CACHED_RESPONSES;
+        let store = OfflineStore::open_memory().unwrap();
+
+        for i in 0..=MAX_CACHED_RESPONSES {
+            store.cache(&format!("/features/{}", i), b"{}").unwrap();
+        }
+
+        assert!(store.cached("/features/0").unwrap().is_none());
+        assert!(store.cached("/features/1").unwrap().is_some());
+        assert!(store
+            .cached(&format!("/features/{}", MAX_CACHED_RESPONSES))
+            .unwrap()
+            .is_some());
     }
 }
@@ -1,21 +1,53 @@
-use manifest::db::Database;
+use manifest::db::{Backend, Database, PostgresStore};
 use manifest::models::*;
 use speculate2::speculate;
 use uuid::Uuid;
 
+/// An empty, migrated database: a schema of its own on the PostgreSQL server
+/// at `MANIFEST_TEST_DATABASE_URL` if that is set, else in-memory SQLite.
+fn open_test_database() -> Database {
+    let db = match std::env::var("MANIFEST_TEST_DATABASE_URL") {
+        Ok(url) => {
+            Database::new(PostgresStore::connect_temporary(&url).expect("Failed to connect"))
+        }
+        Err(_) => Database::open_memory().expect("Failed to create in-memory database"),
+    };
+    db.migrate().expect("Failed to run migrations");
+    db
+}
+
 fn create_test_project(db: &Database) -> Project {
     db.create_project(CreateProjectInput {
         name: "Test Project".to_string(),
+        key: None,
         description: None,
         instructions: None,
     })
     .expect("Failed to create project")
 }
 
+fn commit(sha: &str, message: &str) -> RecordCommitInput {
+    RecordCommitInput {
+        commit: CommitRef {
+            sha: sha.to_string(),
+            message: message.to_string(),
+            author: Some("Agent".to_string()),
+        },
+        branch: None,
+    }
+}
+
+fn created_response() -> StoredResponse {
+    StoredResponse {
+        status: 201,
+        content_type: Some("application/json".to_string()),
+        body: b"{}".to_vec(),
+    }
+}
+
 speculate! {
     before {
-        let db = Database::open_memory().expect("Failed to create in-memory database");
-        db.migrate().expect("Failed to run migrations");
+        let db = open_test_database();
     }
 
     describe "projects" {
@@ -23,6 +55,7 @@ speculate! {
             it "creates a project with required fields" {
                 let project = db.create_project(CreateProjectInput {
                     name: "My Project".to_string(),
+                    key: None,
                     description: None,
                     instructions: None,
                 }).expect("Failed to create project");
@@ -34,6 +67,7 @@ speculate! {
             it "creates a project with all fields" {
                 let project = db.create_project(CreateProjectInput {
                     name: "Full Project".to_string(),
+                    key: None,
                     description: Some("A complete project".to_string()),
                     instructions: Some("Use cargo test to run tests".to_string()),
                 }).expect("Failed to create project");
@@ -53,6 +87,7 @@ speculate! {
             it "returns the project by id" {
                 let created = db.create_project(CreateProjectInput {
                     name: "Test".to_string(),
+                    key: None,
                     description: None,
                     instructions: None,
                 }).expect("Failed to create");
@@ -72,12 +107,14 @@ speculate! {
             it "returns all projects ordered by name" {
                 db.create_project(CreateProjectInput {
                     name: "Zebra".to_string(),
+                    key: None,
                     description: None,
                     instructions: None,
                 }).expect("Failed to create");
 
                 db.create_project(CreateProjectInput {
                     name: "Alpha".to_string(),
+                    key: None,
                     description: None,
                     instructions: None,
                 }).expect("Failed to create");
@@ -100,6 +137,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create feature");
 
                 db.delete_project(project.id).expect("Failed to delete");
@@ -155,6 +193,181 @@ speculate! {
         }
     }
 
+    describe "agent_types" {
+        it "registers the default agent types for new projects" {
+            let project = create_test_project(&db);
+
+            let types = db.get_agent_types(project.id).expect("Query failed");
+            let names: Vec<&str> = types.iter().map(|t| t.name.as_str()).collect();
+            assert_eq!(names.len(), 3);
+            assert!(names.contains(&"claude"));
+            assert!(names.contains(&"gemini"));
+            assert!(names.contains(&"codex"));
+        }
+
+        it "allows tasks with a custom registered agent type" {
+            let project = create_test_project(&db);
+            db.create_agent_type(project.id, CreateAgentTypeInput {
+                name: AgentType::new("human-review"),
+                label: "Human review".to_string(),
+                instructions: Some("Check the diff by hand".to_string()),
+                max_concurrency: None,
+            }).expect("Failed to register");
+
+            let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create");
+            let session = db.create_session(CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![],
+            }).expect("Failed to create");
+
+            let task = db.create_task(session.session.id, CreateTaskInput {
+                parent_id: None,
+                title: "Review".to_string(),
+                scope: "Review the change".to_string(),
+                agent_type: AgentType::new("human-review"),
+            }).expect("Failed to create task");
+
+            assert_eq!(task.agent_type.as_str(), "human-review");
+        }
+
+        it "rejects tasks with an unregistered agent type" {
+            let project = create_test_project(&db);
+            let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create");
+
+            let result = db.create_session(CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![CreateTaskInput {
+                    parent_id: None,
+                    title: "Task".to_string(),
+                    scope: "Scope".to_string(),
+                    agent_type: AgentType::new("local-llama"),
+                }],
+            });
+
+            let err = result.expect_err("Should reject unknown agent type");
+            assert!(err.to_string().contains("Unknown agent type 'local-llama'"));
+            assert!(db.get_sessions_by_feature(feature.id).expect("Query failed").is_empty());
+        }
+
+        it "rejects duplicate agent type names" {
+            let project = create_test_project(&db);
+
+            let result = db.create_agent_type(project.id, CreateAgentTypeInput {
+                name: AgentType::new("claude"),
+                label: "Claude again".to_string(),
+                instructions: None,
+                max_concurrency: None,
+            });
+
+            assert!(result.is_err());
+        }
+
+        it "refuses to start tasks beyond max_concurrency" {
+            let project = create_test_project(&db);
+            db.create_agent_type(project.id, CreateAgentTypeInput {
+                name: AgentType::new("local"),
+                label: "Local model".to_string(),
+                instructions: None,
+                max_concurrency: Some(1),
+            }).expect("Failed to register");
+
+            let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create");
+            let task_input = |title: &str| CreateTaskInput {
+                parent_id: None,
+                title: title.to_string(),
+                scope: "Scope".to_string(),
+                agent_type: AgentType::new("local"),
+            };
+            let session = db.create_session(CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![task_input("First"), task_input("Second")],
+            }).expect("Failed to create");
+
+            let start = UpdateTaskInput {
+                status: Some(TaskStatus::Running),
+                worktree_path: None,
+                branch: None,
+                result: None,
+            };
+            db.update_task(session.tasks[0].id, start.clone()).expect("First task should start");
+            let result = db.update_task(session.tasks[1].id, start);
+
+            let err = result.expect_err("Second task should be refused");
+            assert!(err.to_string().contains("max_concurrency is 1"));
+        }
+
+        it "lets only max_concurrency of several concurrent starts through" {
+            let project = create_test_project(&db);
+            db.create_agent_type(project.id, CreateAgentTypeInput {
+                name: AgentType::new("local"),
+                label: "Local model".to_string(),
+                instructions: None,
+                max_concurrency: Some(1),
+            }).expect("Failed to register");
+            let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create");
+            let session = db.create_session(CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: (0..4).map(|i| CreateTaskInput {
+                    parent_id: None,
+                    title: format!("Task {}", i),
+                    scope: "Scope".to_string(),
+                    agent_type: AgentType::new("local"),
+                }).collect(),
+            }).expect("Failed to create");
+
+            let started = std::thread::scope(|scope| {
+                let starts: Vec<_> = session.tasks.iter().map(|task| {
+                    let db = db.clone();
+                    scope.spawn(move || db.update_task(task.id, UpdateTaskInput {
+                        status: Some(TaskStatus::Running),
+                        worktree_path: None,
+                        branch: None,
+                        result: None,
+                    }))
+                }).collect();
+                starts.into_iter().filter_map(|s| s.join().unwrap().ok()).count()
+            });
+
+            assert_eq!(started, 1);
+        }
+    }
+
     describe "features" {
         describe "create_feature" {
             it "creates a feature with required fields" {
@@ -166,6 +379,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 };
 
                 let feature = db.create_feature(project.id, input).expect("Failed to create feature");
@@ -183,6 +397,7 @@ speculate! {
                     details: Some("As a user, I want to log in with OAuth.\n\n## Technical Notes\n\nUse PKCE flow".to_string()),
                     state: Some(FeatureState::Specified),
                     priority: None,
+                    tags: vec![],
                 };
 
                 let feature = db.create_feature(project.id, input).expect("Failed to create feature");
@@ -209,6 +424,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 };
                 let created = db.create_feature(project.id, input).expect("Failed to create");
 
@@ -235,6 +451,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -244,6 +461,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let features = db.get_all_features().expect("Query failed");
@@ -263,6 +481,7 @@ speculate! {
                     desired_details: None,
                     priority: None,
                     state: None,
+                    tags: None,
                 };
 
                 let result = db.update_feature(Uuid::new_v4(), input).expect("Query failed");
@@ -278,6 +497,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: Some(FeatureState::Proposed),
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let updated = db.update_feature(created.id, UpdateFeatureInput {
@@ -287,6 +507,7 @@ speculate! {
                     desired_details: None,
                     priority: None,
                     state: None,
+                    tags: None,
                 }).expect("Query failed").expect("Feature not found");
 
                 assert_eq!(updated.title, "Updated Title");
@@ -302,6 +523,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: Some(FeatureState::Proposed),
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let updated = db.update_feature(created.id, UpdateFeatureInput {
@@ -311,6 +533,7 @@ speculate! {
                     desired_details: None,
                     priority: None,
                     state: Some(FeatureState::Implemented),
+                    tags: None,
                 }).expect("Query failed").expect("Feature not found");
 
                 assert_eq!(updated.state, FeatureState::Implemented);
@@ -332,6 +555,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let deleted = db.delete_feature(created.id).expect("Query failed");
@@ -351,6 +575,7 @@ speculate! {
                     details: Some("Current details".to_string()),
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let diff = db.get_feature_diff(feature.id).expect("Query failed").unwrap();
@@ -367,6 +592,7 @@ speculate! {
                     details: Some("Current".to_string()),
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.update_feature(feature.id, UpdateFeatureInput {
@@ -376,6 +602,7 @@ speculate! {
                     desired_details: Some("Desired".to_string()),
                     priority: None,
                     state: None,
+                    tags: None,
                 }).expect("Failed to update");
 
                 let diff = db.get_feature_diff(feature.id).expect("Query failed").unwrap();
@@ -399,6 +626,7 @@ speculate! {
                     details: Some("Current".to_string()),
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let updated = db.update_feature(feature.id, UpdateFeatureInput {
@@ -408,6 +636,7 @@ speculate! {
                     desired_details: Some("Desired".to_string()),
                     priority: None,
                     state: None,
+                    tags: None,
                 }).expect("Failed to update").unwrap();
 
                 assert_eq!(updated.details, Some("Current".to_string()));
@@ -425,9 +654,10 @@ speculate! {
                     details: None,
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
-                let results = db.search_features("nonexistent", None, None).expect("Query failed");
+                let results = db.search_features("nonexistent", None, &[], &PageQuery::default()).expect("Query failed").items;
                 assert!(results.is_empty());
             }
 
@@ -439,13 +669,14 @@ speculate! {
                     details: None,
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
-                let results = db.search_features("user", None, None).expect("Query failed");
+                let results = db.search_features("user", None, &[], &PageQuery::default()).expect("Query failed").items;
                 assert_eq!(results.len(), 1);
                 assert_eq!(results[0].title, "User Authentication");
 
-                let results = db.search_features("USER", None, None).expect("Query failed");
+                let results = db.search_features("USER", None, &[], &PageQuery::default()).expect("Query failed").items;
                 assert_eq!(results.len(), 1);
             }
 
@@ -457,9 +688,10 @@ speculate! {
                     details: Some("Implement Google OAuth using PKCE flow".to_string()),
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
-                let results = db.search_features("PKCE", None, None).expect("Query failed");
+                let results = db.search_features("PKCE", None, &[], &PageQuery::default()).expect("Query failed").items;
                 assert_eq!(results.len(), 1);
                 assert_eq!(results[0].title, "OAuth Integration");
             }
@@ -472,6 +704,7 @@ speculate! {
                     details: Some("Some login details".to_string()),
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -480,9 +713,10 @@ speculate! {
                     details: Some("User must click login button".to_string()),
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
-                let results = db.search_features("login", None, None).expect("Query failed");
+                let results = db.search_features("login", None, &[], &PageQuery::default()).expect("Query failed").items;
                 assert_eq!(results.len(), 2);
                 // "User Login" should be first (title match)
                 assert_eq!(results[0].title, "User Login");
@@ -493,12 +727,14 @@ speculate! {
             it "filters by project_id" {
                 let project1 = db.create_project(CreateProjectInput {
                     name: "Project 1".to_string(),
+                    key: None,
                     description: None,
                     instructions: None,
                 }).expect("Failed to create project");
 
                 let project2 = db.create_project(CreateProjectInput {
                     name: "Project 2".to_string(),
+                    key: None,
                     description: None,
                     instructions: None,
                 }).expect("Failed to create project");
@@ -509,6 +745,7 @@ speculate! {
                     details: None,
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project2.id, CreateFeatureInput { id: None,
@@ -517,9 +754,10 @@ speculate! {
                     details: None,
                     priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
-                let results = db.search_features("Auth", Some(project1.id), None).expect("Query failed");
+                let results = db.search_features("Auth", Some(project1.id), &[], &PageQuery::default()).expect("Query failed").items;
                 assert_eq!(results.len(), 1);
                 assert_eq!(results[0].title, "Auth Feature");
             }
@@ -533,10 +771,11 @@ speculate! {
                         details: None,
                         priority: None,
                         state: None,
+                        tags: vec![],
                     }).expect("Failed to create");
                 }
 
-                let results = db.search_features("Feature", None, Some(2)).expect("Query failed");
+                let results = db.search_features("Feature", None, &[], &PageQuery { cursor: None, limit: Some(2) }).expect("Query failed").items;
                 assert_eq!(results.len(), 2);
             }
 
@@ -549,10 +788,11 @@ speculate! {
                         details: None,
                         priority: None,
                         state: None,
+                        tags: vec![],
                     }).expect("Failed to create");
                 }
 
-                let results = db.search_features("Feature", None, None).expect("Query failed");
+                let results = db.search_features("Feature", None, &[], &PageQuery::default()).expect("Query failed").items;
                 assert_eq!(results.len(), 10);
             }
 
@@ -564,9 +804,10 @@ speculate! {
                     details: Some("Detailed description".to_string()),
                     priority: Some(5),
                     state: Some(FeatureState::Specified),
+                    tags: vec![],
                 }).expect("Failed to create");
 
-                let results = db.search_features("Test", None, None).expect("Query failed");
+                let results = db.search_features("Test", None, &[], &PageQuery::default()).expect("Query failed").items;
                 assert_eq!(results.len(), 1);
 
                 let summary = &results[0];
@@ -579,6 +820,952 @@ speculate! {
         }
     }
 
+    describe "code_links" {
+        before {
+            let project = create_test_project(&db);
+            db.add_project_directory(project.id, AddDirectoryInput {
+                path: "/src/app".to_string(),
+                git_remote: None,
+                is_primary: true,
+                instructions: None,
+            }).expect("Failed to add directory");
+            let create_feature = |title: &str| db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create feature");
+            let link = |path: &str| CreateCodeLinkInput {
+                directory_id: None,
+                path: path.to_string(),
+                line_start: None,
+                line_end: None,
+                symbol: None,
+            };
+        }
+
+        it "links to the primary directory and normalizes the path" {
+            let feature = create_feature("Routing");
+
+            let created = db.create_code_link(feature.id, CreateCodeLinkInput {
+                line_start: Some(10),
+                line_end: Some(42),
+                symbol: Some("create_router".to_string()),
+                ..link("./src/api/")
+            }).expect("Failed to link");
+
+            let primary = &db.get_project_directories(project.id).expect("Query failed")[0];
+            assert_eq!(created.directory_id, primary.id);
+            assert_eq!(created.path, "src/api");
+            let links = db.get_code_links(feature.id).expect("Query failed");
+            assert_eq!(links.len(), 1);
+            assert_eq!(links[0].symbol.as_deref(), Some("create_router"));
+            assert_eq!(links[0].line_end, Some(42));
+        }
+
+        it "rejects absolute paths and inverted line ranges" {
+            let feature = create_feature("Routing");
+
+            let absolute = db.create_code_link(feature.id, link("/etc/passwd"));
+            let inverted = db.create_code_link(feature.id, CreateCodeLinkInput {
+                line_start: Some(20),
+                line_end: Some(10),
+                ..link("src/lib.rs")
+            });
+
+            let err = absolute.expect_err("Should reject absolute path");
+            assert!(err.to_string().contains("must be relative"));
+            let err = inverted.expect_err("Should reject inverted range");
+            assert!(err.to_string().contains("line_end must not be before line_start"));
+        }
+
+        it "finds features by file path through globs and directories" {
+            let routing = create_feature("Routing");
+            let auth = create_feature("Auth");
+            let docs = create_feature("Docs");
+            db.create_code_link(routing.id, link("src/api/**/*.rs")).expect("Failed to link");
+            db.create_code_link(auth.id, link("src/api/handlers")).expect("Failed to link");
+            db.create_code_link(docs.id, link("docs/*.md")).expect("Failed to link");
+
+            let matches = db.find_features_by_path(project.id, "src/api/handlers/mod.rs").expect("Query failed");
+            let mut titles: Vec<&str> = matches.iter().map(|m| m.feature.title.as_str()).collect();
+            titles.sort();
+            assert_eq!(titles, vec!["Auth", "Routing"]);
+
+            // Absolute paths resolve against the directory that contains them
+            let matches = db.find_features_by_path(project.id, "/src/app/docs/intro.md").expect("Query failed");
+            assert_eq!(matches.len(), 1);
+            assert_eq!(matches[0].feature.id, docs.id);
+
+            // `*` does not cross directories
+            let matches = db.find_features_by_path(project.id, "docs/guide/intro.md").expect("Query failed");
+            assert!(matches.is_empty());
+        }
+
+        it "matches many-star globs quickly and takes brackets literally" {
+            let feature = create_feature("Routes");
+            db.create_code_link(feature.id, link(&format!("{}b", "*a".repeat(30)))).expect("Failed to link");
+            db.create_code_link(feature.id, link("app/[id]/*.ts")).expect("Failed to link");
+
+            let slow = db.find_features_by_path(project.id, &"a".repeat(60)).expect("Query failed");
+            let bracket = db.find_features_by_path(project.id, "app/[id]/page.ts").expect("Query failed");
+            let class = db.find_features_by_path(project.id, "app/i/page.ts").expect("Query failed");
+
+            assert!(slow.is_empty());
+            assert_eq!(bracket.len(), 1);
+            assert!(class.is_empty());
+        }
+
+        it "removes links when the feature is deleted" {
+            let feature = create_feature("Routing");
+            let created = db.create_code_link(feature.id, link("src/api")).expect("Failed to link");
+
+            db.delete_feature(feature.id).expect("Failed to delete");
+
+            assert!(db.get_code_link(created.id).expect("Query failed").is_none());
+        }
+    }
+
+    describe "commit_trailers" {
+        before {
+            let project = create_test_project(&db);
+            let create_feature = |title: &str| db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create feature");
+        }
+
+        it "resolves features by id or title slug" {
+            let feature = create_feature("OAuth Login");
+            let other = create_test_project(&db);
+
+            let by_id = db.resolve_feature(project.id, &feature.id.to_string()).expect("Query failed");
+            let by_slug = db.resolve_feature(project.id, "oauth-login").expect("Query failed");
+            let elsewhere = db.resolve_feature(other.id, &feature.id.to_string()).expect("Query failed");
+
+            assert_eq!(by_id.map(|f| f.id), Some(feature.id));
+            assert_eq!(by_slug.map(|f| f.id), Some(feature.id));
+            assert!(elsewhere.is_none());
+        }
+
+        it "rejects slugs shared by features under different parents" {
+            for parent in ["Docs", "Billing"] {
+                let parent = create_feature(parent);
+                db.create_feature(project.id, CreateFeatureInput { id: None,
+                    parent_id: Some(parent.id),
+                    title: "Search".to_string(),
+                    details: None,
+                    priority: None,
+                    state: None,
+                    tags: vec![],
+                }).expect("Failed to create feature");
+            }
+
+            let err = db.resolve_feature(project.id, "search").expect_err("Should be ambiguous");
+            let by_path = db.resolve_feature(project.id, "docs/search").expect("Query failed");
+
+            assert!(err.to_string().contains("ambiguous"));
+            assert_eq!(by_path.map(|f| f.path), Some("docs/search".to_string()));
+        }
+
+        it "records commits without an active session as history entries" {
+            let feature = create_feature("Search");
+
+            let recorded = db.record_commit(feature.id, commit("abc123", "Add search index")).expect("Failed to record");
+            let again = db.record_commit(feature.id, commit("abc123def", "Add search index")).expect("Failed to record");
+
+            assert!(!recorded.already_recorded);
+            assert!(again.already_recorded);
+            let history = db.get_feature_history(feature.id).expect("Query failed");
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].details.summary, "Add search index");
+            assert_eq!(history[0].details.commits[0].sha, "abc123");
+        }
+
+        it "attaches commits to the active session until it completes" {
+            let feature = create_feature("Search");
+            let session = db.create_session(CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Build search".to_string(),
+                kind: None,
+                tasks: vec![],
+            }).expect("Failed to create session");
+
+            let recorded = db.record_commit(feature.id, commit("abc123", "Add search index")).expect("Failed to record");
+
+            assert_eq!(recorded.session_id, Some(session.session.id));
+            assert!(db.get_feature_history(feature.id).expect("Query failed").is_empty());
+
+            let result = db.complete_session(session.session.id, CompleteSessionInput {
+                summary: "Search done".to_string(),
+                commits: vec![],
+                feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
+            }).expect("Failed to complete").expect("Session not found");
+
+            assert_eq!(result.history_entry.details.commits.len(), 1);
+            assert_eq!(result.history_entry.details.commits[0].sha, "abc123");
+        }
+    }
+
+    describe "feature_paths" {
+        before {
+            let project = create_test_project(&db);
+            let create_feature = |parent_id: Option<Uuid>, title: &str| db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create feature");
+            let resolve = |reference: &str| db.resolve_feature_ref(&FeatureRef::from(reference)).expect("Query failed");
+        }
+
+        it "derives paths from titles and keeps sibling slugs unique" {
+            let auth = create_feature(None, "Auth");
+            let oauth = create_feature(Some(auth.id), "OAuth Integration!");
+            let again = create_feature(Some(auth.id), "OAuth integration");
+
+            assert_eq!(auth.path, "auth");
+            assert_eq!(oauth.slug, "oauth-integration");
+            assert_eq!(oauth.path, "auth/oauth-integration");
+            assert_eq!(again.path, "auth/oauth-integration-2");
+            assert_eq!(resolve("auth/oauth-integration"), Some(oauth.id));
+            assert_eq!(resolve("/Auth/OAuth-Integration-2/"), Some(again.id));
+            assert_eq!(resolve("auth/missing"), None);
+        }
+
+        it "redirects old paths after a rename" {
+            let auth = create_feature(None, "Auth");
+            let oauth = create_feature(Some(auth.id), "OAuth");
+
+            let renamed = db.update_feature(auth.id, UpdateFeatureInput {
+                parent_id: None,
+                title: Some("Identity".to_string()),
+                details: None,
+                desired_details: None,
+                state: None,
+                priority: None,
+                tags: None,
+            }).expect("Failed to update").unwrap();
+
+            assert_eq!(renamed.path, "identity");
+            let child = db.get_feature(oauth.id).expect("Query failed").unwrap();
+            assert_eq!(child.path, "identity/oauth");
+            assert_eq!(resolve("auth"), Some(auth.id));
+            assert_eq!(resolve("auth/oauth"), Some(oauth.id));
+        }
+
+        it "redirects old paths after a move and frees them for reuse" {
+            let auth = create_feature(None, "Auth");
+            let billing = create_feature(None, "Billing");
+            let tokens = create_feature(Some(auth.id), "Tokens");
+
+            let moved = db.update_feature(tokens.id, UpdateFeatureInput {
+                parent_id: Some(billing.id),
+                title: None,
+                details: None,
+                desired_details: None,
+                state: None,
+                priority: None,
+                tags: None,
+            }).expect("Failed to update").unwrap();
+            assert_eq!(moved.path, "billing/tokens");
+            assert_eq!(resolve("auth/tokens"), Some(tokens.id));
+
+            let replacement = create_feature(Some(auth.id), "Tokens");
+            assert_eq!(replacement.path, "auth/tokens");
+            assert_eq!(resolve("auth/tokens"), Some(replacement.id));
+        }
+
+        it "asks for a project prefix when a path exists in several projects" {
+            let auth = create_feature(None, "Auth");
+            let other = create_test_project(&db);
+            db.create_feature(other.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Auth".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create feature");
+
+            let err = db.resolve_feature_ref(&FeatureRef::from("auth")).expect_err("Should be ambiguous");
+            let qualified = resolve(&format!("{}/auth", project.id));
+
+            assert!(err.to_string().contains("several projects"));
+            assert_eq!(qualified, Some(auth.id));
+        }
+    }
+
+    describe "feature_keys" {
+        before {
+            let create_project = |name: &str, key: Option<&str>| db.create_project(CreateProjectInput {
+                name: name.to_string(),
+                key: key.map(str::to_string),
+                description: None,
+                instructions: None,
+            });
+        }
+
+        it "derives unique project keys from names" {
+            let manifest = create_project("Manifest", None).expect("Failed to create project");
+            let rocket = create_project("Rocket Ship", None).expect("Failed to create project");
+            let again = create_project("manifest", None).expect("Failed to create project");
+            let web = create_project("Web", Some("web2")).expect("Failed to create project");
+
+            assert_eq!(manifest.key, "MAN");
+            assert_eq!(rocket.key, "RS");
+            assert_eq!(again.key, "MAN2");
+            assert_eq!(web.key, "WEB2");
+            assert_eq!(db.get_project(web.id).expect("Query failed").unwrap().key, "WEB2");
+
+            let taken = create_project("Other", Some("MAN")).unwrap_err();
+            assert!(taken.to_string().contains("already in use"));
+            assert!(create_project("Other", Some("2X")).is_err());
+        }
+
+        it "numbers features in creation order and resolves their keys" {
+            let project = create_project("Manifest", None).expect("Failed to create project");
+            let input = |title: &str| CreateFeatureInput { id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            };
+
+            let first = db.create_feature(project.id, input("Auth")).expect("Failed to create feature");
+            let bulk = db.create_features_bulk(project.id, vec![input("Billing"), input("Search")])
+                .expect("Failed to create features");
+
+            assert_eq!(first.key, "MAN-1");
+            assert_eq!(bulk[0].key, "MAN-2");
+            assert_eq!(bulk[1].key, "MAN-3");
+            assert_eq!(db.get_feature(bulk[1].id).expect("Query failed").unwrap().key, "MAN-3");
+            assert_eq!(
+                db.resolve_feature_ref(&FeatureRef::from("MAN-2")).expect("Query failed"),
+                Some(bulk[0].id)
+            );
+            assert_eq!(db.resolve_feature_ref(&FeatureRef::from("MAN-9")).expect("Query failed"), None);
+            assert_eq!(
+                db.resolve_feature(project.id, "MAN-1").expect("Query failed").map(|f| f.id),
+                Some(first.id)
+            );
+
+            let found = db.search_features("man-3", Some(project.id), &[], &PageQuery::default()).expect("Search failed").items;
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].id, bulk[1].id);
+        }
+
+        it "does not reuse numbers of deleted features" {
+            let project = create_project("Manifest", None).expect("Failed to create project");
+            let input = |title: &str| CreateFeatureInput { id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            };
+
+            let first = db.create_feature(project.id, input("Auth")).expect("Failed to create feature");
+            db.delete_feature(first.id).expect("Failed to delete");
+            let second = db.create_feature(project.id, input("Auth")).expect("Failed to create feature");
+
+            assert_eq!(second.key, "MAN-2");
+        }
+    }
+
+    describe "tags" {
+        before {
+            let project = create_test_project(&db);
+            let create_feature = |title: &str, tags: &[&str]| db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+            }).expect("Failed to create feature");
+        }
+
+        it "creates missing tags and matches names ignoring case" {
+            let login = create_feature("Login", &["v2", "security"]);
+            let audit = create_feature("Audit", &["Security"]);
+
+            assert_eq!(login.tags, vec!["security", "v2"]);
+            assert_eq!(audit.tags, vec!["security"]);
+            let names: Vec<String> = db.get_tags(project.id).expect("Query failed").into_iter().map(|t| t.name).collect();
+            assert_eq!(names, vec!["security", "v2"]);
+            assert_eq!(db.get_feature(login.id).expect("Query failed").unwrap().tags, vec!["security", "v2"]);
+        }
+
+        it "filters features by all given tags" {
+            let login = create_feature("Login", &["security", "v2"]);
+            create_feature("Audit log", &["security"]);
+            create_feature("Dashboard", &["frontend"]);
+            let tag_query = |tag: &str| ListFeaturesQuery {
+                tag: Some(tag.to_string()),
+                ..Default::default()
+            };
+
+            let secure = db.list_features(Some(project.id), &tag_query("security")).expect("Query failed").items;
+            let both = db.list_features(Some(project.id), &tag_query("SECURITY, v2")).expect("Query failed").items;
+            let none = db.list_features(Some(project.id), &tag_query("missing")).expect("Query failed").items;
+
+            assert_eq!(secure.len(), 2);
+            assert_eq!(both.len(), 1);
+            assert_eq!(both[0].id, login.id);
+            assert!(none.is_empty());
+
+            let found = db.search_features("log", Some(project.id), &["v2".to_string()], &PageQuery::default()).expect("Search failed").items;
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].tags, vec!["security", "v2"]);
+        }
+
+        it "replaces tags on update and keeps them when omitted" {
+            let login = create_feature("Login", &["security"]);
+            let update = |tags: Option<Vec<String>>| db.update_feature(login.id, UpdateFeatureInput {
+                parent_id: None,
+                title: None,
+                details: Some("Updated".to_string()),
+                desired_details: None,
+                state: None,
+                priority: None,
+                tags,
+            }).expect("Failed to update").unwrap();
+
+            assert_eq!(update(None).tags, vec!["security"]);
+            assert_eq!(update(Some(vec!["v2".to_string()])).tags, vec!["v2"]);
+            assert!(update(Some(vec![])).tags.is_empty());
+        }
+
+        it "renames and deletes tags across features" {
+            let login = create_feature("Login", &["security"]);
+            let tag = db.get_tags(project.id).expect("Query failed").pop().unwrap();
+
+            let renamed = db.update_tag(tag.id, UpdateTagInput {
+                name: Some("auth".to_string()),
+                color: Some("#ff8800".to_string()),
+            }).expect("Failed to update").unwrap();
+            assert_eq!(renamed.color.as_deref(), Some("#ff8800"));
+            assert_eq!(db.get_feature(login.id).expect("Query failed").unwrap().tags, vec!["auth"]);
+
+            assert!(db.delete_tag(tag.id).expect("Failed to delete"));
+            assert!(db.get_feature(login.id).expect("Query failed").unwrap().tags.is_empty());
+        }
+
+        it "rejects duplicate names, bad colors and commas" {
+            create_feature("Login", &["security"]);
+            let input = |name: &str, color: Option<&str>| CreateTagInput {
+                name: name.to_string(),
+                color: color.map(str::to_string),
+            };
+
+            assert!(db.create_tag(project.id, input("Security", None)).is_err());
+            assert!(db.create_tag(project.id, input("frontend", Some("red"))).is_err());
+            assert!(db.create_tag(project.id, input("a,b", None)).is_err());
+            let frontend = db.create_tag(project.id, input(" frontend ", Some("#00AA00"))).expect("Failed to create tag");
+            assert_eq!(frontend.name, "frontend");
+        }
+    }
+
+    describe "feature_filters" {
+        before {
+            let project = create_test_project(&db);
+            let create_feature = |title: &str, parent_id: Option<Uuid>, state: FeatureState| db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: Some(state),
+                tags: vec![],
+            }).expect("Failed to create feature");
+        }
+
+        it "filters by any of several states and rejects unknown ones" {
+            create_feature("Login", None, FeatureState::Specified);
+            create_feature("Audit", None, FeatureState::Implemented);
+            create_feature("Legacy", None, FeatureState::Deprecated);
+            let query = ListFeaturesQuery {
+                state: Some("specified,implemented".to_string()),
+                ..Default::default()
+            };
+
+            let titles: Vec<String> = db.list_features(Some(project.id), &query).expect("Query failed").items.into_iter().map(|f| f.title).collect();
+            assert_eq!(titles, vec!["Audit", "Login"]);
+
+            let unknown = ListFeaturesQuery { state: Some("done".to_string()), ..Default::default() };
+            assert!(db.list_features(Some(project.id), &unknown).is_err());
+            let bad_sort = ListFeaturesQuery { sort: Some("size".to_string()), ..Default::default() };
+            assert!(db.list_features(Some(project.id), &bad_sort).is_err());
+        }
+
+        it "filters by parent, depth and leaves" {
+            let auth = create_feature("Auth", None, FeatureState::Proposed);
+            let oauth = create_feature("OAuth", Some(auth.id), FeatureState::Proposed);
+            create_feature("Google", Some(oauth.id), FeatureState::Proposed);
+            create_feature("Password", Some(auth.id), FeatureState::Proposed);
+            let titles = |query: ListFeaturesQuery| -> Vec<String> {
+                db.list_features(Some(project.id), &query).expect("Query failed").items.into_iter().map(|f| f.title).collect()
+            };
+
+            assert_eq!(titles(ListFeaturesQuery { parent_id: Some(auth.id), ..Default::default() }), vec!["OAuth", "Password"]);
+            assert_eq!(titles(ListFeaturesQuery { depth: Some(0), ..Default::default() }), vec!["Auth"]);
+            assert_eq!(titles(ListFeaturesQuery { depth: Some(1), ..Default::default() }), vec!["Auth", "OAuth", "Password"]);
+            assert_eq!(titles(ListFeaturesQuery { leaf: Some(true), ..Default::default() }), vec!["Google", "Password"]);
+            assert_eq!(titles(ListFeaturesQuery { leaf: Some(false), ..Default::default() }), vec!["Auth", "OAuth"]);
+        }
+
+        it "filters by pending changes and active sessions" {
+            let login = create_feature("Login", None, FeatureState::Specified);
+            let audit = create_feature("Audit", None, FeatureState::Specified);
+            db.update_feature(login.id, UpdateFeatureInput {
+                parent_id: None,
+                title: None,
+                details: None,
+                desired_details: Some("Add SSO".to_string()),
+                state: None,
+                priority: None,
+                tags: None,
+            }).expect("Failed to update");
+            db.create_session(CreateSessionInput {
+                feature_id: audit.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![],
+            }).expect("Failed to create session");
+
+            let pending = db.list_features(Some(project.id), &ListFeaturesQuery { has_pending_changes: Some(true), ..Default::default() }).expect("Query failed").items;
+            let active = db.list_features(Some(project.id), &ListFeaturesQuery { has_active_session: Some(true), ..Default::default() }).expect("Query failed").items;
+            let idle = db.list_features(Some(project.id), &ListFeaturesQuery { has_active_session: Some(false), ..Default::default() }).expect("Query failed").items;
+
+            assert_eq!(pending.len(), 1);
+            assert_eq!(pending[0].id, login.id);
+            assert_eq!(active.len(), 1);
+            assert_eq!(active[0].id, audit.id);
+            assert_eq!(idle.len(), 1);
+            assert_eq!(idle[0].id, login.id);
+        }
+
+        it "sorts and filters by update time" {
+            create_feature("Audit", None, FeatureState::Proposed);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            let login = create_feature("Login", None, FeatureState::Proposed);
+            let titles = |query: ListFeaturesQuery| -> Vec<String> {
+                db.list_features(Some(project.id), &query).expect("Query failed").items.into_iter().map(|f| f.title).collect()
+            };
+
+            assert_eq!(titles(ListFeaturesQuery { sort: Some("-title".to_string()), ..Default::default() }), vec!["Login", "Audit"]);
+            assert_eq!(titles(ListFeaturesQuery { sort: Some("-updated_at".to_string()), ..Default::default() }), vec!["Login", "Audit"]);
+            assert_eq!(titles(ListFeaturesQuery { updated_since: Some(login.updated_at), ..Default::default() }), vec!["Login"]);
+        }
+    }
+
+    describe "pagination" {
+        before {
+            let project = create_test_project(&db);
+            let create_feature = |title: &str, priority: i32| db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: Some(priority),
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create feature");
+        }
+
+        it "pages features with cursors that survive inserts" {
+            for (i, title) in ["A", "B", "C", "D", "E"].iter().enumerate() {
+                create_feature(title, i as i32);
+            }
+            let page = |cursor: Option<String>| db.list_features(Some(project.id), &ListFeaturesQuery {
+                limit: Some(2),
+                cursor,
+                ..Default::default()
+            }).expect("Query failed");
+
+            let first = page(None);
+            assert_eq!(first.total, Some(5));
+            // A feature sorting before the cursor doesn't shift the next page
+            create_feature("Before", -1);
+            let second = page(first.next_cursor.clone());
+            let third = page(second.next_cursor.clone());
+
+            let titles: Vec<String> = [first.items, second.items, third.items].concat().into_iter().map(|f| f.title).collect();
+            assert_eq!(titles, vec!["A", "B", "C", "D", "E"]);
+            assert_eq!(second.total, None);
+            assert!(third.next_cursor.is_none());
+        }
+
+        it "rejects garbled cursors and cursors from another sort" {
+            create_feature("A", 0);
+            create_feature("B", 1);
+            let first = db.list_features(Some(project.id), &ListFeaturesQuery { limit: Some(1), ..Default::default() }).expect("Query failed");
+            let cursor = first.next_cursor.expect("Missing cursor");
+
+            let other_sort = ListFeaturesQuery { cursor: Some(cursor), sort: Some("title".to_string()), ..Default::default() };
+            let garbled = ListFeaturesQuery { cursor: Some("zz".to_string()), ..Default::default() };
+            assert!(db.list_features(Some(project.id), &other_sort).is_err());
+            assert!(db.list_features(Some(project.id), &garbled).is_err());
+        }
+
+        it "pages search results and sessions" {
+            let login = create_feature("Login", 0);
+            create_feature("Login audit", 1);
+            let first = db.search_features("login", None, &[], &PageQuery { cursor: None, limit: Some(1) }).expect("Search failed");
+            let second = db.search_features("login", None, &[], &PageQuery { cursor: first.next_cursor.clone(), limit: Some(1) }).expect("Search failed");
+
+            assert_eq!(first.items[0].title, "Login");
+            assert_eq!(second.items[0].title, "Login audit");
+            assert!(second.next_cursor.is_none());
+
+            let session = db.create_session(CreateSessionInput {
+                feature_id: login.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![],
+            }).expect("Failed to create session");
+            let sessions = db.list_sessions_by_feature(login.id, &PageQuery { cursor: None, limit: Some(1) }).expect("Query failed");
+            assert_eq!(sessions.total, Some(1));
+            assert_eq!(sessions.items[0].id, session.session.id);
+            assert!(sessions.next_cursor.is_none());
+        }
+    }
+
+    describe "feature_relations" {
+        before {
+            let project = create_test_project(&db);
+            let create_feature = |title: &str, state: FeatureState| db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: title.to_string(),
+                details: None,
+                priority: None,
+                state: Some(state),
+                tags: vec![],
+            }).expect("Failed to create feature");
+            let relate = |from: &Feature, to: &Feature, kind: RelationKind| db.create_relation(from.id, CreateRelationInput {
+                to_feature_id: to.id,
+                kind,
+            });
+        }
+
+        it "rejects dependency cycles, self relations and duplicates" {
+            let oauth = create_feature("OAuth", FeatureState::Specified);
+            let sessions = create_feature("Sessions", FeatureState::Specified);
+            let storage = create_feature("Storage", FeatureState::Specified);
+            relate(&oauth, &sessions, RelationKind::DependsOn).expect("Failed to relate");
+            relate(&sessions, &storage, RelationKind::DependsOn).expect("Failed to relate");
+
+            assert!(relate(&storage, &oauth, RelationKind::DependsOn).is_err());
+            // Storage needing OAuth first, spelled the other way round
+            assert!(relate(&oauth, &storage, RelationKind::Blocks).is_err());
+            assert!(relate(&oauth, &oauth, RelationKind::RelatesTo).is_err());
+            assert!(relate(&oauth, &sessions, RelationKind::DependsOn).is_err());
+            relate(&storage, &oauth, RelationKind::RelatesTo).expect("Non-dependencies may loop");
+            assert_eq!(db.get_relations(oauth.id).expect("Query failed").len(), 2);
+        }
+
+        it "follows dependencies transitively both ways" {
+            let oauth = create_feature("OAuth", FeatureState::Specified);
+            let sessions = create_feature("Sessions", FeatureState::Specified);
+            let storage = create_feature("Storage", FeatureState::Specified);
+            relate(&oauth, &sessions, RelationKind::DependsOn).expect("Failed to relate");
+            relate(&storage, &sessions, RelationKind::Blocks).expect("Failed to relate");
+            let titles = |features: Vec<Feature>| -> Vec<String> { features.into_iter().map(|f| f.title).collect() };
+
+            let upstream = db.get_dependencies(oauth.id, DependencyDirection::Upstream).expect("Query failed");
+            let downstream = db.get_dependencies(storage.id, DependencyDirection::Downstream).expect("Query failed");
+
+            assert_eq!(titles(upstream), vec!["Sessions", "Storage"]);
+            assert_eq!(titles(downstream), vec!["OAuth", "Sessions"]);
+        }
+
+        it "lists specified leaves whose dependencies are implemented as next" {
+            let oauth = create_feature("OAuth", FeatureState::Specified);
+            let sessions = create_feature("Sessions", FeatureState::Specified);
+            let storage = create_feature("Storage", FeatureState::Implemented);
+            create_feature("Idea", FeatureState::Proposed);
+            relate(&oauth, &sessions, RelationKind::DependsOn).expect("Failed to relate");
+            relate(&sessions, &storage, RelationKind::DependsOn).expect("Failed to relate");
+
+            let next: Vec<String> = db.get_next_features(project.id).expect("Query failed").into_iter().map(|f| f.title).collect();
+
+            assert_eq!(next, vec!["Sessions"]);
+        }
+    }
+
+    describe "acceptance_criteria" {
+        before {
+            let project = create_test_project(&db);
+            let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Login".to_string(),
+                details: None,
+                priority: None,
+                state: Some(FeatureState::Specified),
+                tags: vec![],
+            }).expect("Failed to create feature");
+            let add = |text: &str, position: Option<u32>| db.create_criterion(feature.id, CreateCriterionInput {
+                text: text.to_string(),
+                status: None,
+                test_name: None,
+                position,
+            }).expect("Failed to add criterion");
+            let texts = || -> Vec<String> {
+                db.get_criteria(feature.id).expect("Query failed").into_iter().map(|c| c.text).collect()
+            };
+        }
+
+        it "keeps criteria ordered when inserting, moving and deleting" {
+            let wrong = add("Rejects a wrong password", None);
+            add("Locks after five attempts", None);
+            let valid = add("Accepts a valid password", Some(0));
+            assert_eq!(texts(), vec!["Accepts a valid password", "Rejects a wrong password", "Locks after five attempts"]);
+
+            db.update_criterion(valid.id, UpdateCriterionInput { position: Some(9), ..Default::default() })
+                .expect("Failed to update").expect("Criterion not found");
+            assert_eq!(texts(), vec!["Rejects a wrong password", "Locks after five attempts", "Accepts a valid password"]);
+
+            db.delete_criterion(wrong.id).expect("Failed to delete");
+            let positions: Vec<u32> = db.get_criteria(feature.id).expect("Query failed").into_iter().map(|c| c.position).collect();
+            assert_eq!(positions, vec![0, 1]);
+            let blank = db.create_criterion(feature.id, CreateCriterionInput { text: "  ".to_string(), status: None, test_name: None, position: None });
+            assert!(blank.is_err());
+        }
+
+        it "refuses to mark the feature implemented until criteria pass" {
+            let criterion = add("Accepts a valid password", None);
+            assert_eq!(texts(), vec!["Accepts a valid password"]);
+            let session = db.create_session(CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Build login".to_string(),
+                kind: None,
+                tasks: vec![],
+            }).expect("Failed to create session");
+            let complete = |allow_unverified_criteria| db.complete_session(session.session.id, CompleteSessionInput {
+                summary: "Login done".to_string(),
+                commits: vec![],
+                feature_state: Some(FeatureState::Implemented),
+                require_feature_trailer: false,
+                allow_unverified_criteria,
+            });
+
+            assert!(complete(false).is_err());
+            let updated = db.update_criterion(criterion.id, UpdateCriterionInput {
+                status: Some(CriterionStatus::Passing),
+                test_name: Some("login::accepts_valid_password".to_string()),
+                ..Default::default()
+            }).expect("Failed to update").expect("Criterion not found");
+            assert_eq!(updated.text, "Accepts a valid password");
+            complete(false).expect("Failed to complete").expect("Session not found");
+            assert_eq!(db.get_feature(feature.id).expect("Query failed").expect("Feature not found").state, FeatureState::Implemented);
+        }
+    }
+
+    describe "test_results" {
+        before {
+            let project = create_test_project(&db);
+            let login = db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Password Login".to_string(),
+                details: None,
+                priority: None,
+                state: Some(FeatureState::Implemented),
+                tags: vec![],
+            }).expect("Failed to create feature");
+            let case = |name: &str, outcome: TestOutcome| TestCaseResult {
+                name: name.to_string(),
+                outcome,
+                message: None,
+            };
+        }
+
+        it "matches tests by criterion test name, key and slug" {
+            let criterion = db.create_criterion(login.id, CreateCriterionInput {
+                text: "Locks after five attempts".to_string(),
+                status: None,
+                test_name: Some("lockout::locks_after_five_attempts".to_string()),
+                position: None,
+            }).expect("Failed to add criterion");
+            let key_test = format!("{}_accepts_valid_password", login.key.to_lowercase().replace('-', "_"));
+
+            let result = db.record_test_results(project.id, vec![
+                case("auth::lockout::locks_after_five_attempts", TestOutcome::Failed),
+                case(&key_test, TestOutcome::Passed),
+                case("auth::password_login::rejects_wrong_password", TestOutcome::Passed),
+                case("billing::charges_card", TestOutcome::Passed),
+            ]).expect("Failed to record").expect("Project not found");
+
+            assert_eq!(result.received, 4);
+            assert_eq!(result.matched, 3);
+            assert_eq!(result.unmatched, vec!["billing::charges_card"]);
+            assert_eq!((result.features[0].passed, result.features[0].failed), (2, 1));
+            let criterion = db.get_criterion(criterion.id).expect("Query failed").expect("Criterion not found");
+            assert_eq!(criterion.status, CriterionStatus::Failing);
+        }
+
+        it "replaces a feature's results with the latest report" {
+            db.record_test_results(project.id, vec![
+                case("password_login::accepts", TestOutcome::Failed),
+                case("password_login::rejects", TestOutcome::Passed),
+            ]).expect("Failed to record");
+            db.record_test_results(project.id, vec![
+                case("password_login::accepts", TestOutcome::Passed),
+            ]).expect("Failed to record");
+
+            let results = db.get_test_results(login.id).expect("Query failed");
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].outcome, TestOutcome::Passed);
+            assert_eq!(db.get_test_summaries(project.id).expect("Query failed")[0].failed, 0);
+        }
+    }
+
+    describe "snapshots" {
+        before {
+            let project = create_test_project(&db);
+            let feature_input = |parent_id: Option<Uuid>, title: &str| CreateFeatureInput { id: None,
+                parent_id,
+                title: title.to_string(),
+                details: Some("Spec".to_string()),
+                priority: None,
+                state: None,
+                tags: vec!["auth".to_string()],
+            };
+            let auth = db.create_feature(project.id, feature_input(None, "Auth")).expect("Failed to create feature");
+            let login = db.create_feature(project.id, feature_input(Some(auth.id), "Login")).expect("Failed to create feature");
+            db.create_criterion(login.id, CreateCriterionInput {
+                text: "Accepts a valid password".to_string(),
+                status: Some(CriterionStatus::Passing),
+                test_name: None,
+                position: None,
+            }).expect("Failed to add criterion");
+            db.record_commit(login.id, commit("abc123", "Add login form")).expect("Failed to record");
+            let snapshot = db.export_snapshot(project.id).expect("Export failed").expect("Project not found");
+        }
+
+        it "restores a project into another database unchanged" {
+            let other = open_test_database();
+
+            let imported = other.import_snapshot(&snapshot, false).expect("Import failed");
+
+            assert_eq!(imported.id, project.id);
+            let restored = other.get_feature(login.id).expect("Query failed").expect("Feature not found");
+            assert_eq!(restored.key, login.key);
+            assert_eq!(restored.path, "auth/login");
+            assert_eq!(restored.tags, vec!["auth"]);
+            assert_eq!(other.get_feature_history(login.id).expect("Query failed").len(), 1);
+            let again = other.export_snapshot(project.id).expect("Export failed").expect("Project not found");
+            assert_eq!(again.tables, snapshot.tables);
+            assert_eq!(again.tables["acceptance_criteria"].len(), 1);
+        }
+
+        it "moves a project from SQLite to either backend" {
+            let sqlite = Database::open_memory().expect("Failed to create in-memory database");
+            sqlite.migrate().expect("Failed to run migrations");
+            let source = create_test_project(&sqlite);
+            let feature = sqlite.create_feature(source.id, feature_input(None, "Billing")).expect("Failed to create feature");
+            let exported = sqlite.export_snapshot(source.id).expect("Export failed").expect("Project not found");
+            let other = open_test_database();
+
+            other.import_snapshot(&exported, false).expect("Import failed");
+
+            let moved = other.export_snapshot(source.id).expect("Export failed").expect("Project not found");
+            assert_eq!(moved.schema_version, exported.schema_version);
+            assert_eq!(snapshot.schema_version, exported.schema_version);
+            let restored = other.get_feature(feature.id).expect("Query failed").expect("Feature not found");
+            assert_eq!(restored.key, feature.key);
+            assert_eq!(restored.tags, vec!["auth"]);
+            assert_eq!(restored.created_at.timestamp_micros(), feature.created_at.timestamp_micros());
+        }
+
+        it "overwrites an existing project only when replacing" {
+            db.create_feature(project.id, feature_input(None, "Billing")).expect("Failed to create feature");
+
+            let refused = db.import_snapshot(&snapshot, false).unwrap_err();
+            assert!(refused.to_string().contains("already exists"));
+            db.import_snapshot(&snapshot, true).expect("Import failed");
+
+            let titles: Vec<String> = db.get_features_by_project(project.id).expect("Query failed")
+                .into_iter().map(|f| f.title).collect();
+            assert_eq!(titles.len(), 2);
+            assert!(!titles.contains(&"Billing".to_string()));
+            assert_eq!(db.get_feature(auth.id).expect("Query failed").expect("Feature not found").title, "Auth");
+        }
+    }
+
+    describe "backups" {
+        before {
+            // Backups are SQLite files; PostgreSQL has pg_dump for that
+            let db = if db.backend() == Backend::Sqlite {
+                db
+            } else {
+                let db = Database::open_memory().expect("Failed to create in-memory database");
+                db.migrate().expect("Failed to run migrations");
+                db
+            };
+            let project = create_test_project(&db);
+            let dir = std::env::temp_dir().join(format!("manifest-backup-{}", Uuid::new_v4()));
+            let backup = dir.join("backup.db");
+        }
+
+        it "restores a backup over another database" {
+            db.backup_to(&backup).expect("Backup failed");
+            let other = Database::open(dir.join("other.db")).expect("Failed to open database");
+            other.migrate().expect("Failed to run migrations");
+            create_test_project(&other);
+
+            let version = other.restore_from(&backup).expect("Restore failed");
+
+            assert_eq!(version.len(), 3);
+            let projects = other.list_projects(&PageQuery::default()).expect("Query failed").items;
+            assert_eq!(projects.iter().map(|p| p.id).collect::<Vec<_>>(), vec![project.id]);
+            std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+        }
+
+        it "backs up a database larger than one copy step" {
+            let source = Database::open(dir.join("source.db")).expect("Failed to open database");
+            source.migrate().expect("Failed to run migrations");
+            for i in 0..100 {
+                source.create_project(CreateProjectInput {
+                    name: format!("Project {}", i),
+                    key: None,
+                    description: Some("x".repeat(20_000)),
+                    instructions: None,
+                }).expect("Failed to create project");
+            }
+
+            source.backup_to(&backup).expect("Backup failed");
+            db.restore_from(&backup).expect("Restore failed");
+
+            let projects = db.list_projects(&PageQuery::default()).expect("Query failed").items;
+            assert_eq!(projects.len(), 100);
+            assert!(projects.iter().all(|p| p.id != project.id));
+            std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+        }
+
+        it "refuses files that are not Manifest databases" {
+            std::fs::create_dir_all(&dir).expect("Failed to create directory");
+            std::fs::write(&backup, "not a database").expect("Failed to write file");
+
+            let err = db.restore_from(&backup).unwrap_err();
+
+            assert!(err.to_string().contains("not a Manifest database"));
+            assert!(db.get_project(project.id).expect("Query failed").is_some());
+            std::fs::remove_dir_all(&dir).expect("Failed to clean up");
+        }
+    }
+
     describe "feature_hierarchy" {
         describe "nested features" {
             it "creates a child feature under a parent" {
@@ -590,6 +1777,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create parent");
 
                 let child = db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -599,6 +1787,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create child");
 
                 assert_eq!(child.parent_id, Some(parent.id));
@@ -613,6 +1802,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let level1 = db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -622,6 +1812,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let level2 = db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -631,6 +1822,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 assert_eq!(level2.parent_id, Some(level1.id));
@@ -650,6 +1842,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let _root2 = db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -659,6 +1852,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -668,6 +1862,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let roots = db.get_root_features(project.id).expect("Query failed");
@@ -687,6 +1882,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let children = db.get_children(leaf.id).expect("Query failed");
@@ -702,6 +1898,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -711,6 +1908,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -720,6 +1918,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let children = db.get_children(parent.id).expect("Query failed");
@@ -738,6 +1937,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let child = db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -747,6 +1947,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -756,6 +1957,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let children = db.get_children(parent.id).expect("Query failed");
@@ -774,6 +1976,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 assert!(db.is_leaf(leaf.id).expect("Query failed"));
@@ -788,6 +1991,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -797,6 +2001,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 assert!(!db.is_leaf(parent.id).expect("Query failed"));
@@ -813,6 +2018,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let child = db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -822,6 +2028,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.delete_feature(parent.id).expect("Failed to delete");
@@ -843,11 +2050,13 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let result = db.create_session(CreateSessionInput {
                     feature_id: leaf.id,
                     goal: "Implement feature".to_string(),
+                    kind: None,
                     tasks: vec![],
                 });
 
@@ -863,6 +2072,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -872,11 +2082,13 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let result = db.create_session(CreateSessionInput {
                     feature_id: parent.id,
                     goal: "Implement feature".to_string(),
+                    kind: None,
                     tasks: vec![],
                 });
 
@@ -885,6 +2097,125 @@ speculate! {
             }
         }
 
+        describe "concurrent sessions" {
+            it "allows one active session per feature by default" {
+                let project = create_test_project(&db);
+                let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                    parent_id: None,
+                    title: "Feature".to_string(),
+                    details: None,
+                    priority: None,
+                    state: None,
+                    tags: vec![],
+                }).expect("Failed to create");
+
+                let session_input = |kind: &str| CreateSessionInput {
+                    feature_id: feature.id,
+                    goal: "Goal".to_string(),
+                    kind: Some(kind.to_string()),
+                    tasks: vec![],
+                };
+                db.create_session(session_input("implementation")).expect("Failed to create");
+
+                let result = db.create_session(session_input("docs"));
+                assert!(result.is_err());
+            }
+
+            it "allows sessions of distinct kinds when the project opts in" {
+                let project = create_test_project(&db);
+                db.update_project(project.id, UpdateProjectInput {
+                    name: None,
+                    description: None,
+                    instructions: None,
+                    max_concurrent_sessions: Some(2),
+                    provision_worktrees: None,
+                }).expect("Failed to update");
+                let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                    parent_id: None,
+                    title: "Feature".to_string(),
+                    details: None,
+                    priority: None,
+                    state: None,
+                    tags: vec![],
+                }).expect("Failed to create");
+
+                let session_input = |kind: &str| CreateSessionInput {
+                    feature_id: feature.id,
+                    goal: "Goal".to_string(),
+                    kind: Some(kind.to_string()),
+                    tasks: vec![],
+                };
+                db.create_session(session_input("implementation")).expect("Failed to create");
+                db.create_session(session_input("docs")).expect("Second kind should be allowed");
+
+                let same_kind = db.create_session(session_input("docs"));
+                assert!(same_kind.is_err());
+                let over_limit = db.create_session(session_input("review"));
+                assert!(over_limit.is_err());
+
+                let active = db.get_active_sessions(feature.id).expect("Query failed");
+                let kinds: Vec<_> = active.iter().map(|s| s.kind.as_deref()).collect();
+                assert_eq!(kinds, vec![Some("implementation"), Some("docs")]);
+            }
+
+            it "promotes desired_details only when the last active session completes" {
+                let project = create_test_project(&db);
+                db.update_project(project.id, UpdateProjectInput {
+                    name: None,
+                    description: None,
+                    instructions: None,
+                    max_concurrent_sessions: Some(2),
+                    provision_worktrees: None,
+                }).expect("Failed to update");
+                let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                    parent_id: None,
+                    title: "Feature".to_string(),
+                    details: Some("Original".to_string()),
+                    priority: None,
+                    state: Some(FeatureState::Specified),
+                    tags: vec![],
+                }).expect("Failed to create");
+                db.update_feature(feature.id, UpdateFeatureInput {
+                    parent_id: None,
+                    title: None,
+                    details: None,
+                    desired_details: Some("Desired".to_string()),
+                    priority: None,
+                    state: None,
+                    tags: None,
+                }).expect("Failed to update");
+
+                let session_input = |kind: &str| CreateSessionInput {
+                    feature_id: feature.id,
+                    goal: "Goal".to_string(),
+                    kind: Some(kind.to_string()),
+                    tasks: vec![],
+                };
+                let implementation = db.create_session(session_input("implementation")).expect("Failed to create");
+                let docs = db.create_session(session_input("docs")).expect("Failed to create");
+                let complete = |feature_state| CompleteSessionInput {
+                    summary: "Done".to_string(),
+                    commits: vec![],
+                    feature_state,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
+                };
+
+                let early = db.complete_session(docs.session.id, complete(Some(FeatureState::Implemented)));
+                assert!(early.is_err());
+
+                db.complete_session(docs.session.id, complete(None)).expect("Failed to complete");
+                let feature_after_docs = db.get_feature(feature.id).expect("Query failed").unwrap();
+                assert_eq!(feature_after_docs.details, Some("Original".to_string()));
+
+                db.complete_session(implementation.session.id, complete(Some(FeatureState::Implemented)))
+                    .expect("Failed to complete");
+                let done = db.get_feature(feature.id).expect("Query failed").unwrap();
+                assert_eq!(done.details, Some("Desired".to_string()));
+                assert_eq!(done.state, FeatureState::Implemented);
+            }
+        }
+
         describe "auto_state_transition" {
             it "transitions proposed feature to specified on session creation" {
                 let project = create_test_project(&db);
@@ -895,6 +2226,7 @@ speculate! {
                     details: None,
                     priority: None,
                     state: Some(FeatureState::Proposed),
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 // Verify initial state is proposed
@@ -904,6 +2236,7 @@ speculate! {
                 db.create_session(CreateSessionInput {
                     feature_id: feature.id,
                     goal: "Implement feature".to_string(),
+                    kind: None,
                     tasks: vec![],
                 }).expect("Failed to create session");
 
@@ -921,12 +2254,14 @@ speculate! {
                     details: None,
                     priority: None,
                     state: Some(FeatureState::Specified),
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 // Create session
                 db.create_session(CreateSessionInput {
                     feature_id: feature.id,
                     goal: "Implement feature".to_string(),
+                    kind: None,
                     tasks: vec![],
                 }).expect("Failed to create session");
 
@@ -944,12 +2279,14 @@ speculate! {
                     details: None,
                     priority: None,
                     state: Some(FeatureState::Implemented),
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 // Create session
                 db.create_session(CreateSessionInput {
                     feature_id: feature.id,
                     goal: "Additional work".to_string(),
+                    kind: None,
                     tasks: vec![],
                 }).expect("Failed to create session");
 
@@ -965,6 +2302,8 @@ speculate! {
                     summary: "Done".to_string(),
                     commits: vec![],
                     feature_state: None,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
                 }).expect("Query failed");
 
                 assert!(result.is_none());
@@ -979,16 +2318,18 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let session_response = db.create_session(CreateSessionInput {
                     feature_id: feature.id,
                     goal: "Implement feature".to_string(),
+                    kind: None,
                     tasks: vec![CreateTaskInput {
                         parent_id: None,
                         title: "Task".to_string(),
                         scope: "Scope".to_string(),
-                        agent_type: AgentType::Claude,
+                        agent_type: AgentType::new("claude"),
                     }],
                 }).expect("Failed to create");
 
@@ -996,6 +2337,8 @@ speculate! {
                     summary: "Implemented the feature".to_string(),
                     commits: vec![],
                     feature_state: None,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
                 }).expect("Query failed").expect("Session not found");
 
                 assert_eq!(result.session.status, SessionStatus::Completed);
@@ -1013,16 +2356,18 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let session_response = db.create_session(CreateSessionInput {
                     feature_id: feature.id,
                     goal: "Goal".to_string(),
+                    kind: None,
                     tasks: vec![CreateTaskInput {
                         parent_id: None,
                         title: "Task".to_string(),
                         scope: "Scope".to_string(),
-                        agent_type: AgentType::Claude,
+                        agent_type: AgentType::new("claude"),
                     }],
                 }).expect("Failed to create");
 
@@ -1032,6 +2377,8 @@ speculate! {
                     summary: "Done".to_string(),
                     commits: vec![],
                     feature_state: None,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
                 }).expect("Failed to complete");
 
                 // Task should be deleted
@@ -1048,11 +2395,13 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let session_response = db.create_session(CreateSessionInput {
                     feature_id: feature.id,
                     goal: "Goal".to_string(),
+                    kind: None,
                     tasks: vec![],
                 }).expect("Failed to create");
 
@@ -1060,6 +2409,8 @@ speculate! {
                     summary: "First completion".to_string(),
                     commits: vec![],
                     feature_state: None,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
                 }).expect("Failed to complete");
 
                 // Try to complete again
@@ -1067,6 +2418,8 @@ speculate! {
                     summary: "Second completion".to_string(),
                     commits: vec![],
                     feature_state: None,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
                 });
 
                 assert!(result.is_err());
@@ -1081,6 +2434,7 @@ speculate! {
                     details: Some("Original details".to_string()),
                     priority: None,
                     state: Some(FeatureState::Specified),
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 // Set desired_details
@@ -1091,11 +2445,13 @@ speculate! {
                     desired_details: Some("New desired details".to_string()),
                     priority: None,
                     state: None,
+                    tags: None,
                 }).expect("Failed to update");
 
                 let session_response = db.create_session(CreateSessionInput {
                     feature_id: feature.id,
                     goal: "Implement feature".to_string(),
+                    kind: None,
                     tasks: vec![],
                 }).expect("Failed to create session");
 
@@ -1104,6 +2460,8 @@ speculate! {
                     summary: "Implemented".to_string(),
                     commits: vec![],
                     feature_state: Some(FeatureState::Implemented),
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
                 }).expect("Failed to complete");
 
                 // Check that desired_details was promoted to details
@@ -1112,6 +2470,259 @@ speculate! {
                 assert!(updated_feature.desired_details.is_none());
                 assert_eq!(updated_feature.state, FeatureState::Implemented);
             }
+
+            it "records task results and merges their commits into history" {
+                let project = create_test_project(&db);
+                let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                    parent_id: None,
+                    title: "Feature".to_string(),
+                    details: None,
+                    priority: None,
+                    state: None,
+                    tags: vec![],
+                }).expect("Failed to create");
+
+                let session_response = db.create_session(CreateSessionInput {
+                    feature_id: feature.id,
+                    goal: "Goal".to_string(),
+                    kind: None,
+                    tasks: vec![CreateTaskInput {
+                        parent_id: None,
+                        title: "Task".to_string(),
+                        scope: "Scope".to_string(),
+                        agent_type: AgentType::new("claude"),
+                    }],
+                }).expect("Failed to create");
+
+                let task_id = session_response.tasks[0].id;
+                db.update_task(task_id, UpdateTaskInput {
+                    status: Some(TaskStatus::Completed),
+                    worktree_path: None,
+                    branch: None,
+                    result: Some(TaskResult {
+                        summary: "Added the handler".to_string(),
+                        files_changed: vec!["src/handler.rs".to_string()],
+                        commits: vec![CommitRef {
+                            sha: "abc123".to_string(),
+                            message: "Add handler".to_string(),
+                            author: None,
+                        }],
+                        test_outcome: Some(TestOutcome::Passed),
+                        failure_reason: None,
+                    }),
+                }).expect("Failed to update task");
+
+                let stored = db.get_task(task_id).expect("Query failed").unwrap();
+                assert_eq!(stored.result.as_ref().unwrap().files_changed, vec!["src/handler.rs"]);
+
+                let result = db.complete_session(session_response.session.id, CompleteSessionInput {
+                    summary: "Done".to_string(),
+                    // Same commit as the task's abbreviated SHA
+                    commits: vec![CommitRef {
+                        sha: "abc123def456".to_string(),
+                        message: "Add handler".to_string(),
+                        author: None,
+                    }],
+                    feature_state: None,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
+                }).expect("Query failed").expect("Session not found");
+
+                let details = result.history_entry.details;
+                assert_eq!(details.summary, "Done");
+                assert_eq!(details.commits.len(), 1);
+                assert_eq!(details.task_results.len(), 1);
+                assert_eq!(details.task_results[0].task_id, task_id);
+                assert_eq!(details.task_results[0].status, TaskStatus::Completed);
+                let task_result = details.task_results[0].result.as_ref().unwrap();
+                assert_eq!(task_result.summary, "Added the handler");
+                assert_eq!(task_result.test_outcome, Some(TestOutcome::Passed));
+            }
+
+            it "drafts summary from task results when summary is empty" {
+                let project = create_test_project(&db);
+                let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                    parent_id: None,
+                    title: "Feature".to_string(),
+                    details: None,
+                    priority: None,
+                    state: None,
+                    tags: vec![],
+                }).expect("Failed to create");
+
+                let session_response = db.create_session(CreateSessionInput {
+                    feature_id: feature.id,
+                    goal: "Add login".to_string(),
+                    kind: None,
+                    tasks: vec![
+                        CreateTaskInput {
+                            parent_id: None,
+                            title: "Form".to_string(),
+                            scope: "Scope".to_string(),
+                            agent_type: AgentType::new("claude"),
+                        },
+                        CreateTaskInput {
+                            parent_id: None,
+                            title: "Tests".to_string(),
+                            scope: "Scope".to_string(),
+                            agent_type: AgentType::new("claude"),
+                        },
+                    ],
+                }).expect("Failed to create");
+
+                db.update_task(session_response.tasks[0].id, UpdateTaskInput {
+                    status: Some(TaskStatus::Completed),
+                    worktree_path: None,
+                    branch: None,
+                    result: Some(TaskResult {
+                        summary: "Built the login form".to_string(),
+                        ..Default::default()
+                    }),
+                }).expect("Failed to update task");
+
+                db.update_task(session_response.tasks[1].id, UpdateTaskInput {
+                    status: Some(TaskStatus::Failed),
+                    worktree_path: None,
+                    branch: None,
+                    result: Some(TaskResult {
+                        failure_reason: Some("No test database available".to_string()),
+                        ..Default::default()
+                    }),
+                }).expect("Failed to update task");
+
+                let result = db.complete_session(session_response.session.id, CompleteSessionInput {
+                    summary: "".to_string(),
+                    commits: vec![],
+                    feature_state: None,
+                    require_feature_trailer: false,
+                    allow_unverified_criteria: false,
+                }).expect("Query failed").expect("Session not found");
+
+                assert_eq!(
+                    result.history_entry.details.summary,
+                    "Add login\n\n- Form: Built the login form\n- Tests (failed): No test database available"
+                );
+            }
+        }
+    }
+
+    describe "tasks" {
+        describe "timestamps" {
+            it "sets started_at and finished_at on status transitions" {
+                let project = create_test_project(&db);
+                let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                    parent_id: None,
+                    title: "Feature".to_string(),
+                    details: None,
+                    priority: None,
+                    state: None,
+                    tags: vec![],
+                }).expect("Failed to create");
+                let session = db.create_session(CreateSessionInput {
+                    feature_id: feature.id,
+                    goal: "Goal".to_string(),
+                    kind: None,
+                    tasks: vec![CreateTaskInput {
+                        parent_id: None,
+                        title: "Task".to_string(),
+                        scope: "Scope".to_string(),
+                        agent_type: AgentType::new("claude"),
+                    }],
+                }).expect("Failed to create");
+                let task_id = session.tasks[0].id;
+                let set_status = |status| UpdateTaskInput {
+                    status: Some(status),
+                    worktree_path: None,
+                    branch: None,
+                    result: None,
+                };
+
+                let task = db.get_task(task_id).expect("Query failed").expect("Task missing");
+                assert!(task.started_at.is_none());
+                assert!(task.finished_at.is_none());
+
+                db.update_task(task_id, set_status(TaskStatus::Running)).expect("Failed to update");
+                let running = db.get_task(task_id).expect("Query failed").expect("Task missing");
+                assert!(running.started_at.is_some());
+                assert!(running.finished_at.is_none());
+
+                db.update_task(task_id, set_status(TaskStatus::Completed)).expect("Failed to update");
+                let done = db.get_task(task_id).expect("Query failed").expect("Task missing");
+                assert_eq!(done.started_at, running.started_at);
+                assert!(done.finished_at.is_some());
+            }
+        }
+    }
+
+    describe "metrics" {
+        it "returns None for non-existent project" {
+            let metrics = db.get_project_metrics(Uuid::new_v4()).expect("Query failed");
+            assert!(metrics.is_none());
+        }
+
+        it "aggregates completed sessions and archived task timings" {
+            let project = create_test_project(&db);
+            let feature = db.create_feature(project.id, CreateFeatureInput { id: None,
+                parent_id: None,
+                title: "Feature".to_string(),
+                details: None,
+                priority: None,
+                state: None,
+                tags: vec![],
+            }).expect("Failed to create");
+            let task_input = |title: &str| CreateTaskInput {
+                parent_id: None,
+                title: title.to_string(),
+                scope: "Scope".to_string(),
+                agent_type: AgentType::new("claude"),
+            };
+            let session = db.create_session(CreateSessionInput {
+                feature_id: feature.id,
+                goal: "Goal".to_string(),
+                kind: None,
+                tasks: vec![task_input("Works"), task_input("Breaks")],
+            }).expect("Failed to create");
+
+            for (task, outcome) in session.tasks.iter().zip([TaskStatus::Completed, TaskStatus::Failed]) {
+                for status in [TaskStatus::Running, outcome] {
+                    db.update_task(task.id, UpdateTaskInput {
+                        status: Some(status),
+                        worktree_path: None,
+                        branch: None,
+                        result: None,
+                    }).expect("Failed to update");
+                }
+            }
+
+            db.complete_session(session.session.id, CompleteSessionInput {
+                summary: "Done".to_string(),
+                commits: vec![],
+                feature_state: None,
+                require_feature_trailer: false,
+                allow_unverified_criteria: false,
+            }).expect("Failed to complete");
+
+            let metrics = db.get_project_metrics(project.id)
+                .expect("Query failed")
+                .expect("Project missing");
+
+            assert_eq!(metrics.features.len(), 1);
+            assert_eq!(metrics.features[0].feature_id, feature.id);
+            assert_eq!(metrics.features[0].sessions, 1);
+
+            assert_eq!(metrics.agent_types.len(), 1);
+            let claude = &metrics.agent_types[0];
+            assert_eq!(claude.agent_type.as_str(), "claude");
+            assert_eq!(claude.finished, 2);
+            assert_eq!(claude.failed, 1);
+            assert_eq!(claude.failure_rate, 0.5);
+            assert_eq!(claude.duration.count, 2);
+
+            assert_eq!(metrics.weekly_throughput.len(), 1);
+            let week = &metrics.weekly_throughput[0];
+            assert_eq!(week.sessions_completed, 1);
+            assert_eq!(week.tasks_completed, 1);
+            assert_eq!(week.tasks_failed, 1);
         }
     }
 
@@ -1126,6 +2737,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create feature");
 
                 let session_id = Uuid::new_v4();
@@ -1135,6 +2747,7 @@ speculate! {
                     details: HistoryDetails {
                         summary: "Implemented login flow".to_string(),
                         commits: vec![],
+                        task_results: vec![],
                     },
                 }).expect("Failed to create history entry");
 
@@ -1152,6 +2765,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create feature");
 
                 let entry = db.create_history_entry(CreateHistoryInput {
@@ -1160,6 +2774,7 @@ speculate! {
                     details: HistoryDetails {
                         summary: "Manual update".to_string(),
                         commits: vec![],
+                        task_results: vec![],
                     },
                 }).expect("Failed to create history entry");
 
@@ -1177,6 +2792,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create feature");
 
                 let history = db.get_feature_history(feature.id).expect("Query failed");
@@ -1192,6 +2808,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create feature");
 
                 db.create_history_entry(CreateHistoryInput {
@@ -1200,6 +2817,7 @@ speculate! {
                     details: HistoryDetails {
                         summary: "First change".to_string(),
                         commits: vec![],
+                        task_results: vec![],
                     },
                 }).expect("Failed to create");
 
@@ -1209,6 +2827,7 @@ speculate! {
                     details: HistoryDetails {
                         summary: "Second change".to_string(),
                         commits: vec![],
+                        task_results: vec![],
                     },
                 }).expect("Failed to create");
 
@@ -1228,6 +2847,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 let feature2 = db.create_feature(project.id, CreateFeatureInput { id: None,
@@ -1237,6 +2857,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_history_entry(CreateHistoryInput {
@@ -1245,6 +2866,7 @@ speculate! {
                     details: HistoryDetails {
                         summary: "Change to feature 1".to_string(),
                         commits: vec![],
+                        task_results: vec![],
                     },
                 }).expect("Failed to create");
 
@@ -1254,6 +2876,7 @@ speculate! {
                     details: HistoryDetails {
                         summary: "Change to feature 2".to_string(),
                         commits: vec![],
+                        task_results: vec![],
                     },
                 }).expect("Failed to create");
 
@@ -1274,6 +2897,7 @@ speculate! {
                     details: None,
                 priority: None,
                     state: None,
+                    tags: vec![],
                 }).expect("Failed to create");
 
                 db.create_history_entry(CreateHistoryInput {
@@ -1282,6 +2906,7 @@ speculate! {
                     details: HistoryDetails {
                         summary: "Some work".to_string(),
                         commits: vec![],
+                        task_results: vec![],
                     },
                 }).expect("Failed to create");
 
@@ -1294,4 +2919,51 @@ speculate! {
         }
     }
 
+    describe "idempotency_keys" {
+        before {
+            let day = std::time::Duration::from_secs(24 * 60 * 60);
+        }
+
+        it "claims a new key, then replays its response" {
+            let response = created_response();
+            let first = db.claim_idempotency_key("k1", "POST /features", day).unwrap();
+            db.complete_idempotency_key("k1", &response).unwrap();
+            let retry = db.claim_idempotency_key("k1", "POST /features", day).unwrap();
+
+            assert_eq!(first, IdempotencyClaim::Claimed);
+            assert_eq!(retry, IdempotencyClaim::Completed(response));
+        }
+
+        it "reports a key whose request is still running" {
+            db.claim_idempotency_key("k1", "POST /features", day).unwrap();
+
+            let retry = db.claim_idempotency_key("k1", "POST /features", day).unwrap();
+
+            assert_eq!(retry, IdempotencyClaim::InProgress);
+        }
+
+        it "refuses a key used for a different request" {
+            db.claim_idempotency_key("k1", "POST /features", day).unwrap();
+            db.complete_idempotency_key("k1", &created_response()).unwrap();
+
+            let other = db.claim_idempotency_key("k1", "POST /sessions", day).unwrap();
+
+            assert_eq!(other, IdempotencyClaim::Mismatch);
+        }
+
+        it "forgets released and expired keys" {
+            db.claim_idempotency_key("k1", "POST /features", day).unwrap();
+            db.release_idempotency_key("k1").unwrap();
+            let released = db.claim_idempotency_key("k1", "POST /features", day).unwrap();
+            db.complete_idempotency_key("k1", &created_response()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            let expired = db
+                .claim_idempotency_key("k1", "POST /features", std::time::Duration::from_millis(10))
+                .unwrap();
+
+            assert_eq!(released, IdempotencyClaim::Claimed);
+            assert_eq!(expired, IdempotencyClaim::Claimed);
+        }
+    }
+
 }
@@ -217,11 +217,11 @@ mod protocol {
         let tools = result.get("tools").expect("Expected tools array");
         let tools_array = tools.as_array().expect("Tools should be array");
 
-        // CLI mode has 12 tools
+        // CLI mode has 15 tools
         assert_eq!(
             tools_array.len(),
-            12,
-            "Expected 12 CLI tools, got {}",
+            15,
+            "Expected 15 CLI tools, got {}",
             tools_array.len()
         );
 
@@ -238,11 +238,14 @@ mod protocol {
         assert!(tool_names.contains(&"get_feature"));
         assert!(tool_names.contains(&"get_feature_history"));
         assert!(tool_names.contains(&"render_feature_tree"));
+        assert!(tool_names.contains(&"get_next_features"));
+        assert!(tool_names.contains(&"verify_criterion"));
         // Setup tools
         assert!(tool_names.contains(&"create_project"));
         assert!(tool_names.contains(&"add_project_directory"));
         assert!(tool_names.contains(&"create_feature"));
         assert!(tool_names.contains(&"plan_features"));
+        assert!(tool_names.contains(&"relate_features"));
         // Work tools
         assert!(tool_names.contains(&"start_feature"));
         assert!(tool_names.contains(&"complete_feature"));
@@ -260,11 +263,11 @@ mod protocol {
         let tools = result.get("tools").expect("Expected tools array");
         let tools_array = tools.as_array().expect("Tools should be array");
 
-        // IDE mode has 21 tools
+        // IDE mode has 26 tools
         assert_eq!(
             tools_array.len(),
-            21,
-            "Expected 21 IDE tools, got {}",
+            26,
+            "Expected 26 IDE tools, got {}",
             tools_array.len()
         );
 
@@ -278,6 +281,8 @@ mod protocol {
         assert!(tool_names.contains(&"get_task_context"));
         assert!(tool_names.contains(&"start_task"));
         assert!(tool_names.contains(&"complete_task"));
+        assert!(tool_names.contains(&"fail_task"));
+        assert!(tool_names.contains(&"link_code"));
         assert!(tool_names.contains(&"create_session"));
         assert!(tool_names.contains(&"get_feature_session"));
         assert!(tool_names.contains(&"create_task"));
@@ -292,6 +297,9 @@ mod protocol {
         assert!(tool_names.contains(&"get_project_context"));
         assert!(tool_names.contains(&"create_project"));
         assert!(tool_names.contains(&"create_feature"));
+        assert!(tool_names.contains(&"relate_features"));
+        assert!(tool_names.contains(&"get_next_features"));
+        assert!(tool_names.contains(&"verify_criterion"));
     }
 
     #[test]